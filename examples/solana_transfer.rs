@@ -0,0 +1,69 @@
+//! Signs a Solana transfer transaction with a threshold group that includes a blind custodian.
+//!
+//! The group key is the fee payer and source account. The message is a legacy Solana transaction
+//! message with a single System Program transfer, and the signed transaction is printed in hex.
+#![allow(non_snake_case)]
+
+use blind_threshold_sig::{
+    SchnorrSignature, SignerRole, SigningPackage,
+    helper::encode_hex,
+    solana::{public_key, solana_group, to_solana_signature, verify_solana_signature},
+};
+
+/// Index of the transfer instruction of the System Program.
+const TRANSFER: u32 = 2;
+
+fn main() {
+    // two regular signers and a blind custodian, any two of which can sign
+    let frost = solana_group(
+        2,
+        &[SignerRole::Regular, SignerRole::Regular, SignerRole::Blind],
+    );
+    let payer = public_key(&frost);
+    let recipient = [7u8; 32];
+    let system_program = [0u8; 32];
+    let recent_blockhash = [1u8; 32];
+
+    // header: one required signature, no read-only signed and one read-only unsigned account
+    let mut message = vec![1, 0, 1];
+    message.push(3);
+    message.extend_from_slice(&payer);
+    message.extend_from_slice(&recipient);
+    message.extend_from_slice(&system_program);
+    message.extend_from_slice(&recent_blockhash);
+    // one instruction: program index 2, accounts [0, 1], 12 bytes of data
+    message.extend_from_slice(&[1, 2, 2, 0, 1, 12]);
+    message.extend_from_slice(&TRANSFER.to_le_bytes());
+    message.extend_from_slice(&1_000_000u64.to_le_bytes());
+
+    // the first regular signer co-signs with the blind custodian
+    let mut signers = [frost.signers[0].clone(), frost.signers[2].clone()];
+    let signing_package = SigningPackage::new(
+        &message,
+        signers
+            .iter()
+            .map(|signer| signer.get_commitments())
+            .collect(),
+    );
+    let (R, _) = signing_package
+        .group_commitment_and_challenge(frost.group_pk)
+        .expect("signers have distinct identifiers");
+    let sig_shares: Vec<_> = signers
+        .iter_mut()
+        .map(|signer| {
+            signer
+                .sign(&signing_package)
+                .expect("signer rejected the signing package")
+        })
+        .collect();
+    let signature = to_solana_signature(&SchnorrSignature {
+        R,
+        s: frost.signature_aggregate(sig_shares),
+    });
+    assert!(verify_solana_signature(&payer, &message, &signature));
+
+    // a transaction is the list of signatures followed by the message
+    let transaction = [&[1u8][..], &signature, &message].concat();
+    println!("payer: {}", encode_hex(&payer));
+    println!("transaction: {}", encode_hex(&transaction));
+}
@@ -0,0 +1,200 @@
+//! This module renders the group public key as the addresses of Ed25519 chains.
+//!
+//! - `solana_address` is the base58 encoding of the 32-byte public key, as on Solana.
+//! - `ss58_address` is the SS58 encoding used by Substrate chains, e.g. Polkadot (prefix 0),
+//!   Kusama (2) or the generic Substrate prefix 42.
+//!
+//! These chains verify plain RFC 8032 signatures, so the group must use the standard basepoint,
+//! e.g. be set up with `solana::solana_group`, for its signatures to be accepted for the address.
+//!
+//! Reference: https://docs.substrate.io/reference/address-formats/
+use crate::{
+    ciphersuite::{Ciphersuite, Ed25519Sha512},
+    keys::GroupPublicKey,
+};
+
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Prefix of the preimage of SS58 checksums.
+const SS58_CONTEXT: &[u8] = b"SS58PRE";
+
+/// Returns the Solana address of the group, the base58 encoding of its public key.
+pub fn solana_address(group_pk: &GroupPublicKey<Ed25519Sha512>) -> String {
+    base58_encode(&Ed25519Sha512::serialize_element(&group_pk.to_element()))
+}
+
+/// Returns the SS58 address of the group on the network identified by `prefix`.
+///
+/// # Panics
+///
+/// Panics if `prefix` is 16384 or more, which SS58 cannot encode.
+pub fn ss58_address(group_pk: &GroupPublicKey<Ed25519Sha512>, prefix: u16) -> String {
+    ss58_encode(
+        &Ed25519Sha512::serialize_element(&group_pk.to_element()),
+        prefix,
+    )
+}
+
+fn ss58_encode(public_key: &[u8], prefix: u16) -> String {
+    let mut payload = match prefix {
+        0..=63 => vec![prefix as u8],
+        64..=16383 => vec![
+            ((prefix & 0b1111_1100) >> 2) as u8 | 0b0100_0000,
+            (prefix >> 8) as u8 | ((prefix & 0b11) << 6) as u8,
+        ],
+        _ => panic!("SS58 prefixes are below 16384"),
+    };
+    payload.extend_from_slice(public_key);
+    let checksum = blake2b_512(&[SS58_CONTEXT, &payload].concat());
+    payload.extend_from_slice(&checksum[..2]);
+    base58_encode(&payload)
+}
+
+/// Encodes `bytes` in base58 with the Bitcoin alphabet, keeping leading zero bytes as `1`s.
+pub fn base58_encode(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|byte| **byte == 0).count();
+    // base-58 digits, least significant first
+    let mut digits: Vec<u8> = Vec::new();
+    for byte in &bytes[zeros..] {
+        let mut carry = u32::from(*byte);
+        for digit in digits.iter_mut() {
+            carry += u32::from(*digit) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    std::iter::repeat_n('1', zeros)
+        .chain(
+            digits
+                .iter()
+                .rev()
+                .map(|digit| BASE58_ALPHABET[usize::from(*digit)] as char),
+        )
+        .collect()
+}
+
+const BLAKE2B_IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+const BLAKE2B_SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// Unkeyed BLAKE2b with a 64-byte output, as specified in RFC 7693.
+fn blake2b_512(input: &[u8]) -> [u8; 64] {
+    let mut h = BLAKE2B_IV;
+    h[0] ^= 0x0101_0040;
+
+    let blocks = input.len().div_ceil(128).max(1);
+    for index in 0..blocks {
+        let chunk = &input[index * 128..input.len().min((index + 1) * 128)];
+        let mut block = [0u8; 128];
+        block[..chunk.len()].copy_from_slice(chunk);
+        let last = index + 1 == blocks;
+        let counter = if last {
+            input.len() as u128
+        } else {
+            (index as u128 + 1) * 128
+        };
+        blake2b_compress(&mut h, &block, counter, last);
+    }
+
+    let mut output = [0u8; 64];
+    for (bytes, word) in output.chunks_exact_mut(8).zip(h) {
+        bytes.copy_from_slice(&word.to_le_bytes());
+    }
+    output
+}
+
+fn blake2b_compress(h: &mut [u64; 8], block: &[u8; 128], counter: u128, last: bool) {
+    let mut m = [0u64; 16];
+    for (word, bytes) in m.iter_mut().zip(block.chunks_exact(8)) {
+        *word = u64::from_le_bytes(bytes.try_into().expect("chunks of 8 bytes"));
+    }
+    let mut v = [0u64; 16];
+    v[..8].copy_from_slice(h);
+    v[8..].copy_from_slice(&BLAKE2B_IV);
+    v[12] ^= counter as u64;
+    v[13] ^= (counter >> 64) as u64;
+    if last {
+        v[14] = !v[14];
+    }
+
+    for sigma in BLAKE2B_SIGMA.iter().cycle().take(12) {
+        for (i, [a, b, c, d]) in [
+            [0, 4, 8, 12],
+            [1, 5, 9, 13],
+            [2, 6, 10, 14],
+            [3, 7, 11, 15],
+            [0, 5, 10, 15],
+            [1, 6, 11, 12],
+            [2, 7, 8, 13],
+            [3, 4, 9, 14],
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let (x, y) = (m[sigma[2 * i]], m[sigma[2 * i + 1]]);
+            v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+            v[d] = (v[d] ^ v[a]).rotate_right(32);
+            v[c] = v[c].wrapping_add(v[d]);
+            v[b] = (v[b] ^ v[c]).rotate_right(24);
+            v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+            v[d] = (v[d] ^ v[a]).rotate_right(16);
+            v[c] = v[c].wrapping_add(v[d]);
+            v[b] = (v[b] ^ v[c]).rotate_right(63);
+        }
+    }
+
+    for i in 0..8 {
+        h[i] ^= v[i] ^ v[i + 8];
+    }
+}
+
+#[test]
+fn test_addresses() {
+    use crate::helper::{decode_hex, encode_hex};
+
+    assert_eq!(
+        encode_hex(&blake2b_512(b"abc")),
+        "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+         7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+    );
+    assert_eq!(base58_encode(&[0, 0, 0x28, 0x7f, 0xb4, 0xcd]), "11233QC4");
+
+    // the public key of Substrate's development account Alice
+    let public_key =
+        decode_hex("d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d").unwrap();
+    assert_eq!(
+        ss58_encode(&public_key, 42),
+        "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+    );
+
+    let frost = crate::solana::solana_group(2, &[crate::frost::SignerRole::Regular; 3]);
+    assert_eq!(
+        solana_address(&frost.group_pk),
+        base58_encode(&crate::solana::public_key(&frost))
+    );
+}
@@ -0,0 +1,172 @@
+//! This module implements batches of nonce commitments pre-published by the custodian.
+//!
+//! Following the anti-extortion idea of the gist, a custodian publishes a signed `NonceBatch` of
+//! commitments ahead of time. Users verify the batch once and later start signing sessions with
+//! its commitments without asking the custodian for fresh ones, so the custodian cannot single
+//! out a user by withholding nonces, and any commitment it refuses to sign with is provably its
+//! own.
+use ark_serialize::SerializationError;
+use ark_std::rand::Rng;
+
+use crate::{
+    ciphersuite::{Ciphersuite, Ed25519Sha512, Element},
+    helper::{Decoder, NonZeroScalar, serialize_scalar},
+    keys::{KeyPackage, VerificationShare, verify_message_signature},
+    nonces::SigningCommitments,
+    schnorr::SchnorrSignature,
+};
+
+/// Domain separator prefixed to the message signed for a batch.
+const BATCH_CONTEXT: &[u8] = b"FROST-NONCE-BATCH-v1";
+
+/// A batch of nonce commitments signed by the custodian that issued them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonceBatch<C: Ciphersuite = Ed25519Sha512> {
+    /// Identifier of the custodian.
+    pub identifier: NonZeroScalar<C>,
+    /// The commitments not consumed yet, in the order they are to be used.
+    pub commitments: Vec<SigningCommitments<C>>,
+    /// Schnorr signature over the batch, under the custodian's verification share.
+    pub signature: SchnorrSignature<C>,
+}
+
+impl<C: Ciphersuite> NonceBatch<C> {
+    /// Signs `commitments` with the secret share of `key_package`.
+    pub fn sign<R: Rng>(
+        key_package: &KeyPackage<C>,
+        commitments: Vec<SigningCommitments<C>>,
+        rng: &mut R,
+    ) -> Self {
+        let identifier = key_package.identifier;
+        let signature = key_package.sign_message(&Self::message(identifier, &commitments), rng);
+
+        NonceBatch {
+            identifier,
+            commitments,
+            signature,
+        }
+    }
+
+    /// Returns `true` if the batch is signed under `verification_share` and every commitment
+    /// belongs to the custodian.
+    ///
+    /// Only an intact batch verifies, as the signature covers every commitment.
+    pub fn verify(&self, verification_share: VerificationShare<C>, generator: Element<C>) -> bool {
+        if self
+            .commitments
+            .iter()
+            .any(|commitments| commitments.identifier != self.identifier)
+        {
+            return false;
+        }
+
+        verify_message_signature(
+            &self.signature,
+            &Self::message(self.identifier, &self.commitments),
+            verification_share.to_element(),
+            generator,
+        )
+    }
+
+    /// Removes and returns the next commitment to use, if any is left.
+    pub fn take(&mut self) -> Option<SigningCommitments<C>> {
+        if self.commitments.is_empty() {
+            None
+        } else {
+            Some(self.commitments.remove(0))
+        }
+    }
+
+    /// Encodes the batch as `signature || identifier || count || commitments`, where `count` is
+    /// a little-endian `u32`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the batch holds more than `u32::MAX` commitments.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.signature.to_bytes();
+        bytes.extend(serialize_scalar::<C>(&self.identifier.to_scalar()));
+        let count = u32::try_from(self.commitments.len()).expect("too many commitments");
+        bytes.extend_from_slice(&count.to_le_bytes());
+        for commitments in &self.commitments {
+            bytes.extend(commitments.to_bytes());
+        }
+        bytes
+    }
+
+    /// Parses a batch encoded by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let mut decoder = Decoder::new(bytes);
+        let signature =
+            SchnorrSignature::from_bytes(decoder.take(SchnorrSignature::<C>::encoded_length())?)?;
+        let identifier = decoder.identifier()?;
+        let commitments = (0..decoder.u32()?)
+            .map(|_| decoder.commitments())
+            .collect::<Result<_, _>>()?;
+        decoder.finish()?;
+
+        Ok(NonceBatch {
+            identifier,
+            commitments,
+            signature,
+        })
+    }
+
+    /// The message signed for a batch: the context, the identifier and every commitment.
+    fn message(identifier: NonZeroScalar<C>, commitments: &[SigningCommitments<C>]) -> Vec<u8> {
+        let mut message = BATCH_CONTEXT.to_vec();
+        message.extend(serialize_scalar::<C>(&identifier.to_scalar()));
+        for commitments in commitments {
+            message.extend(commitments.to_bytes());
+        }
+        message
+    }
+}
+
+#[test]
+fn test_nonce_batch() {
+    use std::time::Duration;
+
+    use crate::{
+        custodian::{Custodian, CustodianPolicy},
+        frost::{Frost, SigningPackage},
+    };
+
+    let frost: Frost = Frost::signature_share(2, 3);
+    let key_package = frost
+        .key_package(frost.participants().last().unwrap())
+        .unwrap();
+    let (verification_share, generator) = (key_package.verification_share, frost.generator);
+    let policy = CustodianPolicy {
+        max_signatures: 10,
+        window: Duration::from_secs(60),
+        max_pending: 3,
+        pending_ttl: Duration::from_secs(60),
+        digest_only: false,
+    };
+    let mut custodian = Custodian::new(key_package, policy);
+
+    let batch = custodian.publish_batch(3).unwrap();
+    assert!(custodian.publish_batch(1).is_err());
+    // a count near usize::MAX cannot wrap around the limit
+    assert!(
+        custodian
+            .handle(&format!("BATCH {}", usize::MAX))
+            .starts_with("ERR")
+    );
+    let mut batch = NonceBatch::from_bytes(&batch.to_bytes()).unwrap();
+    assert!(batch.verify(verification_share, generator));
+
+    let mut tampered = batch.clone();
+    tampered.commitments.swap(0, 1);
+    assert!(!tampered.verify(verification_share, generator));
+
+    // the custodian signs with a commitment taken from the batch
+    let signer = &frost.signers[0];
+    let signing_package = SigningPackage::new(
+        b"testing",
+        vec![signer.get_commitments(), batch.take().unwrap()],
+    );
+    assert!(custodian.sign(&signing_package).is_ok());
+    assert_eq!(batch.commitments.len(), 2);
+}
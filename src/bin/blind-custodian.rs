@@ -0,0 +1,92 @@
+//! Runs a collaborative custodian that serves blind signature shares over TCP.
+//!
+//! Usage: `blind-custodian <key-package> <ledger> <address> [<max-signatures> <window-seconds>]`
+//!
+//! `<key-package>` is a file containing the hex encoding of the custodian's `KeyPackage`, and
+//! `<ledger>` the nonce ledger file, created if it does not exist. By default, at most 10
+//! signature shares are issued per hour. Commitments that are not used within the window are
+//! discarded.
+//!
+//! To serve as a Tor onion service, listen on a loopback address and add to the `torrc`:
+//!
+//! ```text
+//! HiddenServiceDir /var/lib/tor/blind-custodian
+//! HiddenServicePort 7000 127.0.0.1:7000
+//! ```
+//!
+//! Every connection then appears to come from Tor itself, and clients reach the `.onion` address
+//! in the `hostname` file with `CustodianClient::connect_via`.
+use std::{env, fs, net::TcpListener, process, time::Duration};
+
+use blind_threshold_sig::{
+    KeyPackage,
+    custodian::{Custodian, CustodianPolicy, serve},
+    helper::decode_hex,
+    ledger::FileNonceLedger,
+};
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 4 && args.len() != 6 {
+        eprintln!(
+            "usage: {} <key-package> <ledger> <address> [<max-signatures> <window-seconds>]",
+            args[0]
+        );
+        process::exit(2);
+    }
+
+    let key_package: KeyPackage = fs::read_to_string(&args[1])
+        .ok()
+        .and_then(|hex| decode_hex(hex.trim()))
+        .and_then(|bytes| KeyPackage::from_bytes(&bytes).ok())
+        .unwrap_or_else(|| {
+            eprintln!("could not load a key package from {}", args[1]);
+            process::exit(1);
+        });
+
+    let (max_signatures, window_seconds) = match &args[4..] {
+        [max_signatures, window_seconds] => (
+            max_signatures
+                .parse()
+                .unwrap_or_else(|_| usage_error("max-signatures")),
+            window_seconds
+                .parse()
+                .unwrap_or_else(|_| usage_error("window-seconds")),
+        ),
+        _ => (10, 3600),
+    };
+    let policy = CustodianPolicy {
+        max_signatures,
+        window: Duration::from_secs(window_seconds),
+        max_pending: max_signatures,
+        pending_ttl: Duration::from_secs(window_seconds),
+        digest_only: false,
+    };
+
+    let ledger = FileNonceLedger::open(&args[2]).unwrap_or_else(|error| {
+        eprintln!("could not open the nonce ledger {}: {error}", args[2]);
+        process::exit(1);
+    });
+
+    let listener = TcpListener::bind(&args[3]).unwrap_or_else(|error| {
+        eprintln!("could not listen on {}: {error}", args[3]);
+        process::exit(1);
+    });
+    println!(
+        "custodian {:?} listening on {}",
+        key_package.identifier, args[3]
+    );
+
+    if let Err(error) = serve(
+        Custodian::with_ledger(key_package, policy, Box::new(ledger)),
+        listener,
+    ) {
+        eprintln!("custodian stopped: {error}");
+        process::exit(1);
+    }
+}
+
+fn usage_error<T>(argument: &str) -> T {
+    eprintln!("{argument} must be a non-negative integer");
+    process::exit(2);
+}
@@ -0,0 +1,481 @@
+//! This module implements signature share verification and exportable blame certificates.
+//!
+//! When a signature share fails verification, the coordinator produces a `BlameCertificate`
+//! holding the signing package the signer answered, i.e. the message, session id, counter and
+//! every commitment, the key the session signed under, the signer's verification share and the
+//! share itself. `BlameCertificate::verify` recomputes the binding factor, Lagrange coefficient
+//! and challenge from the package rather than trusting the coordinator's values, so a third party
+//! can confirm that the share is invalid without access to the coordinator's state. The third
+//! party must still check the group key and verification share against the public key package of
+//! the group.
+//!
+//! That the blamed signer answered this package with this share at all only holds if it signed
+//! them: a signer that returns its share with `attest_share`, an identity signature over the
+//! share and the whole signing package, lets the coordinator attach the signature with
+//! `BlameCertificate::attest`, and a certificate checked with `verify_attributed` settles a
+//! dispute with a custodian. Without it, the certificate rests on the coordinator's word that the
+//! signer returned the share for this package.
+//!
+//! For high-assurance deployments, signers can also attach a `ShareProof` to their shares, a
+//! non-interactive proof that `z_i` was computed from the nonces behind their commitments and the
+//! secret share behind their verification share. Anyone holding the signing package can check it,
+//! so blame no longer rests on trusting the coordinator.
+//!
+//! Reference: https://www.rfc-editor.org/rfc/rfc9591.html#section-5.4
+#![allow(non_snake_case)]
+
+use ark_ff::{PrimeField, UniformRand};
+use ark_serialize::SerializationError;
+use ark_std::rand::Rng;
+
+use crate::{
+    ciphersuite::{Ciphersuite, Ed25519Sha512, Element, ScalarField},
+    error::FrostError,
+    frost::{SignatureShare, SigningPackage},
+    helper::{
+        Decoder, NonZeroScalar, compute_binding_factors, compute_challenge,
+        compute_group_commitment, derive_interpolating_value, serialize_scalar,
+    },
+    identity::{IdentityKey, IdentityRoster},
+    keys::{GroupPublicKey, VerificationShare},
+    nonces::{SigningCommitments, SigningNonces},
+    schnorr::SchnorrSignature,
+};
+
+/// Domain separator of the message a signer signs in `attest_share`.
+const ATTESTATION_CONTEXT: &[u8] = b"FROST-SHARE-ATTESTATION-v1";
+
+/// A self-contained record of an invalid signature share.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlameCertificate<C: Ciphersuite = Ed25519Sha512> {
+    /// The key the session signed under: the group public key, or a key derived from it.
+    pub group_pk: GroupPublicKey<C>,
+    /// Generator of the group the signer's key share lives in.
+    pub generator: Element<C>,
+    /// The signer's public verification share `Y_i = g^{x_i}`.
+    pub verification_share: VerificationShare<C>,
+    /// The package the signer answered, with the message, session id, counter and the
+    /// commitments of every participant.
+    pub signing_package: SigningPackage<C>,
+    /// The signature share returned by the signer.
+    pub sig_share: SignatureShare<C>,
+    /// The signer's identity signature over the share and the signing package, as by
+    /// `attest_share`, if it returned one.
+    pub attestation: Option<SchnorrSignature<C>>,
+}
+
+impl<C: Ciphersuite> BlameCertificate<C> {
+    /// Identifier of the signer being blamed.
+    pub fn identifier(&self) -> NonZeroScalar<C> {
+        self.sig_share.identifier
+    }
+
+    /// Returns `true` if the recorded share is indeed an invalid answer to the signing package,
+    /// i.e. the blame holds.
+    ///
+    /// A share is valid when `g^{z_i} == D_i + E_i * rho_i + Y_i * (c * lambda_i)`, where
+    /// `rho_i`, `lambda_i` and `c` are recomputed from the signing package. The blame does not
+    /// hold if the share was produced for another session or counter, or if the package does not
+    /// contain a commitment of the signer.
+    pub fn verify(&self) -> bool {
+        if self.sig_share.session_id != self.signing_package.session_id
+            || self.sig_share.counter != self.signing_package.counter
+        {
+            return false;
+        }
+        match ShareStatement::new(
+            self.identifier(),
+            &self.signing_package,
+            self.generator,
+            self.verification_share,
+            self.group_pk,
+        ) {
+            Ok(statement) => !statement.is_satisfied_by(self.sig_share.z_i),
+            Err(_) => false,
+        }
+    }
+
+    /// Returns `true` if the blame holds, as by `verify`, and the attestation is the signature
+    /// of the blamed signer's identity key in `roster` over the share and the signing package,
+    /// so that neither can have been made up by the coordinator.
+    pub fn verify_attributed(&self, roster: &IdentityRoster<C>) -> bool {
+        let Some(signature) = self.attestation else {
+            return false;
+        };
+        let message = attested_message(&self.sig_share, &self.signing_package);
+        self.verify()
+            && roster
+                .verify(self.identifier(), &message, &signature)
+                .is_ok()
+    }
+
+    /// Attaches the `attestation` the signer returned with its share, as by `attest_share`.
+    pub fn attest(mut self, attestation: SchnorrSignature<C>) -> Self {
+        self.attestation = Some(attestation);
+        self
+    }
+}
+
+/// Signs `sig_share` as the answer to `signing_package` with the signer's identity `key`, for the
+/// signer to return along with the share, so that a `BlameCertificate` can be attributed to it.
+pub fn attest_share<C: Ciphersuite, R: Rng>(
+    sig_share: &SignatureShare<C>,
+    signing_package: &SigningPackage<C>,
+    key: &IdentityKey<C>,
+    rng: &mut R,
+) -> SchnorrSignature<C> {
+    key.sign(&attested_message(sig_share, signing_package), rng)
+}
+
+fn attested_message<C: Ciphersuite>(
+    sig_share: &SignatureShare<C>,
+    signing_package: &SigningPackage<C>,
+) -> Vec<u8> {
+    [
+        ATTESTATION_CONTEXT.to_vec(),
+        sig_share.to_bytes(),
+        signing_package.to_bytes(),
+    ]
+    .concat()
+}
+
+/// Verifies `sig_share` as an answer to `signing_package` under the key `group_pk`, given the
+/// `verification_share` of the participant that produced it.
+///
+/// Returns `FrostError::InvalidSignatureShare` with a `BlameCertificate` if the share does not
+/// verify, `FrostError::UnknownSession` if it was produced for another session or counter, and
+/// `FrostError::MissingCommitment` if the package has no commitment from the participant.
+pub fn verify_signature_share<C: Ciphersuite>(
+    sig_share: SignatureShare<C>,
+    signing_package: &SigningPackage<C>,
+    group_pk: GroupPublicKey<C>,
+    verification_share: VerificationShare<C>,
+    generator: Element<C>,
+) -> Result<(), FrostError<C>> {
+    if sig_share.session_id != signing_package.session_id
+        || sig_share.counter != signing_package.counter
+    {
+        return Err(FrostError::UnknownSession(sig_share.session_id));
+    }
+    let statement = ShareStatement::new(
+        sig_share.identifier,
+        signing_package,
+        generator,
+        verification_share,
+        group_pk,
+    )?;
+    if statement.is_satisfied_by(sig_share.z_i) {
+        return Ok(());
+    }
+
+    Err(FrostError::InvalidSignatureShare(Box::new(
+        BlameCertificate {
+            group_pk,
+            generator,
+            verification_share,
+            signing_package: signing_package.clone(),
+            sig_share,
+            attestation: None,
+        },
+    )))
+}
+
+/// A non-interactive proof that a signature share `z_i = d_i + rho_i * e_i + lambda_i * c * x_i`
+/// is consistent with the commitments `D_i = g^{d_i}`, `E_i = g^{e_i}` and the verification share
+/// `Y_i = g^{x_i}` of the signer that produced it.
+///
+/// The proof is a Fiat-Shamir transformed sigma protocol for knowledge of `d_i`, `e_i` and `x_i`
+/// satisfying the linear relation, bound to the encoded share.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ShareProof<C: Ciphersuite = Ed25519Sha512> {
+    /// The Fiat-Shamir challenge.
+    pub challenge: ScalarField<C>,
+    /// Response for the hiding nonce `d_i`.
+    pub hiding_response: ScalarField<C>,
+    /// Response for the binding nonce `e_i`.
+    pub binding_response: ScalarField<C>,
+    /// Response for the secret share `x_i`.
+    pub share_response: ScalarField<C>,
+}
+
+/// The public inputs a `ShareProof` is checked against.
+struct ShareStatement<C: Ciphersuite> {
+    generator: Element<C>,
+    commitments: SigningCommitments<C>,
+    verification_share: Element<C>,
+    binding_factor: ScalarField<C>,
+    /// `lambda_i * c`, the coefficient of the secret share.
+    share_coefficient: ScalarField<C>,
+}
+
+impl<C: Ciphersuite> ShareProof<C> {
+    /// Proves that `sig_share` was computed from the hiding nonce `d`, the binding nonce `e` and
+    /// the secret share `x`.
+    ///
+    /// Returns an error if `signing_package` does not contain the signer's commitments.
+    pub(crate) fn prove<R: Rng>(
+        sig_share: &SignatureShare<C>,
+        signing_package: &SigningPackage<C>,
+        generator: Element<C>,
+        group_pk: GroupPublicKey<C>,
+        nonces: &SigningNonces<C>,
+        x: ScalarField<C>,
+        rng: &mut R,
+    ) -> Result<Self, FrostError<C>> {
+        let statement = ShareStatement::new(
+            sig_share.identifier,
+            signing_package,
+            generator,
+            VerificationShare::new(generator * x),
+            group_pk,
+        )?;
+
+        let (k_d, k_e, k_x) = (
+            ScalarField::<C>::rand(rng),
+            ScalarField::<C>::rand(rng),
+            ScalarField::<C>::rand(rng),
+        );
+        let challenge = statement.challenge(
+            sig_share,
+            [generator * k_d, generator * k_e, generator * k_x],
+            k_d + statement.binding_factor * k_e + statement.share_coefficient * k_x,
+        );
+
+        Ok(ShareProof {
+            challenge,
+            hiding_response: k_d + challenge * nonces.hiding(),
+            binding_response: k_e + challenge * nonces.binding(),
+            share_response: k_x + challenge * x,
+        })
+    }
+
+    /// Returns `true` if the proof shows that `sig_share` is consistent with the commitments in
+    /// `signing_package` and the `verification_share` of the signer that produced it.
+    pub fn verify(
+        &self,
+        sig_share: &SignatureShare<C>,
+        signing_package: &SigningPackage<C>,
+        verification_share: VerificationShare<C>,
+        generator: Element<C>,
+        group_pk: GroupPublicKey<C>,
+    ) -> bool {
+        let Ok(statement) = ShareStatement::new(
+            sig_share.identifier,
+            signing_package,
+            generator,
+            verification_share,
+            group_pk,
+        ) else {
+            return false;
+        };
+
+        // recompute the prover's commitments from the responses
+        let nonce_commitments = [
+            generator * self.hiding_response - statement.commitments.hiding * self.challenge,
+            generator * self.binding_response - statement.commitments.binding * self.challenge,
+            generator * self.share_response - verification_share.to_element() * self.challenge,
+        ];
+        let combined = self.hiding_response
+            + statement.binding_factor * self.binding_response
+            + statement.share_coefficient * self.share_response
+            - self.challenge * sig_share.z_i;
+
+        statement.challenge(sig_share, nonce_commitments, combined) == self.challenge
+    }
+
+    /// Encodes the proof as `challenge || hiding_response || binding_response || share_response`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [
+            self.challenge,
+            self.hiding_response,
+            self.binding_response,
+            self.share_response,
+        ]
+        .iter()
+        .flat_map(serialize_scalar::<C>)
+        .collect()
+    }
+
+    /// Parses a proof encoded by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let mut decoder = Decoder::new(bytes);
+        let proof = ShareProof {
+            challenge: decoder.scalar::<C>()?,
+            hiding_response: decoder.scalar::<C>()?,
+            binding_response: decoder.scalar::<C>()?,
+            share_response: decoder.scalar::<C>()?,
+        };
+        decoder.finish()?;
+        Ok(proof)
+    }
+}
+
+impl<C: Ciphersuite> ShareStatement<C> {
+    fn new(
+        identifier: NonZeroScalar<C>,
+        signing_package: &SigningPackage<C>,
+        generator: Element<C>,
+        verification_share: VerificationShare<C>,
+        group_pk: GroupPublicKey<C>,
+    ) -> Result<Self, FrostError<C>> {
+        let participants = signing_package.participants()?;
+        let commitments = *participants
+            .get(identifier)
+            .ok_or(FrostError::MissingCommitment(identifier.to_scalar()))?;
+        let binding_factors = compute_binding_factors(
+            group_pk.to_element(),
+            &participants,
+            &signing_package.message,
+        );
+        let binding_factor = binding_factors
+            .get(identifier)
+            .ok_or(FrostError::MissingCommitment(identifier.to_scalar()))?;
+        let group_commitment = compute_group_commitment(&participants, &binding_factors)?;
+        let challenge = compute_challenge::<C>(
+            group_commitment,
+            group_pk.to_element(),
+            &signing_package.message,
+        );
+        let lambda = derive_interpolating_value(&participants, identifier)?;
+
+        Ok(ShareStatement {
+            generator,
+            commitments,
+            verification_share: verification_share.to_element(),
+            binding_factor,
+            share_coefficient: lambda * challenge.to_scalar(),
+        })
+    }
+
+    /// Returns `true` if `z_i` is the share the statement calls for, i.e.
+    /// `g^{z_i} == D_i + E_i * rho_i + Y_i * (c * lambda_i)`.
+    fn is_satisfied_by(&self, z_i: ScalarField<C>) -> bool {
+        self.generator * z_i
+            == self.commitments.hiding
+                + self.commitments.binding * self.binding_factor
+                + self.verification_share * self.share_coefficient
+    }
+
+    /// Derives the Fiat-Shamir challenge from the statement, the share and the prover's
+    /// commitments.
+    fn challenge(
+        &self,
+        sig_share: &SignatureShare<C>,
+        nonce_commitments: [Element<C>; 3],
+        combined: ScalarField<C>,
+    ) -> ScalarField<C> {
+        let mut transcript = sig_share.to_bytes();
+        for element in [
+            self.generator,
+            self.commitments.hiding,
+            self.commitments.binding,
+            self.verification_share,
+        ]
+        .iter()
+        .chain(&nonce_commitments)
+        {
+            transcript.extend(C::serialize_element(element));
+        }
+        for scalar in [self.binding_factor, self.share_coefficient, combined] {
+            transcript.extend(serialize_scalar::<C>(&scalar));
+        }
+
+        ScalarField::<C>::from_le_bytes_mod_order(&C::H_share_proof(&transcript))
+    }
+}
+
+#[test]
+fn test_blame_certificate() {
+    use ark_ff::One;
+
+    use crate::{frost::Frost, identity::IdentityKey};
+
+    let mut frost: Frost = Frost::signature_share(2, 3);
+    let signing_package = frost.signing_package(b"testing");
+
+    let sig_share = frost.signers[0].sign(&signing_package).unwrap();
+    let signer = &frost.signers[0];
+    let identifier = NonZeroScalar::new(signer.get_identifier()).unwrap();
+    let verify = |share| {
+        verify_signature_share(
+            share,
+            &signing_package,
+            frost.group_pk,
+            signer.get_verification_share(),
+            frost.generator,
+        )
+    };
+
+    assert!(verify(sig_share).is_ok());
+
+    let wrong_share = SignatureShare {
+        z_i: sig_share.z_i + ScalarField::<Ed25519Sha512>::one(),
+        ..sig_share
+    };
+    let certificate = match verify(wrong_share) {
+        Err(FrostError::InvalidSignatureShare(certificate)) => *certificate,
+        result => panic!("unexpected result {result:?}"),
+    };
+    assert_eq!(certificate.identifier(), identifier);
+    assert!(certificate.verify());
+
+    // the recorded values are recomputed, so an honest share cannot be blamed in its own package
+    let mut framed = certificate.clone();
+    framed.sig_share = sig_share;
+    assert!(!framed.verify());
+
+    // the blame is attributed to the signer once it is bound to the signer's signature
+    let mut rng = ark_std::test_rng();
+    let identity_key = IdentityKey::generate(frost.generator, &mut rng);
+    let mut roster = IdentityRoster::new(frost.generator);
+    roster
+        .insert(identifier, identity_key.public_key())
+        .unwrap();
+    assert!(!certificate.verify_attributed(&roster));
+    let attestation = attest_share(&wrong_share, &signing_package, &identity_key, &mut rng);
+    assert!(
+        certificate
+            .clone()
+            .attest(attestation)
+            .verify_attributed(&roster)
+    );
+
+    // but not once the coordinator swaps in a package the signer did not answer
+    let attestation = attest_share(&sig_share, &signing_package, &identity_key, &mut rng);
+    let mut framed = framed.attest(attestation);
+    framed.signing_package.message = b"something else".to_vec();
+    assert!(framed.verify());
+    assert!(!framed.verify_attributed(&roster));
+}
+
+#[test]
+fn test_share_proof() {
+    use crate::frost::Frost;
+
+    let mut frost: Frost = Frost::signature_share(2, 3);
+    let signing_package = frost.signing_package(b"testing");
+    let mut rng = ark_std::test_rng();
+
+    // the blind signer proves its share too
+    let (generator, group_pk) = (frost.generator, frost.group_pk);
+    for signer in frost.signers_mut() {
+        let (sig_share, proof) = signer.sign_with_proof(&signing_package, &mut rng).unwrap();
+        let proof = ShareProof::from_bytes(&proof.to_bytes()).unwrap();
+        let verify = |sig_share: &SignatureShare| {
+            proof.verify(
+                sig_share,
+                &signing_package,
+                signer.get_verification_share(),
+                generator,
+                group_pk,
+            )
+        };
+        assert!(verify(&sig_share));
+
+        let wrong_share = SignatureShare {
+            z_i: sig_share.z_i + ark_ed25519::Fr::from(1u64),
+            ..sig_share
+        };
+        assert!(!verify(&wrong_share));
+    }
+}
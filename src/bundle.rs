@@ -0,0 +1,368 @@
+//! This module implements signed message bundles and detached signatures.
+//!
+//! A `SignedMessage` is an attached signature: it packs a message the group signed together with
+//! the signature and the hash of the `GroupConfig` of the group, so that a relying party can
+//! verify an artifact produced by the group with `verify_bundle` and nothing but the group
+//! descriptor: the hash tells it which group the bundle claims to come from, and the descriptor
+//! holds the key and generator to check the signature with.
+//!
+//! A `DetachedSignature` carries the same hash and signature without the message, for documents
+//! that are stored or distributed on their own. `verify_detached` checks it against a message in
+//! memory, and a `DetachedVerifier` against a message read in chunks, e.g. a large file, which is
+//! hashed as it is read and never held in memory as a whole.
+use std::io::{self, Read};
+
+use ark_ff::PrimeField;
+use ark_serialize::SerializationError;
+use sha2::Digest;
+
+use crate::{
+    ciphersuite::{Ciphersuite, Ed25519Sha512, Element, ScalarField},
+    config::GroupConfig,
+    error::FrostError,
+    helper::Decoder,
+    schnorr::SchnorrSignature,
+};
+
+/// Size of the chunks `verify_detached_reader` reads the message in.
+const CHUNK_SIZE: usize = 8192;
+
+/// A message together with the signature of the group over it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedMessage<C: Ciphersuite = Ed25519Sha512> {
+    /// The hash of the descriptor of the signing group, as by `GroupConfig::hash`.
+    pub group_config_hash: [u8; 32],
+    pub message: Vec<u8>,
+    pub signature: SchnorrSignature<C>,
+}
+
+impl<C: Ciphersuite> SignedMessage<C> {
+    /// Bundles `message` with the `signature` of the group described by `config`.
+    pub fn new(config: &GroupConfig<C>, message: &[u8], signature: SchnorrSignature<C>) -> Self {
+        SignedMessage {
+            group_config_hash: config.hash(),
+            message: message.to_vec(),
+            signature,
+        }
+    }
+
+    /// Encodes the bundle as `group_config_hash || len(message) || message || signature`, where
+    /// the length is a little-endian `u32`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the message is longer than `u32::MAX` bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let message_len = u32::try_from(self.message.len()).expect("message too long");
+        let mut bytes = self.group_config_hash.to_vec();
+        bytes.extend_from_slice(&message_len.to_le_bytes());
+        bytes.extend_from_slice(&self.message);
+        bytes.extend(self.signature.to_bytes());
+        bytes
+    }
+
+    /// Parses a bundle encoded by `to_bytes`. The bundle is not verified.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let mut decoder = Decoder::new(bytes);
+        let group_config_hash = decoder.array()?;
+        let message_len = decoder.u32()? as usize;
+        let message = decoder.take(message_len)?.to_vec();
+        let signature = SchnorrSignature::from_bytes(decoder.rest())?;
+        Ok(SignedMessage {
+            group_config_hash,
+            message,
+            signature,
+        })
+    }
+
+    /// Splits the bundle into the message and its detached signature.
+    pub fn detach(self) -> (Vec<u8>, DetachedSignature<C>) {
+        let signature = DetachedSignature {
+            group_config_hash: self.group_config_hash,
+            signature: self.signature,
+        };
+        (self.message, signature)
+    }
+}
+
+/// The signature of the group over a message that is kept separately.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetachedSignature<C: Ciphersuite = Ed25519Sha512> {
+    /// The hash of the descriptor of the signing group, as by `GroupConfig::hash`.
+    pub group_config_hash: [u8; 32],
+    pub signature: SchnorrSignature<C>,
+}
+
+impl<C: Ciphersuite> DetachedSignature<C> {
+    /// Wraps the `signature` of the group described by `config`.
+    pub fn new(config: &GroupConfig<C>, signature: SchnorrSignature<C>) -> Self {
+        DetachedSignature {
+            group_config_hash: config.hash(),
+            signature,
+        }
+    }
+
+    /// Bundles the signature with the `message` it signs.
+    pub fn attach(self, message: &[u8]) -> SignedMessage<C> {
+        SignedMessage {
+            group_config_hash: self.group_config_hash,
+            message: message.to_vec(),
+            signature: self.signature,
+        }
+    }
+
+    /// Encodes the signature as `group_config_hash || signature`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [self.group_config_hash.to_vec(), self.signature.to_bytes()].concat()
+    }
+
+    /// Parses a signature encoded by `to_bytes`. The signature is not verified.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let mut decoder = Decoder::new(bytes);
+        let group_config_hash = decoder.array()?;
+        let signature = SchnorrSignature::from_bytes(decoder.rest())?;
+        Ok(DetachedSignature {
+            group_config_hash,
+            signature,
+        })
+    }
+}
+
+/// Verifies a `DetachedSignature` against a message fed to it in chunks.
+#[derive(Debug, Clone)]
+pub struct DetachedVerifier<C: Ciphersuite = Ed25519Sha512> {
+    signature: SchnorrSignature<C>,
+    group_pk: Element<C>,
+    generator: Element<C>,
+    hasher: C::Hash,
+}
+
+impl<C: Ciphersuite> DetachedVerifier<C> {
+    /// Starts verifying `signature` as a signature of the group described by `config`.
+    ///
+    /// Returns an error if the signature was produced by another group.
+    pub fn new(
+        signature: &DetachedSignature<C>,
+        config: &GroupConfig<C>,
+    ) -> Result<Self, FrostError<C>> {
+        if signature.group_config_hash != config.hash() {
+            return Err(FrostError::InvalidBundle(
+                "the signature was produced by another group".to_string(),
+            ));
+        }
+        let group_pk = config.group_pk.to_element();
+        // the challenge is H2(R || group_pk || message), as in `compute_challenge`
+        let mut hasher = C::H2_hasher();
+        hasher.update(C::serialize_element(&signature.signature.R));
+        hasher.update(C::serialize_element(&group_pk));
+        Ok(DetachedVerifier {
+            signature: signature.signature,
+            group_pk,
+            generator: config.generator(),
+            hasher,
+        })
+    }
+
+    /// Feeds the next chunk of the message.
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.hasher.update(chunk);
+    }
+
+    /// Checks the signature over the message fed so far.
+    pub fn finish(self) -> Result<(), FrostError<C>> {
+        let challenge = ScalarField::<C>::from_le_bytes_mod_order(&self.hasher.finalize());
+        if self.generator * self.signature.s != self.signature.R + self.group_pk * challenge {
+            return Err(FrostError::InvalidBundle(
+                "the signature does not verify under the group public key".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl<C: Ciphersuite> io::Write for DetachedVerifier<C> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Verifies that `bundle` was produced by the group described by `config`: that the bundle names
+/// this group, and that its signature over the message verifies under the group public key.
+pub fn verify_bundle<C: Ciphersuite>(
+    bundle: &SignedMessage<C>,
+    config: &GroupConfig<C>,
+) -> Result<(), FrostError<C>> {
+    let signature = DetachedSignature {
+        group_config_hash: bundle.group_config_hash,
+        signature: bundle.signature,
+    };
+    verify_detached(&signature, &bundle.message, config)
+}
+
+/// Verifies that `signature` is the signature of the group described by `config` over `message`.
+pub fn verify_detached<C: Ciphersuite>(
+    signature: &DetachedSignature<C>,
+    message: &[u8],
+    config: &GroupConfig<C>,
+) -> Result<(), FrostError<C>> {
+    let mut verifier = DetachedVerifier::new(signature, config)?;
+    verifier.update(message);
+    verifier.finish()
+}
+
+/// Verifies `signature` like `verify_detached`, over the message read from `reader` until its
+/// end.
+pub fn verify_detached_reader<C: Ciphersuite, R: Read>(
+    signature: &DetachedSignature<C>,
+    mut reader: R,
+    config: &GroupConfig<C>,
+) -> Result<(), FrostError<C>> {
+    let mut verifier = DetachedVerifier::new(signature, config)?;
+    let mut chunk = vec![0; CHUNK_SIZE];
+    loop {
+        match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => verifier.update(&chunk[..read]),
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => {
+                return Err(FrostError::InvalidBundle(format!(
+                    "the message could not be read: {error}"
+                )));
+            }
+        }
+    }
+    verifier.finish()
+}
+
+#[test]
+fn test_signed_message_bundle() {
+    use crate::{
+        frost::Frost,
+        helper::NonZeroScalar,
+        identity::IdentityKey,
+        roster::{Member, MemberRole, Roster},
+    };
+
+    let mut rng = ark_std::test_rng();
+    let mut frost: Frost = Frost::signature_share(2, 3);
+    let mut roster = Roster::new(frost.generator);
+    for (position, signer) in frost.signers().enumerate() {
+        roster
+            .add(Member {
+                identifier: NonZeroScalar::new(signer.get_identifier()).unwrap(),
+                identity_key: IdentityKey::<Ed25519Sha512>::generate(frost.generator, &mut rng)
+                    .public_key(),
+                role: MemberRole::Signer(signer.role()),
+                endpoint: format!("signer-{}.example:7000", position + 1),
+            })
+            .unwrap();
+    }
+    let config = GroupConfig::new(frost.group_pk, frost.threshold, roster.clone()).unwrap();
+
+    let signing_package = frost.signing_package(b"release v1.0");
+    let sig_shares: Vec<_> = frost
+        .signers_mut()
+        .map(|signer| signer.sign(&signing_package).unwrap())
+        .collect();
+    let (group_commitment, _) = signing_package
+        .group_commitment_and_challenge(frost.group_pk)
+        .unwrap();
+    let signature = SchnorrSignature {
+        R: group_commitment,
+        s: frost.signature_aggregate(sig_shares),
+    };
+
+    let bundle = SignedMessage::new(&config, b"release v1.0", signature);
+    verify_bundle(&bundle, &config).unwrap();
+    let decoded = SignedMessage::from_bytes(&bundle.to_bytes()).unwrap();
+    assert_eq!(decoded, bundle);
+
+    // a tampered message no longer verifies
+    let mut tampered = bundle.clone();
+    tampered.message = b"release v6.6".to_vec();
+    assert!(matches!(
+        verify_bundle(&tampered, &config),
+        Err(FrostError::InvalidBundle(_))
+    ));
+
+    // a bundle is only accepted with the descriptor of its own group
+    let other = GroupConfig::new(frost.group_pk, 3, roster).unwrap();
+    assert!(matches!(
+        verify_bundle(&bundle, &other),
+        Err(FrostError::InvalidBundle(_))
+    ));
+
+    assert!(SignedMessage::<Ed25519Sha512>::from_bytes(&bundle.to_bytes()[..40]).is_err());
+}
+
+#[test]
+fn test_detached_signature() {
+    use crate::{
+        frost::Frost,
+        helper::NonZeroScalar,
+        identity::IdentityKey,
+        roster::{Member, MemberRole, Roster},
+    };
+
+    let mut rng = ark_std::test_rng();
+    let mut frost: Frost = Frost::signature_share(2, 3);
+    let mut roster = Roster::new(frost.generator);
+    for (position, signer) in frost.signers().enumerate() {
+        roster
+            .add(Member {
+                identifier: NonZeroScalar::new(signer.get_identifier()).unwrap(),
+                identity_key: IdentityKey::<Ed25519Sha512>::generate(frost.generator, &mut rng)
+                    .public_key(),
+                role: MemberRole::Signer(signer.role()),
+                endpoint: format!("signer-{}.example:7000", position + 1),
+            })
+            .unwrap();
+    }
+    let config = GroupConfig::new(frost.group_pk, frost.threshold, roster.clone()).unwrap();
+
+    // a document spanning several chunks
+    let document: Vec<u8> = (0..3 * CHUNK_SIZE + 17).map(|i| i as u8).collect();
+    let signing_package = frost.signing_package(&document);
+    let sig_shares: Vec<_> = frost
+        .signers_mut()
+        .map(|signer| signer.sign(&signing_package).unwrap())
+        .collect();
+    let (group_commitment, _) = signing_package
+        .group_commitment_and_challenge(frost.group_pk)
+        .unwrap();
+    let signature = SchnorrSignature {
+        R: group_commitment,
+        s: frost.signature_aggregate(sig_shares),
+    };
+
+    let detached = DetachedSignature::new(&config, signature);
+    verify_detached(&detached, &document, &config).unwrap();
+    verify_detached_reader(&detached, &document[..], &config).unwrap();
+    let mut verifier = DetachedVerifier::new(&detached, &config).unwrap();
+    io::copy(&mut &document[..], &mut verifier).unwrap();
+    verifier.finish().unwrap();
+    assert_eq!(
+        DetachedSignature::from_bytes(&detached.to_bytes()).unwrap(),
+        detached
+    );
+
+    // attaching and detaching the message round-trips
+    let bundle = detached.clone().attach(&document);
+    verify_bundle(&bundle, &config).unwrap();
+    assert_eq!(bundle.detach(), (document.clone(), detached.clone()));
+
+    // a truncated document no longer verifies
+    assert!(matches!(
+        verify_detached_reader(&detached, &document[..document.len() - 1], &config),
+        Err(FrostError::InvalidBundle(_))
+    ));
+
+    // the signature is only accepted with the descriptor of its own group
+    let other = GroupConfig::new(frost.group_pk, 3, roster).unwrap();
+    assert!(DetachedVerifier::new(&detached, &other).is_err());
+}
@@ -0,0 +1,237 @@
+//! This module implements group certificates.
+//!
+//! A `GroupCertificate` attests to the parameters of a group: its public key and generator, its
+//! threshold, the hash of its roster, its ciphersuite and a validity period. The group signs the
+//! certificate itself, in an ordinary signing session over `CertificateBody::to_bytes`, so a
+//! relying party that checks the certificate before trusting signatures from the group knows
+//! that a threshold of the signers agreed to these parameters, and until when.
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use ark_serialize::SerializationError;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    ciphersuite::{Ciphersuite, Ed25519Sha512, Element},
+    error::FrostError,
+    helper::{Decoder, compute_challenge},
+    keys::GroupPublicKey,
+    roster::Roster,
+    schnorr::SchnorrSignature,
+};
+
+/// Domain separator prefixed to the encoding of the certified parameters.
+const CERTIFICATE_CONTEXT: &[u8] = b"FROST-GROUP-CERTIFICATE-v1";
+
+/// The parameters of a group attested to by a `GroupCertificate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertificateBody<C: Ciphersuite = Ed25519Sha512> {
+    pub group_pk: GroupPublicKey<C>,
+    /// The generator `g` of the group.
+    pub generator: Element<C>,
+    /// Number of signers required to sign.
+    pub threshold: u32,
+    /// SHA-256 hash of the encoding of the roster.
+    pub roster_hash: [u8; 32],
+    /// The context string of the ciphersuite.
+    pub ciphersuite: String,
+    /// Start of the validity period, in seconds since the Unix epoch.
+    pub not_before: u64,
+    /// End of the validity period, in seconds since the Unix epoch.
+    pub not_after: u64,
+}
+
+impl<C: Ciphersuite> CertificateBody<C> {
+    /// Describes the group with `roster`, valid for `validity` from now.
+    pub fn new(
+        group_pk: GroupPublicKey<C>,
+        generator: Element<C>,
+        threshold: u32,
+        roster: &Roster<C>,
+        validity: Duration,
+    ) -> Self {
+        let not_before = unix_seconds(SystemTime::now());
+        CertificateBody {
+            group_pk,
+            generator,
+            threshold,
+            roster_hash: roster_hash(roster),
+            ciphersuite: C::CONTEXT_STRING.to_string(),
+            not_before,
+            not_after: not_before.saturating_add(validity.as_secs()),
+        }
+    }
+
+    /// Encodes the body as the message the group signs: the context string, `group_pk ||
+    /// generator || threshold || roster_hash || len(ciphersuite) || ciphersuite || not_before ||
+    /// not_after`, with integers in little-endian.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the ciphersuite is longer than `u32::MAX` bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let ciphersuite_len = u32::try_from(self.ciphersuite.len()).expect("ciphersuite too long");
+        let mut bytes = CERTIFICATE_CONTEXT.to_vec();
+        bytes.extend(C::serialize_element(&self.group_pk.to_element()));
+        bytes.extend(C::serialize_element(&self.generator));
+        bytes.extend_from_slice(&self.threshold.to_le_bytes());
+        bytes.extend_from_slice(&self.roster_hash);
+        bytes.extend_from_slice(&ciphersuite_len.to_le_bytes());
+        bytes.extend_from_slice(self.ciphersuite.as_bytes());
+        bytes.extend_from_slice(&self.not_before.to_le_bytes());
+        bytes.extend_from_slice(&self.not_after.to_le_bytes());
+        bytes
+    }
+
+    /// Parses a body encoded by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let mut decoder = Decoder::new(bytes);
+        let body = Self::decode(&mut decoder)?;
+        decoder.finish()?;
+        Ok(body)
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, SerializationError> {
+        if decoder.take(CERTIFICATE_CONTEXT.len())? != CERTIFICATE_CONTEXT {
+            return Err(SerializationError::InvalidData);
+        }
+        let group_pk = GroupPublicKey::new(decoder.element::<C>()?);
+        let generator = decoder.element::<C>()?;
+        let threshold = decoder.u32()?;
+        let roster_hash = decoder.array()?;
+        let ciphersuite_len = decoder.u32()? as usize;
+        let ciphersuite = String::from_utf8(decoder.take(ciphersuite_len)?.to_vec())
+            .map_err(|_| SerializationError::InvalidData)?;
+        Ok(CertificateBody {
+            group_pk,
+            generator,
+            threshold,
+            roster_hash,
+            ciphersuite,
+            not_before: decoder.u64()?,
+            not_after: decoder.u64()?,
+        })
+    }
+}
+
+/// A `CertificateBody` signed by the group it describes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupCertificate<C: Ciphersuite = Ed25519Sha512> {
+    pub body: CertificateBody<C>,
+    pub signature: SchnorrSignature<C>,
+}
+
+impl<C: Ciphersuite> GroupCertificate<C> {
+    /// Issues the certificate from `body` and the group's `signature` over `body.to_bytes()`.
+    ///
+    /// Returns an error if the signature does not verify under the group public key of `body`.
+    pub fn issue(
+        body: CertificateBody<C>,
+        signature: SchnorrSignature<C>,
+    ) -> Result<Self, FrostError<C>> {
+        let certificate = GroupCertificate { body, signature };
+        if !certificate.signature_verifies() {
+            return Err(FrostError::InvalidCertificate(
+                "the signature does not verify under the group public key".to_string(),
+            ));
+        }
+        Ok(certificate)
+    }
+
+    /// Verifies the certificate at time `now`: that it is for the ciphersuite `C`, that `now`
+    /// falls within its validity period and that the group signed it.
+    pub fn verify(&self, now: SystemTime) -> Result<(), FrostError<C>> {
+        let invalid = |reason: &str| Err(FrostError::InvalidCertificate(reason.to_string()));
+        if self.body.ciphersuite != C::CONTEXT_STRING {
+            return invalid("the certificate is for another ciphersuite");
+        }
+        let now = unix_seconds(now);
+        if now < self.body.not_before {
+            return invalid("the certificate is not valid yet");
+        }
+        if now > self.body.not_after {
+            return invalid("the certificate has expired");
+        }
+        if !self.signature_verifies() {
+            return invalid("the signature does not verify under the group public key");
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the certificate attests to `roster`.
+    pub fn certifies_roster(&self, roster: &Roster<C>) -> bool {
+        self.body.roster_hash == roster_hash(roster)
+    }
+
+    /// Encodes the certificate as `body || signature`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [self.body.to_bytes(), self.signature.to_bytes()].concat()
+    }
+
+    /// Parses a certificate encoded by `to_bytes`. The certificate is not verified.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let mut decoder = Decoder::new(bytes);
+        let body = CertificateBody::decode(&mut decoder)?;
+        let signature = SchnorrSignature::from_bytes(decoder.rest())?;
+        Ok(GroupCertificate { body, signature })
+    }
+
+    fn signature_verifies(&self) -> bool {
+        let group_pk = self.body.group_pk.to_element();
+        let challenge = compute_challenge::<C>(self.signature.R, group_pk, &self.body.to_bytes());
+        self.body.generator * self.signature.s
+            == self.signature.R + group_pk * challenge.to_scalar()
+    }
+}
+
+fn roster_hash<C: Ciphersuite>(roster: &Roster<C>) -> [u8; 32] {
+    Sha256::digest(roster.to_bytes()).into()
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[test]
+fn test_group_certificate() {
+    use crate::frost::Frost;
+
+    let mut frost: Frost = Frost::signature_share(2, 3);
+    let roster = Roster::new(frost.generator);
+    let body = CertificateBody::new(
+        frost.group_pk,
+        frost.generator,
+        2,
+        &roster,
+        Duration::from_secs(3600),
+    );
+
+    // the group signs the certificate in an ordinary session
+    let signing_package = frost.signing_package(&body.to_bytes());
+    let sig_shares: Vec<_> = frost
+        .signers_mut()
+        .map(|signer| signer.sign(&signing_package).unwrap())
+        .collect();
+    let (group_commitment, _) = signing_package
+        .group_commitment_and_challenge(frost.group_pk)
+        .unwrap();
+    let signature = SchnorrSignature {
+        R: group_commitment,
+        s: frost.signature_aggregate(sig_shares),
+    };
+
+    let certificate = GroupCertificate::issue(body.clone(), signature).unwrap();
+    certificate.verify(SystemTime::now()).unwrap();
+    assert!(certificate.certifies_roster(&roster));
+    let decoded = GroupCertificate::from_bytes(&certificate.to_bytes()).unwrap();
+    assert_eq!(decoded, certificate);
+
+    assert!(
+        certificate
+            .verify(SystemTime::now() + Duration::from_secs(7200))
+            .is_err()
+    );
+    let mut extended = certificate.clone();
+    extended.body.not_after += 3600;
+    assert!(extended.verify(SystemTime::now()).is_err());
+}
@@ -1,61 +1,260 @@
 //! This module implements the hash functions corresponding to the ciphersuite FROST(Ed25519, SHA-512).
 //!
 //! Source: https://www.rfc-editor.org/rfc/rfc9591.html#name-frosted25519-sha-512
+//!
+//! Custom (non-RFC) ciphersuites can be defined by implementing `Ciphersuite` with a different
+//! hash function, e.g. `sha3::Sha3_512` or `blake3::Hasher` (with its `traits-preview` feature),
+//! and any arkworks curve, e.g. BLS12-381 G1 or Jubjub, together with a context string that
+//! encodes that choice.
 #![allow(non_snake_case)]
 
+use std::fmt::Debug;
+
+use ark_ec::{CurveGroup, PrimeGroup, twisted_edwards::Affine};
+use ark_ed25519::{EdwardsConfig, Fq};
+use ark_ff::{BigInteger, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
 use sha2::{Digest, Sha512};
 
 pub const CONTEXT_STRING: &str = "FROST-ED25519-SHA512-v1";
 
-pub fn H1(m: Vec<u8>) -> Vec<u8> {
-    let mut hasher = Sha512::new();
+/// A FROST ciphersuite, i.e. a choice of prime-order group, hash function and the context string
+/// used for domain separation of H1, H3, H4 and H5.
+pub trait Ciphersuite: Copy + Clone + Debug + PartialEq + Eq + 'static {
+    /// Identifies the ciphersuite, including its hash function, e.g. `FROST-ED25519-SHA3-512-v1`.
+    const CONTEXT_STRING: &'static str;
 
-    hasher.update(CONTEXT_STRING);
-    hasher.update(b"rho");
-    hasher.update(m);
+    /// The prime-order group signatures are produced in.
+    type Group: CurveGroup;
 
-    let output = hasher.finalize();
-    output.to_vec()
+    /// The underlying hash function.
+    type Hash: Digest;
+
+    fn H1(m: &[u8]) -> Vec<u8> {
+        hash_with_tag::<Self::Hash>(Self::CONTEXT_STRING, b"rho", m)
+    }
+
+    fn H2(m: &[u8]) -> Vec<u8> {
+        let mut hasher = Self::H2_hasher();
+
+        hasher.update(m);
+
+        let output = hasher.finalize();
+        output.to_vec()
+    }
+
+    /// Not part of the RFC: returns a hasher that computes `H2(m)` when fed `m` and finalized, to
+    /// hash challenges over messages streamed in chunks. Ciphersuites that override `H2` must
+    /// override this consistently.
+    fn H2_hasher() -> Self::Hash {
+        Self::Hash::new()
+    }
+
+    fn H3(m: &[u8]) -> Vec<u8> {
+        hash_with_tag::<Self::Hash>(Self::CONTEXT_STRING, b"nonce", m)
+    }
+
+    fn H4(m: &[u8]) -> Vec<u8> {
+        hash_with_tag::<Self::Hash>(Self::CONTEXT_STRING, b"msg", m)
+    }
+
+    fn H5(m: &[u8]) -> Vec<u8> {
+        hash_with_tag::<Self::Hash>(Self::CONTEXT_STRING, b"com", m)
+    }
+
+    /// Not part of the RFC: hashes the input of a digest-only signing request.
+    fn H_digest(m: &[u8]) -> Vec<u8> {
+        hash_with_tag::<Self::Hash>(Self::CONTEXT_STRING, b"digest", m)
+    }
+
+    /// Not part of the RFC: hashes the transcript of a `ShareProof`.
+    fn H_share_proof(m: &[u8]) -> Vec<u8> {
+        hash_with_tag::<Self::Hash>(Self::CONTEXT_STRING, b"share-proof", m)
+    }
+
+    /// Not part of the RFC: hashes the transcript of a `CommitmentProof`.
+    fn H_commitment_proof(m: &[u8]) -> Vec<u8> {
+        hash_with_tag::<Self::Hash>(Self::CONTEXT_STRING, b"com-proof", m)
+    }
+
+    /// Not part of the RFC: ranks the participants of a session for leader election.
+    fn H_leader(m: &[u8]) -> Vec<u8> {
+        hash_with_tag::<Self::Hash>(Self::CONTEXT_STRING, b"leader", m)
+    }
+
+    /// Not part of the RFC: derives the tweak and chain code of a child key in `derivation`.
+    fn H_derive(m: &[u8]) -> Vec<u8> {
+        hash_with_tag::<Self::Hash>(Self::CONTEXT_STRING, b"derive", m)
+    }
+
+    /// Not part of the RFC: maps a human-meaningful name to the identifier of a participant.
+    fn H_identifier(m: &[u8]) -> Vec<u8> {
+        hash_with_tag::<Self::Hash>(Self::CONTEXT_STRING, b"id", m)
+    }
+
+    /// Encodes an `Element` of the group. Defaults to arkworks' compressed serialization.
+    ///
+    /// # Panics
+    ///
+    /// Panics if serialization fails.
+    fn serialize_element(element: &Self::Group) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        element
+            .serialize_compressed(&mut bytes)
+            .expect("serialization failed");
+        bytes
+    }
+
+    /// Appends the encoding of `serialize_element` to `bytes`. Defaults to arkworks' compressed
+    /// serialization, written in place.
+    ///
+    /// # Panics
+    ///
+    /// Panics if serialization fails.
+    fn write_element(element: &Self::Group, bytes: &mut Vec<u8>) {
+        element
+            .serialize_compressed(bytes)
+            .expect("serialization failed");
+    }
+
+    /// Decodes an `Element` of the group, rejecting points outside the prime-order subgroup.
+    fn deserialize_element(bytes: &[u8]) -> Result<Self::Group, SerializationError> {
+        Self::Group::deserialize_compressed(bytes)
+    }
 }
 
-pub fn H2(m: Vec<u8>) -> Vec<u8> {
-    let mut hasher = Sha512::new();
+/// An element of the group of the ciphersuite `C`.
+pub type Element<C> = <C as Ciphersuite>::Group;
 
-    hasher.update(m);
+/// A scalar of the group of the ciphersuite `C`.
+pub type ScalarField<C> = <<C as Ciphersuite>::Group as PrimeGroup>::ScalarField;
 
-    let output = hasher.finalize();
-    output.to_vec()
+/// The FROST(Ed25519, SHA-512) ciphersuite from the RFC.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Ed25519Sha512;
+
+impl Ciphersuite for Ed25519Sha512 {
+    const CONTEXT_STRING: &'static str = CONTEXT_STRING;
+    type Group = ark_ed25519::EdwardsProjective;
+    type Hash = Sha512;
+
+    /// Encodes a point as in RFC 8032: the little-endian `y` coordinate, with the most significant
+    /// bit set to the least significant bit of `x`.
+    fn serialize_element(element: &Self::Group) -> Vec<u8> {
+        let point = element.into_affine();
+        let mut bytes = point.y.into_bigint().to_bytes_le();
+        if point.x.into_bigint().is_odd() {
+            bytes[31] |= 0x80;
+        }
+        bytes
+    }
+
+    fn write_element(element: &Self::Group, bytes: &mut Vec<u8>) {
+        let point = element.into_affine();
+        let start = bytes.len();
+        point
+            .y
+            .serialize_compressed(&mut *bytes)
+            .expect("serialization failed");
+        if point.x.into_bigint().is_odd() {
+            bytes[start + 31] |= 0x80;
+        }
+    }
+
+    /// Decodes a point encoded as in RFC 8032, rejecting non-canonical encodings and points
+    /// outside the prime-order subgroup.
+    fn deserialize_element(bytes: &[u8]) -> Result<Self::Group, SerializationError> {
+        if bytes.len() != 32 {
+            return Err(SerializationError::InvalidData);
+        }
+        let x_is_odd = bytes[31] & 0x80 != 0;
+        let mut y_bytes = bytes.to_vec();
+        y_bytes[31] &= 0x7f;
+
+        let y = Fq::deserialize_compressed(&y_bytes[..])?;
+        let (x, neg_x) = Affine::<EdwardsConfig>::get_xs_from_y_unchecked(y)
+            .ok_or(SerializationError::InvalidData)?;
+        let x = if x.into_bigint().is_odd() == x_is_odd {
+            x
+        } else {
+            neg_x
+        };
+        if x.into_bigint().is_odd() != x_is_odd {
+            // x is zero, which has no odd representative
+            return Err(SerializationError::InvalidData);
+        }
+
+        let point = Affine::<EdwardsConfig>::new_unchecked(x, y);
+        if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(point.into())
+    }
 }
 
-pub fn H3(m: Vec<u8>) -> Vec<u8> {
-    let mut hasher = Sha512::new();
+fn hash_with_tag<D: Digest>(context_string: &str, tag: &[u8], m: &[u8]) -> Vec<u8> {
+    let mut hasher = D::new();
 
-    hasher.update(CONTEXT_STRING);
-    hasher.update(b"nonce");
+    hasher.update(context_string);
+    hasher.update(tag);
     hasher.update(m);
 
     let output = hasher.finalize();
     output.to_vec()
 }
 
-pub fn H4(m: Vec<u8>) -> Vec<u8> {
-    let mut hasher = Sha512::new();
+pub fn H1(m: &[u8]) -> Vec<u8> {
+    Ed25519Sha512::H1(m)
+}
 
-    hasher.update(CONTEXT_STRING);
-    hasher.update(b"msg");
-    hasher.update(m);
+pub fn H2(m: &[u8]) -> Vec<u8> {
+    Ed25519Sha512::H2(m)
+}
 
-    let output = hasher.finalize();
-    output.to_vec()
+pub fn H3(m: &[u8]) -> Vec<u8> {
+    Ed25519Sha512::H3(m)
 }
 
-pub fn H5(m: Vec<u8>) -> Vec<u8> {
-    let mut hasher = Sha512::new();
+pub fn H4(m: &[u8]) -> Vec<u8> {
+    Ed25519Sha512::H4(m)
+}
 
-    hasher.update(CONTEXT_STRING);
-    hasher.update(b"com");
-    hasher.update(m);
+pub fn H5(m: &[u8]) -> Vec<u8> {
+    Ed25519Sha512::H5(m)
+}
 
-    let output = hasher.finalize();
-    output.to_vec()
+#[test]
+fn test_custom_ciphersuite_domain_separation() {
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    struct Research;
+    impl Ciphersuite for Research {
+        const CONTEXT_STRING: &'static str = "FROST-ED25519-SHA512-RESEARCH-v1";
+        type Group = ark_ed25519::EdwardsProjective;
+        type Hash = Sha512;
+    }
+
+    let m = b"testing";
+    assert_ne!(Research::H1(m), H1(m));
+    assert_eq!(Research::H2(m), H2(m));
+}
+
+#[test]
+fn test_ed25519_element_encoding() {
+    use ark_ff::UniformRand;
+
+    let mut rng = ark_std::test_rng();
+    for _ in 0..8 {
+        let element = ark_ed25519::EdwardsProjective::rand(&mut rng);
+        let bytes = Ed25519Sha512::serialize_element(&element);
+        assert_eq!(Ed25519Sha512::deserialize_element(&bytes).unwrap(), element);
+        let mut written = vec![0xff];
+        Ed25519Sha512::write_element(&element, &mut written);
+        assert_eq!(written[1..], bytes);
+    }
+
+    // the RFC 8032 base point
+    let base_point = <ark_ed25519::EdwardsProjective as PrimeGroup>::generator();
+    let mut expected = [0x66u8; 32];
+    expected[0] = 0x58;
+    assert_eq!(Ed25519Sha512::serialize_element(&base_point), expected);
 }
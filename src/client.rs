@@ -0,0 +1,381 @@
+//! This module implements the client a user wallet embeds to obtain blind co-signatures.
+//!
+//! `CustodianClient` speaks the line-based protocol served by the `custodian` module, and
+//! `co_sign` runs a complete signing session between the wallet's own key packages and the
+//! custodian, returning the final signature. `co_sign_from_batch` does the same with a
+//! commitment taken from a `NonceBatch` the custodian published earlier.
+#![allow(non_snake_case)]
+
+use std::{
+    fmt,
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpStream, ToSocketAddrs},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    batch::NonceBatch,
+    ciphersuite::{Ciphersuite, Ed25519Sha512},
+    error::FrostError,
+    frost::{FrostSigner, SessionId, SignatureShare, SigningPackage},
+    helper::{decode_hex, encode_hex, message_digest, structured_digest},
+    keys::{GroupId, KeyPackage},
+    nonces::SigningCommitments,
+    schnorr::SchnorrSignature,
+    socks::Socks5Proxy,
+    timelock::Intent,
+};
+
+/// Errors returned while requesting a co-signature from a custodian.
+#[derive(Debug)]
+pub enum ClientError<C: Ciphersuite = Ed25519Sha512> {
+    /// The connection to the custodian failed.
+    Io(io::Error),
+    /// The custodian refused the request, with its reason.
+    Rejected(String),
+    /// The custodian's response could not be decoded.
+    MalformedResponse,
+    /// No key packages were given to sign with.
+    NoKeyPackages,
+    /// The nonce batch has no commitments left.
+    BatchExhausted,
+    /// A local signer refused to sign.
+    Signing(FrostError<C>),
+    /// The aggregated signature does not verify under the group public key.
+    InvalidSignature,
+}
+
+impl<C: Ciphersuite> fmt::Display for ClientError<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Io(error) => write!(f, "connection to the custodian failed: {error}"),
+            ClientError::Rejected(reason) => write!(f, "custodian refused the request: {reason}"),
+            ClientError::MalformedResponse => write!(f, "malformed response from the custodian"),
+            ClientError::NoKeyPackages => write!(f, "no key packages to sign with"),
+            ClientError::BatchExhausted => write!(f, "no commitments left in the nonce batch"),
+            ClientError::Signing(error) => write!(f, "local signer failed: {error}"),
+            ClientError::InvalidSignature => write!(f, "aggregated signature does not verify"),
+        }
+    }
+}
+
+impl<C: Ciphersuite> std::error::Error for ClientError<C> {}
+
+impl<C: Ciphersuite> From<io::Error> for ClientError<C> {
+    fn from(error: io::Error) -> Self {
+        ClientError::Io(error)
+    }
+}
+
+impl<C: Ciphersuite> From<FrostError<C>> for ClientError<C> {
+    fn from(error: FrostError<C>) -> Self {
+        ClientError::Signing(error)
+    }
+}
+
+/// A connection to a custodian.
+#[derive(Debug)]
+pub struct CustodianClient {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+
+    /// Counter placed in the next signing package. Starts at the current time in microseconds,
+    /// so that it keeps increasing across reconnects.
+    counter: u64,
+
+    /// Quota label sent with signing requests.
+    label: Option<String>,
+
+    /// Group the requests are for, if the custodian serves many groups.
+    group_id: Option<GroupId>,
+}
+
+impl CustodianClient {
+    pub fn connect(address: impl ToSocketAddrs) -> io::Result<Self> {
+        Self::from_stream(TcpStream::connect(address)?)
+    }
+
+    /// Connects to `address`, failing if the connection is not established within `timeout`.
+    /// Every later request also fails if the custodian does not answer within `timeout`.
+    pub fn connect_timeout(address: &SocketAddr, timeout: Duration) -> io::Result<Self> {
+        let stream = TcpStream::connect_timeout(address, timeout)?;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(timeout))?;
+        Self::from_stream(stream)
+    }
+
+    /// Connects to the custodian at `host` and `port` through `proxy`, e.g. to an onion service
+    /// through Tor, so that the custodian does not learn the wallet's network address.
+    pub fn connect_via(proxy: &Socks5Proxy, host: &str, port: u16) -> io::Result<Self> {
+        Self::from_stream(proxy.connect(host, port)?)
+    }
+
+    fn from_stream(writer: TcpStream) -> io::Result<Self> {
+        let reader = BufReader::new(writer.try_clone()?);
+        let counter = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_micros() as u64);
+        Ok(CustodianClient {
+            reader,
+            writer,
+            counter,
+            label: None,
+            group_id: None,
+        })
+    }
+
+    /// Sends `label`, e.g. an account id, with every signing request so that the custodian counts
+    /// the signatures towards the quota of that label.
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Addresses every request to the custodian of `group_id`, for a process serving many groups
+    /// with `serve_groups`.
+    pub fn with_group(mut self, group_id: GroupId) -> Self {
+        self.group_id = Some(group_id);
+        self
+    }
+
+    /// Checks that the custodian is still responsive.
+    pub fn ping<C: Ciphersuite>(&mut self) -> Result<(), ClientError<C>> {
+        self.request("PING").map(|_| ())
+    }
+
+    /// Round one: requests fresh nonce commitments from the custodian.
+    pub fn commit<C: Ciphersuite>(&mut self) -> Result<SigningCommitments<C>, ClientError<C>> {
+        let bytes = self.request("COMMIT")?;
+        SigningCommitments::from_bytes(&bytes).map_err(|_| ClientError::MalformedResponse)
+    }
+
+    /// Requests a signed batch of `count` commitments to use in later signing sessions.
+    ///
+    /// The batch should be checked with `NonceBatch::verify` before it is relied on.
+    pub fn fetch_batch<C: Ciphersuite>(
+        &mut self,
+        count: usize,
+    ) -> Result<NonceBatch<C>, ClientError<C>> {
+        let bytes = self.request(&format!("BATCH {count}"))?;
+        NonceBatch::from_bytes(&bytes).map_err(|_| ClientError::MalformedResponse)
+    }
+
+    /// Returns the counter to place in the next signing package.
+    pub fn next_counter(&mut self) -> u64 {
+        self.counter += 1;
+        self.counter
+    }
+
+    /// Registers or cancels `intent` with a timelocked custodian, returning the time left until
+    /// its message can be signed, or `None` if it was cancelled.
+    pub fn submit_intent<C: Ciphersuite>(
+        &mut self,
+        intent: &Intent<C>,
+    ) -> Result<Option<Duration>, ClientError<C>> {
+        let bytes = self.request(&format!("INTENT {}", encode_hex(&intent.to_bytes())))?;
+        match bytes[..] {
+            [] => Ok(None),
+            _ => {
+                let millis = bytes
+                    .try_into()
+                    .map_err(|_| ClientError::MalformedResponse)?;
+                Ok(Some(Duration::from_millis(u64::from_le_bytes(millis))))
+            }
+        }
+    }
+
+    /// Round two: requests the custodian's signature share for `signing_package`.
+    pub fn sign<C: Ciphersuite>(
+        &mut self,
+        signing_package: &SigningPackage<C>,
+    ) -> Result<SignatureShare<C>, ClientError<C>> {
+        let mut request = format!("SIGN {}", encode_hex(&signing_package.to_bytes()));
+        if let Some(label) = &self.label {
+            request = format!("{request} {label}");
+        }
+        let bytes = self.request(&request)?;
+        SignatureShare::from_bytes(&bytes).map_err(|_| ClientError::MalformedResponse)
+    }
+
+    /// Reports the final `signature` of the session `session_id` to the custodian, which verifies
+    /// it over the message it co-signed before counting the session as completed.
+    pub fn complete<C: Ciphersuite>(
+        &mut self,
+        session_id: SessionId,
+        signature: &SchnorrSignature<C>,
+    ) -> Result<(), ClientError<C>> {
+        self.request(&format!(
+            "COMPLETE {} {}",
+            encode_hex(&session_id),
+            encode_hex(&signature.to_bytes())
+        ))
+        .map(|_| ())
+    }
+
+    /// Sends a single request line and returns the decoded payload of the response.
+    fn request<C: Ciphersuite>(&mut self, request: &str) -> Result<Vec<u8>, ClientError<C>> {
+        match self.group_id {
+            Some(group_id) if request != "PING" => {
+                writeln!(self.writer, "GROUP {group_id} {request}")?
+            }
+            _ => writeln!(self.writer, "{request}")?,
+        }
+
+        let mut response = String::new();
+        if self.reader.read_line(&mut response)? == 0 {
+            return Err(ClientError::Io(io::ErrorKind::UnexpectedEof.into()));
+        }
+        let response = response.trim_end();
+        match response.split_once(' ').unwrap_or((response, "")) {
+            ("OK", payload) => decode_hex(payload).ok_or(ClientError::MalformedResponse),
+            ("ERR", reason) => Err(ClientError::Rejected(reason.to_string())),
+            _ => Err(ClientError::MalformedResponse),
+        }
+    }
+}
+
+/// Returns `true` if the signer at `endpoint`, e.g. `host:port`, answers a ping within `timeout`.
+///
+/// Suitable as the liveness check of `Roster::select_live`.
+pub fn ping_endpoint(endpoint: &str, timeout: Duration) -> bool {
+    let Some(address) = endpoint
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addresses| addresses.next())
+    else {
+        return false;
+    };
+    CustodianClient::connect_timeout(&address, timeout)
+        .is_ok_and(|mut client| client.ping::<Ed25519Sha512>().is_ok())
+}
+
+/// Signs `message` with the wallet's `key_packages` and the custodian behind `client`, and
+/// returns the aggregated signature.
+///
+/// The signing package is built locally from fresh nonces of every key package and the
+/// custodian's commitments, and the final signature is verified, then reported to the custodian
+/// with `CustodianClient::complete`, before it is returned.
+pub fn co_sign<C: Ciphersuite>(
+    client: &mut CustodianClient,
+    key_packages: &[KeyPackage<C>],
+    message: &[u8],
+) -> Result<SchnorrSignature<C>, ClientError<C>> {
+    if key_packages.is_empty() {
+        return Err(ClientError::NoKeyPackages);
+    }
+    let custodian_commitments = client.commit()?;
+    co_sign_with(client, key_packages, custodian_commitments, message)
+}
+
+/// Like `co_sign`, but only sends the custodian the `message_digest` of `message` under `domain`.
+///
+/// The returned signature is over the digest and verifies against it.
+pub fn co_sign_digest<C: Ciphersuite>(
+    client: &mut CustodianClient,
+    key_packages: &[KeyPackage<C>],
+    domain: &[u8],
+    message: &[u8],
+) -> Result<SchnorrSignature<C>, ClientError<C>> {
+    co_sign(client, key_packages, &message_digest::<C>(domain, message))
+}
+
+/// Like `co_sign_digest`, but signs the `structured_digest` of the JSON `document`, so that the
+/// signature does not depend on how the document was serialized.
+pub fn sign_structured<C: Ciphersuite>(
+    client: &mut CustodianClient,
+    key_packages: &[KeyPackage<C>],
+    domain: &[u8],
+    document: &str,
+) -> Result<SchnorrSignature<C>, ClientError<C>> {
+    co_sign(
+        client,
+        key_packages,
+        &structured_digest::<C>(domain, document)?,
+    )
+}
+
+/// Like `co_sign`, but uses the next commitment of a `batch` pre-published by the custodian
+/// instead of requesting a fresh one.
+pub fn co_sign_from_batch<C: Ciphersuite>(
+    client: &mut CustodianClient,
+    key_packages: &[KeyPackage<C>],
+    batch: &mut NonceBatch<C>,
+    message: &[u8],
+) -> Result<SchnorrSignature<C>, ClientError<C>> {
+    if key_packages.is_empty() {
+        return Err(ClientError::NoKeyPackages);
+    }
+    let custodian_commitments = batch.take().ok_or(ClientError::BatchExhausted)?;
+    co_sign_with(client, key_packages, custodian_commitments, message)
+}
+
+fn co_sign_with<C: Ciphersuite>(
+    client: &mut CustodianClient,
+    key_packages: &[KeyPackage<C>],
+    custodian_commitments: SigningCommitments<C>,
+    message: &[u8],
+) -> Result<SchnorrSignature<C>, ClientError<C>> {
+    let first = key_packages.first().ok_or(ClientError::NoKeyPackages)?;
+    let (generator, group_pk) = (first.generator, first.group_pk);
+
+    let mut signers: Vec<FrostSigner<C>> = key_packages
+        .iter()
+        .map(FrostSigner::from_key_package)
+        .collect();
+    let mut commitments: Vec<SigningCommitments<C>> =
+        signers.iter().map(FrostSigner::get_commitments).collect();
+    commitments.push(custodian_commitments);
+    let mut signing_package = SigningPackage::new(message, commitments);
+    signing_package.counter = client.next_counter();
+
+    let mut sig_shares = signers
+        .iter_mut()
+        .map(|signer| signer.sign(&signing_package))
+        .collect::<Result<Vec<_>, _>>()?;
+    sig_shares.push(client.sign(&signing_package)?);
+
+    let (R, challenge) = signing_package.group_commitment_and_challenge(group_pk)?;
+    let s = sig_shares.iter().map(|sig_share| sig_share.z_i).sum();
+    if generator * s != R + group_pk.to_element() * challenge.to_scalar() {
+        return Err(ClientError::InvalidSignature);
+    }
+
+    let signature = SchnorrSignature { R, s };
+    client.complete(signing_package.session_id, &signature)?;
+    Ok(signature)
+}
+
+#[test]
+fn test_co_sign() {
+    use std::{net::TcpListener, thread, time::Duration};
+
+    use crate::{
+        custodian::{Custodian, CustodianPolicy, serve},
+        frost::Frost,
+    };
+
+    let frost: Frost = Frost::signature_share(2, 3);
+    let participants: Vec<_> = frost.participants().collect();
+    let policy = CustodianPolicy {
+        max_signatures: 1,
+        window: Duration::from_secs(60),
+        max_pending: 2,
+        pending_ttl: Duration::from_secs(60),
+        digest_only: false,
+    };
+    let custodian = Custodian::new(frost.key_package(participants[2]).unwrap(), policy);
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || serve(custodian, listener));
+    assert!(ping_endpoint(&address.to_string(), Duration::from_secs(1)));
+
+    let mut client = CustodianClient::connect(address).unwrap();
+    let key_packages = [frost.key_package(participants[0]).unwrap()];
+    assert!(co_sign(&mut client, &key_packages, b"testing").is_ok());
+
+    // the custodian's rate limit surfaces as a rejection
+    assert!(matches!(
+        co_sign(&mut client, &key_packages, b"testing"),
+        Err(ClientError::Rejected(_))
+    ));
+}
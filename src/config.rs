@@ -0,0 +1,171 @@
+//! This module implements the descriptor of a group.
+//!
+//! A `GroupConfig` bundles everything a verifier or a new participant needs to know about a group
+//! in a single artifact: the ciphersuite, the generator, the group public key, the threshold and
+//! the roster, which records the role of every member and thus which signers are blind. It is
+//! exported once after key generation and imported with `GroupConfig::from_bytes`, which rejects
+//! a descriptor for another ciphersuite.
+use ark_serialize::SerializationError;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    ciphersuite::{Ciphersuite, Ed25519Sha512, Element},
+    error::FrostError,
+    frost::SignerRole,
+    helper::{Decoder, NonZeroScalar},
+    keys::GroupPublicKey,
+    roster::{MemberRole, Roster},
+};
+
+/// The public description of a group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupConfig<C: Ciphersuite = Ed25519Sha512> {
+    pub group_pk: GroupPublicKey<C>,
+    /// Minimum number of signers required to produce a signature.
+    pub threshold: usize,
+    /// The members of the group, with their roles and identity keys.
+    pub roster: Roster<C>,
+}
+
+impl<C: Ciphersuite> GroupConfig<C> {
+    /// Describes the group with public key `group_pk` and the members of `roster`.
+    ///
+    /// Returns an error if the roster has fewer than `threshold` signers, or if its blind signers
+    /// could reach the threshold on their own.
+    pub fn new(
+        group_pk: GroupPublicKey<C>,
+        threshold: usize,
+        roster: Roster<C>,
+    ) -> Result<Self, FrostError<C>> {
+        let config = GroupConfig {
+            group_pk,
+            threshold,
+            roster,
+        };
+        let signers = config.roster.signers().count();
+        if threshold < 2 || signers < threshold {
+            return Err(FrostError::InvalidConfig(format!(
+                "{signers} signers for a threshold of {threshold}"
+            )));
+        }
+        let blind_signers = config.blind_signers().count();
+        if blind_signers >= threshold {
+            return Err(FrostError::BlindSignersReachThreshold {
+                blind_signers,
+                threshold,
+            });
+        }
+        Ok(config)
+    }
+
+    /// The name of the ciphersuite of the group.
+    pub fn ciphersuite(&self) -> &'static str {
+        C::CONTEXT_STRING
+    }
+
+    /// The generator `g` of the group.
+    pub fn generator(&self) -> Element<C> {
+        self.roster.generator()
+    }
+
+    /// Returns the identifiers of the blind signers of the group.
+    pub fn blind_signers(&self) -> impl Iterator<Item = NonZeroScalar<C>> + '_ {
+        self.roster
+            .members()
+            .filter(|member| member.role == MemberRole::Signer(SignerRole::Blind))
+            .map(|member| member.identifier)
+    }
+
+    /// The SHA-256 hash of the encoding of the descriptor, which identifies the group.
+    pub fn hash(&self) -> [u8; 32] {
+        Sha256::digest(self.to_bytes()).into()
+    }
+
+    /// Encodes the descriptor as `len(ciphersuite) || ciphersuite || group_pk || threshold ||
+    /// roster`, where the length is a single byte, the threshold a little-endian `u32`, and the
+    /// roster, which starts with the generator, is encoded as by `Roster::to_bytes`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the threshold exceeds `u32::MAX` or the name of the ciphersuite is longer than
+    /// 255 bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let ciphersuite = C::CONTEXT_STRING.as_bytes();
+        let mut bytes = vec![u8::try_from(ciphersuite.len()).expect("ciphersuite name too long")];
+        bytes.extend_from_slice(ciphersuite);
+        bytes.extend(C::serialize_element(&self.group_pk.to_element()));
+        let threshold = u32::try_from(self.threshold).expect("threshold too large");
+        bytes.extend_from_slice(&threshold.to_le_bytes());
+        bytes.extend(self.roster.to_bytes());
+        bytes
+    }
+
+    /// Parses a descriptor encoded by `to_bytes`.
+    ///
+    /// Returns an error if it describes a group over another ciphersuite than `C`, or a group
+    /// `GroupConfig::new` would not accept.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let mut decoder = Decoder::new(bytes);
+        let ciphersuite_len = decoder.take(1)?[0] as usize;
+        if decoder.take(ciphersuite_len)? != C::CONTEXT_STRING.as_bytes() {
+            return Err(SerializationError::InvalidData);
+        }
+        let group_pk = GroupPublicKey::new(decoder.element::<C>()?);
+        let threshold = decoder.u32()? as usize;
+        let roster = Roster::from_bytes(decoder.rest())?;
+
+        GroupConfig::new(group_pk, threshold, roster).map_err(|_| SerializationError::InvalidData)
+    }
+}
+
+#[test]
+fn test_group_config_round_trip() {
+    use crate::{frost::Frost, identity::IdentityKey, roster::Member};
+
+    let mut rng = ark_std::test_rng();
+    let frost: Frost = Frost::signature_share_with_roles(
+        3,
+        &[
+            SignerRole::Regular,
+            SignerRole::Regular,
+            SignerRole::Blind,
+            SignerRole::Regular,
+        ],
+    );
+    let mut roster = Roster::new(frost.generator);
+    for (position, signer) in frost.signers().enumerate() {
+        roster
+            .add(Member {
+                identifier: NonZeroScalar::new(signer.get_identifier()).unwrap(),
+                identity_key: IdentityKey::<Ed25519Sha512>::generate(frost.generator, &mut rng)
+                    .public_key(),
+                role: MemberRole::Signer(signer.role()),
+                endpoint: format!("signer-{}.example:7000", position + 1),
+            })
+            .unwrap();
+    }
+
+    let config = GroupConfig::new(frost.group_pk, frost.threshold, roster.clone()).unwrap();
+    assert_eq!(config.ciphersuite(), "FROST-ED25519-SHA512-v1");
+    assert_eq!(config.generator(), frost.generator);
+    let blind: Vec<_> = frost
+        .signers()
+        .filter(|signer| signer.is_blind())
+        .map(|signer| signer.get_identifier())
+        .collect();
+    assert_eq!(
+        config
+            .blind_signers()
+            .map(|identifier| identifier.to_scalar())
+            .collect::<Vec<_>>(),
+        blind
+    );
+    assert_eq!(GroupConfig::from_bytes(&config.to_bytes()).unwrap(), config);
+
+    // a descriptor for another ciphersuite is rejected
+    let mut bytes = config.to_bytes();
+    bytes[1] ^= 1;
+    assert!(GroupConfig::<Ed25519Sha512>::from_bytes(&bytes).is_err());
+
+    assert!(GroupConfig::new(frost.group_pk, 5, roster).is_err());
+}
@@ -0,0 +1,1298 @@
+//! This module implements the Coordinator of a FROST signing session.
+//!
+//! The Coordinator collects nonce commitments from the selected signers, sends them a
+//! `SigningPackage`, and aggregates the returned signature shares. Each round has a deadline: if a
+//! selected signer has not responded by then, the session aborts with `FrostError::Timeout`
+//! listing the missing participants, so the caller can retry with a different subset.
+//! `Coordinator::retry_without` restarts round one in place, replacing the dropped participants
+//! with standby signers.
+//!
+//! If the coordinator itself disappears mid-session, a participant takes over: the participants
+//! follow a deterministic `succession` order, ranked by `leader::leader_order` seeded with the
+//! session id, and the coordinator sends its successor a `Handoff` holding the state collected so
+//! far whenever it changes, from which the successor resumes the session with `Handoff::take_over`
+//! without asking the signers to commit again.
+//!
+//! Given the `PublicKeyPackage` of the group with `Coordinator::with_public_key_package`, the
+//! coordinator verifies every signature share as it arrives, rejecting an invalid one with a
+//! `BlameCertificate` a third party can check, and verifies the aggregated signature before
+//! returning it. Without it, the coordinator knows neither the generator nor the verification
+//! shares of the group, and an invalid share only surfaces as an invalid signature once it is
+//! checked by the caller.
+//!
+//! An embedding application can follow the progress of a session, e.g. to show it in a UI, by
+//! subscribing to its `CoordinatorEvent`s with `Coordinator::with_events`.
+#![allow(non_snake_case)]
+use std::{
+    collections::{BTreeMap, btree_map::Entry},
+    sync::{Arc, mpsc::Sender},
+    time::{Duration, Instant},
+};
+
+use ark_ff::AdditiveGroup;
+use ark_serialize::SerializationError;
+
+use crate::{
+    blame::verify_signature_share,
+    ciphersuite::{Ciphersuite, Ed25519Sha512, Element, ScalarField},
+    derivation::DerivedKey,
+    error::{FrostError, Round},
+    frost::{SessionId, SignatureShare, SigningPackage},
+    helper::{CommitmentProof, Decoder, NonZeroScalar, serialize_scalar},
+    keys::{GroupPublicKey, PublicKeyPackage, verify_message_signature},
+    leader::{elect_leader, leader_order},
+    metrics::Metrics,
+    nonces::SigningCommitments,
+    schnorr::SchnorrSignature,
+    transcript::SessionTranscript,
+};
+
+/// A step in the progress of a signing session, sent to the subscriber of
+/// `Coordinator::with_events`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoordinatorEvent<C: Ciphersuite = Ed25519Sha512> {
+    /// The session started, or restarted with `retry_without` under a new counter.
+    SessionStarted { session_id: SessionId, counter: u64 },
+    /// The commitment of a participant was accepted.
+    CommitmentReceived { identifier: NonZeroScalar<C> },
+    /// The commitment of a participant was refused.
+    CommitmentRejected {
+        identifier: NonZeroScalar<C>,
+        reason: FrostError<C>,
+    },
+    /// The signature share of a participant was accepted.
+    ShareReceived { identifier: NonZeroScalar<C> },
+    /// The signature share of a participant was refused.
+    ShareRejected {
+        identifier: NonZeroScalar<C>,
+        reason: FrostError<C>,
+    },
+    /// The deadline of `round` passed before the `missing` participants responded.
+    RoundTimedOut {
+        round: Round,
+        missing: Vec<NonZeroScalar<C>>,
+    },
+    /// The session completed with `signature`.
+    SignatureProduced { signature: SchnorrSignature<C> },
+}
+
+/// State of a single signing session, driven by the Coordinator.
+#[derive(Debug, Clone)]
+pub struct Coordinator<C: Ciphersuite = Ed25519Sha512> {
+    /// Identifies this session.
+    session_id: SessionId,
+
+    /// Counter placed in the signing package of this session.
+    counter: u64,
+
+    /// Public key of the group the signature is produced for.
+    group_pk: GroupPublicKey<C>,
+
+    /// The message to be signed.
+    message: Vec<u8>,
+
+    /// Identifiers of the signers selected for this session.
+    participants: Vec<NonZeroScalar<C>>,
+
+    /// Signers that replace dropped participants when the session is retried.
+    standby: Vec<NonZeroScalar<C>>,
+
+    /// Time each round is given to complete.
+    round_timeout: Duration,
+
+    /// The round the session is currently in.
+    round: Round,
+
+    /// Deadline of the current round.
+    deadline: Instant,
+
+    /// Commitments received during round one, ordered by identifier so that the signing package,
+    /// and hence the binding factors, do not depend on the order in which they arrived.
+    commitments: BTreeMap<ScalarField<C>, SigningCommitments<C>>,
+
+    /// The `SigningPackage` sent to signers at the start of round two.
+    signing_package: Option<SigningPackage<C>>,
+
+    /// Signature shares received during round two.
+    sig_shares: Vec<SignatureShare<C>>,
+
+    /// Metrics of the service this session runs in, if any.
+    metrics: Option<Arc<Metrics>>,
+
+    /// Subscriber to the events of this session, if any.
+    events: Option<Sender<CoordinatorEvent<C>>>,
+
+    /// Whether the session has been recorded as aborted in `metrics`.
+    aborted: bool,
+
+    /// The generator commitment proofs are checked against, if they are required.
+    commitment_proofs: Option<Element<C>>,
+
+    /// The aggregated signature, once the session has completed.
+    signature: Option<SchnorrSignature<C>>,
+
+    /// The key derived from `group_pk` the signature is produced under, if any.
+    derived_key: Option<DerivedKey<C>>,
+
+    /// The public key material shares and the signature are verified against, if known.
+    public_key_package: Option<PublicKeyPackage<C>>,
+}
+
+impl<C: Ciphersuite> Coordinator<C> {
+    /// Starts a new session over `message` among `participants`, giving each round
+    /// `round_timeout` to complete.
+    pub fn new(
+        group_pk: GroupPublicKey<C>,
+        message: &[u8],
+        participants: Vec<NonZeroScalar<C>>,
+        round_timeout: Duration,
+    ) -> Self {
+        let mut session_id = [0u8; 32];
+        rand::RngCore::fill_bytes(&mut rand::rng(), &mut session_id);
+
+        Coordinator {
+            session_id,
+            counter: 0,
+            group_pk,
+            message: message.to_vec(),
+            participants,
+            standby: Vec::new(),
+            round_timeout,
+            round: Round::Commitment,
+            deadline: Instant::now() + round_timeout,
+            commitments: BTreeMap::new(),
+            signing_package: None,
+            sig_shares: Vec::new(),
+            metrics: None,
+            events: None,
+            aborted: false,
+            commitment_proofs: None,
+            signature: None,
+            derived_key: None,
+            public_key_package: None,
+        }
+    }
+
+    /// Records the progress of this session in `metrics`, counting it as started.
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        metrics.session_started();
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Sends the events of this session to `events`, starting with `SessionStarted`. Events
+    /// are dropped once the receiver is gone.
+    pub fn with_events(mut self, events: Sender<CoordinatorEvent<C>>) -> Self {
+        self.events = Some(events);
+        self.emit(CoordinatorEvent::SessionStarted {
+            session_id: self.session_id,
+            counter: self.counter,
+        });
+        self
+    }
+
+    /// Sets the counter placed in the signing package, which must exceed the counter of every
+    /// earlier session that included the same remote signers.
+    pub fn with_counter(mut self, counter: u64) -> Self {
+        self.counter = counter;
+        self
+    }
+
+    /// Only accepts commitments received with a `CommitmentProof` that verifies under
+    /// `generator`, through `receive_proven_commitment`.
+    pub fn with_commitment_proofs(mut self, generator: Element<C>) -> Self {
+        self.commitment_proofs = Some(generator);
+        self
+    }
+
+    /// Produces the signature under `derived_key` instead of the key of the group, applying its
+    /// tweak when aggregating. Signers must sign with `FrostSigner::sign_derived`.
+    ///
+    /// Returns `FrostError::InvalidKeyDerivation` if `derived_key` is not derived from the key of
+    /// the group.
+    pub fn with_derived_key(mut self, derived_key: DerivedKey<C>) -> Result<Self, FrostError<C>> {
+        if !derived_key.is_derived_from(self.group_pk) {
+            return Err(FrostError::InvalidKeyDerivation);
+        }
+        self.derived_key = Some(derived_key);
+        Ok(self)
+    }
+
+    /// Verifies every signature share against the verification share of its signer in
+    /// `public_key_package` as it arrives, and the aggregated signature before returning it.
+    ///
+    /// Returns `FrostError::InvalidPublicKeyPackage` if the package is for another group or its
+    /// verification shares are inconsistent with the group public key.
+    pub fn with_public_key_package(
+        mut self,
+        public_key_package: PublicKeyPackage<C>,
+    ) -> Result<Self, FrostError<C>> {
+        if public_key_package.group_pk != self.group_pk {
+            return Err(FrostError::InvalidPublicKeyPackage(
+                "the package is for another group".to_string(),
+            ));
+        }
+        public_key_package.verify()?;
+        self.public_key_package = Some(public_key_package);
+        Ok(self)
+    }
+
+    /// Sets the signers that replace dropped participants in `retry_without`, in order of
+    /// preference.
+    pub fn with_standby(mut self, standby: Vec<NonZeroScalar<C>>) -> Self {
+        self.standby = standby;
+        self
+    }
+
+    /// Restarts the session at round one without the participants with the given `identifiers`,
+    /// each replaced by the next standby signer while any are left.
+    ///
+    /// The message, session id and settings are kept, but every participant must send fresh
+    /// commitments: the collected ones may already have been signed with, so they are discarded,
+    /// and the counter is incremented so that signers do not mistake the retry for a replay. The
+    /// abandoned attempt is recorded as aborted, and the dropped participants as failed.
+    ///
+    /// Returns an error, leaving the session unchanged, if an identifier is not a participant,
+    /// if fewer signers than the threshold of the group would be left, or if the session has
+    /// already completed. The threshold is taken from the `PublicKeyPackage` given to
+    /// `with_public_key_package`; without one, every participant must be replaced.
+    pub fn retry_without(&mut self, identifiers: &[NonZeroScalar<C>]) -> Result<(), FrostError<C>> {
+        if self.signature.is_some() {
+            return Err(FrostError::UnexpectedRound(self.round));
+        }
+        for identifier in identifiers {
+            self.check_participant(*identifier)?;
+        }
+        let mut participants: Vec<NonZeroScalar<C>> = self
+            .participants
+            .iter()
+            .filter(|id| !identifiers.contains(id))
+            .copied()
+            .collect();
+        // without the public key package, every selected participant is taken to be needed
+        let threshold = self
+            .public_key_package
+            .as_ref()
+            .map_or(self.participants.len(), |package| package.threshold);
+        let replacements = (self.participants.len() - participants.len()).min(self.standby.len());
+        if participants.len() + replacements < threshold {
+            return Err(FrostError::InsufficientSigners {
+                available: participants.len() + self.standby.len(),
+                threshold,
+            });
+        }
+        participants.extend(self.standby.drain(..replacements));
+
+        if !self.aborted {
+            self.record(|metrics| metrics.session_aborted());
+        }
+        self.record(|metrics| {
+            identifiers
+                .iter()
+                .for_each(|id| metrics.signer_failed(id.to_scalar()));
+            metrics.session_started();
+        });
+        self.participants = participants;
+        self.counter += 1;
+        self.round = Round::Commitment;
+        self.deadline = Instant::now() + self.round_timeout;
+        self.commitments.clear();
+        self.signing_package = None;
+        self.sig_shares.clear();
+        self.aborted = false;
+        self.emit(CoordinatorEvent::SessionStarted {
+            session_id: self.session_id,
+            counter: self.counter,
+        });
+
+        Ok(())
+    }
+
+    pub fn get_group_pk(&self) -> GroupPublicKey<C> {
+        self.group_pk
+    }
+
+    pub fn get_session_id(&self) -> SessionId {
+        self.session_id
+    }
+
+    /// Returns the `SigningPackage` of round two, once round one has ended.
+    pub fn get_signing_package(&self) -> Option<&SigningPackage<C>> {
+        self.signing_package.as_ref()
+    }
+
+    /// Returns the aggregated signature, once the session has completed.
+    pub fn get_signature(&self) -> Option<SchnorrSignature<C>> {
+        self.signature
+    }
+
+    /// Returns the participants in the order in which they take over the session if its
+    /// coordinator disappears. Every participant derives the same order from the session id.
+    pub fn succession(&self) -> Vec<NonZeroScalar<C>> {
+        leader_order(&self.session_id, &self.participants)
+    }
+
+    /// Returns the state of the session for the successor to resume it from, should this
+    /// coordinator disappear. Send it again whenever a commitment or share is received.
+    pub fn handoff(&self) -> Handoff<C> {
+        Handoff {
+            coordinator: Coordinator {
+                metrics: None,
+                events: None,
+                ..self.clone()
+            },
+        }
+    }
+
+    /// Returns the participants that have not yet responded in the current round.
+    pub fn missing(&self) -> Vec<NonZeroScalar<C>> {
+        let responded: Vec<NonZeroScalar<C>> = match self.round {
+            Round::Commitment => self
+                .commitments
+                .values()
+                .map(|commitment| commitment.identifier)
+                .collect(),
+            Round::SignatureShare => self
+                .sig_shares
+                .iter()
+                .map(|share| share.identifier)
+                .collect(),
+        };
+
+        self.participants
+            .iter()
+            .filter(|id| !responded.contains(id))
+            .copied()
+            .collect()
+    }
+
+    /// Returns the time left before the deadline of the current round.
+    pub fn time_remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+
+    /// Returns `FrostError::Timeout` if the deadline of the current round has passed while some
+    /// participants have not responded.
+    pub fn check_deadline(&self) -> Result<(), FrostError<C>> {
+        let missing = self.missing();
+        if Instant::now() > self.deadline && !missing.is_empty() {
+            return Err(FrostError::Timeout {
+                round: self.round,
+                missing: missing.iter().map(|id| id.to_scalar()).collect(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Records the round one `commitment` of a participant.
+    ///
+    /// Returns `FrostError::InvalidCommitmentProof` if the session requires commitment proofs.
+    pub fn receive_commitment(
+        &mut self,
+        commitment: SigningCommitments<C>,
+    ) -> Result<(), FrostError<C>> {
+        let identifier = commitment.identifier;
+        let result = self.try_receive_commitment(commitment);
+        self.emit_commitment(identifier, &result);
+        result
+    }
+
+    /// Records the round one `commitment` of a participant together with its `proof` of
+    /// possession, which is checked if the session requires commitment proofs.
+    pub fn receive_proven_commitment(
+        &mut self,
+        commitment: SigningCommitments<C>,
+        proof: &CommitmentProof<C>,
+    ) -> Result<(), FrostError<C>> {
+        let identifier = commitment.identifier;
+        let result = self.try_receive_proven_commitment(commitment, proof);
+        self.emit_commitment(identifier, &result);
+        result
+    }
+
+    fn try_receive_commitment(
+        &mut self,
+        commitment: SigningCommitments<C>,
+    ) -> Result<(), FrostError<C>> {
+        self.check_round(Round::Commitment)?;
+        let identifier = commitment.identifier;
+        self.check_participant(identifier)?;
+
+        if self.commitment_proofs.is_some() {
+            self.record(|metrics| metrics.signer_failed(identifier.to_scalar()));
+            return Err(FrostError::InvalidCommitmentProof(identifier.to_scalar()));
+        }
+        self.accept_commitment(commitment)
+    }
+
+    fn try_receive_proven_commitment(
+        &mut self,
+        commitment: SigningCommitments<C>,
+        proof: &CommitmentProof<C>,
+    ) -> Result<(), FrostError<C>> {
+        self.check_round(Round::Commitment)?;
+        let identifier = commitment.identifier;
+        self.check_participant(identifier)?;
+
+        if let Some(generator) = self.commitment_proofs
+            && !proof.verify(&commitment, generator)
+        {
+            self.record(|metrics| metrics.signer_failed(identifier.to_scalar()));
+            return Err(FrostError::InvalidCommitmentProof(identifier.to_scalar()));
+        }
+        self.accept_commitment(commitment)
+    }
+
+    fn accept_commitment(
+        &mut self,
+        commitment: SigningCommitments<C>,
+    ) -> Result<(), FrostError<C>> {
+        let identifier = commitment.identifier.to_scalar();
+
+        match self.commitments.entry(identifier) {
+            Entry::Occupied(_) => {
+                self.record(|metrics| metrics.signer_failed(identifier));
+                Err(FrostError::DuplicateCommitment(identifier))
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(commitment);
+                Ok(())
+            }
+        }
+    }
+
+    /// Ends round one and returns the `SigningPackage` to send to every participant.
+    ///
+    /// Returns an error if a participant's commitment is still outstanding.
+    pub fn signing_package(&mut self) -> Result<SigningPackage<C>, FrostError<C>> {
+        self.check_round(Round::Commitment)?;
+        if let Some(id) = self.missing().first() {
+            return Err(FrostError::MissingCommitment(id.to_scalar()));
+        }
+
+        let signing_package = SigningPackage {
+            message: self.message.clone(),
+            commitments: self.commitments.values().copied().collect(),
+            session_id: self.session_id,
+            counter: self.counter,
+        };
+        self.signing_package = Some(signing_package.clone());
+        let elapsed = self.elapsed();
+        self.record(|metrics| metrics.round_completed(Round::Commitment, elapsed));
+        self.round = Round::SignatureShare;
+        self.deadline = Instant::now() + self.round_timeout;
+
+        Ok(signing_package)
+    }
+
+    /// Records the round two `sig_share` of a participant.
+    ///
+    /// Shares produced for another session, or another counter, are rejected.
+    pub fn receive_share(&mut self, sig_share: SignatureShare<C>) -> Result<(), FrostError<C>> {
+        let identifier = sig_share.identifier;
+        let result = self.try_receive_share(sig_share);
+        self.emit(match &result {
+            Ok(()) => CoordinatorEvent::ShareReceived { identifier },
+            Err(reason) => CoordinatorEvent::ShareRejected {
+                identifier,
+                reason: reason.clone(),
+            },
+        });
+        result
+    }
+
+    fn try_receive_share(&mut self, sig_share: SignatureShare<C>) -> Result<(), FrostError<C>> {
+        self.check_round(Round::SignatureShare)?;
+        let identifier = sig_share.identifier;
+        self.check_participant(identifier)?;
+        if sig_share.session_id != self.session_id || sig_share.counter != self.counter {
+            return Err(FrostError::UnknownSession(sig_share.session_id));
+        }
+
+        if self
+            .sig_shares
+            .iter()
+            .any(|share| share.identifier == identifier)
+        {
+            self.record(|metrics| metrics.signer_failed(identifier.to_scalar()));
+            return Err(FrostError::DuplicateSignatureShare(identifier.to_scalar()));
+        }
+        if let (Some(public_key_package), Some(signing_package)) =
+            (&self.public_key_package, &self.signing_package)
+        {
+            let verification_share = public_key_package
+                .verification_share(identifier)
+                .ok_or(FrostError::UnknownParticipant(identifier.to_scalar()))?;
+            let result = verify_signature_share(
+                sig_share,
+                signing_package,
+                self.signing_key(),
+                verification_share,
+                public_key_package.generator,
+            );
+            if result.is_err() {
+                self.record(|metrics| metrics.signer_failed(identifier.to_scalar()));
+                return result;
+            }
+        }
+        self.sig_shares.push(sig_share);
+
+        Ok(())
+    }
+
+    /// Aggregates the collected signature shares into a `SchnorrSignature`.
+    ///
+    /// Returns an error if a participant's signature share is still outstanding, and
+    /// `FrostError::InvalidSignature` if the session has a public key package and the signature
+    /// does not verify under the key of the session.
+    pub fn aggregate(&mut self) -> Result<SchnorrSignature<C>, FrostError<C>> {
+        self.check_round(Round::SignatureShare)?;
+        if let Some(id) = self.missing().first() {
+            return Err(FrostError::MissingSignatureShare(id.to_scalar()));
+        }
+
+        let signing_package = self
+            .signing_package
+            .as_ref()
+            .ok_or(FrostError::UnexpectedRound(Round::Commitment))?;
+        let (R, _) = signing_package.group_commitment_and_challenge(self.signing_key())?;
+
+        let mut s = ScalarField::<C>::ZERO;
+        for sig_share in &self.sig_shares {
+            s += sig_share.z_i;
+        }
+        let mut signature = SchnorrSignature { R, s };
+        if let Some(derived_key) = &self.derived_key {
+            signature = derived_key.apply_tweak(&self.message, signature);
+        }
+        if let Some(public_key_package) = &self.public_key_package
+            && !verify_message_signature(
+                &signature,
+                &self.message,
+                self.signing_key().to_element(),
+                public_key_package.generator,
+            )
+        {
+            return Err(FrostError::InvalidSignature);
+        }
+
+        let elapsed = self.elapsed();
+        self.record(|metrics| {
+            metrics.round_completed(Round::SignatureShare, elapsed);
+            metrics.session_completed();
+        });
+        self.emit(CoordinatorEvent::SignatureProduced { signature });
+
+        self.signature = Some(signature);
+        Ok(signature)
+    }
+
+    /// Returns the transcript of the completed session, for an auditor to re-verify. Sign it
+    /// with `Authenticated::sign` to attest to it.
+    ///
+    /// Returns an error if the signature has not been aggregated yet.
+    pub fn transcript(&self) -> Result<SessionTranscript<C>, FrostError<C>> {
+        let (Some(signing_package), Some(signature)) = (&self.signing_package, self.signature)
+        else {
+            return Err(FrostError::UnexpectedRound(Round::SignatureShare));
+        };
+
+        Ok(SessionTranscript {
+            signing_package: signing_package.clone(),
+            binding_factors: signing_package.binding_factors(self.signing_key())?,
+            sig_shares: self.sig_shares.clone(),
+            signature,
+        })
+    }
+
+    /// Encodes the state of the session, so that it can be stored and resumed after a restart
+    /// with `from_bytes`.
+    ///
+    /// The encoding holds the signing package fields (`session_id`, `counter`, message and the
+    /// commitments received so far) in the layout of `SigningPackage::to_bytes` prefixed by its
+    /// length, followed by `group_pk`, the round timeout in milliseconds, the round, the abort
+    /// flag, the participants, the standby signers, the commitment proof generator, the signature
+    /// shares received so far, the signature and the derived key, as its public key, chain code,
+    /// tweak and generator, and the public key package, prefixed by its length. Lists are
+    /// prefixed by their 4-byte length, and optional values by a presence byte. Metrics are not
+    /// encoded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the message or a list is longer than `u32::MAX`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let signing_package = SigningPackage {
+            message: self.message.clone(),
+            commitments: self.commitments.values().copied().collect(),
+            session_id: self.session_id,
+            counter: self.counter,
+        }
+        .to_bytes();
+        let round_timeout = u64::try_from(self.round_timeout.as_millis()).unwrap_or(u64::MAX);
+        let round = match self.round {
+            Round::Commitment => 0,
+            Round::SignatureShare => 1,
+        };
+
+        let mut bytes = encode_len(signing_package.len());
+        bytes.extend(signing_package);
+        bytes.extend(C::serialize_element(&self.group_pk.to_element()));
+        bytes.extend_from_slice(&round_timeout.to_le_bytes());
+        bytes.extend_from_slice(&[round, self.aborted as u8]);
+        for identifiers in [&self.participants, &self.standby] {
+            bytes.extend(encode_len(identifiers.len()));
+            for identifier in identifiers {
+                bytes.extend(serialize_scalar::<C>(&identifier.to_scalar()));
+            }
+        }
+        match self.commitment_proofs {
+            Some(generator) => {
+                bytes.push(1);
+                bytes.extend(C::serialize_element(&generator));
+            }
+            None => bytes.push(0),
+        }
+        bytes.extend(encode_len(self.sig_shares.len()));
+        for sig_share in &self.sig_shares {
+            bytes.extend(sig_share.to_bytes());
+        }
+        match self.signature {
+            Some(signature) => {
+                bytes.push(1);
+                bytes.extend(signature.to_bytes());
+            }
+            None => bytes.push(0),
+        }
+        match &self.derived_key {
+            Some(derived_key) => {
+                bytes.push(1);
+                bytes.extend(C::serialize_element(&derived_key.group_pk.to_element()));
+                bytes.extend_from_slice(&derived_key.chain_code);
+                bytes.extend(serialize_scalar::<C>(&derived_key.tweak));
+                bytes.extend(C::serialize_element(&derived_key.generator));
+            }
+            None => bytes.push(0),
+        }
+        match &self.public_key_package {
+            Some(public_key_package) => {
+                let public_key_package = public_key_package.to_bytes();
+                bytes.push(1);
+                bytes.extend(encode_len(public_key_package.len()));
+                bytes.extend(public_key_package);
+            }
+            None => bytes.push(0),
+        }
+        bytes
+    }
+
+    /// Resumes a session encoded by `to_bytes`. The current round is given a fresh deadline, as
+    /// the time spent while the coordinator was down cannot be attributed to the signers.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let mut decoder = Decoder::new(bytes);
+        let signing_package_len = decoder.u32()? as usize;
+        let signing_package = SigningPackage::<C>::from_bytes(decoder.take(signing_package_len)?)?;
+        let group_pk = GroupPublicKey::new(decoder.element::<C>()?);
+        let round_timeout = Duration::from_millis(decoder.u64()?);
+        let round = match decoder.take(1)?[0] {
+            0 => Round::Commitment,
+            1 => Round::SignatureShare,
+            _ => return Err(SerializationError::InvalidData),
+        };
+        let aborted = decode_flag(&mut decoder)?;
+        let participants = (0..decoder.u32()?)
+            .map(|_| decoder.identifier())
+            .collect::<Result<Vec<_>, _>>()?;
+        let standby = (0..decoder.u32()?)
+            .map(|_| decoder.identifier())
+            .collect::<Result<Vec<_>, _>>()?;
+        let commitment_proofs = match decode_flag(&mut decoder)? {
+            true => Some(decoder.element::<C>()?),
+            false => None,
+        };
+        let sig_shares = (0..decoder.u32()?)
+            .map(|_| {
+                Ok(SignatureShare {
+                    session_id: decoder.array()?,
+                    counter: decoder.u64()?,
+                    identifier: decoder.identifier()?,
+                    z_i: decoder.scalar::<C>()?,
+                })
+            })
+            .collect::<Result<Vec<_>, SerializationError>>()?;
+        let signature = match decode_flag(&mut decoder)? {
+            true => Some(SchnorrSignature::from_bytes(
+                decoder.take(SchnorrSignature::<C>::encoded_length())?,
+            )?),
+            false => None,
+        };
+        let derived_key = match decode_flag(&mut decoder)? {
+            true => Some(DerivedKey {
+                group_pk: GroupPublicKey::new(decoder.element::<C>()?),
+                chain_code: decoder.array()?,
+                tweak: decoder.scalar::<C>()?,
+                generator: decoder.element::<C>()?,
+            }),
+            false => None,
+        };
+        let public_key_package = match decode_flag(&mut decoder)? {
+            true => {
+                let len = decoder.u32()? as usize;
+                Some(PublicKeyPackage::from_bytes(decoder.take(len)?)?)
+            }
+            false => None,
+        };
+        decoder.finish()?;
+
+        let SigningPackage {
+            message,
+            commitments,
+            session_id,
+            counter,
+        } = signing_package;
+        let signing_package = (round == Round::SignatureShare).then(|| SigningPackage {
+            message: message.clone(),
+            commitments: commitments.clone(),
+            session_id,
+            counter,
+        });
+        let received = commitments.len();
+        let commitments: BTreeMap<_, _> = commitments
+            .into_iter()
+            .map(|commitment| (commitment.identifier.to_scalar(), commitment))
+            .collect();
+        if commitments.len() != received {
+            return Err(SerializationError::InvalidData);
+        }
+
+        Ok(Coordinator {
+            session_id,
+            counter,
+            group_pk,
+            message,
+            participants,
+            standby,
+            round_timeout,
+            round,
+            deadline: Instant::now() + round_timeout,
+            commitments,
+            signing_package,
+            sig_shares,
+            metrics: None,
+            events: None,
+            aborted,
+            commitment_proofs,
+            signature,
+            derived_key,
+            public_key_package,
+        })
+    }
+
+    /// Returns the key the signature is produced under.
+    fn signing_key(&self) -> GroupPublicKey<C> {
+        self.derived_key
+            .map_or(self.group_pk, |derived_key| derived_key.group_pk)
+    }
+
+    /// Checks the deadline, then checks that the session is in `round`.
+    ///
+    /// The first time the deadline is found to have passed, the session is recorded as aborted
+    /// and each missing participant as failed.
+    fn check_round(&mut self, round: Round) -> Result<(), FrostError<C>> {
+        if let Err(error) = self.check_deadline() {
+            if let (FrostError::Timeout { missing, .. }, false) = (&error, self.aborted) {
+                self.aborted = true;
+                self.record(|metrics| {
+                    metrics.session_aborted();
+                    missing.iter().for_each(|id| metrics.signer_failed(id));
+                });
+                self.emit(CoordinatorEvent::RoundTimedOut {
+                    round: self.round,
+                    missing: self.missing(),
+                });
+            }
+            return Err(error);
+        }
+        if self.round != round {
+            return Err(FrostError::UnexpectedRound(round));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the time elapsed since the current round started.
+    fn elapsed(&self) -> Duration {
+        self.round_timeout
+            .saturating_sub(self.deadline.saturating_duration_since(Instant::now()))
+    }
+
+    fn record(&self, f: impl FnOnce(&Metrics)) {
+        if let Some(metrics) = &self.metrics {
+            f(metrics);
+        }
+    }
+
+    fn emit(&self, event: CoordinatorEvent<C>) {
+        if let Some(events) = &self.events {
+            // the subscriber may have stopped listening
+            let _ = events.send(event);
+        }
+    }
+
+    fn emit_commitment(&self, identifier: NonZeroScalar<C>, result: &Result<(), FrostError<C>>) {
+        self.emit(match result {
+            Ok(()) => CoordinatorEvent::CommitmentReceived { identifier },
+            Err(reason) => CoordinatorEvent::CommitmentRejected {
+                identifier,
+                reason: reason.clone(),
+            },
+        });
+    }
+
+    fn check_participant(&self, identifier: NonZeroScalar<C>) -> Result<(), FrostError<C>> {
+        if !self.participants.contains(&identifier) {
+            return Err(FrostError::UnknownParticipant(identifier.to_scalar()));
+        }
+
+        Ok(())
+    }
+}
+
+/// The state of a session, sent by its coordinator to the participant that succeeds it.
+///
+/// The handoff carries the collected commitments and signature shares, so the successor can finish
+/// the session without a new round. It is not authenticated: send it over an authenticated
+/// channel, e.g. wrapped in an `Authenticated` envelope.
+#[derive(Debug, Clone)]
+pub struct Handoff<C: Ciphersuite = Ed25519Sha512> {
+    coordinator: Coordinator<C>,
+}
+
+impl<C: Ciphersuite> Handoff<C> {
+    pub fn get_session_id(&self) -> SessionId {
+        self.coordinator.session_id
+    }
+
+    /// Resumes the session as the participant `identifier`, after its coordinator and the
+    /// `unresponsive` participants failed to take over. The current round is given a fresh
+    /// deadline.
+    ///
+    /// Returns `FrostError::InvalidSession` if `identifier` is not the first participant in the
+    /// succession order that is not `unresponsive`, so that at most one participant resumes the
+    /// session.
+    pub fn take_over(
+        self,
+        identifier: NonZeroScalar<C>,
+        unresponsive: &[NonZeroScalar<C>],
+    ) -> Result<Coordinator<C>, FrostError<C>> {
+        let coordinator = self.coordinator;
+        let successor = elect_leader(
+            &coordinator.session_id,
+            &coordinator.participants,
+            unresponsive,
+        );
+        if successor != Some(identifier) {
+            return Err(FrostError::InvalidSession(
+                "not the successor of the coordinator".to_string(),
+            ));
+        }
+
+        Ok(Coordinator {
+            deadline: Instant::now() + coordinator.round_timeout,
+            ..coordinator
+        })
+    }
+
+    /// Encodes the handoff as the state of the session, in the layout of `Coordinator::to_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.coordinator.to_bytes()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        Ok(Handoff {
+            coordinator: Coordinator::from_bytes(bytes)?,
+        })
+    }
+}
+
+fn encode_len(len: usize) -> Vec<u8> {
+    u32::try_from(len)
+        .expect("list too long")
+        .to_le_bytes()
+        .to_vec()
+}
+
+fn decode_flag(decoder: &mut Decoder<'_>) -> Result<bool, SerializationError> {
+    match decoder.take(1)?[0] {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(SerializationError::InvalidData),
+    }
+}
+
+#[test]
+fn test_coordinator_session() {
+    use crate::frost::Frost;
+
+    let message = b"testing";
+    let mut frost: Frost = Frost::signature_share(2, 3);
+    let participants: Vec<NonZeroScalar> = frost.participants().collect();
+    let (events, subscriber) = std::sync::mpsc::channel();
+    let mut coordinator = Coordinator::new(
+        frost.group_pk,
+        message,
+        participants.clone(),
+        Duration::from_secs(60),
+    )
+    .with_events(events);
+
+    for signer in frost.signers() {
+        coordinator
+            .receive_commitment(signer.get_commitments())
+            .unwrap();
+    }
+    let signing_package = coordinator.signing_package().unwrap();
+
+    let mut sig_shares: Vec<_> = frost
+        .signers_mut()
+        .map(|signer| signer.sign(&signing_package).unwrap())
+        .collect();
+    sig_shares.insert(1, sig_shares[0]);
+    for sig_share in sig_shares {
+        let _ = coordinator.receive_share(sig_share);
+    }
+    let signature = coordinator.aggregate().unwrap();
+    assert!(frost.verify(message, signature));
+
+    // the subscriber follows the session, including the rejected duplicate share
+    let events: Vec<_> = subscriber.try_iter().collect();
+    assert_eq!(events.len(), 9);
+    assert_eq!(
+        events[0],
+        CoordinatorEvent::SessionStarted {
+            session_id: coordinator.get_session_id(),
+            counter: 0
+        }
+    );
+    assert_eq!(
+        events[1],
+        CoordinatorEvent::CommitmentReceived {
+            identifier: participants[0]
+        }
+    );
+    assert_eq!(
+        events[5],
+        CoordinatorEvent::ShareRejected {
+            identifier: participants[0],
+            reason: FrostError::DuplicateSignatureShare(participants[0].to_scalar())
+        }
+    );
+    assert_eq!(events[8], CoordinatorEvent::SignatureProduced { signature });
+}
+
+#[test]
+fn test_invalid_share_blamed() {
+    use ark_ff::One;
+
+    use crate::frost::Frost;
+
+    let mut frost: Frost = Frost::signature_share(2, 3);
+    let participants: Vec<NonZeroScalar> = frost.participants().collect();
+    let mut coordinator = Coordinator::new(
+        frost.group_pk,
+        b"testing",
+        participants.clone(),
+        Duration::from_secs(60),
+    )
+    .with_public_key_package(frost.public_key_package())
+    .unwrap();
+    for signer in frost.signers() {
+        coordinator
+            .receive_commitment(signer.get_commitments())
+            .unwrap();
+    }
+    let signing_package = coordinator.signing_package().unwrap();
+    let sig_shares: Vec<_> = frost
+        .signers_mut()
+        .map(|signer| signer.sign(&signing_package).unwrap())
+        .collect();
+
+    // a wrong share is rejected with a certificate that holds up on its own
+    let wrong_share = SignatureShare {
+        z_i: sig_shares[0].z_i + ScalarField::<Ed25519Sha512>::one(),
+        ..sig_shares[0]
+    };
+    match coordinator.receive_share(wrong_share) {
+        Err(FrostError::InvalidSignatureShare(certificate)) => {
+            assert_eq!(certificate.identifier(), participants[0]);
+            assert!(certificate.verify());
+        }
+        result => panic!("unexpected result {result:?}"),
+    }
+    assert_eq!(coordinator.missing(), participants);
+
+    for sig_share in sig_shares {
+        coordinator.receive_share(sig_share).unwrap();
+    }
+    let signature = coordinator.aggregate().unwrap();
+    assert!(frost.verify(b"testing", signature));
+
+    // a package of another group is refused
+    let other: Frost =
+        Frost::signature_share_from_seed(2, &[crate::frost::SignerRole::Regular; 3], [1; 32]);
+    assert!(
+        Coordinator::new(
+            frost.group_pk,
+            b"testing",
+            participants,
+            Duration::from_secs(60)
+        )
+        .with_public_key_package(other.public_key_package())
+        .is_err()
+    );
+}
+
+#[test]
+fn test_coordinator_timeout() {
+    use crate::frost::Frost;
+
+    let frost: Frost = Frost::signature_share(2, 3);
+    let participants: Vec<NonZeroScalar> = frost.participants().collect();
+    let metrics = Arc::new(Metrics::new());
+    let mut coordinator = Coordinator::new(
+        frost.group_pk,
+        b"testing",
+        participants.clone(),
+        Duration::ZERO,
+    )
+    .with_metrics(Arc::clone(&metrics));
+    std::thread::sleep(Duration::from_millis(1));
+
+    let result = coordinator.receive_commitment(frost.signers[0].get_commitments());
+
+    assert_eq!(
+        result,
+        Err(FrostError::Timeout {
+            round: Round::Commitment,
+            missing: participants.iter().map(NonZeroScalar::to_scalar).collect(),
+        })
+    );
+    assert!(coordinator.aggregate().is_err());
+
+    let rendered = metrics.render();
+    assert!(rendered.contains("frost_coordinator_sessions_started_total 1\n"));
+    assert!(rendered.contains("frost_coordinator_sessions_aborted_total 1\n"));
+    let failures = format!("signer=\"{}\"}} 1\n", participants[0].to_scalar());
+    assert!(rendered.contains(&failures));
+}
+
+#[test]
+fn test_commitment_proofs() {
+    use crate::frost::Frost;
+
+    let frost: Frost = Frost::signature_share(2, 3);
+    let participants = frost.participants().collect();
+    let mut coordinator = Coordinator::new(
+        frost.group_pk,
+        b"testing",
+        participants,
+        Duration::from_secs(60),
+    )
+    .with_commitment_proofs(frost.generator);
+    let mut rng = ark_std::test_rng();
+
+    let (first, second) = (&frost.signers[0], &frost.signers[1]);
+    assert_eq!(
+        coordinator.receive_commitment(first.get_commitments()),
+        Err(FrostError::InvalidCommitmentProof(first.get_identifier()))
+    );
+    // a proof for other commitments does not verify
+    assert_eq!(
+        coordinator.receive_proven_commitment(
+            first.get_commitments(),
+            &second.prove_commitments(&mut rng).unwrap()
+        ),
+        Err(FrostError::InvalidCommitmentProof(first.get_identifier()))
+    );
+
+    // the blind signer proves its zero binding nonce too
+    for signer in frost.signers() {
+        let proof =
+            CommitmentProof::from_bytes(&signer.prove_commitments(&mut rng).unwrap().to_bytes())
+                .unwrap();
+        coordinator
+            .receive_proven_commitment(signer.get_commitments(), &proof)
+            .unwrap();
+    }
+    assert!(coordinator.signing_package().is_ok());
+}
+
+#[test]
+fn test_retry_without() {
+    use crate::frost::{Frost, FrostSigner, SignerRole};
+
+    let roles = [
+        SignerRole::Regular,
+        SignerRole::Regular,
+        SignerRole::Regular,
+        SignerRole::Blind,
+    ];
+    let mut frost: Frost = Frost::signature_share_with_roles(2, &roles);
+    let ids: Vec<_> = frost.participants().collect();
+    let mut coordinator = Coordinator::new(
+        frost.group_pk,
+        b"testing",
+        vec![ids[0], ids[1]],
+        Duration::from_secs(60),
+    )
+    .with_standby(vec![ids[2]]);
+
+    for signer in &frost.signers[..2] {
+        coordinator
+            .receive_commitment(signer.get_commitments())
+            .unwrap();
+    }
+    let signing_package = coordinator.signing_package().unwrap();
+    let sig_share = frost.signers[0].sign(&signing_package).unwrap();
+    coordinator.receive_share(sig_share).unwrap();
+
+    // the second signer drops out during round two
+    assert_eq!(
+        coordinator.retry_without(&[ids[3]]),
+        Err(FrostError::UnknownParticipant(ids[3].to_scalar()))
+    );
+    coordinator.retry_without(&[ids[1]]).unwrap();
+    assert_eq!(coordinator.missing(), [ids[0], ids[2]]);
+    // shares for the abandoned attempt are rejected
+    assert!(coordinator.receive_share(sig_share).is_err());
+
+    let mut signers: Vec<_> = [ids[0], ids[2]]
+        .iter()
+        .map(|id| FrostSigner::from_key_package(&frost.key_package(*id).unwrap()))
+        .collect();
+    for signer in &signers {
+        coordinator
+            .receive_commitment(signer.get_commitments())
+            .unwrap();
+    }
+    let signing_package = coordinator.signing_package().unwrap();
+    assert_eq!(signing_package.counter, 1);
+    for signer in &mut signers {
+        let sig_share = signer.sign(&signing_package).unwrap();
+        coordinator.receive_share(sig_share).unwrap();
+    }
+    let signature = coordinator.aggregate().unwrap();
+    assert!(frost.verify(b"testing", signature));
+
+    // no standby signers are left
+    let mut coordinator = Coordinator::new(
+        frost.group_pk,
+        b"testing",
+        vec![ids[0], ids[1]],
+        Duration::from_secs(60),
+    );
+    assert_eq!(
+        coordinator.retry_without(&[ids[0]]),
+        Err(FrostError::InsufficientSigners {
+            available: 1,
+            threshold: 2
+        })
+    );
+
+    // with the public key package, only the threshold of the group must remain
+    let mut coordinator = Coordinator::new(
+        frost.group_pk,
+        b"testing",
+        vec![ids[0], ids[1], ids[2]],
+        Duration::from_secs(60),
+    )
+    .with_public_key_package(frost.public_key_package())
+    .unwrap();
+    assert_eq!(
+        coordinator.retry_without(&[ids[0], ids[1]]),
+        Err(FrostError::InsufficientSigners {
+            available: 1,
+            threshold: 2
+        })
+    );
+    coordinator.retry_without(&[ids[0]]).unwrap();
+    assert_eq!(coordinator.participants, vec![ids[1], ids[2]]);
+}
+
+#[test]
+fn test_commitments_independent_of_arrival_order() {
+    use crate::frost::Frost;
+
+    let frost: Frost = Frost::signature_share(3, 5);
+    let participants: Vec<NonZeroScalar> = frost.participants().collect();
+    let coordinator = Coordinator::new(
+        frost.group_pk,
+        b"testing",
+        participants,
+        Duration::from_secs(60),
+    );
+
+    let mut in_order = coordinator.clone();
+    let mut reversed = coordinator;
+    for signer in frost.signers() {
+        in_order
+            .receive_commitment(signer.get_commitments())
+            .unwrap();
+    }
+    for signer in frost.signers.iter().rev() {
+        reversed
+            .receive_commitment(signer.get_commitments())
+            .unwrap();
+    }
+    assert_eq!(
+        reversed.receive_commitment(frost.signers[0].get_commitments()),
+        Err(FrostError::DuplicateCommitment(
+            frost.signers[0].get_identifier()
+        ))
+    );
+    assert_eq!(
+        in_order.signing_package().unwrap().to_bytes(),
+        reversed.signing_package().unwrap().to_bytes()
+    );
+}
+
+#[test]
+fn test_coordinator_handoff() {
+    use crate::frost::Frost;
+
+    let mut frost: Frost = Frost::signature_share(2, 3);
+    let ids: Vec<_> = frost.participants().collect();
+    let mut coordinator = Coordinator::new(
+        frost.group_pk,
+        b"testing",
+        ids.clone(),
+        Duration::from_secs(60),
+    )
+    .with_public_key_package(frost.public_key_package())
+    .unwrap();
+    for signer in &frost.signers[..2] {
+        coordinator
+            .receive_commitment(signer.get_commitments())
+            .unwrap();
+    }
+    let handoff = Handoff::<Ed25519Sha512>::from_bytes(&coordinator.handoff().to_bytes()).unwrap();
+    assert_eq!(handoff.get_session_id(), coordinator.get_session_id());
+
+    // the coordinator disappears, and so does the first participant in succession
+    let succession = coordinator.succession();
+    assert!(handoff.clone().take_over(succession[1], &[]).is_err());
+    let mut successor = handoff.take_over(succession[1], &succession[..1]).unwrap();
+    assert_eq!(successor.missing(), [ids[2]]);
+
+    successor
+        .receive_commitment(frost.signers[2].get_commitments())
+        .unwrap();
+    let signing_package = successor.signing_package().unwrap();
+    for signer in &mut frost.signers {
+        successor
+            .receive_share(signer.sign(&signing_package).unwrap())
+            .unwrap();
+    }
+    let signature = successor.aggregate().unwrap();
+    assert!(frost.verify(b"testing", signature));
+}
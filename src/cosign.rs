@@ -0,0 +1,229 @@
+//! This module implements co-signing by two independent groups.
+//!
+//! Some artifacts need the approval of two groups with keys of their own, e.g. a user group and
+//! the group of its insurer. A `CoSigningSession` runs a signing session in each group over the
+//! same `CoSigningTranscript`, which binds the message to the keys of both groups, so that neither
+//! signature can be presented as a co-signature with a third group.
+//!
+//! The sessions run in lockstep: both groups complete round one before either group produces a
+//! signature share, and both collect all their shares before either signature is aggregated. If
+//! either session fails, the co-signing session returns no signature at all, and the signature
+//! the other group may have aggregated is discarded.
+use crate::{
+    ciphersuite::{Ciphersuite, Ed25519Sha512, Element},
+    error::FrostError,
+    keys::{GroupPublicKey, verify_message_signature},
+    schnorr::SchnorrSignature,
+    session::{Aborted, Session, SigningSession, SigningSessionBuilder},
+};
+
+/// Domain separator prefixed to the encoding of a co-signing transcript.
+const COSIGN_CONTEXT: &[u8] = b"FROST-COSIGN-v1";
+
+/// The public key and generator of a group.
+pub type GroupKey<C> = (GroupPublicKey<C>, Element<C>);
+
+/// What both groups of a co-signing session sign: the message, bound to the keys of both groups.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoSigningTranscript<C: Ciphersuite = Ed25519Sha512> {
+    /// The key and generator of each group, in the order the sessions were given.
+    pub groups: [GroupKey<C>; 2],
+    pub message: Vec<u8>,
+}
+
+impl<C: Ciphersuite> CoSigningTranscript<C> {
+    /// Encodes the transcript as the message both groups sign: the context string,
+    /// `group_pk_1 || generator_1 || group_pk_2 || generator_2 || message`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = COSIGN_CONTEXT.to_vec();
+        for (group_pk, generator) in &self.groups {
+            bytes.extend(C::serialize_element(&group_pk.to_element()));
+            bytes.extend(C::serialize_element(generator));
+        }
+        bytes.extend_from_slice(&self.message);
+        bytes
+    }
+}
+
+/// The signatures of both groups over a `CoSigningTranscript`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoSignature<C: Ciphersuite = Ed25519Sha512> {
+    pub transcript: CoSigningTranscript<C>,
+    /// The signature of each group, in the order of `transcript.groups`.
+    pub signatures: [SchnorrSignature<C>; 2],
+}
+
+impl<C: Ciphersuite> CoSignature<C> {
+    /// Verifies that the co-signature binds the expected `groups`, in order, and that each group
+    /// signed the transcript.
+    pub fn verify(&self, groups: [GroupKey<C>; 2]) -> Result<(), FrostError<C>> {
+        if self.transcript.groups != groups {
+            return Err(FrostError::InvalidCoSignature(
+                "the transcript binds other groups".to_string(),
+            ));
+        }
+        let bytes = self.transcript.to_bytes();
+        for ((group_pk, generator), signature) in groups.iter().zip(&self.signatures) {
+            if !verify_message_signature(signature, &bytes, group_pk.to_element(), *generator) {
+                return Err(FrostError::InvalidCoSignature(format!(
+                    "the signature of group {} does not verify",
+                    group_pk.group_id()
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A signing session in each of two groups over the same `CoSigningTranscript`.
+#[derive(Debug)]
+pub struct CoSigningSession<C: Ciphersuite = Ed25519Sha512> {
+    transcript: CoSigningTranscript<C>,
+    sessions: [SigningSession<C>; 2],
+}
+
+impl<C: Ciphersuite> CoSigningSession<C> {
+    /// Creates the sessions of both groups over `message`, from builders configured with the
+    /// participants, timeout and policy of each group. The message of the builders is replaced
+    /// by the transcript.
+    ///
+    /// Returns an error if both builders are for the same group, or if either fails to build.
+    pub fn new(
+        message: &[u8],
+        first: SigningSessionBuilder<'_, C>,
+        second: SigningSessionBuilder<'_, C>,
+    ) -> Result<Self, FrostError<C>> {
+        let group_key = |builder: &SigningSessionBuilder<'_, C>| {
+            (builder.frost().group_pk, builder.frost().generator)
+        };
+        let transcript = CoSigningTranscript {
+            groups: [group_key(&first), group_key(&second)],
+            message: message.to_vec(),
+        };
+        if transcript.groups[0].0 == transcript.groups[1].0 {
+            return Err(FrostError::InvalidSession(
+                "both sessions sign for the same group".to_string(),
+            ));
+        }
+
+        let bytes = transcript.to_bytes();
+        let sessions = [
+            first.message(&bytes).build()?,
+            second.message(&bytes).build()?,
+        ];
+        Ok(CoSigningSession {
+            transcript,
+            sessions,
+        })
+    }
+
+    pub fn transcript(&self) -> &CoSigningTranscript<C> {
+        &self.transcript
+    }
+
+    /// Runs the sessions of both groups in lockstep and returns both signatures.
+    ///
+    /// Returns `FrostError::CoSigningAborted`, naming the group that failed, and no signature if
+    /// either session aborts.
+    pub fn run(self) -> Result<CoSignature<C>, FrostError<C>> {
+        let CoSigningSession {
+            transcript,
+            sessions: [first, second],
+        } = self;
+        let group_ids = transcript.groups.map(|(group_pk, _)| group_pk.group_id());
+        let aborted = |index: usize| {
+            move |session: Session<Aborted<C>, C>| FrostError::CoSigningAborted {
+                group: group_ids[index],
+                error: Box::new(session.error().clone()),
+            }
+        };
+
+        // round one in both groups before any signature share is produced
+        let first = first.commit().map_err(aborted(0))?;
+        let second = second.commit().map_err(aborted(1))?;
+
+        // all shares of both groups before any signature is aggregated
+        let first = first.collect_shares().map_err(aborted(0))?;
+        let second = second.collect_shares().map_err(aborted(1))?;
+
+        let first = first.aggregate().map_err(aborted(0))?;
+        let second = second.aggregate().map_err(aborted(1))?;
+        Ok(CoSignature {
+            transcript,
+            signatures: [first.signature(), second.signature()],
+        })
+    }
+}
+
+#[test]
+fn test_cosigning_session() {
+    use std::time::Duration;
+
+    use crate::frost::{Frost, SignerRole};
+
+    let roles = [SignerRole::Regular, SignerRole::Regular, SignerRole::Blind];
+    let user: Frost = Frost::signature_share_from_seed(2, &roles, [1; 32]);
+    let insurer: Frost = Frost::signature_share_from_seed(2, &roles, [2; 32]);
+    let groups = [
+        (user.group_pk, user.generator),
+        (insurer.group_pk, insurer.generator),
+    ];
+
+    let session = CoSigningSession::new(
+        b"withdraw 1 BTC",
+        SigningSession::builder(&user),
+        SigningSession::builder(&insurer),
+    )
+    .unwrap();
+    assert_eq!(session.transcript().groups, groups);
+    let cosignature = session.run().unwrap();
+    cosignature.verify(groups).unwrap();
+    let bytes = cosignature.transcript.to_bytes();
+    assert!(user.verify(&bytes, cosignature.signatures[0]));
+    assert!(insurer.verify(&bytes, cosignature.signatures[1]));
+
+    // the co-signature does not verify for groups in another order, or with a swapped signature
+    assert!(cosignature.verify([groups[1], groups[0]]).is_err());
+    let mut swapped = cosignature.clone();
+    swapped.signatures.swap(0, 1);
+    assert!(matches!(
+        swapped.verify(groups),
+        Err(FrostError::InvalidCoSignature(_))
+    ));
+
+    // a group cannot co-sign with itself
+    assert!(
+        CoSigningSession::new(
+            b"withdraw 1 BTC",
+            SigningSession::builder(&user),
+            SigningSession::builder(&user),
+        )
+        .is_err()
+    );
+
+    // if the session of the insurer times out, no signature is returned
+    let session = CoSigningSession::new(
+        b"withdraw 1 BTC",
+        SigningSession::builder(&user),
+        SigningSession::builder(&insurer).timeout(Duration::ZERO),
+    )
+    .unwrap();
+    match session.run() {
+        Err(FrostError::CoSigningAborted { group, error }) => {
+            assert_eq!(group, insurer.group_pk.group_id());
+            assert!(matches!(*error, FrostError::Timeout { .. }));
+        }
+        result => panic!("unexpected result {result:?}"),
+    }
+
+    // a session that cannot build, e.g. below the threshold of the insurer, signs nothing
+    let insurer_ids: Vec<_> = insurer.participants().collect();
+    assert!(
+        CoSigningSession::new(
+            b"withdraw 1 BTC",
+            SigningSession::builder(&user),
+            SigningSession::builder(&insurer).participants(vec![insurer_ids[0]]),
+        )
+        .is_err()
+    );
+}
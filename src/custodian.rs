@@ -0,0 +1,931 @@
+//! This module implements the collaborative custodian as a standalone service.
+//!
+//! The custodian holds the `KeyPackage` of a blind signer. For every signing request it issues
+//! fresh nonce commitments, and later signs the `SigningPackage` containing them, at most once.
+//! Requests are subject to a `CustodianPolicy`, signing packages must carry increasing counters so
+//! that captured requests cannot be replayed, and every commitment is recorded in a
+//! `NonceLedger` so that a restarted custodian cannot be made to sign with it again.
+//!
+//! `serve` exposes a custodian over TCP with a line-based protocol, one request per line:
+//!
+//! - `COMMIT` is answered with `OK <commitments>`, the hex encoding of fresh `SigningCommitments`.
+//! - `SIGN <package> [<label>]`, with the hex encoding of a `SigningPackage` and an optional
+//!   quota label, is answered with `OK <share>`, the hex encoding of the custodian's
+//!   `SignatureShare`.
+//!
+//! - `BATCH <count>` is answered with `OK <batch>`, the hex encoding of a `NonceBatch` of `count`
+//!   fresh commitments.
+//!
+//! - `INTENT <intent>`, with the hex encoding of an `Intent`, is answered with `OK <remaining>`,
+//!   the milliseconds left until the message can be signed as an 8-byte little-endian integer,
+//!   or with an empty `OK` if the intent was cancelled.
+//!
+//! - `COMPLETE <session> <signature>`, with the hex encodings of the session id of a signed
+//!   package and of the final `SchnorrSignature`, is answered with an empty `OK` once the
+//!   custodian has verified the signature over the message it co-signed.
+//!
+//! Failed requests are answered with `ERR <reason>`, and a request longer than
+//! `MAX_REQUEST_LEN` bytes closes the connection.
+//!
+//! A process holding shares of many groups runs a `Custodian` per group in `CustodianGroups`, and
+//! serves them with `serve_groups`. Requests are then prefixed with the group they are for, as
+//! `GROUP <group id> <request>`, and `PING` is answered for the process as a whole.
+use std::{
+    collections::{BTreeMap, VecDeque},
+    io::{self, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex, PoisonError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use sha2::Digest;
+
+use crate::{
+    batch::NonceBatch,
+    ciphersuite::{Ciphersuite, Ed25519Sha512},
+    error::FrostError,
+    frost::{FrostSigner, SessionId, SignatureShare, SigningPackage},
+    helper::{
+        InterpolationCache, NonZeroScalar, decode_hex, encode_hex, read_bounded_line, secure_rng,
+    },
+    keys::{GroupId, KeyPackage},
+    ledger::{InMemoryNonceLedger, NonceLedger},
+    nonces::SigningCommitments,
+    quota::QuotaTracker,
+    schnorr::SchnorrSignature,
+    timelock::{Intent, IntentRegistry},
+};
+
+/// Maximum length of a request line, in bytes. Longer requests close the connection.
+pub const MAX_REQUEST_LEN: usize = 1 << 16;
+
+/// Limits on the requests a custodian serves.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CustodianPolicy {
+    /// Maximum number of signature shares issued within any `window`.
+    pub max_signatures: usize,
+    /// The sliding window `max_signatures` applies to.
+    pub window: Duration,
+    /// Maximum number of issued commitments that have not been used for signing yet.
+    pub max_pending: usize,
+    /// How long an issued commitment can be used for; unused commitments older than this are
+    /// discarded, so that unanswered `COMMIT` requests do not hold on to the `max_pending` slots.
+    pub pending_ttl: Duration,
+    /// Only sign packages whose message has the length of a `message_digest`, refusing requests
+    /// that disclose the raw message.
+    pub digest_only: bool,
+}
+
+/// A blind signer serving signing requests.
+#[derive(Debug)]
+pub struct Custodian<C: Ciphersuite = Ed25519Sha512> {
+    key_package: KeyPackage<C>,
+    policy: CustodianPolicy,
+
+    /// Signers holding the nonces of the commitments issued but not used yet, with the time
+    /// they were issued at.
+    pending: Vec<(FrostSigner<C>, Instant)>,
+
+    /// Record of every commitment issued and used.
+    ledger: Box<dyn NonceLedger>,
+
+    /// Times at which the signature shares within the current window were issued.
+    issued: VecDeque<Instant>,
+
+    /// Counter of the last signing package signed. A retried session keeps its session id under
+    /// a higher counter, so only the counter tells a replay apart.
+    last_counter: Option<u64>,
+
+    /// Signing packages signed whose final signature has not been reported yet, oldest first.
+    unconfirmed: VecDeque<SigningPackage<C>>,
+
+    /// Number of sessions completed with a verified signature.
+    completed: u64,
+
+    /// Per-label quotas, if configured.
+    quotas: Option<QuotaTracker>,
+
+    /// Intents a message must be registered with before it is signed, if timelocked.
+    timelock: Option<IntentRegistry<C>>,
+
+    /// Interpolation values of the participant sets signed with before.
+    interpolation_cache: Arc<InterpolationCache<C>>,
+}
+
+impl<C: Ciphersuite> Custodian<C> {
+    /// Creates a custodian that keeps its nonce ledger in memory.
+    pub fn new(key_package: KeyPackage<C>, policy: CustodianPolicy) -> Self {
+        Self::with_ledger(key_package, policy, Box::new(InMemoryNonceLedger::new()))
+    }
+
+    pub fn with_ledger(
+        key_package: KeyPackage<C>,
+        policy: CustodianPolicy,
+        ledger: Box<dyn NonceLedger>,
+    ) -> Self {
+        Custodian {
+            key_package,
+            policy,
+            pending: Vec::new(),
+            ledger,
+            issued: VecDeque::new(),
+            last_counter: None,
+            unconfirmed: VecDeque::new(),
+            completed: 0,
+            quotas: None,
+            timelock: None,
+            interpolation_cache: Arc::new(InterpolationCache::new()),
+        }
+    }
+
+    /// Enforces per-label `quotas` on signing requests.
+    pub fn with_quotas(mut self, quotas: QuotaTracker) -> Self {
+        self.quotas = Some(quotas);
+        self
+    }
+
+    /// Returns the per-label quotas, to query the usage of a label.
+    pub fn quotas(&self) -> Option<&QuotaTracker> {
+        self.quotas.as_ref()
+    }
+
+    /// Only signs messages whose intent was registered with `timelock` long enough ago.
+    pub fn with_timelock(mut self, timelock: IntentRegistry<C>) -> Self {
+        self.timelock = Some(timelock);
+        self
+    }
+
+    /// Returns the registry of intents, to query their countdowns.
+    pub fn timelock(&self) -> Option<&IntentRegistry<C>> {
+        self.timelock.as_ref()
+    }
+
+    /// Registers or cancels `intent`, returning the time left until its message can be signed,
+    /// or `None` if it was cancelled.
+    ///
+    /// Returns an error if the custodian is not timelocked or the registry refuses the intent.
+    pub fn submit_intent(&mut self, intent: &Intent<C>) -> Result<Option<Duration>, FrostError<C>> {
+        self.timelock
+            .as_mut()
+            .ok_or_else(|| FrostError::PolicyViolation("custodian is not timelocked".to_string()))?
+            .submit(intent)
+    }
+
+    pub fn get_identifier(&self) -> NonZeroScalar<C> {
+        self.key_package.identifier
+    }
+
+    /// Returns the identifier of the group this custodian holds a share of.
+    pub fn group_id(&self) -> GroupId {
+        self.key_package.group_pk.group_id()
+    }
+
+    /// Round one: issues fresh nonce commitments for a new signing request.
+    ///
+    /// Returns an error if too many issued commitments are still unused, or if the commitments
+    /// cannot be recorded in the ledger.
+    pub fn commit(&mut self) -> Result<SigningCommitments<C>, FrostError<C>> {
+        self.discard_expired();
+        if self.pending.len() >= self.policy.max_pending {
+            return Err(FrostError::PolicyViolation(format!(
+                "more than {} unused commitments",
+                self.policy.max_pending
+            )));
+        }
+
+        let signer = FrostSigner::from_key_package(&self.key_package)
+            .with_interpolation_cache(Arc::clone(&self.interpolation_cache));
+        let commitments = signer.get_commitments();
+        if !self
+            .ledger
+            .issue(&commitments.to_bytes())
+            .map_err(storage_error)?
+        {
+            return Err(FrostError::NonceReused(self.get_identifier().to_scalar()));
+        }
+        self.pending.push((signer, Instant::now()));
+        Ok(commitments)
+    }
+
+    /// Discards the signers of commitments issued more than `pending_ttl` ago.
+    fn discard_expired(&mut self) {
+        let ttl = self.policy.pending_ttl;
+        self.pending.retain(|(_, issued)| issued.elapsed() < ttl);
+    }
+
+    /// Issues `count` commitments at once and returns them as a signed `NonceBatch`, to be
+    /// published ahead of the signing requests using them.
+    ///
+    /// Returns an error if the batch would exceed the limit on unused commitments, or if the
+    /// commitments cannot be recorded in the ledger.
+    pub fn publish_batch(&mut self, count: usize) -> Result<NonceBatch<C>, FrostError<C>> {
+        self.discard_expired();
+        // the count comes off the wire, so the sum must not wrap around below the limit
+        let pending = self.pending.len().checked_add(count);
+        if pending.is_none_or(|pending| pending > self.policy.max_pending) {
+            return Err(FrostError::PolicyViolation(format!(
+                "more than {} unused commitments",
+                self.policy.max_pending
+            )));
+        }
+
+        let commitments = (0..count)
+            .map(|_| self.commit())
+            .collect::<Result<_, _>>()?;
+        Ok(NonceBatch::sign(
+            &self.key_package,
+            commitments,
+            &mut secure_rng(),
+        ))
+    }
+
+    /// Round two: signs `signing_package` with the nonces of the commitments it contains.
+    ///
+    /// The nonces are discarded afterwards, so every issued commitment is used for at most one
+    /// signature share. Returns an error if the package is refused by the `digest_only` policy,
+    /// if it does not carry a counter higher than every
+    /// package signed before, if it does not contain commitments issued by this custodian, if the
+    /// rate limit has been reached, if a timelocked message has no intent whose countdown has
+    /// finished, or if the ledger does not confirm that the commitments are
+    /// unused.
+    ///
+    /// Unlabeled requests count towards the quota of the empty label.
+    pub fn sign(
+        &mut self,
+        signing_package: &SigningPackage<C>,
+    ) -> Result<SignatureShare<C>, FrostError<C>> {
+        self.sign_labeled(signing_package, "")
+    }
+
+    /// Like `sign`, but counts the signature towards the quota of `label`, returning an error if
+    /// the quota of the current period is used up.
+    pub fn sign_labeled(
+        &mut self,
+        signing_package: &SigningPackage<C>,
+        label: &str,
+    ) -> Result<SignatureShare<C>, FrostError<C>> {
+        if self.policy.digest_only
+            && signing_package.message.len() != <C::Hash as Digest>::output_size()
+        {
+            return Err(FrostError::PolicyViolation(
+                "only message digests are signed".to_string(),
+            ));
+        }
+        if let Some(counter) = self.last_counter
+            && signing_package.counter <= counter
+        {
+            return Err(FrostError::ReplayedRequest(signing_package.session_id));
+        }
+
+        self.discard_expired();
+        let identifier = self.get_identifier();
+        let commitments = signing_package
+            .commitments
+            .iter()
+            .find(|commitments| commitments.identifier == identifier)
+            .ok_or(FrostError::MissingCommitment(identifier.to_scalar()))?;
+        let position = self
+            .pending
+            .iter()
+            .position(|(signer, _)| signer.get_commitments() == *commitments)
+            .ok_or(FrostError::CommitmentMismatch(identifier.to_scalar()))?;
+
+        let now = Instant::now();
+        while let Some(issued) = self.issued.front() {
+            if now.duration_since(*issued) < self.policy.window {
+                break;
+            }
+            self.issued.pop_front();
+        }
+        if self.issued.len() >= self.policy.max_signatures {
+            return Err(FrostError::PolicyViolation(format!(
+                "more than {} signatures within {:?}",
+                self.policy.max_signatures, self.policy.window
+            )));
+        }
+        if let Some(quotas) = &self.quotas
+            && quotas.remaining(label) == 0
+        {
+            return Err(FrostError::PolicyViolation(format!(
+                "label {label:?} exceeded its quota of {} signatures per {:?}",
+                quotas.quota().max_signatures,
+                quotas.quota().period
+            )));
+        }
+
+        if let Some(timelock) = &self.timelock {
+            timelock.check(&signing_package.message)?;
+        }
+
+        // nothing is recorded until the share is produced, so a package the signer refuses leaves
+        // the nonces, the replay counter, the quota of the label and the intent as they were
+        let sig_share = self.pending[position].0.sign(signing_package)?;
+        self.pending.swap_remove(position);
+        if !self
+            .ledger
+            .consume(&commitments.to_bytes())
+            .map_err(storage_error)?
+        {
+            return Err(FrostError::NonceReused(identifier.to_scalar()));
+        }
+        if let Some(timelock) = &mut self.timelock {
+            timelock.consume(&signing_package.message)?;
+        }
+        self.last_counter = Some(signing_package.counter);
+        if let Some(quotas) = &mut self.quotas {
+            quotas.record(label).map_err(storage_error)?;
+        }
+        self.issued.push_back(now);
+        self.unconfirmed.push_back(signing_package.clone());
+        if self.unconfirmed.len() > self.policy.max_signatures {
+            self.unconfirmed.pop_front();
+        }
+
+        Ok(sig_share)
+    }
+
+    /// Completes the session `session_id` with its final `signature`, after verifying that it is
+    /// the group's signature over the message this custodian co-signed, with the group commitment
+    /// of that session.
+    ///
+    /// Returns `FrostError::UnknownSession` if no package of the session awaits completion, and
+    /// `FrostError::InvalidSession` if the signature does not verify. Only the latest
+    /// `max_signatures` packages signed await completion.
+    pub fn complete(
+        &mut self,
+        session_id: SessionId,
+        signature: SchnorrSignature<C>,
+    ) -> Result<(), FrostError<C>> {
+        let position = self
+            .unconfirmed
+            .iter()
+            .position(|signing_package| signing_package.session_id == session_id)
+            .ok_or(FrostError::UnknownSession(session_id))?;
+
+        let group_pk = self.key_package.group_pk;
+        let (group_commitment, challenge) =
+            self.unconfirmed[position].group_commitment_and_challenge(group_pk)?;
+        if signature.R != group_commitment
+            || self.key_package.generator * signature.s
+                != group_commitment + group_pk.to_element() * challenge.to_scalar()
+        {
+            return Err(FrostError::InvalidSession(
+                "the signature does not verify over the co-signed message".to_string(),
+            ));
+        }
+
+        self.unconfirmed.remove(position);
+        self.completed += 1;
+        Ok(())
+    }
+
+    /// Returns the sessions signed whose final signature has not been reported yet.
+    pub fn unconfirmed_sessions(&self) -> impl Iterator<Item = SessionId> + '_ {
+        self.unconfirmed
+            .iter()
+            .map(|signing_package| signing_package.session_id)
+    }
+
+    /// Returns the number of sessions completed with a verified signature.
+    pub fn completed_sessions(&self) -> u64 {
+        self.completed
+    }
+
+    /// Handles a single request line of the wire protocol and returns the response line.
+    pub fn handle(&mut self, request: &str) -> String {
+        let response = match request.trim().split_once(' ') {
+            None if request.trim() == "COMMIT" => {
+                self.commit().map(|commitments| commitments.to_bytes())
+            }
+            None if request.trim() == "PING" => Ok(Vec::new()),
+            Some(("SIGN", arguments)) => {
+                let (package, label) = arguments.split_once(' ').unwrap_or((arguments, ""));
+                match decode_hex(package).and_then(|bytes| SigningPackage::from_bytes(&bytes).ok())
+                {
+                    Some(signing_package) => self
+                        .sign_labeled(&signing_package, label)
+                        .map(|sig_share| sig_share.to_bytes()),
+                    None => return "ERR malformed signing package".to_string(),
+                }
+            }
+            Some(("BATCH", count)) => match count.parse() {
+                Ok(count) => self.publish_batch(count).map(|batch| batch.to_bytes()),
+                Err(_) => return "ERR malformed batch size".to_string(),
+            },
+            Some(("INTENT", intent)) => {
+                match decode_hex(intent).and_then(|bytes| Intent::from_bytes(&bytes).ok()) {
+                    Some(intent) => self.submit_intent(&intent).map(|remaining| {
+                        remaining.map_or(Vec::new(), |remaining| {
+                            (remaining.as_millis() as u64).to_le_bytes().to_vec()
+                        })
+                    }),
+                    None => return "ERR malformed intent".to_string(),
+                }
+            }
+            Some(("COMPLETE", arguments)) => {
+                let completion = arguments.split_once(' ').and_then(|(session, signature)| {
+                    let session_id = decode_hex(session)?.try_into().ok()?;
+                    let signature = SchnorrSignature::from_bytes(&decode_hex(signature)?).ok()?;
+                    Some((session_id, signature))
+                });
+                match completion {
+                    Some((session_id, signature)) => {
+                        self.complete(session_id, signature).map(|()| Vec::new())
+                    }
+                    None => return "ERR malformed completion".to_string(),
+                }
+            }
+            _ => return "ERR unknown request".to_string(),
+        };
+
+        match response {
+            Ok(bytes) => format!("OK {}", encode_hex(&bytes)),
+            Err(error) => format!("ERR {error}"),
+        }
+    }
+}
+
+fn storage_error<C: Ciphersuite>(error: io::Error) -> FrostError<C> {
+    FrostError::Storage(error.to_string())
+}
+
+/// The custodians of the groups a process holds shares of, keyed by `GroupId`.
+#[derive(Debug)]
+pub struct CustodianGroups<C: Ciphersuite = Ed25519Sha512> {
+    custodians: BTreeMap<GroupId, Custodian<C>>,
+}
+
+impl<C: Ciphersuite> CustodianGroups<C> {
+    pub fn new() -> Self {
+        CustodianGroups {
+            custodians: BTreeMap::new(),
+        }
+    }
+
+    /// Adds `custodian` under the identifier of its group, and returns that identifier.
+    ///
+    /// Returns an error if the process already holds a share of that group.
+    pub fn insert(&mut self, custodian: Custodian<C>) -> Result<GroupId, FrostError<C>> {
+        let group_id = custodian.group_id();
+        if self.custodians.contains_key(&group_id) {
+            return Err(FrostError::PolicyViolation(format!(
+                "group {group_id} is already served"
+            )));
+        }
+        self.custodians.insert(group_id, custodian);
+        Ok(group_id)
+    }
+
+    pub fn get(&self, group_id: &GroupId) -> Option<&Custodian<C>> {
+        self.custodians.get(group_id)
+    }
+
+    pub fn get_mut(&mut self, group_id: &GroupId) -> Option<&mut Custodian<C>> {
+        self.custodians.get_mut(group_id)
+    }
+
+    /// Stops serving the group `group_id`, returning its custodian.
+    pub fn remove(&mut self, group_id: &GroupId) -> Option<Custodian<C>> {
+        self.custodians.remove(group_id)
+    }
+
+    pub fn group_ids(&self) -> impl Iterator<Item = GroupId> + '_ {
+        self.custodians.keys().copied()
+    }
+
+    /// Handles a single request line of the wire protocol, `GROUP <group id> <request>`, and
+    /// returns the response line of the group's custodian.
+    pub fn handle(&mut self, request: &str) -> String {
+        let request = request.trim();
+        if request == "PING" {
+            return "OK ".to_string();
+        }
+        let Some(("GROUP", arguments)) = request.split_once(' ') else {
+            return "ERR missing group".to_string();
+        };
+        let Some((group_id, request)) = arguments.split_once(' ') else {
+            return "ERR unknown request".to_string();
+        };
+        let Ok(group_id) = group_id.parse::<GroupId>() else {
+            return "ERR malformed group".to_string();
+        };
+        match self.custodians.get_mut(&group_id) {
+            Some(custodian) => custodian.handle(request),
+            None => format!("ERR unknown group {group_id}"),
+        }
+    }
+}
+
+impl<C: Ciphersuite> Default for CustodianGroups<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Anything answering request lines of the wire protocol.
+trait Handler: Send + 'static {
+    fn handle(&mut self, request: &str) -> String;
+}
+
+impl<C: Ciphersuite> Handler for Custodian<C> {
+    fn handle(&mut self, request: &str) -> String {
+        Custodian::handle(self, request)
+    }
+}
+
+impl<C: Ciphersuite> Handler for CustodianGroups<C> {
+    fn handle(&mut self, request: &str) -> String {
+        CustodianGroups::handle(self, request)
+    }
+}
+
+/// Serves `custodian` on `listener`, handling each connection on its own thread.
+///
+/// Only returns if accepting a connection fails.
+pub fn serve<C: Ciphersuite>(custodian: Custodian<C>, listener: TcpListener) -> io::Result<()> {
+    serve_handler(custodian, listener)
+}
+
+/// Serves the custodians of all `groups` on `listener`, like `serve`.
+pub fn serve_groups<C: Ciphersuite>(
+    groups: CustodianGroups<C>,
+    listener: TcpListener,
+) -> io::Result<()> {
+    serve_handler(groups, listener)
+}
+
+fn serve_handler<H: Handler>(handler: H, listener: TcpListener) -> io::Result<()> {
+    let handler = Arc::new(Mutex::new(handler));
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let handler = Arc::clone(&handler);
+        thread::spawn(move || handle_connection(&handler, stream));
+    }
+
+    Ok(())
+}
+
+fn handle_connection<H: Handler>(handler: &Mutex<H>, stream: TcpStream) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let mut request = String::new();
+    while read_bounded_line(&mut reader, MAX_REQUEST_LEN, &mut request)? > 0 {
+        let response = handler
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .handle(request.trim_end_matches(['\r', '\n']));
+        writeln!(writer, "{response}")?;
+        request.clear();
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_custodian_signs_once() {
+    use crate::{frost::Frost, schnorr::SchnorrSignature};
+
+    let frost: Frost = Frost::signature_share(2, 3);
+    let identifier = frost.participants().last().unwrap();
+    let policy = CustodianPolicy {
+        max_signatures: 1,
+        window: Duration::from_secs(60),
+        max_pending: 2,
+        pending_ttl: Duration::from_secs(60),
+        digest_only: false,
+    };
+    let mut custodian = Custodian::new(frost.key_package(identifier).unwrap(), policy);
+
+    // the custodian co-signs with the first signer
+    let mut signer = frost.signers[0].clone();
+    signer.refresh_nonces();
+    let response = custodian.handle("COMMIT");
+    let commitments =
+        SigningCommitments::from_bytes(&decode_hex(&response["OK ".len()..]).unwrap()).unwrap();
+    let signing_package =
+        SigningPackage::new(b"testing", vec![signer.get_commitments(), commitments]);
+
+    let response = custodian.handle(&format!("SIGN {}", encode_hex(&signing_package.to_bytes())));
+    let custodian_share =
+        SignatureShare::from_bytes(&decode_hex(&response["OK ".len()..]).unwrap()).unwrap();
+    let (group_commitment, _) = signing_package
+        .group_commitment_and_challenge(frost.group_pk)
+        .unwrap();
+    let signature = SchnorrSignature {
+        R: group_commitment,
+        s: frost.signature_aggregate([signer.sign(&signing_package).unwrap(), custodian_share]),
+    };
+    assert!(frost.verify(b"testing", signature));
+
+    // the custodian verifies the final signature before counting the session as completed
+    let session = encode_hex(&signing_package.session_id);
+    let mut forged = signature;
+    forged.s += forged.s;
+    assert!(
+        custodian
+            .handle(&format!(
+                "COMPLETE {session} {}",
+                encode_hex(&forged.to_bytes())
+            ))
+            .starts_with("ERR")
+    );
+    assert_eq!(
+        custodian.handle(&format!(
+            "COMPLETE {session} {}",
+            encode_hex(&signature.to_bytes())
+        )),
+        "OK "
+    );
+    assert_eq!(custodian.completed_sessions(), 1);
+    assert_eq!(custodian.unconfirmed_sessions().count(), 0);
+
+    // a replayed package is rejected, and so are its nonces if the session and counter are changed
+    assert_eq!(
+        custodian.sign(&signing_package),
+        Err(FrostError::ReplayedRequest(signing_package.session_id))
+    );
+    let mut replayed = SigningPackage::new(b"testing", signing_package.commitments.clone());
+    replayed.counter = 1;
+    assert_eq!(
+        custodian.sign(&replayed),
+        Err(FrostError::CommitmentMismatch(identifier.to_scalar()))
+    );
+
+    // the rate limit applies to new commitments
+    let commitments = custodian.commit().unwrap();
+    let mut signing_package =
+        SigningPackage::new(b"testing", vec![signer.get_commitments(), commitments]);
+    signing_package.counter = 2;
+    assert!(matches!(
+        custodian.sign(&signing_package),
+        Err(FrostError::PolicyViolation(_))
+    ));
+}
+
+#[test]
+fn test_custodian_signs_retried_session() {
+    use crate::{
+        coordinator::Coordinator,
+        frost::{Frost, SignerRole},
+        session::Session,
+    };
+
+    let roles = [SignerRole::Regular, SignerRole::Regular, SignerRole::Blind];
+    let mut frost: Frost = Frost::signature_share_with_roles(2, &roles);
+    let ids: Vec<_> = frost.participants().collect();
+    let policy = CustodianPolicy {
+        max_signatures: 10,
+        window: Duration::from_secs(60),
+        max_pending: 2,
+        pending_ttl: Duration::from_secs(60),
+        digest_only: false,
+    };
+    let mut custodian = Custodian::new(frost.key_package(ids[2]).unwrap(), policy);
+    let coordinator = Coordinator::new(
+        frost.group_pk,
+        b"testing",
+        vec![ids[0], ids[2]],
+        Duration::from_secs(60),
+    )
+    .with_standby(vec![ids[1]]);
+
+    // the custodian signs, but the first signer never sends its share
+    let mut session = Session::start(coordinator);
+    session
+        .receive_commitment(frost.signers[0].get_commitments())
+        .unwrap();
+    session
+        .receive_commitment(custodian.commit().unwrap())
+        .unwrap();
+    let mut session = session.send_signing_package().unwrap();
+    let sig_share = custodian.sign(session.signing_package()).unwrap();
+    session.receive_share(sig_share).unwrap();
+    let aborted = session.aggregate().unwrap_err();
+
+    // the retry keeps the session id under a higher counter, which the custodian signs
+    let mut session = aborted.retry_without(&[ids[0]]).unwrap();
+    session
+        .receive_commitment(frost.signers[1].get_commitments())
+        .unwrap();
+    session
+        .receive_commitment(custodian.commit().unwrap())
+        .unwrap();
+    let mut session = session.send_signing_package().unwrap();
+    let signing_package = session.signing_package().clone();
+    let sig_share = custodian.sign(&signing_package).unwrap();
+    session.receive_share(sig_share).unwrap();
+    let sig_share = frost.signers[1].sign(&signing_package).unwrap();
+    session.receive_share(sig_share).unwrap();
+    let session = session.aggregate().unwrap();
+    assert!(frost.verify(b"testing", session.signature()));
+}
+
+#[test]
+fn test_refused_package_charges_nothing() {
+    use crate::{
+        frost::Frost,
+        quota::{Quota, QuotaTracker},
+    };
+
+    let frost: Frost = Frost::signature_share(2, 3);
+    let identifier = frost.participants().last().unwrap();
+    let policy = CustodianPolicy {
+        max_signatures: 10,
+        window: Duration::from_secs(60),
+        max_pending: 2,
+        pending_ttl: Duration::from_secs(60),
+        digest_only: false,
+    };
+    let quota = Quota {
+        max_signatures: 1,
+        period: Duration::from_secs(86400),
+    };
+    let mut custodian = Custodian::new(frost.key_package(identifier).unwrap(), policy)
+        .with_quotas(QuotaTracker::new(quota));
+    let commitments = custodian.commit().unwrap();
+
+    // a package listing the custodian twice is refused by the signer
+    let mut malformed = SigningPackage::new(
+        b"testing",
+        vec![frost.signers[0].get_commitments(), commitments],
+    );
+    malformed.commitments.push(commitments);
+    assert_eq!(
+        custodian.sign_labeled(&malformed, "alice"),
+        Err(FrostError::DuplicateCommitment(identifier.to_scalar()))
+    );
+    assert_eq!(custodian.quotas().unwrap().usage("alice"), 0);
+
+    // the nonces, the counter and the quota are still available to a well-formed package
+    let signing_package = SigningPackage::new(
+        b"testing",
+        vec![frost.signers[0].get_commitments(), commitments],
+    );
+    assert!(custodian.sign_labeled(&signing_package, "alice").is_ok());
+    assert_eq!(custodian.quotas().unwrap().usage("alice"), 1);
+}
+
+#[test]
+fn test_refused_package_keeps_intent() {
+    use crate::frost::Frost;
+
+    let frost: Frost = Frost::signature_share(2, 3);
+    let participants: Vec<_> = frost.participants().collect();
+    let policy = CustodianPolicy {
+        max_signatures: 10,
+        window: Duration::from_secs(60),
+        max_pending: 2,
+        pending_ttl: Duration::from_secs(60),
+        digest_only: false,
+    };
+    let owner = frost.key_package(participants[0]).unwrap();
+    let timelock = IntentRegistry::new(
+        Duration::ZERO,
+        frost.generator,
+        vec![(owner.identifier, owner.verification_share)],
+    );
+    let mut custodian =
+        Custodian::new(frost.key_package(participants[2]).unwrap(), policy).with_timelock(timelock);
+    let intent = Intent::register(&owner, b"testing", &mut ark_std::test_rng());
+    custodian.submit_intent(&intent).unwrap();
+    let commitments = custodian.commit().unwrap();
+
+    // the intent outlives a package the signer refuses
+    let mut malformed = SigningPackage::new(
+        b"testing",
+        vec![frost.signers[0].get_commitments(), commitments],
+    );
+    malformed.commitments.push(commitments);
+    assert!(custodian.sign(&malformed).is_err());
+    assert!(
+        custodian
+            .timelock()
+            .unwrap()
+            .remaining(b"testing")
+            .is_some()
+    );
+
+    // and is consumed once a share is issued
+    let signing_package = SigningPackage::new(
+        b"testing",
+        vec![frost.signers[0].get_commitments(), commitments],
+    );
+    assert!(custodian.sign(&signing_package).is_ok());
+    assert!(
+        custodian
+            .timelock()
+            .unwrap()
+            .remaining(b"testing")
+            .is_none()
+    );
+}
+
+#[test]
+fn test_digest_only_policy() {
+    use crate::{frost::Frost, helper::message_digest};
+
+    let frost: Frost = Frost::signature_share(2, 3);
+    let identifier = frost.participants().last().unwrap();
+    let policy = CustodianPolicy {
+        max_signatures: 10,
+        window: Duration::from_secs(60),
+        max_pending: 2,
+        pending_ttl: Duration::from_secs(60),
+        digest_only: true,
+    };
+    let mut custodian = Custodian::new(frost.key_package(identifier).unwrap(), policy);
+    let signer = &frost.signers[0];
+
+    let commitments = vec![signer.get_commitments(), custodian.commit().unwrap()];
+    let raw = SigningPackage::new(b"transfer 1 BTC", commitments.clone());
+    assert!(matches!(
+        custodian.sign(&raw),
+        Err(FrostError::PolicyViolation(_))
+    ));
+
+    let digest = message_digest::<Ed25519Sha512>(b"wallet", b"transfer 1 BTC");
+    let signing_package = SigningPackage::new(&digest, commitments);
+    assert!(custodian.sign(&signing_package).is_ok());
+}
+
+#[test]
+fn test_custodian_groups() {
+    use std::{io::BufRead, net::TcpListener};
+
+    use crate::{
+        client::CustodianClient,
+        frost::{Frost, SignerRole},
+    };
+
+    let policy = CustodianPolicy {
+        max_signatures: 10,
+        window: Duration::from_secs(60),
+        max_pending: 2,
+        pending_ttl: Duration::from_secs(60),
+        digest_only: false,
+    };
+    let mut groups = CustodianGroups::new();
+    let mut group_ids = Vec::new();
+    // two groups over the same generator, with independent keys
+    let generator = Frost::<Ed25519Sha512>::signature_share(2, 2).generator;
+    let roles = [SignerRole::Regular, SignerRole::Blind];
+    for _ in 0..2 {
+        let frost: Frost = Frost::signature_share_with_rng(2, &roles, generator, &mut secure_rng());
+        let identifier = frost.participants().last().unwrap();
+        let custodian = Custodian::new(frost.key_package(identifier).unwrap(), policy);
+        group_ids.push(groups.insert(custodian).unwrap());
+    }
+    assert_eq!(groups.handle("COMMIT"), "ERR missing group");
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || serve_groups(groups, listener));
+
+    // each group's commitments are issued by its own custodian
+    for group_id in &group_ids {
+        let mut client = CustodianClient::connect(address)
+            .unwrap()
+            .with_group(*group_id);
+        assert!(client.ping::<Ed25519Sha512>().is_ok());
+        assert!(client.commit::<Ed25519Sha512>().is_ok());
+        assert!(client.commit::<Ed25519Sha512>().is_ok());
+        // the limit on unused commitments applies per group
+        assert!(client.commit::<Ed25519Sha512>().is_err());
+    }
+
+    // a request line that never ends closes the connection
+    let mut stream = TcpStream::connect(address).unwrap();
+    let _ = stream.write_all(&vec![b'A'; MAX_REQUEST_LEN + 1]);
+    let mut response = String::new();
+    let _ = BufReader::new(stream).read_line(&mut response);
+    assert!(response.is_empty());
+}
+
+#[test]
+fn test_unused_commitments_expire() {
+    use crate::frost::Frost;
+
+    let frost: Frost = Frost::signature_share(2, 3);
+    let identifier = frost.participants().last().unwrap();
+    let policy = CustodianPolicy {
+        max_signatures: 10,
+        window: Duration::from_secs(60),
+        max_pending: 1,
+        pending_ttl: Duration::ZERO,
+        digest_only: false,
+    };
+    let mut custodian = Custodian::new(frost.key_package(identifier).unwrap(), policy);
+
+    // unanswered commitments do not hold on to the slots of unused commitments
+    let commitments = custodian.commit().unwrap();
+    assert!(custodian.commit().is_ok());
+    let signing_package = SigningPackage::new(
+        b"testing",
+        vec![frost.signers[0].get_commitments(), commitments],
+    );
+    assert_eq!(
+        custodian.sign(&signing_package),
+        Err(FrostError::CommitmentMismatch(identifier.to_scalar()))
+    );
+}
@@ -0,0 +1,151 @@
+//! This module implements non-hardened derivation of child group public keys.
+//!
+//! A custodial service gives every user a distinct address by deriving a child key from the group
+//! public key `Y` and a chain code, in the manner of BIP-32: the child key of index `i` is
+//! `Y' = Y + g^t` with the tweak `t = H(chain_code || Y || i)`, and the chain code of the child is
+//! derived alongside, so that children can in turn be derived along a path. Derivation needs no
+//! secret, so the service derives addresses on its own, while the same signer set signs for all of
+//! them: the signers sign under `Y'`, and the aggregated signature is completed with `c * t`,
+//! where `c` is the challenge under `Y'`.
+//!
+//! Anyone who learns the chain code can link the derived keys to the group key, so it should be
+//! kept as private as the addresses' relation to each other.
+use ark_ff::{AdditiveGroup, PrimeField};
+
+use crate::{
+    ciphersuite::{Ciphersuite, Ed25519Sha512, Element, ScalarField},
+    helper::compute_challenge,
+    keys::GroupPublicKey,
+    schnorr::SchnorrSignature,
+};
+
+/// Extends a group public key with the entropy its children are derived from.
+pub type ChainCode = [u8; 32];
+
+/// A group public key, possibly derived from the key of the group, with its chain code and the
+/// tweak relating it to the key of the group.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DerivedKey<C: Ciphersuite = Ed25519Sha512> {
+    /// The derived public key, that signatures verify under.
+    pub group_pk: GroupPublicKey<C>,
+    pub chain_code: ChainCode,
+    /// The sum of the tweaks along the derivation path, zero for the key of the group itself.
+    pub tweak: ScalarField<C>,
+    /// The generator `g` of the group.
+    pub generator: Element<C>,
+}
+
+impl<C: Ciphersuite> DerivedKey<C> {
+    /// Starts derivation at the key of the group, `group_pk`.
+    pub fn new(group_pk: GroupPublicKey<C>, generator: Element<C>, chain_code: ChainCode) -> Self {
+        DerivedKey {
+            group_pk,
+            chain_code,
+            tweak: ScalarField::<C>::ZERO,
+            generator,
+        }
+    }
+
+    /// Derives the child key of `index`.
+    pub fn derive_child(&self, index: u32) -> Self {
+        let mut input = C::serialize_element(&self.group_pk.to_element());
+        input.extend_from_slice(&index.to_be_bytes());
+
+        let tweak = ScalarField::<C>::from_le_bytes_mod_order(&C::H_derive(
+            &[&[0][..], &self.chain_code, &input].concat(),
+        ));
+        let chain_code = C::H_derive(&[&[1][..], &self.chain_code, &input].concat())[..32]
+            .try_into()
+            .expect("hash outputs are at least 32 bytes");
+
+        DerivedKey {
+            group_pk: GroupPublicKey::new(self.group_pk.to_element() + self.generator * tweak),
+            chain_code,
+            tweak: self.tweak + tweak,
+            generator: self.generator,
+        }
+    }
+
+    /// Derives the key at the end of `path`, one child index per step.
+    pub fn derive_path(&self, path: &[u32]) -> Self {
+        path.iter()
+            .fold(*self, |key, index| key.derive_child(*index))
+    }
+
+    /// Returns `true` if this key is derived from the key of the group `group_pk`.
+    pub fn is_derived_from(&self, group_pk: GroupPublicKey<C>) -> bool {
+        group_pk.to_element() + self.generator * self.tweak == self.group_pk.to_element()
+    }
+
+    /// Completes the aggregate of signature shares produced under this key into a signature that
+    /// verifies under it, by adding `c * t` to `s`.
+    pub fn apply_tweak(
+        &self,
+        message: &[u8],
+        signature: SchnorrSignature<C>,
+    ) -> SchnorrSignature<C> {
+        let challenge = compute_challenge::<C>(signature.R, self.group_pk.to_element(), message);
+        SchnorrSignature {
+            R: signature.R,
+            s: signature.s + challenge.to_scalar() * self.tweak,
+        }
+    }
+
+    /// Verifies `signature` on `message` under this key.
+    pub fn verify(&self, message: &[u8], signature: SchnorrSignature<C>) -> bool {
+        let group_pk = self.group_pk.to_element();
+        let challenge = compute_challenge::<C>(signature.R, group_pk, message);
+        self.generator * signature.s == signature.R + group_pk * challenge.to_scalar()
+    }
+}
+
+#[test]
+fn test_derived_key_signing() {
+    use std::time::Duration;
+
+    use crate::{coordinator::Coordinator, frost::Frost};
+
+    let mut frost: Frost = Frost::signature_share(2, 3);
+    let root = DerivedKey::new(frost.group_pk, frost.generator, [7; 32]);
+    let account = root.derive_path(&[0, 42]);
+    assert_eq!(account, root.derive_child(0).derive_child(42));
+    assert_ne!(account.group_pk, root.derive_path(&[0, 43]).group_pk);
+    assert!(account.is_derived_from(frost.group_pk));
+
+    let participants = frost.participants().collect();
+    let mut coordinator = Coordinator::new(
+        frost.group_pk,
+        b"testing",
+        participants,
+        Duration::from_secs(60),
+    )
+    .with_derived_key(account)
+    .unwrap();
+    for signer in &frost.signers {
+        coordinator
+            .receive_commitment(signer.get_commitments())
+            .unwrap();
+    }
+    let signing_package = coordinator.signing_package().unwrap();
+    for signer in &mut frost.signers {
+        coordinator
+            .receive_share(signer.sign_derived(&signing_package, &account).unwrap())
+            .unwrap();
+    }
+    let signature = coordinator.aggregate().unwrap();
+    assert!(account.verify(b"testing", signature));
+    assert!(!frost.verify(b"testing", signature));
+
+    // the coordinator keeps the derived key across a restart
+    let resumed = Coordinator::<Ed25519Sha512>::from_bytes(&coordinator.to_bytes()).unwrap();
+    assert_eq!(resumed.get_signature(), Some(signature));
+
+    // signers refuse a key that is not derived from their group's
+    let mut forged = account;
+    forged.tweak += ScalarField::<Ed25519Sha512>::from(1u64);
+    assert!(
+        frost.signers[0]
+            .sign_derived(&signing_package, &forged)
+            .is_err()
+    );
+}
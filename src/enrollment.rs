@@ -0,0 +1,201 @@
+//! This module implements late enrollment of a blind custodian into an existing group.
+//!
+//! An established group can add a collaborative custodian after key generation without
+//! reconstructing the group secret key or dealing a fresh one. A set of at least `threshold`
+//! helpers jointly evaluates the sharing polynomial at the new identifier `j`:
+//!
+//! 1. Each helper `i` computes `delta_i = lambda_i(j) * x_i`, splits it into one random summand
+//!    per helper, and sends each helper its summand.
+//! 2. Each helper sums the summands it received into `sigma_k` and sends it to the new signer.
+//! 3. The new signer sums the `sigma_k` into its share `x_j` and checks it against the helpers'
+//!    verification shares.
+//!
+//! No single message reveals a helper's `delta_i`, so the new signer only learns its own share.
+#![allow(non_snake_case)]
+
+use ark_ff::{AdditiveGroup, UniformRand};
+use ark_std::rand::Rng;
+
+use crate::{
+    ciphersuite::{Ciphersuite, Element, ScalarField},
+    error::FrostError,
+    frost::{Frost, FrostSigner},
+    helper::{NonZeroScalar, derive_interpolating_value_at},
+    keys::SecretShare,
+};
+
+/// Summands addressed to helpers, as pairs of (recipient, summand).
+type Deltas<C> = Vec<(NonZeroScalar<C>, ScalarField<C>)>;
+
+/// Round one of enrollment, run by `helper`: splits `lambda_i(j) * x_i` into one random summand
+/// for each of the `helpers`, where `j` is the `new_identifier`.
+///
+/// Returns pairs of (recipient, summand), or an error if `helpers` contain a helper twice.
+pub fn enrollment_deltas<C: Ciphersuite, R: Rng>(
+    helper: &FrostSigner<C>,
+    helpers: &[NonZeroScalar<C>],
+    new_identifier: NonZeroScalar<C>,
+    rng: &mut R,
+) -> Result<Deltas<C>, FrostError<C>> {
+    let identifier = helper.get_commitments().identifier;
+    let lambda = derive_interpolating_value_at(helpers, identifier, new_identifier.to_scalar())?;
+    let delta = lambda * helper.secret_share().to_scalar();
+
+    let mut summands: Vec<ScalarField<C>> = (1..helpers.len())
+        .map(|_| ScalarField::<C>::rand(rng))
+        .collect();
+    let remainder = summands
+        .iter()
+        .fold(delta, |remainder, summand| remainder - summand);
+    summands.push(remainder);
+
+    Ok(helpers.iter().copied().zip(summands).collect())
+}
+
+/// Round two of enrollment, run by each helper: sums the summands it received in round one into
+/// the value `sigma_k` it sends to the new signer.
+pub fn enrollment_sigma<C: Ciphersuite>(
+    summands: impl IntoIterator<Item = ScalarField<C>>,
+) -> ScalarField<C> {
+    summands
+        .into_iter()
+        .fold(ScalarField::<C>::ZERO, |sigma, summand| sigma + summand)
+}
+
+impl<C: Ciphersuite> Frost<C> {
+    /// Enrolls a new blind custodian with the help of the signers identified by `helpers`, and
+    /// returns it.
+    ///
+    /// The new signer is given the next unused index. Returns an error if fewer than `threshold`
+    /// helpers are given, if a helper is not a signer of the group, if the issued share does not
+    /// match the helpers' verification shares, or if the blind signers would reach the threshold
+    /// with the new signer.
+    pub fn enroll_blind_signer<R: Rng>(
+        &mut self,
+        helpers: &[NonZeroScalar<C>],
+        rng: &mut R,
+    ) -> Result<&FrostSigner<C>, FrostError<C>> {
+        if helpers.len() < self.threshold {
+            return Err(FrostError::InsufficientHelpers {
+                helpers: helpers.len(),
+                threshold: self.threshold,
+            });
+        }
+        let blind_signers = self.signers().filter(|signer| signer.is_blind()).count() + 1;
+        if blind_signers >= self.threshold {
+            return Err(FrostError::BlindSignersReachThreshold {
+                blind_signers,
+                threshold: self.threshold,
+            });
+        }
+
+        let helper_signers = helpers
+            .iter()
+            .map(|identifier| {
+                self.signers()
+                    .find(|signer| signer.get_identifier() == identifier.to_scalar())
+                    .ok_or(FrostError::UnknownParticipant(identifier.to_scalar()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let index = self.signers.len() + 1;
+        let new_identifier = NonZeroScalar::new(ScalarField::<C>::from(index as u64))?;
+        if self.participants().any(|id| id == new_identifier) {
+            return Err(FrostError::ParticipantExists(new_identifier.to_scalar()));
+        }
+
+        // round one: every helper sends one summand of its delta to every helper
+        let deltas: Vec<_> = helper_signers
+            .iter()
+            .map(|helper| enrollment_deltas(helper, helpers, new_identifier, rng))
+            .collect::<Result<_, _>>()?;
+
+        // round two: every helper sends the sum of its summands to the new signer
+        let sigmas = helpers.iter().map(|recipient| {
+            enrollment_sigma::<C>(deltas.iter().flat_map(|summands| {
+                summands
+                    .iter()
+                    .filter(|(id, _)| id == recipient)
+                    .map(|(_, summand)| *summand)
+            }))
+        });
+        let x = enrollment_sigma::<C>(sigmas);
+
+        // the new signer checks its share against the helpers' verification shares
+        let mut expected = Element::<C>::ZERO;
+        for helper in &helper_signers {
+            let identifier = helper.get_commitments().identifier;
+            let lambda =
+                derive_interpolating_value_at(helpers, identifier, new_identifier.to_scalar())?;
+            expected += helper.get_verification_share().to_element() * lambda;
+        }
+        if self.generator * x != expected {
+            return Err(FrostError::InvalidEnrollmentShare(
+                new_identifier.to_scalar(),
+            ));
+        }
+
+        self.signers.push(FrostSigner::new(
+            index,
+            SecretShare::new(x),
+            self.generator,
+            self.group_pk,
+            true,
+        ));
+        Ok(self.signers.last().expect("signer was just enrolled"))
+    }
+}
+
+#[test]
+fn test_enroll_blind_signer() {
+    use crate::{frost::SignerRole, schnorr::SchnorrSignature};
+
+    let roles = [SignerRole::Regular; 4];
+    let mut frost: Frost = Frost::signature_share_with_roles(3, &roles);
+    let helpers: Vec<NonZeroScalar> = frost.participants().take(3).collect();
+    let mut rng = ark_std::test_rng();
+
+    assert_eq!(
+        frost.enroll_blind_signer(&helpers[..2], &mut rng).err(),
+        Some(FrostError::InsufficientHelpers {
+            helpers: 2,
+            threshold: 3
+        })
+    );
+
+    let custodian = frost.enroll_blind_signer(&helpers, &mut rng).unwrap();
+    assert!(custodian.is_blind());
+    assert_eq!(frost.signers.len(), 5);
+
+    // the custodian signs together with two of the original signers
+    let mut signers = [0, 1, 4].map(|i| frost.signers[i].clone());
+    signers.iter_mut().for_each(|signer| {
+        signer.refresh_nonces();
+    });
+    let signing_package = crate::frost::SigningPackage::new(
+        b"testing",
+        signers
+            .iter()
+            .map(|signer| signer.get_commitments())
+            .collect(),
+    );
+    let (R, _) = signing_package
+        .group_commitment_and_challenge(frost.group_pk)
+        .unwrap();
+    let sig_shares: Vec<_> = signers
+        .iter_mut()
+        .map(|signer| signer.sign(&signing_package).unwrap())
+        .collect();
+    let signature = SchnorrSignature {
+        R,
+        s: frost.signature_aggregate(sig_shares),
+    };
+    assert!(frost.verify(b"testing", signature));
+
+    // a third blind custodian would let the blind signers reach the threshold on their own
+    assert!(frost.enroll_blind_signer(&helpers, &mut rng).is_ok());
+    assert!(matches!(
+        frost.enroll_blind_signer(&helpers, &mut rng),
+        Err(FrostError::BlindSignersReachThreshold { .. })
+    ));
+}
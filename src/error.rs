@@ -0,0 +1,265 @@
+//! This module defines the errors returned by the FROST protocol.
+use std::fmt;
+
+use crate::{
+    blame::BlameCertificate,
+    ciphersuite::{Ciphersuite, Ed25519Sha512, ScalarField},
+    frost::SessionId,
+    keys::GroupId,
+};
+
+/// The rounds of a FROST signing session, as seen by the coordinator.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Round {
+    /// Round one, in which signers send their nonce commitments.
+    Commitment,
+    /// Round two, in which signers send their signature shares.
+    SignatureShare,
+}
+
+impl fmt::Display for Round {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Round::Commitment => write!(f, "commitment round"),
+            Round::SignatureShare => write!(f, "signature share round"),
+        }
+    }
+}
+
+/// Errors that can occur while running the FROST protocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrostError<C: Ciphersuite = Ed25519Sha512> {
+    /// The signing package does not contain a commitment from the given signer.
+    MissingCommitment(ScalarField<C>),
+    /// The signing package contains more than one commitment from the given signer.
+    DuplicateCommitment(ScalarField<C>),
+    /// The commitment attributed to the given signer differs from the one it issued.
+    CommitmentMismatch(ScalarField<C>),
+    /// A message was received from a signer that is not a participant of the session.
+    UnknownParticipant(ScalarField<C>),
+    /// No signature share has been received from the given signer.
+    MissingSignatureShare(ScalarField<C>),
+    /// More than one signature share was received from the given signer.
+    DuplicateSignatureShare(ScalarField<C>),
+    /// A signature share does not verify, as recorded in the certificate.
+    InvalidSignatureShare(Box<BlameCertificate<C>>),
+    /// The aggregated signature does not verify under the key of the session.
+    InvalidSignature,
+    /// A message was received for a round the session is not currently in.
+    UnexpectedRound(Round),
+    /// The blind signers of a setup could reach the threshold without any regular signer.
+    BlindSignersReachThreshold {
+        blind_signers: usize,
+        threshold: usize,
+    },
+    /// No session with the given identifier is known to the coordinator.
+    UnknownSession(SessionId),
+    /// The deadline of `round` passed before the `missing` participants responded, and the session
+    /// was aborted.
+    Timeout {
+        round: Round,
+        missing: Vec<ScalarField<C>>,
+    },
+    /// The given identifier already belongs to a signer of the group.
+    ParticipantExists(ScalarField<C>),
+    /// Fewer than `threshold` signers were selected to help enroll a new signer.
+    InsufficientHelpers { helpers: usize, threshold: usize },
+    /// The share issued to the given signer during enrollment does not match the group's
+    /// verification shares.
+    InvalidEnrollmentShare(ScalarField<C>),
+    /// A request to the custodian was refused by its policy.
+    PolicyViolation(String),
+    /// The nonces behind the commitments of the participant were issued or used before.
+    NonceReused(ScalarField<C>),
+    /// The nonce ledger could not be read or written.
+    Storage(String),
+    /// A signing package was received again, or after a package with a higher counter.
+    ReplayedRequest(SessionId),
+    /// The signature on an intent submitted by the participant does not verify.
+    InvalidIntent(ScalarField<C>),
+    /// Fewer custodians are available than a session requires.
+    CustodiansUnavailable { available: usize, required: usize },
+    /// Signing the request would sign two conflicting messages for the same consensus step.
+    DoubleSign { height: u64, round: u32 },
+    /// An identifier, which must be a non-zero scalar, is zero.
+    ZeroIdentifier,
+    /// The binding factors were not computed for the participants they are used with.
+    ParticipantMismatch,
+    /// The commitments of the given signer lack a valid proof of possession.
+    InvalidCommitmentProof(ScalarField<C>),
+    /// A message attributed to the given participant is not signed with its identity key.
+    InvalidIdentitySignature(ScalarField<C>),
+    /// A session transcript does not re-verify.
+    InvalidTranscript(String),
+    /// Fewer signers are available for a session than the threshold requires.
+    InsufficientSigners { available: usize, threshold: usize },
+    /// A signing session is configured inconsistently.
+    InvalidSession(String),
+    /// A message could not be sent or received.
+    Transport(String),
+    /// A keygen report is inconsistent, or does not match a signer's key package.
+    InvalidKeygenReport(String),
+    /// A derived key is not derived from the key of the signer's group.
+    InvalidKeyDerivation,
+    /// A group certificate is expired, not valid yet, or not signed by its group.
+    InvalidCertificate(String),
+    /// A structured document is not valid JSON or has no canonical form.
+    InvalidDocument(String),
+    /// The verification shares of a public key package are inconsistent with the group key.
+    InvalidPublicKeyPackage(String),
+    /// A group descriptor does not describe a valid group.
+    InvalidConfig(String),
+    /// A signed message bundle is for another group, or its signature does not verify.
+    InvalidBundle(String),
+    /// The session of the given group failed, so a co-signing session produced no signature.
+    CoSigningAborted {
+        group: GroupId,
+        error: Box<FrostError<C>>,
+    },
+    /// A co-signature is not bound to the expected groups, or one of its signatures does not
+    /// verify.
+    InvalidCoSignature(String),
+}
+
+impl<C: Ciphersuite> fmt::Display for FrostError<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrostError::MissingCommitment(id) => {
+                write!(
+                    f,
+                    "signing package is missing the commitment of signer {id}"
+                )
+            }
+            FrostError::DuplicateCommitment(id) => {
+                write!(
+                    f,
+                    "signing package contains multiple commitments of signer {id}"
+                )
+            }
+            FrostError::CommitmentMismatch(id) => {
+                write!(
+                    f,
+                    "signing package contains a substituted commitment for signer {id}"
+                )
+            }
+            FrostError::UnknownParticipant(id) => {
+                write!(f, "signer {id} is not a participant of this session")
+            }
+            FrostError::MissingSignatureShare(id) => {
+                write!(f, "no signature share received from signer {id}")
+            }
+            FrostError::DuplicateSignatureShare(id) => {
+                write!(f, "received multiple signature shares from signer {id}")
+            }
+            FrostError::InvalidSignatureShare(certificate) => write!(
+                f,
+                "signature share of signer {} does not verify",
+                certificate.identifier().to_scalar()
+            ),
+            FrostError::InvalidSignature => write!(f, "aggregated signature does not verify"),
+            FrostError::UnexpectedRound(round) => {
+                write!(f, "session is not in the {round}")
+            }
+            FrostError::BlindSignersReachThreshold {
+                blind_signers,
+                threshold,
+            } => write!(
+                f,
+                "{blind_signers} blind signers can reach the threshold of {threshold} on their own"
+            ),
+            FrostError::UnknownSession(session_id) => {
+                write!(f, "unknown session ")?;
+                for byte in session_id {
+                    write!(f, "{byte:02x}")?;
+                }
+                Ok(())
+            }
+            FrostError::ReplayedRequest(session_id) => {
+                write!(f, "replayed request for session ")?;
+                for byte in session_id {
+                    write!(f, "{byte:02x}")?;
+                }
+                Ok(())
+            }
+            FrostError::InvalidIntent(id) => {
+                write!(f, "intent of signer {id} is not validly signed")
+            }
+            FrostError::CustodiansUnavailable {
+                available,
+                required,
+            } => write!(
+                f,
+                "{available} custodians are available, but {required} are required"
+            ),
+            FrostError::DoubleSign { height, round } => write!(
+                f,
+                "refusing to double sign at height {height}, round {round}"
+            ),
+            FrostError::ZeroIdentifier => write!(f, "identifier must not be zero"),
+            FrostError::InvalidCommitmentProof(id) => {
+                write!(
+                    f,
+                    "commitments of signer {id} lack a valid proof of possession"
+                )
+            }
+            FrostError::InvalidIdentitySignature(id) => {
+                write!(f, "message is not signed by the identity key of {id}")
+            }
+            FrostError::InvalidTranscript(reason) => write!(f, "invalid transcript: {reason}"),
+            FrostError::InvalidSession(reason) => write!(f, "invalid signing session: {reason}"),
+            FrostError::Transport(reason) => write!(f, "transport failed: {reason}"),
+            FrostError::InvalidKeygenReport(reason) => write!(f, "invalid keygen report: {reason}"),
+            FrostError::InvalidKeyDerivation => {
+                write!(f, "key is not derived from the group public key")
+            }
+            FrostError::InvalidCertificate(reason) => {
+                write!(f, "invalid group certificate: {reason}")
+            }
+            FrostError::InvalidDocument(reason) => write!(f, "invalid document: {reason}"),
+            FrostError::InvalidPublicKeyPackage(reason) => {
+                write!(f, "invalid public key package: {reason}")
+            }
+            FrostError::InvalidConfig(reason) => write!(f, "invalid group config: {reason}"),
+            FrostError::InvalidBundle(reason) => write!(f, "invalid signed message: {reason}"),
+            FrostError::CoSigningAborted { group, error } => {
+                write!(f, "co-signing aborted in group {group}: {error}")
+            }
+            FrostError::InvalidCoSignature(reason) => write!(f, "invalid co-signature: {reason}"),
+            FrostError::InsufficientSigners {
+                available,
+                threshold,
+            } => write!(
+                f,
+                "{available} signers are available, but the threshold is {threshold}"
+            ),
+            FrostError::ParticipantMismatch => {
+                write!(f, "binding factors do not match the participants")
+            }
+            FrostError::Timeout { round, missing } => {
+                write!(f, "{round} timed out waiting for signers")?;
+                for id in missing {
+                    write!(f, " {id}")?;
+                }
+                Ok(())
+            }
+            FrostError::ParticipantExists(id) => {
+                write!(f, "signer {id} is already part of the group")
+            }
+            FrostError::InsufficientHelpers { helpers, threshold } => write!(
+                f,
+                "{helpers} helpers cannot enroll a signer, at least {threshold} are required"
+            ),
+            FrostError::InvalidEnrollmentShare(id) => {
+                write!(
+                    f,
+                    "share issued to signer {id} does not match the group key"
+                )
+            }
+            FrostError::PolicyViolation(reason) => write!(f, "custodian policy violated: {reason}"),
+            FrostError::NonceReused(id) => write!(f, "nonces of signer {id} were used before"),
+            FrostError::Storage(reason) => write!(f, "nonce ledger failed: {reason}"),
+        }
+    }
+}
+
+impl<C: Ciphersuite> std::error::Error for FrostError<C> {}
@@ -0,0 +1,56 @@
+//! This module implements the preparation of Ethereum messages before a signing session.
+//!
+//! Wallets do not sign dApp data directly: EIP-191 prefixes it with a version byte and, for
+//! `personal_sign`, a length, and EIP-712 combines the hashes of the domain and the typed data.
+//! These helpers produce the exact byte strings that are hashed with keccak256 and signed, so
+//! integrators do not hand-roll the encoding. Hashing is left to the caller, as this crate does not
+//! depend on keccak256.
+//!
+//! Reference: https://eips.ethereum.org/EIPS/eip-191 and https://eips.ethereum.org/EIPS/eip-712
+
+/// Prefix of every EIP-191 signed data string.
+const EIP191_PREFIX: u8 = 0x19;
+
+/// Returns the bytes signed by `personal_sign` for `message`, i.e. EIP-191 version `0x45`:
+/// `"\x19Ethereum Signed Message:\n" || len(message) || message` with the length in decimal.
+pub fn personal_sign_bytes(message: &[u8]) -> Vec<u8> {
+    [
+        format!("\x19Ethereum Signed Message:\n{}", message.len()).as_bytes(),
+        message,
+    ]
+    .concat()
+}
+
+/// Returns the bytes signed for `data` intended for the contract at `validator`, i.e. EIP-191
+/// version `0x00`: `0x19 || 0x00 || validator || data`.
+pub fn intended_validator_bytes(validator: &[u8; 20], data: &[u8]) -> Vec<u8> {
+    [&[EIP191_PREFIX, 0x00][..], validator, data].concat()
+}
+
+/// Returns the bytes signed for EIP-712 typed data, i.e. EIP-191 version `0x01`:
+/// `0x19 || 0x01 || domainSeparator || hashStruct(message)`, given the keccak256 hashes of the
+/// domain and of the message as specified by EIP-712.
+pub fn typed_data_bytes(domain_separator: &[u8; 32], struct_hash: &[u8; 32]) -> [u8; 66] {
+    let mut bytes = [0u8; 66];
+    bytes[..2].copy_from_slice(&[EIP191_PREFIX, 0x01]);
+    bytes[2..34].copy_from_slice(domain_separator);
+    bytes[34..].copy_from_slice(struct_hash);
+    bytes
+}
+
+#[test]
+fn test_eip191_encodings() {
+    assert_eq!(
+        personal_sign_bytes(b"hello world"),
+        b"\x19Ethereum Signed Message:\n11hello world"
+    );
+    assert_eq!(
+        intended_validator_bytes(&[0xaa; 20], b"data")[..],
+        [&[0x19, 0x00][..], &[0xaa; 20], b"data"].concat()
+    );
+
+    let typed = typed_data_bytes(&[1; 32], &[2; 32]);
+    assert_eq!(typed[..2], [0x19, 0x01]);
+    assert_eq!(typed[2..34], [1; 32]);
+    assert_eq!(typed[34..], [2; 32]);
+}
@@ -1,168 +1,1038 @@
 //! This module implements the [FROST protocol](https://eprint.iacr.org/2020/852.pdf).
 #![allow(non_snake_case)]
 
-use ark_ed25519::{EdwardsProjective as Element, Fr as ScalarField};
+use std::{fmt, sync::Arc};
+
 use ark_ff::{AdditiveGroup, UniformRand};
+use ark_serialize::SerializationError;
+use ark_std::rand::Rng;
 
 use crate::{
+    blame::ShareProof,
+    ciphersuite::{Ciphersuite, Ed25519Sha512, Element, ScalarField},
+    derivation::DerivedKey,
+    error::FrostError,
     helper::{
-        BindingFactor, NonZeroScalar, binding_factor_for_participant, derive_interpolating_value,
-        nonce_generate,
+        BindingFactorList, Challenge, CommitmentProof, Decoder, InterpolationCache, NonZeroScalar,
+        ParticipantSet, compute_binding_factors, compute_challenge, compute_group_commitment,
+        derive_interpolating_value, serialize_scalar,
     },
+    keys::{GroupPublicKey, KeyPackage, SecretShare, VerificationShare},
+    nonces::{SigningCommitments, SigningNonces},
     schnorr::SchnorrSignature,
-    shamir::shamir_split,
+    secret::Secret,
+    shamir::{shamir_split_at_with_rng, shamir_split_with_rng},
 };
 
-/// A pair of `Element`s which represent the commitments to the hiding nonce and the binding nonce
-/// respectively.
-#[derive(Debug, Copy, Clone)]
-pub struct NonceCommitment {
-    /// Commitment for hiding nonce.
-    pub D: Element,
-    /// Commitment for binding nonce.
-    pub E: Element,
+/// A signature share `z_i` together with the identifier of the signer that produced it and the
+/// request it answers.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SignatureShare<C: Ciphersuite = Ed25519Sha512> {
+    /// Identifier of the signer.
+    pub identifier: NonZeroScalar<C>,
+    /// The signature share.
+    pub z_i: ScalarField<C>,
+    /// Session of the `SigningPackage` the share was produced for.
+    pub session_id: SessionId,
+    /// Counter of the `SigningPackage` the share was produced for.
+    pub counter: u64,
+}
+
+impl<C: Ciphersuite> SignatureShare<C> {
+    /// Encodes the share as `session_id || counter || identifier || z_i`, with the counter as an
+    /// 8-byte little-endian integer.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [
+            self.session_id.to_vec(),
+            self.counter.to_le_bytes().to_vec(),
+            serialize_scalar::<C>(&self.identifier.to_scalar()),
+            serialize_scalar::<C>(&self.z_i),
+        ]
+        .concat()
+    }
+
+    /// Parses a share encoded by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let mut decoder = Decoder::new(bytes);
+        let sig_share = SignatureShare {
+            session_id: decoder.array()?,
+            counter: decoder.u64()?,
+            identifier: decoder.identifier()?,
+            z_i: decoder.scalar::<C>()?,
+        };
+        decoder.finish()?;
+        Ok(sig_share)
+    }
+}
+
+/// The role a signer is assigned at setup.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum SignerRole {
+    /// A regular signer, which commits to both a hiding and a binding nonce.
+    #[default]
+    Regular,
+    /// A blind custodian, which signs with a zero binding nonce.
+    Blind,
+}
+
+/// Unique identifier of a signing session.
+pub type SessionId = [u8; 32];
+
+/// The package the coordinator builds at the end of round one and sends to every selected signer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SigningPackage<C: Ciphersuite = Ed25519Sha512> {
+    /// The message to be signed.
+    pub message: Vec<u8>,
+    /// The commitments of all participants of this session.
+    pub commitments: Vec<SigningCommitments<C>>,
+    /// Identifies the session this package belongs to.
+    pub session_id: SessionId,
+    /// Position of the request in the sequence of requests of its sender. Signers that serve
+    /// requests remotely only accept increasing counters, so captured packages cannot be replayed.
+    pub counter: u64,
+}
+
+impl<C: Ciphersuite> SigningPackage<C> {
+    /// Creates a new `SigningPackage` for `message` with a fresh random `session_id` and a zero
+    /// `counter`. The commitments are sorted by identifier, as RFC 9591 requires.
+    pub fn new(message: &[u8], mut commitments: Vec<SigningCommitments<C>>) -> Self {
+        commitments.sort_by_key(|commitment| commitment.identifier.to_scalar());
+        let mut session_id = [0u8; 32];
+        rand::RngCore::fill_bytes(&mut rand::rng(), &mut session_id);
+
+        SigningPackage {
+            message: message.to_vec(),
+            commitments,
+            session_id,
+            counter: 0,
+        }
+    }
+
+    /// Encodes the package as `session_id || counter || len(message) || message ||
+    /// len(commitments) || commitments`, with the counter as an 8-byte and lengths as 4-byte
+    /// little-endian integers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the message or the commitment list is longer than `u32::MAX`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.session_id.to_vec();
+        bytes.extend_from_slice(&self.counter.to_le_bytes());
+        let message_len = u32::try_from(self.message.len()).expect("message too long");
+        bytes.extend_from_slice(&message_len.to_le_bytes());
+        bytes.extend_from_slice(&self.message);
+        let commitments_len = u32::try_from(self.commitments.len()).expect("too many commitments");
+        bytes.extend_from_slice(&commitments_len.to_le_bytes());
+        for commitment in &self.commitments {
+            bytes.extend(commitment.to_bytes());
+        }
+        bytes
+    }
+
+    /// Parses a package encoded by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let mut decoder = Decoder::new(bytes);
+        let session_id = decoder.array()?;
+        let counter = decoder.u64()?;
+        let message_len = decoder.u32()? as usize;
+        let message = decoder.take(message_len)?.to_vec();
+        let commitments = (0..decoder.u32()?)
+            .map(|_| decoder.commitments())
+            .collect::<Result<_, _>>()?;
+        decoder.finish()?;
+
+        Ok(SigningPackage {
+            message,
+            commitments,
+            session_id,
+            counter,
+        })
+    }
+
+    /// Validates the commitment list into the participants of this session.
+    ///
+    /// Returns an error if the list contains more than one commitment from a participant.
+    pub fn participants(&self) -> Result<ParticipantSet<C>, FrostError<C>> {
+        ParticipantSet::new(self.commitments.clone())
+    }
+
+    /// Computes the binding factors of all participants under the group public key `group_pk`.
+    pub fn binding_factors(
+        &self,
+        group_pk: GroupPublicKey<C>,
+    ) -> Result<BindingFactorList<C>, FrostError<C>> {
+        Ok(compute_binding_factors(
+            group_pk.to_element(),
+            &self.participants()?,
+            &self.message,
+        ))
+    }
+
+    /// Computes the group commitment `R` and the challenge under the group public key `group_pk`.
+    pub fn group_commitment_and_challenge(
+        &self,
+        group_pk: GroupPublicKey<C>,
+    ) -> Result<(Element<C>, Challenge<C>), FrostError<C>> {
+        let participants = self.participants()?;
+        let binding_factors =
+            compute_binding_factors(group_pk.to_element(), &participants, &self.message);
+        let group_commitment = compute_group_commitment(&participants, &binding_factors)?;
+        let challenge =
+            compute_challenge::<C>(group_commitment, group_pk.to_element(), &self.message);
+        Ok((group_commitment, challenge))
+    }
 }
 
 /// Each signer has a secret share and can generate a signature share
 /// Each signer will generate a hiding nonce and a binding nonce
-#[derive(Debug, Copy, Clone)]
-pub struct FrostSigner {
+pub struct FrostSigner<C: Ciphersuite = Ed25519Sha512> {
     /// Unique identifier for this `FrostSigner`.
-    identifier: ScalarField,
+    identifier: ScalarField<C>,
 
     /// The secret key share that belongs to this `FrostSigner`.
-    x: ScalarField,
+    x: Secret<ScalarField<C>>,
 
-    /// The hiding nonce.
-    d: ScalarField,
+    /// The public verification share `Y = g^x` corresponding to `x`.
+    verification_share: VerificationShare<C>,
 
-    /// The binding nonce.
-    e: ScalarField,
+    /// The generator `g` of the group.
+    generator: Element<C>,
 
-    /// The `NonceCommitment`, which is a pair of commitments to `d` and `e` respectively.
-    commitment: NonceCommitment,
+    /// The hiding and binding nonces of the current session, until they are used to sign.
+    nonces: Option<SigningNonces<C>>,
 
-    /// The binding factor.
-    rho: ScalarField,
-}
+    /// The `SigningCommitments` to the `nonces`.
+    commitment: SigningCommitments<C>,
 
-impl FrostSigner {
-    pub fn new(index: usize, x: ScalarField, g: Element, is_blind: bool) -> Self {
-        let mut seed = [0u8; 32];
-        let index_bytes = index.to_le_bytes();
-        seed[..index_bytes.len()].copy_from_slice(&index_bytes);
-        let identifier = ScalarField::from(index as u64);
+    /// The public key of the group this `FrostSigner` holds a share of.
+    group_pk: GroupPublicKey<C>,
 
-        // generate a hiding nonce d and its commitment D
-        let d = nonce_generate(x);
-        let D = g * d;
+    /// Whether this `FrostSigner` is a blind signer, i.e. signs with a zero binding nonce.
+    is_blind: bool,
 
-        // generate a binding nonce e and its commitment E.
-        // In the suggested rough draft, we want the `NoncePair` to be (D, 0) for blinding.
-        let mut e = ScalarField::ZERO;
-        if !is_blind {
-            e = nonce_generate(x);
-        }
-        let E = g * e;
+    /// Interpolation values shared with the other `FrostSigner`s of the same key package, if any.
+    interpolation_cache: Option<Arc<InterpolationCache<C>>>,
+}
 
+/// Clones the signer without its nonces, so that the original and the clone cannot both sign
+/// with them. The clone must `refresh_nonces` before it signs.
+impl<C: Ciphersuite> Clone for FrostSigner<C> {
+    fn clone(&self) -> Self {
         Self {
+            identifier: self.identifier,
+            x: Secret::new(*self.x.expose_secret()),
+            verification_share: self.verification_share,
+            generator: self.generator,
+            nonces: None,
+            commitment: self.commitment,
+            group_pk: self.group_pk,
+            is_blind: self.is_blind,
+            interpolation_cache: self.interpolation_cache.clone(),
+        }
+    }
+}
+
+/// Redacts the secret share and the nonces, so that a `FrostSigner` can be logged safely.
+impl<C: Ciphersuite> fmt::Debug for FrostSigner<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrostSigner")
+            .field("identifier", &self.identifier)
+            .field("x", &format_args!("<redacted>"))
+            .field("verification_share", &self.verification_share)
+            .field("generator", &self.generator)
+            .field("nonces", &self.nonces)
+            .field("commitment", &self.commitment)
+            .field("group_pk", &self.group_pk)
+            .field("is_blind", &self.is_blind)
+            .field("interpolation_cache", &self.interpolation_cache)
+            .finish()
+    }
+}
+
+impl<C: Ciphersuite> FrostSigner<C> {
+    pub fn new(
+        index: usize,
+        x: SecretShare<C>,
+        g: Element<C>,
+        group_pk: GroupPublicKey<C>,
+        is_blind: bool,
+    ) -> Self {
+        let identifier = NonZeroScalar::new(ScalarField::<C>::from(index as u64))
+            .expect("signer indices start at 1");
+        Self::with_identifier(identifier, x, g, group_pk, is_blind)
+    }
+
+    /// Like `new`, but for a signer with an arbitrary `identifier`, e.g. one derived from a name
+    /// with `NonZeroScalar::derive`.
+    pub fn with_identifier(
+        identifier: NonZeroScalar<C>,
+        x: SecretShare<C>,
+        g: Element<C>,
+        group_pk: GroupPublicKey<C>,
+        is_blind: bool,
+    ) -> Self {
+        let x = x.to_scalar();
+        // In the suggested rough draft, we want the commitments to be (D, 0) for blinding.
+        let role = if is_blind {
+            SignerRole::Blind
+        } else {
+            SignerRole::Regular
+        };
+
+        Self::from_parts(identifier, x, g, group_pk, SigningNonces::generate(x, role))
+    }
+
+    /// Creates a `FrostSigner` with fresh nonces from the key material in `key_package`.
+    pub fn from_key_package(key_package: &KeyPackage<C>) -> Self {
+        let x = key_package.secret_share.to_scalar();
+
+        Self::from_parts(
+            key_package.identifier,
+            x,
+            key_package.generator,
+            key_package.group_pk,
+            SigningNonces::generate(x, key_package.role),
+        )
+    }
+
+    /// Creates a `FrostSigner` with the given hiding nonce `d` and binding nonce `e`, e.g. to
+    /// replay a test vector. The signer is blind if `e` is zero.
+    pub(crate) fn with_nonces(
+        index: usize,
+        x: ScalarField<C>,
+        g: Element<C>,
+        group_pk: GroupPublicKey<C>,
+        d: ScalarField<C>,
+        e: ScalarField<C>,
+    ) -> Self {
+        let identifier = NonZeroScalar::new(ScalarField::<C>::from(index as u64))
+            .expect("signer indices start at 1");
+        Self::from_parts(
             identifier,
             x,
-            d,
-            e,
-            commitment: NonceCommitment { D, E },
-            rho: ScalarField::ZERO,
+            g,
+            group_pk,
+            SigningNonces::from_scalars(d, e),
+        )
+    }
+
+    fn from_parts(
+        identifier: NonZeroScalar<C>,
+        x: ScalarField<C>,
+        g: Element<C>,
+        group_pk: GroupPublicKey<C>,
+        nonces: SigningNonces<C>,
+    ) -> Self {
+        Self {
+            identifier: identifier.to_scalar(),
+            x: Secret::new(x),
+            verification_share: VerificationShare::new(g * x),
+            generator: g,
+            commitment: nonces.commit(identifier, g),
+            is_blind: nonces.is_blind(),
+            nonces: Some(nonces),
+            group_pk,
+            interpolation_cache: None,
         }
     }
 
-    /// Stores the `binding_factor` locally for use during signing.
-    pub fn store_rho(&mut self, binding_factor: ScalarField) {
-        self.rho = binding_factor;
+    /// Looks up the interpolation value in `cache` when signing, instead of deriving it for
+    /// every signing package.
+    pub fn with_interpolation_cache(mut self, cache: Arc<InterpolationCache<C>>) -> Self {
+        self.interpolation_cache = Some(cache);
+        self
     }
 
-    /// Signs and returns a signature share of type `ScalarField`.
-    pub fn sign(&self, challenge: ScalarField, x_coordinates: &[NonZeroScalar]) -> ScalarField {
-        let lambda = derive_interpolating_value(
-            x_coordinates,
-            NonZeroScalar::new(ScalarField::from(self.identifier)),
+    /// Signs the message in `signing_package` and returns this signer's `SignatureShare`.
+    ///
+    /// The binding factor and the challenge are derived locally from the commitment list and the
+    /// message, so that a coordinator cannot feed this signer inconsistent values.
+    ///
+    /// The nonces are wiped once the share is produced, so a signer signs at most once with the
+    /// commitments it issued; use `refresh_nonces` to take part in another session.
+    ///
+    /// Returns an error if `signing_package` does not contain exactly one commitment from this
+    /// signer, if that commitment differs from the one this signer issued, or if the nonces
+    /// behind it have already been used.
+    pub fn sign(
+        &mut self,
+        signing_package: &SigningPackage<C>,
+    ) -> Result<SignatureShare<C>, FrostError<C>> {
+        self.sign_under(signing_package, self.group_pk)
+    }
+
+    /// Signs like `sign`, but under `derived_key`, a key derived from the key of this signer's
+    /// group. The coordinator applies the tweak of the key when aggregating.
+    ///
+    /// Returns `FrostError::InvalidKeyDerivation` if `derived_key` is not derived from the key of
+    /// this signer's group.
+    pub fn sign_derived(
+        &mut self,
+        signing_package: &SigningPackage<C>,
+        derived_key: &DerivedKey<C>,
+    ) -> Result<SignatureShare<C>, FrostError<C>> {
+        if derived_key.generator != self.generator || !derived_key.is_derived_from(self.group_pk) {
+            return Err(FrostError::InvalidKeyDerivation);
+        }
+        self.sign_under(signing_package, derived_key.group_pk)
+    }
+
+    fn sign_under(
+        &mut self,
+        signing_package: &SigningPackage<C>,
+        group_pk: GroupPublicKey<C>,
+    ) -> Result<SignatureShare<C>, FrostError<C>> {
+        let nonces = self
+            .nonces
+            .as_ref()
+            .ok_or(FrostError::NonceReused(self.identifier))?;
+        let sig_share = self.signature_share(nonces, signing_package, group_pk)?;
+        // dropping the nonces zeroizes them
+        self.nonces = None;
+        Ok(sig_share)
+    }
+
+    fn signature_share(
+        &self,
+        nonces: &SigningNonces<C>,
+        signing_package: &SigningPackage<C>,
+        group_pk: GroupPublicKey<C>,
+    ) -> Result<SignatureShare<C>, FrostError<C>> {
+        let identifier = self.commitment.identifier;
+        self.check_own_commitment(signing_package)?;
+
+        let participants = signing_package.participants()?;
+        let binding_factors = compute_binding_factors(
+            group_pk.to_element(),
+            &participants,
+            &signing_package.message,
+        );
+        let rho = binding_factors
+            .get(identifier)
+            .ok_or(FrostError::MissingCommitment(self.identifier))?;
+        let group_commitment = compute_group_commitment(&participants, &binding_factors)?;
+        let challenge = compute_challenge::<C>(
+            group_commitment,
+            group_pk.to_element(),
+            &signing_package.message,
         );
-        self.d + (self.rho * self.e) + (lambda * self.x * challenge)
+        let lambda = match &self.interpolation_cache {
+            Some(cache) => cache.interpolating_value(&participants, identifier)?,
+            None => derive_interpolating_value(&participants, identifier)?,
+        };
+
+        Ok(SignatureShare {
+            identifier,
+            z_i: nonces.hiding()
+                + (rho * nonces.binding())
+                + (lambda * self.x.expose_secret() * challenge.to_scalar()),
+            session_id: signing_package.session_id,
+            counter: signing_package.counter,
+        })
+    }
+
+    /// Proves possession of the nonces behind this signer's commitments, for coordinators that
+    /// require it.
+    ///
+    /// Returns an error if the nonces have already been used to sign.
+    pub fn prove_commitments<R: Rng>(
+        &self,
+        rng: &mut R,
+    ) -> Result<CommitmentProof<C>, FrostError<C>> {
+        let nonces = self
+            .nonces
+            .as_ref()
+            .ok_or(FrostError::NonceReused(self.identifier))?;
+        Ok(CommitmentProof::prove(
+            &self.commitment,
+            nonces,
+            self.generator,
+            rng,
+        ))
     }
 
-    pub fn get_identifier(&self) -> ScalarField {
+    /// Draws fresh nonces, wiping the current ones if they are still unused, and returns the
+    /// commitments to them for the next session.
+    pub fn refresh_nonces(&mut self) -> SigningCommitments<C> {
+        let nonces = SigningNonces::generate(*self.x.expose_secret(), self.role());
+        self.commitment = nonces.commit(self.commitment.identifier, self.generator);
+        self.nonces = Some(nonces);
+        self.commitment
+    }
+
+    /// Signs like `sign`, and attaches a `ShareProof` that the share is consistent with this
+    /// signer's commitments and verification share, which anyone can check.
+    pub fn sign_with_proof<R: Rng>(
+        &mut self,
+        signing_package: &SigningPackage<C>,
+        rng: &mut R,
+    ) -> Result<(SignatureShare<C>, ShareProof<C>), FrostError<C>> {
+        let nonces = self
+            .nonces
+            .as_ref()
+            .ok_or(FrostError::NonceReused(self.identifier))?;
+        let sig_share = self.signature_share(nonces, signing_package, self.group_pk)?;
+        let proof = ShareProof::prove(
+            &sig_share,
+            signing_package,
+            self.generator,
+            self.group_pk,
+            nonces,
+            *self.x.expose_secret(),
+            rng,
+        )?;
+        self.nonces = None;
+        Ok((sig_share, proof))
+    }
+
+    /// Checks that `signing_package` contains exactly one commitment from this signer and that it
+    /// is the `SigningCommitments` this signer previously issued.
+    fn check_own_commitment(
+        &self,
+        signing_package: &SigningPackage<C>,
+    ) -> Result<(), FrostError<C>> {
+        let mut own_commitments = signing_package
+            .commitments
+            .iter()
+            .filter(|commitment| commitment.identifier == self.commitment.identifier);
+
+        let commitment = own_commitments
+            .next()
+            .ok_or(FrostError::MissingCommitment(self.identifier))?;
+        if own_commitments.next().is_some() {
+            return Err(FrostError::DuplicateCommitment(self.identifier));
+        }
+        if *commitment != self.commitment {
+            return Err(FrostError::CommitmentMismatch(self.identifier));
+        }
+
+        Ok(())
+    }
+
+    pub fn get_identifier(&self) -> ScalarField<C> {
         self.identifier
     }
 
-    pub fn get_nonce_commitment(&self) -> &NonceCommitment {
-        &self.commitment
+    pub fn get_commitments(&self) -> SigningCommitments<C> {
+        self.commitment
+    }
+
+    /// Returns the secret key share, for protocols run among the signers such as enrollment.
+    pub(crate) fn secret_share(&self) -> SecretShare<C> {
+        SecretShare::new(*self.x.expose_secret())
+    }
+
+    pub fn get_verification_share(&self) -> VerificationShare<C> {
+        self.verification_share
+    }
+
+    pub fn get_group_pk(&self) -> GroupPublicKey<C> {
+        self.group_pk
+    }
+
+    pub fn is_blind(&self) -> bool {
+        self.is_blind
+    }
+
+    pub fn role(&self) -> SignerRole {
+        if self.is_blind {
+            SignerRole::Blind
+        } else {
+            SignerRole::Regular
+        }
     }
 }
 
 /// Represents an instance of a FROST protocol.
 #[derive(Debug, Clone)]
-pub struct Frost {
-    pub generator: Element,
-    pub signers: Vec<FrostSigner>,
+pub struct Frost<C: Ciphersuite = Ed25519Sha512> {
+    pub generator: Element<C>,
+    pub signers: Vec<FrostSigner<C>>,
     /// public key of the group
-    pub group_pk: Element,
+    pub group_pk: GroupPublicKey<C>,
+    /// Minimum number of signers required to produce a signature.
+    pub threshold: usize,
 }
 
-impl Frost {
-    /// Instantiates a new FROST protocol given a `threshold` and `total_signers`.
+impl<C: Ciphersuite> Frost<C> {
+    /// Instantiates a new FROST protocol over a given `generator`, drawing the group secret key and
+    /// the Shamir polynomial from `rng`. The signer with index `i` is assigned `roles[i - 1]`.
     ///
-    /// Shamir secret sharing is done here to generate the secret key shares for the signers.
-    pub fn signature_share(threshold: usize, total_signers: usize) -> Self {
-        let mut rng = ark_std::test_rng();
-        let secret_key = ScalarField::rand(&mut rng);
-        let generator = Element::rand(&mut rng);
-        let group_pk = generator * secret_key;
+    /// # Panics
+    ///
+    /// Panics if the signers marked as blind could reach `threshold` on their own.
+    pub fn signature_share_with_rng<R: Rng>(
+        threshold: usize,
+        roles: &[SignerRole],
+        generator: Element<C>,
+        rng: &mut R,
+    ) -> Self {
+        let secret_key = ScalarField::<C>::rand(rng);
+        let group_pk = GroupPublicKey::new(generator * secret_key);
 
-        let shamir_shares = shamir_split(secret_key, threshold, total_signers);
+        let shamir_shares = shamir_split_with_rng(secret_key, threshold, roles.len(), rng);
         let signers = shamir_shares
             .iter()
-            .map(|shamir_share| {
-                let mut is_blind = false;
-                if shamir_share.index > threshold {
-                    is_blind = true; // set a few signers to be blind
-                }
-                FrostSigner::new(shamir_share.index, shamir_share.secret, generator, is_blind)
+            .zip(roles)
+            .map(|(shamir_share, role)| {
+                FrostSigner::new(
+                    shamir_share.index,
+                    SecretShare::new(shamir_share.secret),
+                    generator,
+                    group_pk,
+                    *role == SignerRole::Blind,
+                )
             })
             .collect();
 
-        Frost {
+        let frost = Frost {
             generator,
             signers,
             group_pk,
+            threshold,
+        };
+        if let Err(error) = frost.check_blind_policy() {
+            panic!("{error}");
         }
+
+        frost
     }
 
-    pub fn update_binding_factors(&mut self, binding_factors: Vec<BindingFactor>) {
-        for signer in self.signers.iter_mut() {
-            let binding_factor = binding_factor_for_participant(
-                &binding_factors,
-                NonZeroScalar::new(signer.get_identifier()),
-            );
-            signer.store_rho(binding_factor);
+    /// Instantiates a new FROST protocol like `signature_share_with_rng`, for signers with the
+    /// given identifiers and roles instead of signers numbered from 1. Identifiers derived from
+    /// names with `NonZeroScalar::derive` are carried through commitments, binding factors and
+    /// interpolation like numeric ones.
+    ///
+    /// # Panics
+    ///
+    /// Panics if two signers share an identifier, or if the signers marked as blind could reach
+    /// `threshold` on their own.
+    pub fn signature_share_with_identifiers<R: Rng>(
+        threshold: usize,
+        signers: &[(NonZeroScalar<C>, SignerRole)],
+        generator: Element<C>,
+        rng: &mut R,
+    ) -> Self {
+        let x_coordinates: Vec<ScalarField<C>> = signers
+            .iter()
+            .map(|(identifier, _)| identifier.to_scalar())
+            .collect();
+        for (position, x) in x_coordinates.iter().enumerate() {
+            if x_coordinates[..position].contains(x) {
+                panic!("{}", FrostError::<C>::ParticipantExists(*x));
+            }
         }
+
+        let secret_key = ScalarField::<C>::rand(rng);
+        let group_pk = GroupPublicKey::new(generator * secret_key);
+
+        let shares = shamir_split_at_with_rng(secret_key, threshold, &x_coordinates, rng);
+        let signers = shares
+            .into_iter()
+            .zip(signers)
+            .map(|(share, (identifier, role))| {
+                FrostSigner::with_identifier(
+                    *identifier,
+                    SecretShare::new(share),
+                    generator,
+                    group_pk,
+                    *role == SignerRole::Blind,
+                )
+            })
+            .collect();
+
+        let frost = Frost {
+            generator,
+            signers,
+            group_pk,
+            threshold,
+        };
+        if let Err(error) = frost.check_blind_policy() {
+            panic!("{error}");
+        }
+
+        frost
+    }
+
+    /// Checks that the blind signers alone cannot reach the threshold, which would let a blind
+    /// custodian produce signatures without any of the regular signers.
+    pub fn check_blind_policy(&self) -> Result<(), FrostError<C>> {
+        let blind_signers = self.signers().filter(|signer| signer.is_blind()).count();
+        if blind_signers >= self.threshold {
+            return Err(FrostError::BlindSignersReachThreshold {
+                blind_signers,
+                threshold: self.threshold,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Returns an iterator over the signers, without copying their secret material.
+    pub fn signers(&self) -> impl Iterator<Item = &FrostSigner<C>> {
+        self.signers.iter()
     }
 
-    /// Coordinator aggregates each share to produce a final `ScalarField`, which represents the
-    /// Schnorr signature.
-    pub fn signature_aggregate(&self, sig_shares: Vec<ScalarField>) -> ScalarField {
-        let mut z = ScalarField::ZERO;
+    /// Returns an iterator over the signers, e.g. to sign with each of them.
+    pub fn signers_mut(&mut self) -> impl Iterator<Item = &mut FrostSigner<C>> {
+        self.signers.iter_mut()
+    }
 
-        for z_i in sig_shares {
-            z += z_i;
+    /// Returns an iterator over the identifiers of all signers.
+    pub fn participants(&self) -> impl Iterator<Item = NonZeroScalar<C>> + '_ {
+        self.signers().map(|signer| signer.commitment.identifier)
+    }
+
+    /// Coordinator collects the nonce commitments of all signers and builds the `SigningPackage`
+    /// for `message`.
+    pub fn signing_package(&self, message: &[u8]) -> SigningPackage<C> {
+        let commitments = self.signers().map(FrostSigner::get_commitments).collect();
+
+        SigningPackage::new(message, commitments)
+    }
+
+    /// Coordinator aggregates each `SignatureShare` to produce a final `ScalarField`, which
+    /// represents the Schnorr signature.
+    pub fn signature_aggregate<I>(&self, sig_shares: I) -> ScalarField<C>
+    where
+        I: IntoIterator<Item = SignatureShare<C>>,
+    {
+        let mut z = ScalarField::<C>::ZERO;
+
+        for sig_share in sig_shares {
+            z += sig_share.z_i;
         }
 
         z
     }
 
-    /// Verifies a given `signature`.
-    pub fn verify(&self, signature: SchnorrSignature, challenge: ScalarField) -> bool {
+    /// Verifies a given `signature` on `message` under the group public key.
+    ///
+    /// The challenge is derived here from `R`, the group public key and `message`, rather than
+    /// trusted from the caller.
+    pub fn verify(&self, message: &[u8], signature: SchnorrSignature<C>) -> bool {
+        let group_pk = self.group_pk.to_element();
+        let challenge = compute_challenge::<C>(signature.R, group_pk, message);
         let lhs = self.generator * signature.s; // g^z
-        let rhs = signature.R + self.group_pk * challenge;
+        let rhs = signature.R + group_pk * challenge.to_scalar();
 
         lhs == rhs
     }
+
+    /// Verifies an encoded signature on `message`, rejecting non-canonical encodings of `R` or
+    /// `s` before any group arithmetic takes place.
+    pub fn verify_encoded(&self, message: &[u8], signature_bytes: &[u8]) -> bool {
+        match SchnorrSignature::from_bytes(signature_bytes) {
+            Ok(signature) => self.verify(message, signature),
+            Err(_) => false,
+        }
+    }
+}
+
+/// Constructors for reproducible tests against known-answer values. Fixed nonces are only safe
+/// for a single signature, so these are only available with the `testing` feature.
+#[cfg(any(test, feature = "testing"))]
+impl<C: Ciphersuite> FrostSigner<C> {
+    /// Creates a `FrostSigner` from the key material in `key_package`, drawing its nonces from
+    /// `rng` instead of the hedged `nonce_generate`.
+    pub fn from_key_package_with_rng<R: Rng + ?Sized>(
+        key_package: &KeyPackage<C>,
+        rng: &mut R,
+    ) -> Self {
+        Self::from_parts(
+            key_package.identifier,
+            key_package.secret_share.to_scalar(),
+            key_package.generator,
+            key_package.group_pk,
+            SigningNonces::generate_with_rng(key_package.role, rng),
+        )
+    }
+
+    /// Creates a `FrostSigner` from the key material in `key_package`, with the given hiding
+    /// nonce `d` and binding nonce `e` instead of fresh ones.
+    pub fn from_key_package_with_nonces(
+        key_package: &KeyPackage<C>,
+        d: ScalarField<C>,
+        e: ScalarField<C>,
+    ) -> Self {
+        Self::from_parts(
+            key_package.identifier,
+            key_package.secret_share.to_scalar(),
+            key_package.generator,
+            key_package.group_pk,
+            SigningNonces::from_scalars(d, e),
+        )
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl<C: Ciphersuite> Frost<C> {
+    /// Instantiates a new FROST protocol given a `threshold` and `total_signers`.
+    ///
+    /// Shamir secret sharing is done here to generate the secret key shares for the signers. The
+    /// signers with an index above `threshold` are made blind; use `signature_share_with_roles` to
+    /// choose the blind signers explicitly.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the signers marked as blind could reach `threshold` on their own.
+    pub fn signature_share(threshold: usize, total_signers: usize) -> Self {
+        let roles: Vec<SignerRole> = (1..=total_signers)
+            .map(|index| {
+                if index > threshold {
+                    SignerRole::Blind
+                } else {
+                    SignerRole::Regular
+                }
+            })
+            .collect();
+        Self::signature_share_with_roles(threshold, &roles)
+    }
+
+    /// Instantiates a new FROST protocol given a `threshold` and the `roles` of the signers, where
+    /// the signer with index `i` is assigned `roles[i - 1]`.
+    ///
+    /// The generator, the group key and the shares are drawn from `ark_std::test_rng()`, so every
+    /// call returns the same group. Use `signature_share_with_rng` with `secure_rng` for real keys.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the signers marked as blind could reach `threshold` on their own.
+    pub fn signature_share_with_roles(threshold: usize, roles: &[SignerRole]) -> Self {
+        let mut rng = ark_std::test_rng();
+        let generator = Element::<C>::rand(&mut rng);
+        Self::signature_share_with_rng(threshold, roles, generator, &mut rng)
+    }
+
+    /// Instantiates a FROST protocol like `signature_share_with_roles`, deriving the generator,
+    /// the group secret key, the Shamir polynomial and the nonces from `seed`.
+    pub fn signature_share_from_seed(
+        threshold: usize,
+        roles: &[SignerRole],
+        seed: [u8; 32],
+    ) -> Self {
+        use ark_std::rand::{SeedableRng, rngs::StdRng};
+
+        let mut rng = StdRng::from_seed(seed);
+        let generator = Element::<C>::rand(&mut rng);
+        let mut frost = Self::signature_share_with_rng(threshold, roles, generator, &mut rng);
+        frost.signers = frost
+            .signers()
+            .map(|signer| {
+                let nonces = SigningNonces::generate_with_rng(signer.role(), &mut rng);
+                Self::fixed_signer(signer, nonces)
+            })
+            .collect();
+        frost
+    }
+
+    fn fixed_signer(signer: &FrostSigner<C>, nonces: SigningNonces<C>) -> FrostSigner<C> {
+        FrostSigner::from_parts(
+            signer.commitment.identifier,
+            *signer.x.expose_secret(),
+            signer.generator,
+            signer.group_pk,
+            nonces,
+        )
+    }
+}
+
+#[test]
+fn test_sign_rejects_substituted_commitment() {
+    let mut frost: Frost = Frost::signature_share(2, 3);
+    let signing_package = frost.signing_package(b"testing");
+    let generator = frost.generator;
+    let signer = &mut frost.signers[0];
+
+    let mut substituted = signing_package.clone();
+    substituted.commitments[0].hiding = generator;
+    assert_eq!(
+        signer.sign(&substituted),
+        Err(FrostError::CommitmentMismatch(signer.get_identifier()))
+    );
+
+    substituted.commitments.clear();
+    assert_eq!(
+        signer.sign(&substituted),
+        Err(FrostError::MissingCommitment(signer.get_identifier()))
+    );
+
+    // a rejected package leaves the nonces in place, but they are wiped once used
+    assert!(signer.sign(&signing_package).is_ok());
+    assert!(signer.nonces.is_none());
+    assert_eq!(
+        signer.sign(&signing_package),
+        Err(FrostError::NonceReused(signer.get_identifier()))
+    );
+    // fresh nonces come with fresh commitments, which the old package does not hold
+    signer.refresh_nonces();
+    assert_eq!(
+        signer.sign(&signing_package),
+        Err(FrostError::CommitmentMismatch(signer.get_identifier()))
+    );
+}
+
+#[test]
+fn test_clone_cannot_reuse_nonces() {
+    let mut frost: Frost = Frost::signature_share(2, 3);
+    let signing_package = frost.signing_package(b"testing");
+    let mut clone = frost.signers[0].clone();
+    assert!(frost.signers[0].sign(&signing_package).is_ok());
+
+    // the clone holds the same commitments but not the nonces behind them, so it cannot sign a
+    // second package over the same commitments
+    let mut second = signing_package.clone();
+    second.message = b"other".to_vec();
+    assert_eq!(clone.get_commitments(), frost.signers[0].get_commitments());
+    assert_eq!(
+        clone.sign(&second),
+        Err(FrostError::NonceReused(clone.get_identifier()))
+    );
+}
+
+#[test]
+fn test_debug_redacts_secrets() {
+    let frost: Frost = Frost::signature_share(2, 3);
+    let signer = &frost.signers[0];
+    let debug = format!("{frost:?}");
+
+    for secret in [
+        *signer.x.expose_secret(),
+        signer.nonces.as_ref().unwrap().hiding(),
+        signer.nonces.as_ref().unwrap().binding(),
+    ] {
+        assert!(!debug.contains(&format!("{secret:?}")));
+    }
+    assert!(debug.contains(&format!("{:?}", signer.commitment)));
+}
+
+#[test]
+fn test_blind_policy() {
+    let mut frost: Frost = Frost::signature_share(3, 5);
+    assert!(frost.check_blind_policy().is_ok());
+
+    frost.threshold = 2;
+    assert_eq!(
+        frost.check_blind_policy(),
+        Err(FrostError::BlindSignersReachThreshold {
+            blind_signers: 2,
+            threshold: 2,
+        })
+    );
+}
+
+#[test]
+fn test_explicit_blind_signers() {
+    use SignerRole::{Blind, Regular};
+
+    let frost: Frost = Frost::signature_share_with_roles(3, &[Blind, Regular, Regular, Blind]);
+    let roles: Vec<SignerRole> = frost.signers().map(FrostSigner::role).collect();
+    assert_eq!(roles, vec![Blind, Regular, Regular, Blind]);
+    assert_eq!(
+        frost.signers[0].get_commitments().binding,
+        Element::<Ed25519Sha512>::ZERO
+    );
+}
+
+#[test]
+#[should_panic(expected = "blind signers")]
+fn test_blind_signers_reaching_threshold_rejected() {
+    let _: Frost = Frost::signature_share(2, 5);
+}
+
+#[test]
+fn test_named_signers() {
+    let mut rng = ark_std::test_rng();
+    let identifier = |name| NonZeroScalar::<Ed25519Sha512>::derive(name).unwrap();
+    assert_eq!(identifier("alice-phone"), identifier("alice-phone"));
+    assert_ne!(identifier("alice-phone"), identifier("alice-laptop"));
+
+    let mut frost: Frost = Frost::signature_share_with_identifiers(
+        2,
+        &[
+            (identifier("alice-phone"), SignerRole::Regular),
+            (identifier("bob-laptop"), SignerRole::Regular),
+            (identifier("acme-custodian"), SignerRole::Blind),
+        ],
+        Element::<Ed25519Sha512>::rand(&mut rng),
+        &mut rng,
+    );
+    assert_eq!(frost.participants().next(), Some(identifier("alice-phone")));
+
+    let signing_package = frost.signing_package(b"testing");
+    let (R, _) = signing_package
+        .group_commitment_and_challenge(frost.group_pk)
+        .unwrap();
+    let sig_shares: Vec<_> = frost
+        .signers_mut()
+        .map(|signer| signer.sign(&signing_package).unwrap())
+        .collect();
+    let s = frost.signature_aggregate(sig_shares);
+    assert!(frost.verify(b"testing", SchnorrSignature { R, s }));
+}
+
+#[test]
+fn test_signing_over_custom_curve() {
+    use sha2::Sha512;
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    struct Curve25519Sha512;
+    impl Ciphersuite for Curve25519Sha512 {
+        const CONTEXT_STRING: &'static str = "FROST-CURVE25519-SHA512-v1";
+        type Group = ark_curve25519::EdwardsProjective;
+        type Hash = Sha512;
+    }
+
+    let mut frost = Frost::<Curve25519Sha512>::signature_share(2, 3);
+    let signing_package = frost.signing_package(b"testing");
+    let sig_shares: Vec<_> = frost
+        .signers_mut()
+        .map(|signer| signer.sign(&signing_package).unwrap())
+        .collect();
+
+    let (R, _) = signing_package
+        .group_commitment_and_challenge(frost.group_pk)
+        .unwrap();
+    let signature = SchnorrSignature {
+        R,
+        s: frost.signature_aggregate(sig_shares),
+    };
+    assert!(frost.verify(b"testing", signature));
+}
+
+#[test]
+fn test_seeded_signing_is_reproducible() {
+    let roles = [SignerRole::Regular, SignerRole::Regular, SignerRole::Blind];
+    let sign = || {
+        let mut frost: Frost = Frost::signature_share_from_seed(2, &roles, [7; 32]);
+        let signing_package = SigningPackage {
+            session_id: [0; 32],
+            ..frost.signing_package(b"testing")
+        };
+        let (R, _) = signing_package
+            .group_commitment_and_challenge(frost.group_pk)
+            .unwrap();
+        let sig_shares: Vec<_> = frost
+            .signers_mut()
+            .map(|signer| signer.sign(&signing_package).unwrap())
+            .collect();
+        let s = frost.signature_aggregate(sig_shares);
+        let signature = SchnorrSignature::<Ed25519Sha512> { R, s };
+        assert!(frost.verify(b"testing", signature));
+        signature
+    };
+    assert_eq!(sign(), sign());
+
+    // a signer rebuilt from its key package with the same nonces commits identically
+    let frost: Frost = Frost::signature_share_from_seed(2, &roles, [7; 32]);
+    let signer = &frost.signers[0];
+    let key_package = frost
+        .key_package(signer.get_commitments().identifier)
+        .unwrap();
+    let rebuilt = FrostSigner::from_key_package_with_nonces(
+        &key_package,
+        signer.nonces.as_ref().unwrap().hiding(),
+        signer.nonces.as_ref().unwrap().binding(),
+    );
+    assert_eq!(rebuilt.get_commitments(), signer.get_commitments());
 }
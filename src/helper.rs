@@ -1,200 +1,849 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{self, BufRead, Read},
+    str::FromStr,
+    sync::{
+        Mutex, PoisonError,
+        atomic::{AtomicU64, Ordering as AtomicOrdering},
+    },
+};
+
 use ark_ec::AdditiveGroup;
-use ark_ed25519::{EdwardsProjective as Element, Fr as ScalarField};
-use ark_ff::{Field, PrimeField};
-use ark_serialize::CanonicalSerialize;
+use ark_ff::{BigInteger, Field, PrimeField, UniformRand, batch_inversion};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use ark_std::rand::{Rng, SeedableRng, rngs::StdRng};
 use rand::RngCore;
 
-use crate::ciphersuite::{H1, H2, H3, H4, H5};
+use crate::{
+    ciphersuite::{Ciphersuite, Ed25519Sha512, Element, ScalarField},
+    error::FrostError,
+    json::Json,
+    nonces::{SigningCommitments, SigningNonces},
+};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub struct NonZeroScalar(ScalarField);
+pub struct NonZeroScalar<C: Ciphersuite = Ed25519Sha512>(ScalarField<C>);
+
+impl<C: Ciphersuite> NonZeroScalar<C> {
+    /// Returns an error if `value` is zero.
+    pub fn new(value: ScalarField<C>) -> Result<Self, FrostError<C>> {
+        if value == ScalarField::<C>::ZERO {
+            return Err(FrostError::ZeroIdentifier);
+        }
+
+        Ok(NonZeroScalar(value))
+    }
+
+    /// Derives the identifier of a participant from a human-meaningful `name`, e.g.
+    /// `"acme-custodian"`, with a domain-separated hash, so that participants need not be numbered
+    /// densely.
+    ///
+    /// Returns an error in the negligible case that the name hashes to zero.
+    pub fn derive(name: &str) -> Result<Self, FrostError<C>> {
+        Self::new(ScalarField::<C>::from_le_bytes_mod_order(&C::H_identifier(
+            name.as_bytes(),
+        )))
+    }
+
+    /// Returns the underlying `ScalarField`.
+    pub fn to_scalar(&self) -> ScalarField<C> {
+        self.0
+    }
+}
+
+/// Formats the identifier as the hex encoding of its scalar.
+impl<C: Ciphersuite> fmt::Display for NonZeroScalar<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&encode_hex(&serialize_scalar::<C>(&self.0)))
+    }
+}
+
+/// Parses the hex encoding of a non-zero scalar.
+impl<C: Ciphersuite> FromStr for NonZeroScalar<C> {
+    type Err = SerializationError;
+
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        let bytes = decode_hex(hex).ok_or(SerializationError::InvalidData)?;
+        let mut decoder = Decoder::new(&bytes);
+        let identifier = decoder.identifier()?;
+        decoder.finish()?;
+        Ok(identifier)
+    }
+}
+
+/// The binding factor `rho_i` of the participant with the given `identifier`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BindingFactor<C: Ciphersuite = Ed25519Sha512> {
+    /// Identifier of the participant.
+    pub identifier: NonZeroScalar<C>,
+    /// The binding factor `rho_i`.
+    pub rho: ScalarField<C>,
+}
+
+/// The binding factors of all participants of a signing session, indexed by identifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindingFactorList<C: Ciphersuite = Ed25519Sha512>(
+    BTreeMap<ScalarField<C>, BindingFactor<C>>,
+);
+
+impl<C: Ciphersuite> BindingFactorList<C> {
+    /// Returns the binding factor `rho_i` of the participant with the given `identifier`.
+    pub fn get(&self, identifier: NonZeroScalar<C>) -> Option<ScalarField<C>> {
+        self.0
+            .get(&identifier.to_scalar())
+            .map(|binding_factor| binding_factor.rho)
+    }
+
+    /// Returns an iterator over the binding factors, ordered by identifier.
+    pub fn iter(&self) -> impl Iterator<Item = &BindingFactor<C>> {
+        self.0.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<C: Ciphersuite> FromIterator<BindingFactor<C>> for BindingFactorList<C> {
+    fn from_iter<I: IntoIterator<Item = BindingFactor<C>>>(binding_factors: I) -> Self {
+        BindingFactorList(
+            binding_factors
+                .into_iter()
+                .map(|binding_factor| (binding_factor.identifier.to_scalar(), binding_factor))
+                .collect(),
+        )
+    }
+}
 
-impl NonZeroScalar {
-    pub fn new(value: ScalarField) -> Self {
-        if value == ScalarField::ZERO {
-            panic!("NonZeroScalar cannot be zero")
+/// The participants of a signing session, given by their commitments in the order they are
+/// encoded.
+///
+/// The list is validated once, on construction, and the binding factors, the group commitment
+/// and the interpolation values are all derived from it, so they cannot disagree on who takes
+/// part in the session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParticipantSet<C: Ciphersuite = Ed25519Sha512> {
+    commitments: Vec<SigningCommitments<C>>,
+    x_coordinates: Vec<NonZeroScalar<C>>,
+}
+
+impl<C: Ciphersuite> ParticipantSet<C> {
+    /// Sorts `commitments` by identifier, as RFC 9591 requires of the commitment list, and
+    /// returns an error if they contain more than one commitment from a participant.
+    pub fn new(mut commitments: Vec<SigningCommitments<C>>) -> Result<Self, FrostError<C>> {
+        commitments.sort_by_key(|commitment| commitment.identifier.0);
+        if let Some(pair) = commitments
+            .windows(2)
+            .find(|pair| pair[0].identifier == pair[1].identifier)
+        {
+            return Err(FrostError::DuplicateCommitment(pair[0].identifier.0));
         }
+        let x_coordinates = commitments
+            .iter()
+            .map(|commitment| commitment.identifier)
+            .collect();
+
+        Ok(ParticipantSet {
+            commitments,
+            x_coordinates,
+        })
+    }
 
-        NonZeroScalar(value)
+    /// Returns the commitments of all participants.
+    pub fn commitments(&self) -> &[SigningCommitments<C>] {
+        &self.commitments
+    }
+
+    /// Returns the identifiers of all participants.
+    pub fn x_coordinates(&self) -> &[NonZeroScalar<C>] {
+        &self.x_coordinates
+    }
+
+    /// Returns the commitments of the participant with the given `identifier`.
+    pub fn get(&self, identifier: NonZeroScalar<C>) -> Option<&SigningCommitments<C>> {
+        self.commitments
+            .iter()
+            .find(|commitment| commitment.identifier == identifier)
     }
 }
 
-/// A binding factor is a tuple of (identifier i, rho_i)
-pub type BindingFactor = (NonZeroScalar, ScalarField);
+/// A proof of possession of the nonces behind `SigningCommitments`: a Schnorr proof of knowledge
+/// of `d_i` and `e_i` with `D_i = g^{d_i}` and `E_i = g^{e_i}`, bound to the commitments.
+///
+/// A coordinator that requires these proofs only accepts commitments whose discrete logs the
+/// sender knows, so a participant cannot choose its commitments as a function of the others'.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CommitmentProof<C: Ciphersuite = Ed25519Sha512> {
+    /// The Fiat-Shamir challenge.
+    pub challenge: ScalarField<C>,
+    /// Response for the hiding nonce `d_i`.
+    pub hiding_response: ScalarField<C>,
+    /// Response for the binding nonce `e_i`.
+    pub binding_response: ScalarField<C>,
+}
+
+impl<C: Ciphersuite> CommitmentProof<C> {
+    /// Proves knowledge of the hiding nonce `d` and the binding nonce `e` behind `commitments`.
+    pub(crate) fn prove<R: Rng>(
+        commitments: &SigningCommitments<C>,
+        nonces: &SigningNonces<C>,
+        generator: Element<C>,
+        rng: &mut R,
+    ) -> Self {
+        let (k_d, k_e) = (ScalarField::<C>::rand(rng), ScalarField::<C>::rand(rng));
+        let challenge =
+            Self::derive_challenge(commitments, generator, generator * k_d, generator * k_e);
+
+        CommitmentProof {
+            challenge,
+            hiding_response: k_d + challenge * nonces.hiding(),
+            binding_response: k_e + challenge * nonces.binding(),
+        }
+    }
+
+    /// Returns `true` if the proof shows knowledge of the nonces behind `commitments`.
+    pub fn verify(&self, commitments: &SigningCommitments<C>, generator: Element<C>) -> bool {
+        let hiding = generator * self.hiding_response - commitments.hiding * self.challenge;
+        let binding = generator * self.binding_response - commitments.binding * self.challenge;
+
+        Self::derive_challenge(commitments, generator, hiding, binding) == self.challenge
+    }
+
+    /// Encodes the proof as `challenge || hiding_response || binding_response`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [self.challenge, self.hiding_response, self.binding_response]
+            .iter()
+            .flat_map(serialize_scalar::<C>)
+            .collect()
+    }
+
+    /// Parses a proof encoded by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let mut decoder = Decoder::new(bytes);
+        let proof = CommitmentProof {
+            challenge: decoder.scalar::<C>()?,
+            hiding_response: decoder.scalar::<C>()?,
+            binding_response: decoder.scalar::<C>()?,
+        };
+        decoder.finish()?;
+        Ok(proof)
+    }
+
+    fn derive_challenge(
+        commitments: &SigningCommitments<C>,
+        generator: Element<C>,
+        hiding: Element<C>,
+        binding: Element<C>,
+    ) -> ScalarField<C> {
+        let transcript = [
+            C::serialize_element(&generator),
+            commitments.to_bytes(),
+            C::serialize_element(&hiding),
+            C::serialize_element(&binding),
+        ]
+        .concat();
+        ScalarField::<C>::from_le_bytes_mod_order(&C::H_commitment_proof(&transcript))
+    }
+}
+
+/// Encodes `bytes` as a lowercase hex string.
+pub fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Decodes a hex string, returning `None` if it has odd length or contains non-hex characters.
+pub fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Encodes a scalar as arkworks' compressed serialization does, i.e. little-endian for the
+/// Ed25519 scalar field.
+pub fn serialize_scalar<C: Ciphersuite>(scalar: &ScalarField<C>) -> Vec<u8> {
+    let mut bytes = scalar.into_bigint().to_bytes_le();
+    bytes.resize(scalar.compressed_size(), 0);
+    bytes
+}
+
+/// Appends the encoding of `serialize_scalar` to `bytes`. Unlike arkworks' serialization, which
+/// returns a `Result` for writers that can fail, writing to a `Vec` this way cannot fail.
+pub(crate) fn write_scalar<C: Ciphersuite>(scalar: &ScalarField<C>, bytes: &mut Vec<u8>) {
+    let start = bytes.len();
+    bytes.extend(scalar.into_bigint().to_bytes_le());
+    bytes.resize(start + scalar.compressed_size(), 0);
+}
+
+/// Reads consecutive values from a byte string produced by the `to_bytes` encodings.
+pub(crate) struct Decoder<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Decoder { bytes }
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8], SerializationError> {
+        if self.bytes.len() < len {
+            return Err(SerializationError::InvalidData);
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    pub(crate) fn array<const N: usize>(&mut self) -> Result<[u8; N], SerializationError> {
+        self.take(N)?
+            .try_into()
+            .map_err(|_| SerializationError::InvalidData)
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, SerializationError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, SerializationError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    pub(crate) fn scalar<C: Ciphersuite>(&mut self) -> Result<ScalarField<C>, SerializationError> {
+        let len = ScalarField::<C>::ZERO.compressed_size();
+        ScalarField::<C>::deserialize_compressed(self.take(len)?)
+    }
+
+    pub(crate) fn identifier<C: Ciphersuite>(
+        &mut self,
+    ) -> Result<NonZeroScalar<C>, SerializationError> {
+        let scalar = self.scalar::<C>()?;
+        if scalar == ScalarField::<C>::ZERO {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(NonZeroScalar(scalar))
+    }
+
+    pub(crate) fn element<C: Ciphersuite>(&mut self) -> Result<Element<C>, SerializationError> {
+        let len = C::serialize_element(&Element::<C>::ZERO).len();
+        C::deserialize_element(self.take(len)?)
+    }
 
-/// A Commitment R_i is a tuple of (identifier i, D_i, E_i)
-pub type Commitment = (NonZeroScalar, Element, Element);
+    pub(crate) fn commitments<C: Ciphersuite>(
+        &mut self,
+    ) -> Result<SigningCommitments<C>, SerializationError> {
+        Ok(SigningCommitments {
+            identifier: self.identifier()?,
+            hiding: self.element::<C>()?,
+            binding: self.element::<C>()?,
+        })
+    }
 
-pub fn nonce_generate(secret: ScalarField) -> ScalarField {
-    // Generate a 32-byte random number
-    let mut rng = rand::rng();
-    let mut random_bytes = [0u8; 32];
-    rng.fill_bytes(&mut random_bytes);
+    /// Returns the remaining bytes, for a trailing value with an encoding of its own.
+    pub(crate) fn rest(self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Rejects trailing bytes.
+    pub(crate) fn finish(self) -> Result<(), SerializationError> {
+        if !self.bytes.is_empty() {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(())
+    }
+}
 
-    let mut secret_bytes = Vec::with_capacity(32);
-    secret
-        .serialize_compressed(&mut secret_bytes)
-        .expect("serialization failed");
+/// Compares two byte strings in time independent of where they differ, for values derived from
+/// secrets such as authentication tags.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let diff = a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y));
+    a.len() == b.len() && std::hint::black_box(diff) == 0
+}
 
-    let mut message = Vec::with_capacity(64);
-    message.extend_from_slice(&random_bytes);
-    message.extend_from_slice(&secret_bytes);
+/// Compares two secret scalars, e.g. key shares or nonces, in constant time. The derived
+/// `PartialEq` of `ScalarField` may return as soon as a limb differs.
+pub(crate) fn scalars_eq<C: Ciphersuite>(a: &ScalarField<C>, b: &ScalarField<C>) -> bool {
+    constant_time_eq(&serialize_scalar::<C>(a), &serialize_scalar::<C>(b))
+}
 
-    // return H3
-    let hash_output = H3(message);
-    ScalarField::from_le_bytes_mod_order(&hash_output)
+/// Reads a line into `line`, like `BufRead::read_line`, but fails with
+/// `io::ErrorKind::InvalidData` once the line exceeds `max_len` bytes, so that a peer that never
+/// sends a newline cannot make the reader buffer without bound.
+pub(crate) fn read_bounded_line<R: BufRead>(
+    reader: &mut R,
+    max_len: usize,
+    line: &mut String,
+) -> io::Result<usize> {
+    let read = reader.by_ref().take(max_len as u64 + 1).read_line(line)?;
+    if read > max_len && !line.ends_with('\n') {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "line too long"));
+    }
+    Ok(read)
 }
 
-/// Derives and returns a value used for polynomial interpolation.
+/// Returns an arkworks-compatible RNG seeded from the operating system's entropy source.
 ///
-/// # Panics
+/// Unlike `ark_std::test_rng`, the output is not reproducible across runs.
+pub fn secure_rng() -> StdRng {
+    let mut seed = [0u8; 32];
+    rand::rng().fill_bytes(&mut seed);
+    StdRng::from_seed(seed)
+}
+
+/// Derives and returns the value used for polynomial interpolation of `x_i` among the
+/// `participants`.
 ///
-/// Panics if `x_coordinates` do not contain `x_i`, or if `x_i` is found more than once in
-/// `x_coordinates`.
+/// Returns an error if `x_i` is not a participant.
 ///
 /// Reference: https://www.rfc-editor.org/rfc/rfc9591.html#section-4.2
-pub fn derive_interpolating_value(
-    x_coordinates: &[NonZeroScalar],
-    x_i: NonZeroScalar,
-) -> ScalarField {
-    let mut numerator = ScalarField::ONE;
-    let mut denominator = ScalarField::ONE;
-
-    for x_j in x_coordinates {
+pub fn derive_interpolating_value<C: Ciphersuite>(
+    participants: &ParticipantSet<C>,
+    x_i: NonZeroScalar<C>,
+) -> Result<ScalarField<C>, FrostError<C>> {
+    if !participants.x_coordinates.contains(&x_i) {
+        return Err(FrostError::UnknownParticipant(x_i.0));
+    }
+    derive_interpolating_value_at(&participants.x_coordinates, x_i, ScalarField::<C>::ZERO)
+}
+
+/// Derives the Lagrange coefficient of `x_i` for interpolating the polynomial at `x` rather than
+/// at zero, e.g. to issue a share for a new participant.
+///
+/// Returns an error if `x_coordinates` contain an x-coordinate more than once.
+pub fn derive_interpolating_value_at<C: Ciphersuite>(
+    x_coordinates: &[NonZeroScalar<C>],
+    x_i: NonZeroScalar<C>,
+    x: ScalarField<C>,
+) -> Result<ScalarField<C>, FrostError<C>> {
+    let mut numerator = ScalarField::<C>::ONE;
+    let mut denominator = ScalarField::<C>::ONE;
+
+    for (j, x_j) in x_coordinates.iter().enumerate() {
+        if x_coordinates[..j].contains(x_j) {
+            return Err(FrostError::DuplicateCommitment(x_j.0));
+        }
         if x_j == &x_i {
             continue;
         }
-        numerator *= x_j.0;
+        numerator *= x_j.0 - x;
         denominator *= x_j.0 - x_i.0;
     }
 
-    numerator / denominator
+    // the denominator is only zero if `x_i` is listed more than once
+    let inverse = denominator
+        .inverse()
+        .ok_or(FrostError::DuplicateCommitment(x_i.0))?;
+    Ok(numerator * inverse)
 }
 
-/// Encodes and returns a list of participant `Commitment`s into a byte string for use in the FROST
-/// protocol, mostly for hashing purposes.
-///
-/// # Panics
-///
-/// Panics if serialization fails.
+/// Interpolation values of each participant set, keyed by its sorted identifiers, with the tick
+/// at which the set was last used.
+type InterpolationValues<C> = BTreeMap<Vec<ScalarField<C>>, (u64, Vec<ScalarField<C>>)>;
+
+/// Default number of participant sets an `InterpolationCache` holds.
+pub const DEFAULT_INTERPOLATION_CACHE_CAPACITY: usize = 64;
+
+/// Interpolation values of the participant sets signed with before, so that signing repeatedly
+/// with the same subset of signers skips the field inversions.
 ///
-/// Reference: https://www.rfc-editor.org/rfc/rfc9591.html#section-4.3
-fn encode_group_commitment_list(commitment_list: &[Commitment]) -> Vec<u8> {
-    let mut encoded = vec![];
+/// Entries are keyed by the sorted identifiers of a set. The participant sets may come from
+/// untrusted requests, so the cache holds at most `capacity` sets and evicts the least recently
+/// used one to make room for a new set.
+#[derive(Debug)]
+pub struct InterpolationCache<C: Ciphersuite = Ed25519Sha512> {
+    values: Mutex<InterpolationValues<C>>,
+    capacity: usize,
+    /// Incremented on every lookup, to order the entries by recency.
+    tick: AtomicU64,
+}
 
-    for (identifier, hiding_nonce_commitment, binding_nonce_commitment) in commitment_list {
-        let mut identifier_bytes = Vec::new();
-        let mut hiding_nonce_commitment_bytes = Vec::new();
-        let mut binding_nonce_commitment_bytes = Vec::new();
+impl<C: Ciphersuite> InterpolationCache<C> {
+    /// Creates a cache holding up to `DEFAULT_INTERPOLATION_CACHE_CAPACITY` participant sets.
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_INTERPOLATION_CACHE_CAPACITY)
+    }
 
-        identifier
-            .0
-            .serialize_compressed(&mut identifier_bytes)
-            .unwrap();
-
-        hiding_nonce_commitment
-            .serialize_compressed(&mut hiding_nonce_commitment_bytes)
-            .unwrap();
-        binding_nonce_commitment
-            .serialize_compressed(&mut binding_nonce_commitment_bytes)
-            .unwrap();
-
-        let mut encoded_commitment = [
-            identifier_bytes,
-            hiding_nonce_commitment_bytes,
-            binding_nonce_commitment_bytes,
-        ]
-        .concat();
+    /// Creates a cache holding up to `capacity` participant sets.
+    pub fn with_capacity(capacity: usize) -> Self {
+        InterpolationCache {
+            values: Mutex::new(BTreeMap::new()),
+            capacity,
+            tick: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the value used for polynomial interpolation of `x_i` among the `participants`,
+    /// deriving the values of every participant with a single inversion when the set is new.
+    ///
+    /// Returns an error if `x_i` is not a participant.
+    pub fn interpolating_value(
+        &self,
+        participants: &ParticipantSet<C>,
+        x_i: NonZeroScalar<C>,
+    ) -> Result<ScalarField<C>, FrostError<C>> {
+        let mut x_coordinates: Vec<ScalarField<C>> = participants
+            .x_coordinates()
+            .iter()
+            .map(NonZeroScalar::to_scalar)
+            .collect();
+        x_coordinates.sort();
+        let position = x_coordinates
+            .binary_search(&x_i.0)
+            .map_err(|_| FrostError::UnknownParticipant(x_i.0))?;
+
+        let mut values = self.values.lock().unwrap_or_else(PoisonError::into_inner);
+        let tick = self.tick.fetch_add(1, AtomicOrdering::Relaxed);
+        if let Some((last_used, lambdas)) = values.get_mut(&x_coordinates) {
+            *last_used = tick;
+            return Ok(lambdas[position]);
+        }
 
-        encoded.append(&mut encoded_commitment);
+        let lambdas = derive_interpolating_values::<C>(&x_coordinates);
+        let lambda = lambdas[position];
+        if self.capacity > 0 {
+            if values.len() >= self.capacity
+                && let Some(least_recent) = values
+                    .iter()
+                    .min_by_key(|(_, (last_used, _))| *last_used)
+                    .map(|(key, _)| key.clone())
+            {
+                values.remove(&least_recent);
+            }
+            values.insert(x_coordinates, (tick, lambdas));
+        }
+        Ok(lambda)
     }
 
-    encoded
+    /// Returns the number of participant sets cached.
+    pub fn len(&self) -> usize {
+        self.values
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
 
-/// Extracts and returns a `BindingFactor` from a `Vec<BindingFactor>` given a `NonZeroScalar`
-/// identifier.
-pub fn binding_factor_for_participant(
-    binding_factor_list: &[BindingFactor],
-    identifier: NonZeroScalar,
-) -> ScalarField {
-    binding_factor_list
+impl<C: Ciphersuite> Default for InterpolationCache<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Derives the interpolation values at zero of all distinct `x_coordinates`, batching the
+/// inversions of the denominators into one.
+fn derive_interpolating_values<C: Ciphersuite>(
+    x_coordinates: &[ScalarField<C>],
+) -> Vec<ScalarField<C>> {
+    let mut numerators = vec![ScalarField::<C>::ONE; x_coordinates.len()];
+    let mut denominators = vec![ScalarField::<C>::ONE; x_coordinates.len()];
+    for (i, x_i) in x_coordinates.iter().enumerate() {
+        for (j, x_j) in x_coordinates.iter().enumerate() {
+            if i != j {
+                numerators[i] *= x_j;
+                denominators[i] *= *x_j - x_i;
+            }
+        }
+    }
+
+    batch_inversion(&mut denominators);
+    numerators
         .iter()
-        .find(|(id, _)| *id == identifier)
-        .unwrap()
-        .1
+        .zip(denominators)
+        .map(|(numerator, denominator)| *numerator * denominator)
+        .collect()
+}
+
+/// Encodes and returns a list of participant `SigningCommitments` into a byte string for use in
+/// the FROST protocol, mostly for hashing purposes.
+///
+/// Reference: https://www.rfc-editor.org/rfc/rfc9591.html#section-4.3
+fn encode_group_commitment_list<C: Ciphersuite>(
+    commitment_list: &[SigningCommitments<C>],
+) -> Vec<u8> {
+    let scalar_len = ScalarField::<C>::ZERO.compressed_size();
+    let element_len = Element::<C>::default().compressed_size();
+    let mut encoded = Vec::with_capacity(commitment_list.len() * (scalar_len + 2 * element_len));
+
+    for commitment in commitment_list {
+        write_scalar::<C>(&commitment.identifier.0, &mut encoded);
+        C::write_element(&commitment.hiding, &mut encoded);
+        C::write_element(&commitment.binding, &mut encoded);
+    }
+
+    encoded
 }
 
-/// Computes and returns `Vec<BindingFactor>` based on participant `commitment_list`, `msg` and
-/// the group public key `group_pk`.
+/// Computes and returns the `BindingFactorList` of the `participants` based on `msg` and the group
+/// public key `group_pk`.
 ///
 /// Reference: https://www.rfc-editor.org/rfc/rfc9591.html#section-4.4
-pub fn compute_binding_factors(
-    group_pk: Element,
-    commitment_list: &[Commitment],
-    msg: Vec<u8>,
-) -> Vec<BindingFactor> {
-    let mut group_pk_encoded = vec![];
-    group_pk
-        .serialize_compressed(&mut group_pk_encoded)
-        .unwrap();
+pub fn compute_binding_factors<C: Ciphersuite>(
+    group_pk: Element<C>,
+    participants: &ParticipantSet<C>,
+    msg: &[u8],
+) -> BindingFactorList<C> {
+    let commitment_list = participants.commitments();
+
+    // rho_input = group_pk || H4(msg) || H5(encoded commitments) || identifier, built once and
+    // completed with each identifier in turn
+    let mut rho_input = C::serialize_element(&group_pk);
+    rho_input.extend(C::H4(msg));
+    rho_input.extend(C::H5(&encode_group_commitment_list(commitment_list)));
+    let prefix_len = rho_input.len();
+
+    commitment_list
+        .iter()
+        .map(|SigningCommitments { identifier, .. }| {
+            rho_input.truncate(prefix_len);
+            rho_input.extend(serialize_scalar::<C>(&identifier.0));
+            BindingFactor {
+                identifier: *identifier,
+                rho: ScalarField::<C>::from_le_bytes_mod_order(&C::H1(&rho_input)),
+            }
+        })
+        .collect()
+}
 
-    let msg_hash = H4(msg);
-    let encoded_commitment_hash = H5(encode_group_commitment_list(commitment_list));
+/// Computes the group commitment `R` from the commitments of the `participants`.
+///
+/// Returns an error if `binding_factor_list` does not hold exactly one binding factor for each
+/// participant.
+pub fn compute_group_commitment<C: Ciphersuite>(
+    participants: &ParticipantSet<C>,
+    binding_factor_list: &BindingFactorList<C>,
+) -> Result<Element<C>, FrostError<C>> {
+    if binding_factor_list.len() != participants.commitments.len() {
+        return Err(FrostError::ParticipantMismatch);
+    }
+    let mut group_commitment = Element::<C>::ZERO;
 
-    let rho_input_prefix: Vec<u8> = [group_pk_encoded, msg_hash, encoded_commitment_hash].concat();
+    for commitment in participants.commitments() {
+        let binding_factor = binding_factor_list
+            .get(commitment.identifier)
+            .ok_or(FrostError::ParticipantMismatch)?;
+        let binding_nonce = commitment.binding * binding_factor;
 
-    let mut binding_factor_list = Vec::with_capacity(commitment_list.len());
+        group_commitment += commitment.hiding + binding_nonce;
+    }
 
-    for (identifier, _, _) in commitment_list {
-        let mut identifier_bytes = Vec::new();
-        identifier
-            .0
-            .serialize_compressed(&mut identifier_bytes)
-            .unwrap();
+    Ok(group_commitment)
+}
+
+/// Computes the digest of `message` under the application-chosen `domain`, to be signed in place
+/// of the message in digest-only signing requests, so that the custodian never sees the message.
+///
+/// The digest is then the message of the `SigningPackage`, so every signer derives the binding
+/// factors and the challenge from it, and the signature verifies against it.
+pub fn message_digest<C: Ciphersuite>(domain: &[u8], message: &[u8]) -> Vec<u8> {
+    let domain_len = domain.len() as u64;
+    C::H_digest(&[&domain_len.to_le_bytes(), domain, message].concat())
+}
+
+/// Computes the `message_digest` of the JSON `document` in its RFC 8785 canonical form, so that
+/// signers who construct the same logical document, whatever its member order, whitespace or
+/// number formatting, sign the same bytes.
+///
+/// Returns an error if `document` is not valid JSON, has duplicate member names, or holds a
+/// number that is not a finite double.
+pub fn structured_digest<C: Ciphersuite>(
+    domain: &[u8],
+    document: &str,
+) -> Result<Vec<u8>, FrostError<C>> {
+    let canonical = Json::parse(document)
+        .and_then(|document| document.canonical())
+        .map_err(|error| FrostError::InvalidDocument(error.to_string()))?;
+    Ok(message_digest::<C>(domain, canonical.as_bytes()))
+}
 
-        let rho_input = [rho_input_prefix.clone(), identifier_bytes].concat();
-        let binding_factor = ScalarField::from_le_bytes_mod_order(&H1(rho_input));
+/// The challenge `c` of a FROST signing session, derived by `compute_challenge`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Challenge<C: Ciphersuite = Ed25519Sha512>(ScalarField<C>);
 
-        binding_factor_list.push((*identifier, binding_factor));
+impl<C: Ciphersuite> Challenge<C> {
+    pub(crate) fn new(scalar: ScalarField<C>) -> Self {
+        Challenge(scalar)
     }
 
-    binding_factor_list
+    pub fn to_scalar(&self) -> ScalarField<C> {
+        self.0
+    }
 }
 
-pub fn compute_group_commitment(
-    commitment_list: &[Commitment],
-    binding_factor_list: Vec<BindingFactor>,
-) -> Element {
-    // TODO: fix
-    let mut group_commitment = Element::ZERO;
+pub fn compute_challenge<C: Ciphersuite>(
+    group_commitment: Element<C>,
+    group_pk: Element<C>,
+    msg: &[u8],
+) -> Challenge<C> {
+    let mut challenge_input = C::serialize_element(&group_commitment);
+    challenge_input.extend(C::serialize_element(&group_pk));
+    challenge_input.extend_from_slice(msg);
+    let challenge_bytes = C::H2(&challenge_input);
+
+    Challenge::new(ScalarField::<C>::from_le_bytes_mod_order(&challenge_bytes))
+}
 
-    for (identifier, hiding_nonce_commitment, binding_nonce_commitment) in commitment_list {
-        let binding_factor = binding_factor_for_participant(&binding_factor_list, *identifier);
-        let binding_nonce = *binding_nonce_commitment * binding_factor;
+#[test]
+fn test_participant_set_consistency() {
+    use crate::frost::Frost;
+
+    let frost: Frost = Frost::signature_share(2, 3);
+    let signing_package = frost.signing_package(b"testing");
+    let participants = signing_package.participants().unwrap();
+
+    let mut commitments = signing_package.commitments.clone();
+    commitments.push(commitments[0]);
+    assert_eq!(
+        ParticipantSet::new(commitments),
+        Err(FrostError::DuplicateCommitment(
+            participants.x_coordinates()[0].0
+        ))
+    );
+
+    // the commitment list is sorted by identifier, whatever the order it is given in
+    let mut reversed = signing_package.commitments.clone();
+    reversed.reverse();
+    let reordered = ParticipantSet::new(reversed).unwrap();
+    assert_eq!(reordered, participants);
+    assert!(
+        reordered
+            .x_coordinates()
+            .windows(2)
+            .all(|pair| pair[0].0 < pair[1].0)
+    );
+
+    // binding factors computed for a subset of the participants
+    let subset = ParticipantSet::new(participants.commitments()[1..].to_vec()).unwrap();
+    let binding_factors = compute_binding_factors(frost.group_pk.to_element(), &subset, b"testing");
+    assert_eq!(
+        compute_group_commitment(&participants, &binding_factors),
+        Err(FrostError::ParticipantMismatch)
+    );
+
+    // the commitment list is encoded in place as the concatenation of the commitments
+    let encoded = encode_group_commitment_list(participants.commitments());
+    let expected: Vec<u8> = participants
+        .commitments()
+        .iter()
+        .flat_map(|commitment| {
+            [
+                serialize_scalar::<Ed25519Sha512>(&commitment.identifier.0),
+                Ed25519Sha512::serialize_element(&commitment.hiding),
+                Ed25519Sha512::serialize_element(&commitment.binding),
+            ]
+            .concat()
+        })
+        .collect();
+    assert_eq!(encoded, expected);
+}
+
+#[test]
+fn test_interpolation_cache() {
+    use crate::frost::Frost;
+
+    let frost: Frost = Frost::signature_share(2, 3);
+    let participants = frost.signing_package(b"testing").participants().unwrap();
+    let cache = InterpolationCache::new();
 
-        group_commitment += hiding_nonce_commitment + binding_nonce;
+    for _ in 0..2 {
+        for &x_i in participants.x_coordinates() {
+            assert_eq!(
+                cache.interpolating_value(&participants, x_i),
+                derive_interpolating_value(&participants, x_i)
+            );
+        }
     }
+    assert_eq!(cache.len(), 1);
+
+    // the least recently used set is evicted once the cache is full
+    let cache = InterpolationCache::with_capacity(2);
+    let commitments: Vec<_> = frost
+        .signers()
+        .map(|signer| signer.get_commitments())
+        .collect();
+    let sets: Vec<ParticipantSet> = [[0, 1], [0, 2], [1, 2]]
+        .iter()
+        .map(|pair| ParticipantSet::new(pair.map(|i| commitments[i]).to_vec()).unwrap())
+        .collect();
+    for set in [&sets[0], &sets[1], &sets[0], &sets[2]] {
+        let x_i = set.x_coordinates()[0];
+        assert_eq!(
+            cache.interpolating_value(set, x_i),
+            derive_interpolating_value(set, x_i)
+        );
+    }
+    assert_eq!(cache.len(), 2);
+    let values = cache.values.lock().unwrap();
+    assert!(values.keys().all(|key| key.len() == 2));
+    assert!(!values.contains_key(&{
+        let mut key: Vec<_> = sets[1].x_coordinates().iter().map(|x| x.0).collect();
+        key.sort();
+        key
+    }));
+}
 
-    group_commitment
+#[test]
+fn test_hex_formatting() {
+    use crate::frost::Frost;
+
+    let frost: Frost = Frost::signature_share(2, 3);
+    let commitments = frost.signers[0].get_commitments();
+    assert_eq!(
+        commitments
+            .to_string()
+            .parse::<SigningCommitments>()
+            .unwrap(),
+        commitments
+    );
+
+    let identifier = commitments.identifier;
+    assert_eq!(
+        identifier.to_string(),
+        "0100000000000000000000000000000000000000000000000000000000000000"
+    );
+    assert_eq!(
+        identifier.to_string().parse::<NonZeroScalar>().unwrap(),
+        identifier
+    );
+    assert!(
+        "0000000000000000000000000000000000000000000000000000000000000000"
+            .parse::<NonZeroScalar>()
+            .is_err()
+    );
 }
 
-pub fn compute_challenge(
-    group_commitment: Element,
-    group_pk: Element,
-    msg: Vec<u8>,
-) -> ScalarField {
-    let mut group_commitment_encoded_bytes = Vec::new();
-    let mut group_pk_encoded_bytes = Vec::new();
+#[test]
+fn test_constant_time_eq() {
+    assert!(constant_time_eq(b"tag", b"tag"));
+    assert!(!constant_time_eq(b"tag", b"taG"));
+    assert!(!constant_time_eq(b"tag", b"ta"));
+
+    let share = ScalarField::<Ed25519Sha512>::from(7u64);
+    assert!(scalars_eq::<Ed25519Sha512>(
+        &share,
+        &ScalarField::<Ed25519Sha512>::from(7u64)
+    ));
+    assert!(!scalars_eq::<Ed25519Sha512>(&share, &-share));
+}
 
-    group_commitment
-        .serialize_compressed(&mut group_commitment_encoded_bytes)
-        .unwrap();
-    group_pk
-        .serialize_compressed(&mut group_pk_encoded_bytes)
-        .unwrap();
-    let challenge_input = [group_commitment_encoded_bytes, group_pk_encoded_bytes, msg].concat();
-    let challenge_bytes = H2(challenge_input);
+#[test]
+fn test_read_bounded_line() {
+    let mut reader = io::Cursor::new(b"short\nmuch too long\n".to_vec());
+    let mut line = String::new();
+    assert_eq!(read_bounded_line(&mut reader, 8, &mut line).unwrap(), 6);
+    assert_eq!(line, "short\n");
 
-    ScalarField::from_le_bytes_mod_order(&challenge_bytes)
+    line.clear();
+    let error = read_bounded_line(&mut reader, 8, &mut line).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
 }
@@ -0,0 +1,310 @@
+//! This module implements authentication of protocol messages with long-term identity keys.
+//!
+//! Every participant, including the coordinator, holds an `IdentityKey` that is independent of
+//! its key share, and the public identity keys of the group are collected in an
+//! `IdentityRoster`. Commitments, signing packages and signature shares are sent wrapped in
+//! `Authenticated`, signed by their sender, and only unwrapped once the signature verifies under
+//! the sender's identity key. Commitments and shares must moreover be sent by the participant
+//! they belong to.
+use std::{collections::BTreeMap, fmt};
+
+use ark_ff::UniformRand;
+use ark_serialize::SerializationError;
+use ark_std::rand::Rng;
+
+use crate::{
+    ciphersuite::{Ciphersuite, Ed25519Sha512, Element, ScalarField},
+    error::FrostError,
+    frost::{SignatureShare, SigningPackage},
+    helper::{Decoder, NonZeroScalar, scalars_eq, serialize_scalar},
+    keys::{sign_message_with, verify_message_signature},
+    nonces::SigningCommitments,
+    schnorr::SchnorrSignature,
+};
+
+/// Domain separator prefixed to the message signed for an authenticated protocol message.
+const IDENTITY_CONTEXT: &[u8] = b"FROST-IDENTITY-v1";
+
+/// A participant's long-term signing key.
+#[derive(Clone, Eq)]
+pub struct IdentityKey<C: Ciphersuite = Ed25519Sha512> {
+    secret: ScalarField<C>,
+    public_key: Element<C>,
+    generator: Element<C>,
+}
+
+/// Compares the secret keys in constant time.
+impl<C: Ciphersuite> PartialEq for IdentityKey<C> {
+    fn eq(&self, other: &Self) -> bool {
+        scalars_eq::<C>(&self.secret, &other.secret)
+            && self.public_key == other.public_key
+            && self.generator == other.generator
+    }
+}
+
+/// Redacts the secret key, so that an `IdentityKey` can be logged safely.
+impl<C: Ciphersuite> fmt::Debug for IdentityKey<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IdentityKey")
+            .field("secret", &format_args!("<redacted>"))
+            .field("public_key", &self.public_key)
+            .field("generator", &self.generator)
+            .finish()
+    }
+}
+
+impl<C: Ciphersuite> IdentityKey<C> {
+    /// Generates a fresh identity key over `generator`.
+    pub fn generate<R: Rng>(generator: Element<C>, rng: &mut R) -> Self {
+        let secret = ScalarField::<C>::rand(rng);
+        IdentityKey {
+            secret,
+            public_key: generator * secret,
+            generator,
+        }
+    }
+
+    pub fn public_key(&self) -> Element<C> {
+        self.public_key
+    }
+
+    /// Signs `message`, producing a Schnorr signature under the public identity key.
+    pub fn sign<R: Rng>(&self, message: &[u8], rng: &mut R) -> SchnorrSignature<C> {
+        sign_message_with(self.secret, self.public_key, self.generator, message, rng)
+    }
+}
+
+/// The public identity keys of the participants of a group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdentityRoster<C: Ciphersuite = Ed25519Sha512> {
+    generator: Element<C>,
+    keys: BTreeMap<ScalarField<C>, Element<C>>,
+}
+
+impl<C: Ciphersuite> IdentityRoster<C> {
+    pub fn new(generator: Element<C>) -> Self {
+        IdentityRoster {
+            generator,
+            keys: BTreeMap::new(),
+        }
+    }
+
+    /// Registers the public identity key of the participant with the given `identifier`.
+    ///
+    /// Returns an error if the participant already has an identity key.
+    pub fn insert(
+        &mut self,
+        identifier: NonZeroScalar<C>,
+        public_key: Element<C>,
+    ) -> Result<(), FrostError<C>> {
+        if self.keys.contains_key(&identifier.to_scalar()) {
+            return Err(FrostError::ParticipantExists(identifier.to_scalar()));
+        }
+        self.keys.insert(identifier.to_scalar(), public_key);
+        Ok(())
+    }
+
+    pub fn get(&self, identifier: NonZeroScalar<C>) -> Option<Element<C>> {
+        self.keys.get(&identifier.to_scalar()).copied()
+    }
+
+    /// Returns `Ok` if `signature` on `message` verifies under the identity key of `identifier`.
+    pub fn verify(
+        &self,
+        identifier: NonZeroScalar<C>,
+        message: &[u8],
+        signature: &SchnorrSignature<C>,
+    ) -> Result<(), FrostError<C>> {
+        let public_key = self
+            .get(identifier)
+            .ok_or(FrostError::UnknownParticipant(identifier.to_scalar()))?;
+        if !verify_message_signature(signature, message, public_key, self.generator) {
+            return Err(FrostError::InvalidIdentitySignature(identifier.to_scalar()));
+        }
+        Ok(())
+    }
+}
+
+/// A protocol message that can be sent `Authenticated`.
+pub trait ProtocolMessage<C: Ciphersuite>: Sized {
+    /// Distinguishes the kinds of messages, so that a signature on one kind is not valid on
+    /// another.
+    const KIND: &'static [u8];
+
+    /// The participant that must send the message, if it belongs to one.
+    fn owner(&self) -> Option<NonZeroScalar<C>>;
+
+    fn to_bytes(&self) -> Vec<u8>;
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError>;
+}
+
+impl<C: Ciphersuite> ProtocolMessage<C> for SigningCommitments<C> {
+    const KIND: &'static [u8] = b"commitments";
+
+    fn owner(&self) -> Option<NonZeroScalar<C>> {
+        Some(self.identifier)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        SigningCommitments::to_bytes(self)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        SigningCommitments::from_bytes(bytes)
+    }
+}
+
+impl<C: Ciphersuite> ProtocolMessage<C> for SigningPackage<C> {
+    const KIND: &'static [u8] = b"signing-package";
+
+    fn owner(&self) -> Option<NonZeroScalar<C>> {
+        None
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        SigningPackage::to_bytes(self)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        SigningPackage::from_bytes(bytes)
+    }
+}
+
+impl<C: Ciphersuite> ProtocolMessage<C> for SignatureShare<C> {
+    const KIND: &'static [u8] = b"signature-share";
+
+    fn owner(&self) -> Option<NonZeroScalar<C>> {
+        Some(self.identifier)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        SignatureShare::to_bytes(self)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        SignatureShare::from_bytes(bytes)
+    }
+}
+
+/// A protocol message signed by the identity key of its `sender`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Authenticated<M, C: Ciphersuite = Ed25519Sha512> {
+    /// Identifier of the participant that sent the message.
+    pub sender: NonZeroScalar<C>,
+    /// The message.
+    pub message: M,
+    /// Signature of the sender over the kind of the message, the sender and the message.
+    pub signature: SchnorrSignature<C>,
+}
+
+impl<C: Ciphersuite, M: ProtocolMessage<C>> Authenticated<M, C> {
+    /// Signs `message` as `sender` with its identity `key`.
+    pub fn sign<R: Rng>(
+        sender: NonZeroScalar<C>,
+        message: M,
+        key: &IdentityKey<C>,
+        rng: &mut R,
+    ) -> Self {
+        let signature = key.sign(&Self::signed_message(sender, &message), rng);
+        Authenticated {
+            sender,
+            message,
+            signature,
+        }
+    }
+
+    /// Returns the message if it is signed under the identity key of its sender in `roster`,
+    /// and sent by the participant it belongs to.
+    pub fn verify(self, roster: &IdentityRoster<C>) -> Result<M, FrostError<C>> {
+        if let Some(owner) = self.message.owner()
+            && owner != self.sender
+        {
+            return Err(FrostError::InvalidIdentitySignature(owner.to_scalar()));
+        }
+        roster.verify(
+            self.sender,
+            &Self::signed_message(self.sender, &self.message),
+            &self.signature,
+        )?;
+        Ok(self.message)
+    }
+
+    /// Encodes the message as `sender || signature || message`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [
+            serialize_scalar::<C>(&self.sender.to_scalar()),
+            self.signature.to_bytes(),
+            self.message.to_bytes(),
+        ]
+        .concat()
+    }
+
+    /// Parses a message encoded by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let mut decoder = Decoder::new(bytes);
+        let sender = decoder.identifier()?;
+        let signature =
+            SchnorrSignature::from_bytes(decoder.take(SchnorrSignature::<C>::encoded_length())?)?;
+        let message = M::from_bytes(decoder.rest())?;
+
+        Ok(Authenticated {
+            sender,
+            message,
+            signature,
+        })
+    }
+
+    fn signed_message(sender: NonZeroScalar<C>, message: &M) -> Vec<u8> {
+        [
+            IDENTITY_CONTEXT,
+            &(M::KIND.len() as u64).to_le_bytes(),
+            M::KIND,
+            &serialize_scalar::<C>(&sender.to_scalar()),
+            &message.to_bytes(),
+        ]
+        .concat()
+    }
+}
+
+#[test]
+fn test_authenticated_messages() {
+    use crate::frost::Frost;
+
+    let frost: Frost = Frost::signature_share(2, 3);
+    let mut rng = ark_std::test_rng();
+    let mut roster = IdentityRoster::new(frost.generator);
+    let keys: Vec<IdentityKey> = frost
+        .participants()
+        .map(|identifier| {
+            let key = IdentityKey::generate(frost.generator, &mut rng);
+            roster.insert(identifier, key.public_key()).unwrap();
+            key
+        })
+        .collect();
+
+    let signer = &frost.signers[0];
+    let identifier = signer.get_commitments().identifier;
+    let authenticated =
+        Authenticated::sign(identifier, signer.get_commitments(), &keys[0], &mut rng);
+    let authenticated = Authenticated::from_bytes(&authenticated.to_bytes()).unwrap();
+    assert_eq!(
+        authenticated.clone().verify(&roster),
+        Ok(signer.get_commitments())
+    );
+
+    // signed with the key of another participant
+    let forged = Authenticated::sign(identifier, signer.get_commitments(), &keys[1], &mut rng);
+    assert_eq!(
+        forged.verify(&roster),
+        Err(FrostError::InvalidIdentitySignature(identifier.to_scalar()))
+    );
+
+    // a participant cannot send the commitments of another
+    let other = frost.signers[1].get_commitments().identifier;
+    let relayed = Authenticated::sign(other, signer.get_commitments(), &keys[1], &mut rng);
+    assert_eq!(
+        relayed.verify(&roster),
+        Err(FrostError::InvalidIdentitySignature(identifier.to_scalar()))
+    );
+}
@@ -0,0 +1,563 @@
+//! This module implements the minimal JSON support shared by the test vectors and the JSON-RPC
+//! interface: a recursive-descent parser and a serializer, and the canonical serialization of
+//! RFC 8785 (JCS) for signing structured data.
+//!
+//! Reference: https://www.rfc-editor.org/rfc/rfc8785
+use std::{collections::BTreeMap, fmt};
+
+/// Maximum nesting depth of arrays and objects the parser accepts, so that a hostile document
+/// cannot exhaust the stack.
+const MAX_DEPTH: usize = 128;
+
+/// Error returned when a JSON document cannot be parsed, or lacks an expected value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct JsonError(pub(crate) String);
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A JSON value. Numbers are kept as written, so that large integers lose no precision.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(BTreeMap<String, Json>),
+}
+
+impl Json {
+    /// Parses a complete JSON document.
+    pub(crate) fn parse(input: &str) -> Result<Json, JsonError> {
+        Parser::new(input).parse_document()
+    }
+
+    /// Builds an object from its fields.
+    pub(crate) fn object<const N: usize>(fields: [(&str, Json); N]) -> Json {
+        Json::Object(
+            fields
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+        )
+    }
+
+    pub(crate) fn field(&self, name: &str) -> Result<&Json, JsonError> {
+        match self {
+            Json::Object(fields) => fields
+                .get(name)
+                .ok_or_else(|| JsonError(format!("missing field {name}"))),
+            _ => Err(JsonError(format!("expected an object containing {name}"))),
+        }
+    }
+
+    pub(crate) fn array(&self) -> Result<&[Json], JsonError> {
+        match self {
+            Json::Array(values) => Ok(values),
+            _ => Err(JsonError("expected an array".to_string())),
+        }
+    }
+
+    pub(crate) fn string(&self) -> Result<&str, JsonError> {
+        match self {
+            Json::String(value) => Ok(value),
+            _ => Err(JsonError("expected a string".to_string())),
+        }
+    }
+
+    /// Returns an integer, which the vectors encode either as a number or as a decimal string.
+    pub(crate) fn integer(&self) -> Result<u64, JsonError> {
+        let digits = match self {
+            Json::Number(digits) | Json::String(digits) => digits,
+            _ => return Err(JsonError("expected an integer".to_string())),
+        };
+        digits
+            .parse()
+            .map_err(|_| JsonError(format!("invalid integer {digits}")))
+    }
+
+    /// Returns the bytes of a hex-encoded string.
+    pub(crate) fn bytes(&self) -> Result<Vec<u8>, JsonError> {
+        let hex = self.string()?;
+        if hex.len() % 2 != 0 {
+            return Err(JsonError(format!("odd length hex {hex}")));
+        }
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| {
+                hex.get(i..i + 2)
+                    .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                    .ok_or_else(|| JsonError(format!("invalid hex {hex}")))
+            })
+            .collect()
+    }
+}
+
+impl Json {
+    /// Serializes the value per RFC 8785: without whitespace, with object members sorted by the
+    /// UTF-16 code units of their names, and numbers and strings in their ECMAScript form.
+    ///
+    /// Returns an error if a number is not finite as an IEEE 754 double.
+    pub(crate) fn canonical(&self) -> Result<String, JsonError> {
+        let mut output = String::new();
+        self.write_canonical(&mut output)?;
+        Ok(output)
+    }
+
+    fn write_canonical(&self, output: &mut String) -> Result<(), JsonError> {
+        match self {
+            Json::Null | Json::Bool(_) => output.push_str(&self.to_string()),
+            Json::Number(digits) => output.push_str(&canonical_number(digits)?),
+            Json::String(value) => write_canonical_string(output, value),
+            Json::Array(values) => {
+                output.push('[');
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        output.push(',');
+                    }
+                    value.write_canonical(output)?;
+                }
+                output.push(']');
+            }
+            Json::Object(fields) => {
+                let mut fields: Vec<_> = fields.iter().collect();
+                fields.sort_by_cached_key(|(name, _)| name.encode_utf16().collect::<Vec<u16>>());
+                output.push('{');
+                for (i, (name, value)) in fields.into_iter().enumerate() {
+                    if i > 0 {
+                        output.push(',');
+                    }
+                    write_canonical_string(output, name);
+                    output.push(':');
+                    value.write_canonical(output)?;
+                }
+                output.push('}');
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Formats a number as ECMAScript's `Number.prototype.toString` does: the shortest digits that
+/// round-trip the double, in fixed notation for exponents from -7 to 20 and exponential otherwise.
+fn canonical_number(digits: &str) -> Result<String, JsonError> {
+    let value: f64 = digits
+        .parse()
+        .ok()
+        .filter(|value: &f64| value.is_finite())
+        .ok_or_else(|| JsonError(format!("number {digits} is not a finite double")))?;
+    if value == 0.0 {
+        return Ok("0".to_string());
+    }
+
+    // shortest round-trip digits, as `d.ddde<exponent>`
+    let exponential = format!("{:e}", value.abs());
+    let (mantissa, exponent) = exponential
+        .split_once('e')
+        .expect("exponential notation has an exponent");
+    let significand: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let k = significand.len() as i32;
+    let n = exponent.parse::<i32>().expect("the exponent is an integer") + 1;
+
+    let magnitude = if k <= n && n <= 21 {
+        significand + &"0".repeat((n - k) as usize)
+    } else if 0 < n && n <= 21 {
+        format!(
+            "{}.{}",
+            &significand[..n as usize],
+            &significand[n as usize..]
+        )
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{significand}", "0".repeat(-n as usize))
+    } else {
+        let (first, rest) = significand.split_at(1);
+        let fraction = if rest.is_empty() {
+            String::new()
+        } else {
+            format!(".{rest}")
+        };
+        let sign = if n - 1 < 0 { '-' } else { '+' };
+        format!("{first}{fraction}e{sign}{}", (n - 1).abs())
+    };
+    Ok(if value < 0.0 {
+        format!("-{magnitude}")
+    } else {
+        magnitude
+    })
+}
+
+fn write_canonical_string(output: &mut String, value: &str) {
+    output.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\u{8}' => output.push_str("\\b"),
+            '\u{c}' => output.push_str("\\f"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if c < ' ' => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+}
+
+/// Serializes the value compactly, on a single line.
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(value) => write!(f, "{value}"),
+            Json::Number(digits) => f.write_str(digits),
+            Json::String(value) => write_string(f, value),
+            Json::Array(values) => {
+                f.write_str("[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                f.write_str("]")
+            }
+            Json::Object(fields) => {
+                f.write_str("{")?;
+                for (i, (name, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write_string(f, name)?;
+                    write!(f, ":{value}")?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c < ' ' => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{c}")?,
+        }
+    }
+    f.write_str("\"")
+}
+
+/// A minimal recursive-descent JSON parser.
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+    /// Number of arrays and objects the parser is inside of.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Parser {
+            input: input.as_bytes(),
+            position: 0,
+            depth: 0,
+        }
+    }
+
+    fn parse_document(&mut self) -> Result<Json, JsonError> {
+        let value = self.parse_value()?;
+        self.skip_whitespace();
+        if self.position != self.input.len() {
+            return Err(self.error("trailing characters"));
+        }
+        Ok(value)
+    }
+
+    fn parse_value(&mut self) -> Result<Json, JsonError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.nested(Self::parse_object),
+            Some(b'[') => self.nested(Self::parse_array),
+            Some(b'"') => Ok(Json::String(self.parse_string()?)),
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            Some(b't') => self.parse_literal("true", Json::Bool(true)),
+            Some(b'f') => self.parse_literal("false", Json::Bool(false)),
+            Some(b'n') => self.parse_literal("null", Json::Null),
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    /// Parses an array or object with `parse`, one level deeper.
+    fn nested(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<Json, JsonError>,
+    ) -> Result<Json, JsonError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("nesting too deep"));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn parse_object(&mut self) -> Result<Json, JsonError> {
+        self.expect(b'{')?;
+        let mut fields = BTreeMap::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Ok(Json::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let name = self.parse_string()?;
+            self.skip_whitespace();
+            self.expect(b':')?;
+            if fields.insert(name, self.parse_value()?).is_some() {
+                return Err(self.error("duplicate member name"));
+            }
+            self.skip_whitespace();
+            match self.next() {
+                Some(b',') => continue,
+                Some(b'}') => return Ok(Json::Object(fields)),
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Json, JsonError> {
+        self.expect(b'[')?;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.next() {
+                Some(b',') => continue,
+                Some(b']') => return Ok(Json::Array(values)),
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, JsonError> {
+        self.expect(b'"')?;
+        let mut value = Vec::new();
+        loop {
+            match self.next() {
+                Some(b'"') => break,
+                Some(b'\\') => {
+                    let escaped = match self.next() {
+                        Some(escaped @ (b'"' | b'\\' | b'/')) => escaped as char,
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => self.parse_unicode_escape()?,
+                        _ => return Err(self.error("invalid escape sequence")),
+                    };
+                    value.extend_from_slice(escaped.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                // RFC 8259 requires control characters to be escaped
+                Some(0x00..=0x1F) => return Err(self.error("control character in string")),
+                Some(byte) => value.push(byte),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+        String::from_utf8(value).map_err(|_| self.error("invalid UTF-8"))
+    }
+
+    /// Parses the code point of a `\u` escape, combining a surrogate pair into one character.
+    fn parse_unicode_escape(&mut self) -> Result<char, JsonError> {
+        let high = self.parse_hex4()?;
+        let code_point = if (0xD800..0xDC00).contains(&high) {
+            if self.next() != Some(b'\\') || self.next() != Some(b'u') {
+                return Err(self.error("unpaired surrogate"));
+            }
+            let low = self.parse_hex4()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(self.error("unpaired surrogate"));
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+        char::from_u32(code_point).ok_or_else(|| self.error("invalid code point"))
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, JsonError> {
+        let digits = self
+            .input
+            .get(self.position..self.position + 4)
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        self.position += 4;
+        Ok(digits)
+    }
+
+    /// Parses a number following the grammar of RFC 8259, section 6:
+    /// `-? (0 | [1-9][0-9]*) (. [0-9]+)? ([eE] [+-]? [0-9]+)?`.
+    fn parse_number(&mut self) -> Result<Json, JsonError> {
+        let start = self.position;
+        if self.peek() == Some(b'-') {
+            self.position += 1;
+        }
+        match self.peek() {
+            Some(b'0') => self.position += 1,
+            Some(b'1'..=b'9') => self.skip_digits(),
+            _ => return Err(self.error("expected a digit")),
+        }
+        if self.peek() == Some(b'.') {
+            self.position += 1;
+            self.expect_digits()?;
+        }
+        if let Some(b'e' | b'E') = self.peek() {
+            self.position += 1;
+            if let Some(b'+' | b'-') = self.peek() {
+                self.position += 1;
+            }
+            self.expect_digits()?;
+        }
+        // the number is ASCII, as checked above
+        Ok(Json::Number(
+            String::from_utf8_lossy(&self.input[start..self.position]).into_owned(),
+        ))
+    }
+
+    fn expect_digits(&mut self) -> Result<(), JsonError> {
+        if !matches!(self.peek(), Some(b'0'..=b'9')) {
+            return Err(self.error("expected a digit"));
+        }
+        self.skip_digits();
+        Ok(())
+    }
+
+    fn skip_digits(&mut self) {
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    fn parse_literal(&mut self, literal: &str, value: Json) -> Result<Json, JsonError> {
+        if self.input[self.position..].starts_with(literal.as_bytes()) {
+            self.position += literal.len();
+            Ok(value)
+        } else {
+            Err(self.error("invalid literal"))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), JsonError> {
+        if self.next() == Some(byte) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", byte as char)))
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.position += 1;
+        Some(byte)
+    }
+
+    fn error(&self, reason: &str) -> JsonError {
+        JsonError(format!("{reason} at byte {}", self.position))
+    }
+}
+
+#[test]
+fn test_json_roundtrip() {
+    let document = r#"{"b":[1,-2.5e3,true,null],"a":"line\nbreak \"quoted\" é😀"}"#;
+    let value = Json::parse(document).unwrap();
+    assert_eq!(
+        value.field("a").unwrap().string().unwrap(),
+        "line\nbreak \"quoted\" é😀"
+    );
+    assert_eq!(Json::parse(&value.to_string()).unwrap(), value);
+    assert_eq!(
+        Json::object([("x", Json::Null)]).to_string(),
+        r#"{"x":null}"#
+    );
+    assert!(Json::parse(r#"{"a":1}x"#).is_err());
+    assert!(Json::parse(r#""\ud83d""#).is_err());
+
+    // control characters must be escaped
+    assert!(Json::parse("\"line\nbreak\"").is_err());
+    assert!(Json::parse("\"nul\u{0}\"").is_err());
+    assert!(Json::parse(r#""line\nbreak""#).is_ok());
+
+    // numbers follow the grammar of RFC 8259
+    for number in ["0", "-0", "10", "1.5", "-1e5", "2E+3", "0.25e-2"] {
+        assert_eq!(Json::parse(number), Ok(Json::Number(number.to_string())));
+    }
+    for number in [
+        "01", "1.", ".5", "1-2", "-", "1e", "1e+", "+1", "--1", "1.e3",
+    ] {
+        assert!(Json::parse(number).is_err(), "{number} parses");
+    }
+
+    // nesting is limited, so that deep documents cannot overflow the stack
+    let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    assert!(Json::parse(&nested(MAX_DEPTH)).is_ok());
+    assert!(Json::parse(&nested(MAX_DEPTH + 1)).is_err());
+    assert!(Json::parse(&nested(1_000_000)).is_err());
+}
+
+#[test]
+fn test_canonical_json() {
+    // the example of RFC 8785, section 3.2.2
+    let document = r#"{
+        "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+        "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+        "literals": [null, true, false]
+    }"#;
+    assert_eq!(
+        Json::parse(document).unwrap().canonical().unwrap(),
+        r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+    );
+
+    for (number, canonical) in [
+        ("-0", "0"),
+        ("1e21", "1e+21"),
+        ("1e20", "100000000000000000000"),
+        ("0.000001", "0.000001"),
+        ("1e-7", "1e-7"),
+        ("-12.5e-1", "-1.25"),
+    ] {
+        assert_eq!(canonical_number(number).unwrap(), canonical);
+    }
+    assert!(canonical_number("1e400").is_err());
+
+    // members are sorted by UTF-16 code units, not by code points
+    let value = Json::parse(r#"{"😀":1,"דּ":2}"#).unwrap();
+    assert_eq!(value.canonical().unwrap(), r#"{"😀":1,"דּ":2}"#);
+    assert!(Json::parse(r#"{"a":1,"a":2}"#).is_err());
+}
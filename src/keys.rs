@@ -0,0 +1,540 @@
+//! This module implements the `KeyPackage`, the key material a single signer holds.
+//!
+//! A `KeyPackage` lets a signer run in its own process, e.g. a collaborative custodian, instead
+//! of being held in a `Frost` instance together with every other signer.
+//!
+//! The `PublicKeyPackage` holds the public side of the key generation: the group public key and
+//! the verification share `Y_i = g^{x_i}` of every signer, which a coordinator needs to check
+//! individual signature shares. `PublicKeyPackage::verify` checks that the verification shares
+//! interpolate to the group public key.
+//!
+//! Key material is wrapped in the `GroupPublicKey`, `VerificationShare` and `SecretShare`
+//! newtypes, so that the compiler rejects a verification share passed where the group public key
+//! is expected, or a nonce passed as a secret share.
+#![allow(non_snake_case)]
+
+use std::{fmt, str::FromStr};
+
+use ark_ff::{AdditiveGroup, Field, UniformRand};
+use ark_serialize::SerializationError;
+use ark_std::rand::Rng;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    ciphersuite::{Ciphersuite, Ed25519Sha512, Element, ScalarField},
+    error::FrostError,
+    frost::{Frost, SignerRole},
+    helper::{
+        Decoder, NonZeroScalar, compute_challenge, decode_hex, encode_hex, scalars_eq,
+        serialize_scalar,
+    },
+    schnorr::SchnorrSignature,
+    secret::Secret,
+};
+
+/// The public key of a group, `g^s` for the group secret key `s`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GroupPublicKey<C: Ciphersuite = Ed25519Sha512>(Element<C>);
+
+impl<C: Ciphersuite> GroupPublicKey<C> {
+    pub fn new(element: Element<C>) -> Self {
+        GroupPublicKey(element)
+    }
+
+    pub fn to_element(&self) -> Element<C> {
+        self.0
+    }
+
+    /// Returns the identifier of the group this key belongs to.
+    pub fn group_id(&self) -> GroupId {
+        let digest = Sha256::new()
+            .chain_update(GROUP_ID_CONTEXT)
+            .chain_update(C::serialize_element(&self.0))
+            .finalize();
+        GroupId(digest.into())
+    }
+}
+
+/// Formats the key as the hex encoding of its point.
+impl<C: Ciphersuite> fmt::Display for GroupPublicKey<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&encode_hex(&C::serialize_element(&self.0)))
+    }
+}
+
+/// Parses the hex encoding of a point.
+impl<C: Ciphersuite> FromStr for GroupPublicKey<C> {
+    type Err = SerializationError;
+
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        parse_element::<C>(hex).map(GroupPublicKey)
+    }
+}
+
+/// Domain separator of the hash deriving a `GroupId` from the group public key.
+const GROUP_ID_CONTEXT: &[u8] = b"FROST-GROUP-ID-v1";
+
+/// Identifies a FROST group, so that a process holding shares of many groups can tell their
+/// messages and stored state apart. It is the SHA-256 hash of the group public key, so any party
+/// derives the same identifier without coordination.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GroupId([u8; 32]);
+
+impl GroupId {
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        GroupId(bytes)
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+}
+
+/// Formats the identifier as hex.
+impl fmt::Display for GroupId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&encode_hex(&self.0))
+    }
+}
+
+/// Parses the hex encoding of an identifier.
+impl FromStr for GroupId {
+    type Err = SerializationError;
+
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        decode_hex(hex)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(GroupId)
+            .ok_or(SerializationError::InvalidData)
+    }
+}
+
+/// The public verification share `Y_i = g^{x_i}` of a signer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct VerificationShare<C: Ciphersuite = Ed25519Sha512>(Element<C>);
+
+impl<C: Ciphersuite> VerificationShare<C> {
+    pub fn new(element: Element<C>) -> Self {
+        VerificationShare(element)
+    }
+
+    pub fn to_element(&self) -> Element<C> {
+        self.0
+    }
+}
+
+/// Formats the share as the hex encoding of its point.
+impl<C: Ciphersuite> fmt::Display for VerificationShare<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&encode_hex(&C::serialize_element(&self.0)))
+    }
+}
+
+/// Parses the hex encoding of a point.
+impl<C: Ciphersuite> FromStr for VerificationShare<C> {
+    type Err = SerializationError;
+
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        parse_element::<C>(hex).map(VerificationShare)
+    }
+}
+
+fn parse_element<C: Ciphersuite>(hex: &str) -> Result<Element<C>, SerializationError> {
+    let bytes = decode_hex(hex).ok_or(SerializationError::InvalidData)?;
+    let mut decoder = Decoder::new(&bytes);
+    let element = decoder.element::<C>()?;
+    decoder.finish()?;
+    Ok(element)
+}
+
+/// The secret key share `x_i` of a signer.
+pub struct SecretShare<C: Ciphersuite = Ed25519Sha512>(Secret<ScalarField<C>>);
+
+/// Copies the share explicitly, as `Secret` is not `Clone`, so that `KeyPackage`s can be cloned.
+impl<C: Ciphersuite> Clone for SecretShare<C> {
+    fn clone(&self) -> Self {
+        SecretShare(Secret::new(*self.0.expose_secret()))
+    }
+}
+
+/// Compares the shares in constant time.
+impl<C: Ciphersuite> PartialEq for SecretShare<C> {
+    fn eq(&self, other: &Self) -> bool {
+        scalars_eq::<C>(self.0.expose_secret(), other.0.expose_secret())
+    }
+}
+
+impl<C: Ciphersuite> Eq for SecretShare<C> {}
+
+impl<C: Ciphersuite> SecretShare<C> {
+    pub fn new(scalar: ScalarField<C>) -> Self {
+        SecretShare(Secret::new(scalar))
+    }
+
+    /// Exposes the share, e.g. to sign with it.
+    pub fn to_scalar(&self) -> ScalarField<C> {
+        *self.0.expose_secret()
+    }
+}
+
+/// Redacts the share, so that a `SecretShare` can be logged safely.
+impl<C: Ciphersuite> fmt::Debug for SecretShare<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretShare(<redacted>)")
+    }
+}
+
+/// The key material of a single signer.
+#[derive(Clone, PartialEq, Eq)]
+pub struct KeyPackage<C: Ciphersuite = Ed25519Sha512> {
+    /// Identifier of the signer.
+    pub identifier: NonZeroScalar<C>,
+    /// The signer's secret key share `x_i`.
+    pub secret_share: SecretShare<C>,
+    /// The signer's public verification share `Y_i = g^{x_i}`.
+    pub verification_share: VerificationShare<C>,
+    /// The public key of the group.
+    pub group_pk: GroupPublicKey<C>,
+    /// The generator `g` of the group.
+    pub generator: Element<C>,
+    /// Whether the signer is a regular or a blind signer.
+    pub role: SignerRole,
+}
+
+/// Redacts the secret share, so that a `KeyPackage` can be logged safely.
+impl<C: Ciphersuite> fmt::Debug for KeyPackage<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyPackage")
+            .field("identifier", &self.identifier)
+            .field("secret_share", &format_args!("<redacted>"))
+            .field("verification_share", &self.verification_share)
+            .field("group_pk", &self.group_pk)
+            .field("generator", &self.generator)
+            .field("role", &self.role)
+            .finish()
+    }
+}
+
+impl<C: Ciphersuite> KeyPackage<C> {
+    /// Encodes the package as `identifier || x_i || Y_i || group_pk || g || role`, where `role` is
+    /// a single byte: 0 for a regular signer and 1 for a blind signer.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let role = match self.role {
+            SignerRole::Regular => 0,
+            SignerRole::Blind => 1,
+        };
+
+        [
+            serialize_scalar::<C>(&self.identifier.to_scalar()),
+            serialize_scalar::<C>(&self.secret_share.to_scalar()),
+            C::serialize_element(&self.verification_share.0),
+            C::serialize_element(&self.group_pk.0),
+            C::serialize_element(&self.generator),
+            vec![role],
+        ]
+        .concat()
+    }
+
+    /// Parses a package encoded by `to_bytes`.
+    ///
+    /// Returns an error if the verification share does not match the secret share.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let mut decoder = Decoder::new(bytes);
+        let identifier = decoder.identifier()?;
+        let secret_share = SecretShare::new(decoder.scalar::<C>()?);
+        let verification_share = VerificationShare(decoder.element::<C>()?);
+        let group_pk = GroupPublicKey(decoder.element::<C>()?);
+        let generator = decoder.element::<C>()?;
+        let role = match decoder.take(1)? {
+            [0] => SignerRole::Regular,
+            [1] => SignerRole::Blind,
+            _ => return Err(SerializationError::InvalidData),
+        };
+        decoder.finish()?;
+
+        if generator * secret_share.to_scalar() != verification_share.0 {
+            return Err(SerializationError::InvalidData);
+        }
+
+        Ok(KeyPackage {
+            identifier,
+            secret_share,
+            verification_share,
+            group_pk,
+            generator,
+            role,
+        })
+    }
+
+    /// Signs `message` with the secret share alone, producing a Schnorr signature under the
+    /// verification share. Authenticates messages of this signer, e.g. a `NonceBatch`; it is not
+    /// a share of a group signature.
+    pub fn sign_message<R: Rng>(&self, message: &[u8], rng: &mut R) -> SchnorrSignature<C> {
+        sign_message_with(
+            self.secret_share.to_scalar(),
+            self.verification_share.0,
+            self.generator,
+            message,
+            rng,
+        )
+    }
+}
+
+/// Signs `message` with the secret key `x` of the public key `g^x`, such that the signature
+/// verifies with `verify_message_signature`.
+pub(crate) fn sign_message_with<C: Ciphersuite, R: Rng>(
+    x: ScalarField<C>,
+    public_key: Element<C>,
+    generator: Element<C>,
+    message: &[u8],
+    rng: &mut R,
+) -> SchnorrSignature<C> {
+    let k = ScalarField::<C>::rand(rng);
+    let R = generator * k;
+    let challenge = compute_challenge::<C>(R, public_key, message);
+
+    SchnorrSignature {
+        R,
+        s: k + challenge.to_scalar() * x,
+    }
+}
+
+/// Verifies a signature produced by `KeyPackage::sign_message` under `public_key`, i.e. the
+/// signer's verification share, or by `IdentityKey::sign` under its identity key.
+pub fn verify_message_signature<C: Ciphersuite>(
+    signature: &SchnorrSignature<C>,
+    message: &[u8],
+    public_key: Element<C>,
+    generator: Element<C>,
+) -> bool {
+    let challenge = compute_challenge::<C>(signature.R, public_key, message);
+    generator * signature.s == signature.R + public_key * challenge.to_scalar()
+}
+
+/// The public key material of a group: the group public key and the verification share of every
+/// signer, in order of identifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKeyPackage<C: Ciphersuite = Ed25519Sha512> {
+    pub group_pk: GroupPublicKey<C>,
+    /// The generator `g` of the group.
+    pub generator: Element<C>,
+    /// Minimum number of signers required to produce a signature.
+    pub threshold: usize,
+    pub verification_shares: Vec<(NonZeroScalar<C>, VerificationShare<C>)>,
+}
+
+impl<C: Ciphersuite> PublicKeyPackage<C> {
+    /// Returns the verification share of the signer with the given `identifier`.
+    pub fn verification_share(&self, identifier: NonZeroScalar<C>) -> Option<VerificationShare<C>> {
+        self.verification_shares
+            .iter()
+            .find(|(signer, _)| *signer == identifier)
+            .map(|(_, verification_share)| *verification_share)
+    }
+
+    /// Checks that the verification shares are consistent with the group public key: that the
+    /// first `threshold` of them interpolate in the exponent to `group_pk` at zero, and to every
+    /// other verification share at the identifier of its signer.
+    pub fn verify(&self) -> Result<(), FrostError<C>> {
+        let invalid = |reason: String| Err(FrostError::InvalidPublicKeyPackage(reason));
+        if self.threshold < 2 || self.verification_shares.len() < self.threshold {
+            return invalid(format!(
+                "{} verification shares for a threshold of {}",
+                self.verification_shares.len(),
+                self.threshold
+            ));
+        }
+        let (points, others) = self.verification_shares.split_at(self.threshold);
+        for (position, (identifier, _)) in self.verification_shares.iter().enumerate() {
+            if self.verification_shares[..position]
+                .iter()
+                .any(|(other, _)| other == identifier)
+            {
+                return invalid(format!("duplicate signer {}", identifier.to_scalar()));
+            }
+        }
+
+        if interpolate_at::<C>(points, ScalarField::<C>::ZERO) != self.group_pk.0 {
+            return invalid(
+                "the verification shares do not interpolate to the group public key".to_string(),
+            );
+        }
+        for (identifier, verification_share) in others {
+            if interpolate_at::<C>(points, identifier.to_scalar()) != verification_share.0 {
+                return invalid(format!(
+                    "the verification share of signer {} is inconsistent",
+                    identifier.to_scalar()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Encodes the package as `group_pk || g || threshold || n || (identifier || Y_i)*`, with the
+    /// threshold and count as 4-byte little-endian integers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the threshold or the number of signers exceeds `u32::MAX`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let threshold = u32::try_from(self.threshold).expect("threshold too large");
+        let count = u32::try_from(self.verification_shares.len()).expect("too many signers");
+        let mut bytes = C::serialize_element(&self.group_pk.0);
+        bytes.extend(C::serialize_element(&self.generator));
+        bytes.extend_from_slice(&threshold.to_le_bytes());
+        bytes.extend_from_slice(&count.to_le_bytes());
+        for (identifier, verification_share) in &self.verification_shares {
+            bytes.extend(serialize_scalar::<C>(&identifier.to_scalar()));
+            bytes.extend(C::serialize_element(&verification_share.0));
+        }
+        bytes
+    }
+
+    /// Parses a package encoded by `to_bytes`. The package is not verified.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let mut decoder = Decoder::new(bytes);
+        let group_pk = GroupPublicKey(decoder.element::<C>()?);
+        let generator = decoder.element::<C>()?;
+        let threshold = decoder.u32()? as usize;
+        let verification_shares = (0..decoder.u32()?)
+            .map(|_| {
+                Ok((
+                    decoder.identifier()?,
+                    VerificationShare(decoder.element::<C>()?),
+                ))
+            })
+            .collect::<Result<Vec<_>, SerializationError>>()?;
+        decoder.finish()?;
+
+        Ok(PublicKeyPackage {
+            group_pk,
+            generator,
+            threshold,
+            verification_shares,
+        })
+    }
+}
+
+/// Evaluates at `x`, in the exponent, the polynomial through the verification shares `points`.
+fn interpolate_at<C: Ciphersuite>(
+    points: &[(NonZeroScalar<C>, VerificationShare<C>)],
+    x: ScalarField<C>,
+) -> Element<C> {
+    points
+        .iter()
+        .map(|(x_i, y_i)| {
+            let mut numerator = ScalarField::<C>::ONE;
+            let mut denominator = ScalarField::<C>::ONE;
+            for (x_j, _) in points.iter().filter(|(x_j, _)| x_j != x_i) {
+                numerator *= x - x_j.to_scalar();
+                denominator *= x_i.to_scalar() - x_j.to_scalar();
+            }
+            let lambda = numerator
+                * denominator
+                    .inverse()
+                    .expect("identifiers of distinct signers differ");
+            y_i.0 * lambda
+        })
+        .sum()
+}
+
+impl<C: Ciphersuite> Frost<C> {
+    /// Exports the `PublicKeyPackage` of the group, with the verification share of every signer.
+    pub fn public_key_package(&self) -> PublicKeyPackage<C> {
+        let mut verification_shares: Vec<(NonZeroScalar<C>, VerificationShare<C>)> = self
+            .participants()
+            .zip(self.signers())
+            .map(|(identifier, signer)| (identifier, signer.get_verification_share()))
+            .collect();
+        verification_shares.sort_by_key(|(identifier, _)| identifier.to_scalar());
+
+        PublicKeyPackage {
+            group_pk: self.group_pk,
+            generator: self.generator,
+            threshold: self.threshold,
+            verification_shares,
+        }
+    }
+
+    /// Exports the `KeyPackage` of the signer with the given `identifier`, if there is one.
+    pub fn key_package(&self, identifier: NonZeroScalar<C>) -> Option<KeyPackage<C>> {
+        let signer = self
+            .signers()
+            .find(|signer| signer.get_identifier() == identifier.to_scalar())?;
+
+        Some(KeyPackage {
+            identifier,
+            secret_share: signer.secret_share(),
+            verification_share: signer.get_verification_share(),
+            group_pk: self.group_pk,
+            generator: self.generator,
+            role: signer.role(),
+        })
+    }
+}
+
+#[test]
+fn test_key_package_roundtrip() {
+    let frost: Frost = Frost::signature_share(2, 3);
+    let identifier = frost.participants().last().unwrap();
+    let key_package = frost.key_package(identifier).unwrap();
+    assert_eq!(key_package.role, SignerRole::Blind);
+
+    let bytes = key_package.to_bytes();
+    assert_eq!(KeyPackage::from_bytes(&bytes).unwrap(), key_package);
+
+    // a tampered secret share no longer matches the verification share
+    let mut tampered = bytes.clone();
+    tampered[32] ^= 1;
+    assert!(KeyPackage::<Ed25519Sha512>::from_bytes(&tampered).is_err());
+}
+
+#[test]
+fn test_public_key_package() {
+    let frost: Frost = Frost::signature_share(3, 5);
+    let public_key_package = frost.public_key_package();
+    public_key_package.verify().unwrap();
+    for key_package in frost
+        .participants()
+        .map(|identifier| frost.key_package(identifier).unwrap())
+    {
+        assert_eq!(
+            public_key_package.verification_share(key_package.identifier),
+            Some(key_package.verification_share)
+        );
+    }
+
+    let decoded = PublicKeyPackage::from_bytes(&public_key_package.to_bytes()).unwrap();
+    assert_eq!(decoded, public_key_package);
+
+    // a verification share off the sharing polynomial is detected, wherever it is
+    for position in [0, 4] {
+        let mut tampered = public_key_package.clone();
+        let (_, share) = &mut tampered.verification_shares[position];
+        *share = VerificationShare::new(share.0 + frost.generator);
+        assert!(tampered.verify().is_err());
+    }
+}
+
+#[test]
+fn test_key_newtypes() {
+    let frost: Frost = Frost::signature_share(2, 3);
+    let key_package = frost
+        .key_package(frost.participants().next().unwrap())
+        .unwrap();
+
+    let hex = key_package.group_pk.to_string();
+    assert_eq!(hex.parse::<GroupPublicKey>().unwrap(), frost.group_pk);
+    // the same point parses as either type, but the types cannot be mixed up
+    let share = key_package.verification_share;
+    assert_eq!(
+        share.to_string().parse::<VerificationShare>().unwrap(),
+        share
+    );
+    assert!("zz".parse::<GroupPublicKey>().is_err());
+
+    assert_eq!(
+        format!("{:?}", key_package.secret_share),
+        "SecretShare(<redacted>)"
+    );
+}
@@ -0,0 +1,77 @@
+//! This module implements deterministic election of a session leader, for self-custody groups
+//! that run without a fixed coordinator.
+//!
+//! Every participant ranks the participants of a session by `H_leader(seed || identifier)` and
+//! arrives at the same order without exchanging any message. The first participant in that order
+//! coordinates the session; if it does not respond, the next one takes over, so that no single
+//! device is a point of failure. The seed should be known to all participants and differ between
+//! sessions, e.g. the digest of the message, so that the role rotates.
+use crate::{
+    ciphersuite::Ciphersuite,
+    helper::{NonZeroScalar, serialize_scalar},
+};
+
+/// Returns `participants` in the order in which they lead the session with the given `seed`.
+pub fn leader_order<C: Ciphersuite>(
+    seed: &[u8],
+    participants: &[NonZeroScalar<C>],
+) -> Vec<NonZeroScalar<C>> {
+    let mut ranked: Vec<(Vec<u8>, NonZeroScalar<C>)> = participants
+        .iter()
+        .map(|identifier| {
+            let identifier_bytes = serialize_scalar::<C>(&identifier.to_scalar());
+            (
+                C::H_leader(&[seed, &identifier_bytes].concat()),
+                *identifier,
+            )
+        })
+        .collect();
+    ranked.sort_by(|(a, _), (b, _)| a.cmp(b));
+    ranked.dedup_by(|(_, a), (_, b)| a == b);
+    ranked
+        .into_iter()
+        .map(|(_, identifier)| identifier)
+        .collect()
+}
+
+/// Elects the leader of the session with the given `seed`: the first participant in
+/// `leader_order` that is not `unresponsive`.
+///
+/// Returns `None` if every participant is unresponsive.
+pub fn elect_leader<C: Ciphersuite>(
+    seed: &[u8],
+    participants: &[NonZeroScalar<C>],
+    unresponsive: &[NonZeroScalar<C>],
+) -> Option<NonZeroScalar<C>> {
+    leader_order(seed, participants)
+        .into_iter()
+        .find(|identifier| !unresponsive.contains(identifier))
+}
+
+#[test]
+fn test_elect_leader() {
+    use crate::frost::Frost;
+
+    let frost: Frost = Frost::signature_share(3, 5);
+    let participants: Vec<NonZeroScalar> = frost.participants().collect();
+    let mut shuffled = participants.clone();
+    shuffled.reverse();
+
+    // every participant computes the same order, whatever order it lists the others in
+    let order = leader_order(b"session", &participants);
+    assert_eq!(leader_order(b"session", &shuffled), order);
+    assert_eq!(order.len(), participants.len());
+
+    assert_eq!(elect_leader(b"session", &participants, &[]), Some(order[0]));
+    assert_eq!(
+        elect_leader(b"session", &participants, &order[..2]),
+        Some(order[2])
+    );
+    assert_eq!(elect_leader(b"session", &participants, &participants), None);
+
+    // the role rotates across sessions
+    let leaders: Vec<_> = (0u8..16)
+        .map(|seed| elect_leader(&[seed], &participants, &[]).unwrap())
+        .collect();
+    assert!(leaders.iter().any(|leader| *leader != leaders[0]));
+}
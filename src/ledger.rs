@@ -0,0 +1,147 @@
+//! This module implements persistent tracking of nonce usage for the custodian.
+//!
+//! Signing twice with the same nonces reveals the secret share, so a custodian must never serve
+//! a commitment twice, even across crashes and restarts. A `NonceLedger` records every issued
+//! commitment and whether it has been used; `Custodian` consults it before every signature share.
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::Path,
+};
+
+use crate::helper::{decode_hex, encode_hex};
+
+/// Storage of issued nonce commitments, keyed by their encoding.
+pub trait NonceLedger: fmt::Debug + Send {
+    /// Records newly issued `commitments`. Returns `false` if they were recorded before.
+    fn issue(&mut self, commitments: &[u8]) -> io::Result<bool>;
+
+    /// Marks issued `commitments` as used. Returns `false` if they were not issued, or were
+    /// already used, in which case they must not be signed with.
+    fn consume(&mut self, commitments: &[u8]) -> io::Result<bool>;
+}
+
+/// A ledger kept in memory only, for custodians whose nonces do not outlive the process.
+#[derive(Debug, Default)]
+pub struct InMemoryNonceLedger {
+    /// Whether each issued commitment has been used.
+    used: BTreeMap<Vec<u8>, bool>,
+}
+
+impl InMemoryNonceLedger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl NonceLedger for InMemoryNonceLedger {
+    fn issue(&mut self, commitments: &[u8]) -> io::Result<bool> {
+        if self.used.contains_key(commitments) {
+            return Ok(false);
+        }
+        self.used.insert(commitments.to_vec(), false);
+        Ok(true)
+    }
+
+    fn consume(&mut self, commitments: &[u8]) -> io::Result<bool> {
+        match self.used.get_mut(commitments) {
+            Some(used) if !*used => {
+                *used = true;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+/// A ledger backed by an append-only log file, one `ISSUED <hex>` or `USED <hex>` line per event.
+///
+/// Every event is synced to disk before it takes effect, so a restarted custodian never signs
+/// with a commitment it used before the restart.
+#[derive(Debug)]
+pub struct FileNonceLedger {
+    file: File,
+    entries: InMemoryNonceLedger,
+}
+
+impl FileNonceLedger {
+    /// Opens the ledger at `path`, creating it if it does not exist and replaying its events.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+
+        let mut entries = InMemoryNonceLedger::new();
+        for line in BufReader::new(&file).lines() {
+            let line = line?;
+            let event = line.split_once(' ').and_then(|(event, commitments)| {
+                decode_hex(commitments).map(|commitments| (event, commitments))
+            });
+            match event {
+                Some(("ISSUED", commitments)) => {
+                    entries.issue(&commitments)?;
+                }
+                Some(("USED", commitments)) => {
+                    entries.issue(&commitments)?;
+                    entries.consume(&commitments)?;
+                }
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("malformed nonce ledger entry: {line}"),
+                    ));
+                }
+            }
+        }
+
+        Ok(FileNonceLedger { file, entries })
+    }
+
+    fn append(&mut self, event: &str, commitments: &[u8]) -> io::Result<()> {
+        writeln!(self.file, "{event} {}", encode_hex(commitments))?;
+        self.file.sync_data()
+    }
+}
+
+impl NonceLedger for FileNonceLedger {
+    fn issue(&mut self, commitments: &[u8]) -> io::Result<bool> {
+        if self.entries.used.contains_key(commitments) {
+            return Ok(false);
+        }
+        self.append("ISSUED", commitments)?;
+        self.entries.issue(commitments)
+    }
+
+    fn consume(&mut self, commitments: &[u8]) -> io::Result<bool> {
+        if self.entries.used.get(commitments) != Some(&false) {
+            return Ok(false);
+        }
+        self.append("USED", commitments)?;
+        self.entries.consume(commitments)
+    }
+}
+
+#[test]
+fn test_file_nonce_ledger_survives_restart() {
+    let path = std::env::temp_dir().join(format!("nonce-ledger-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut ledger = FileNonceLedger::open(&path).unwrap();
+    assert!(ledger.issue(b"first").unwrap());
+    assert!(ledger.issue(b"second").unwrap());
+    assert!(!ledger.issue(b"first").unwrap());
+    assert!(ledger.consume(b"first").unwrap());
+    assert!(!ledger.consume(b"first").unwrap());
+    assert!(!ledger.consume(b"unknown").unwrap());
+    drop(ledger);
+
+    let mut ledger = FileNonceLedger::open(&path).unwrap();
+    assert!(!ledger.consume(b"first").unwrap());
+    assert!(ledger.consume(b"second").unwrap());
+
+    std::fs::remove_file(&path).unwrap();
+}
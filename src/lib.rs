@@ -0,0 +1,84 @@
+//! A blind threshold signature scheme built on FROST, in which designated signers act as blind
+//! collaborative custodians.
+//!
+//! The types needed to run the protocol are re-exported at the root of the crate and form its
+//! stable API:
+//!
+//! - key generation: `Frost` deals the key shares of a group, and hands each signer its
+//!   `KeyPackage` and verifiers the `PublicKeyPackage` or `GroupConfig` of the group;
+//! - signing: a `FrostSigner` publishes its `SigningCommitments` and answers a `SigningPackage`
+//!   with a `SignatureShare`;
+//! - coordination: a `Coordinator` runs a session and aggregates the `SchnorrSignature`;
+//! - verification: `verify_message_signature` checks a signature against a group public key, and
+//!   `verify_bundle` and `verify_detached` check a `SignedMessage` or a `DetachedSignature`
+//!   against the `GroupConfig` of the group.
+//!
+//! The modules expose the rest of the implementation, e.g. transports, custodians and key
+//! rotation. See `main.rs` for an end-to-end walkthrough of the protocol.
+
+pub mod address;
+pub mod batch;
+pub mod blame;
+pub mod bundle;
+pub mod certificate;
+pub mod ciphersuite;
+pub mod client;
+pub mod config;
+pub mod coordinator;
+pub mod cosign;
+pub mod custodian;
+pub mod derivation;
+pub mod enrollment;
+pub mod error;
+pub mod ethereum;
+pub mod frost;
+pub mod helper;
+pub mod identity;
+mod json;
+pub mod keys;
+pub mod leader;
+pub mod ledger;
+pub mod metrics;
+pub mod nonces;
+pub mod privval;
+pub mod qr;
+pub mod quota;
+pub mod registry;
+pub mod report;
+pub mod roster;
+pub mod rotation;
+pub mod rpc;
+pub mod schnorr;
+pub mod secret;
+pub mod selection;
+pub mod session;
+pub mod shamir;
+pub mod sim;
+pub mod sim_net;
+pub mod slip39;
+pub mod socks;
+pub mod solana;
+pub mod storage;
+pub mod timelock;
+pub mod transcript;
+pub mod transport;
+pub mod vectors;
+pub mod websocket;
+
+pub use crate::{
+    bundle::{
+        DetachedSignature, SignedMessage, verify_bundle, verify_detached, verify_detached_reader,
+    },
+    ciphersuite::{Ciphersuite, Ed25519Sha512},
+    config::GroupConfig,
+    coordinator::{Coordinator, CoordinatorEvent, Handoff},
+    error::FrostError,
+    frost::{Frost, FrostSigner, SessionId, SignatureShare, SignerRole, SigningPackage},
+    helper::NonZeroScalar,
+    keys::{
+        GroupPublicKey, KeyPackage, PublicKeyPackage, SecretShare, VerificationShare,
+        verify_message_signature,
+    },
+    nonces::{SigningCommitments, SigningNonces},
+    schnorr::SchnorrSignature,
+};
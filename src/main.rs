@@ -16,87 +16,317 @@
 //!    simplicity.
 //!
 //! 2. In the [RFC](https://www.rfc-editor.org/rfc/rfc9591.html), a 'Coordinator' is chosen in the protocol, responsible for various group
-//!    actions such as aggregating signatures. In our simple example, the Coordinator is just
-//!    one of the signers.
+//!    actions such as aggregating signatures. In our example, the Coordinator is the main
+//!    process, and each selected signer runs in a child process of its own, started with the
+//!    `signer` subcommand. The processes exchange the wire encoding of each `GroupMessage`, as
+//!    one hex line per message, over the standard input and output of the signers.
 //!
 //! 3. Each signer generates a hiding and a binding nonce respectively and sends their commitments
-//!    to the Coordinator. The Coordinator collects these commitments and computes the
-//!    binding factors for all signers.
+//!    to the Coordinator. The Coordinator collects these commitments into a signing package and
+//!    sends it to all signers.
 //!
-//! 4.  Each signer generates a signature share using its secret key share, nonces and binding
-//!     factor.
+//! 4.  Each signer derives its own binding factor and the challenge from the signing package, and
+//!     generates a signature share using its secret key share, nonces and binding factor.
 //!
 //! 5. The Coordinator aggregates these signature shares to produce a signature.
 //!
 //! 6. The Coordinator verifies the signature.
+//!
+//! The `ceremony deal` subcommand instead walks the operator of a trusted dealer through a keygen
+//! for real devices. It prompts for the threshold and the signers, then shows each `KeyPackage`
+//! as a QR code on the terminal, one at a time, for the signer device to scan. The key packages
+//! are not encrypted, nor written to disk: they must be scanned directly from the dealer's
+//! screen, in the room. Sealing them to an enrollment key of each device, so that they can
+//! transit untrusted channels, needs a standard KDF and AEAD, which are not dependencies of this
+//! crate yet.
+//!
+//! Only a trusted dealer is supported: there is no distributed key generation in this crate yet.
+
+use std::{
+    env,
+    io::{self, BufRead, BufReader, Write},
+    process::{self, Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::Duration,
+};
 
-use crate::{
-    frost::Frost,
-    helper::{
-        Commitment, NonZeroScalar, compute_binding_factors, compute_challenge,
-        compute_group_commitment,
-    },
-    schnorr::SchnorrSignature,
+use ark_ec::PrimeGroup;
+use blind_threshold_sig::{
+    Coordinator, Ed25519Sha512, Frost, FrostSigner, KeyPackage, NonZeroScalar, SignerRole,
+    ciphersuite::Element,
+    helper::{decode_hex, encode_hex, secure_rng},
+    qr::QrCode,
+    transport::{GroupMessage, Party, Transport, TransportError, coordinate, participate},
 };
-use ark_ed25519::Fr as ScalarField;
 
-pub mod ciphersuite;
-pub mod frost;
-pub mod helper;
-pub mod schnorr;
-pub mod shamir;
+/// Time each round of the demo session is given to complete.
+const ROUND_TIMEOUT: Duration = Duration::from_secs(10);
 
 fn main() {
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("ceremony") => ceremony(&args),
+        Some("signer") => signer(),
+        _ => demo(),
+    }
+}
+
+fn demo() {
     println!("Hello, Blind Schnorr!");
 
     let message = b"asia is underrated";
 
     // Step1: At the start of the protocol, a secret key is generated and shared among signers
     // In this example, we use a threshold signature scheme of 5 signers with a threshold of 3
-    let mut frost_protocol = Frost::signature_share(3, 5);
+    let frost_protocol: Frost = Frost::signature_share_with_rng(
+        3,
+        &[SignerRole::Regular; 5],
+        generator(),
+        &mut secure_rng(),
+    );
 
-    // Step2: Each of these signers generate a hiding nonce and a binding nonce respectively
+    // Step2: The coordinator starts a process for each of three signers, handing it its key
+    // package. Each of these signers generate a hiding nonce and a binding nonce respectively
     // and send the commitment of these nonces to the coordinator
-    // The coordinator collects these commitments and compute the binding factors rho for all signers
-    let mut commitments: Vec<Commitment> = Vec::new();
-    for signer in frost_protocol.clone().signers {
-        // FIXME: clean up various commitment types
-        let commitment = signer.get_nonce_commitment();
-        commitments.push((
-            NonZeroScalar::new(signer.get_identifier()),
-            commitment.D,
-            commitment.E,
-        ));
+    // The coordinator collects these commitments into a signing package
+    // Step3: The coordinator sends the signing package to each signer
+    // Step4: Each signer derives its binding factor rho and the challenge from the signing
+    // package, and generates a signature share using its secret share, nonces and binding factor
+    // Step5: The coordinator aggregates the signature shares to produce a signature
+    let participants: Vec<NonZeroScalar> = frost_protocol.participants().take(3).collect();
+    let key_packages = participants.iter().map(|identifier| {
+        frost_protocol
+            .key_package(*identifier)
+            .expect("every participant is a signer")
+    });
+    let mut transport = SignerProcesses::spawn(key_packages).unwrap_or_else(|error| {
+        eprintln!("could not start the signer processes: {error}");
+        process::exit(1);
+    });
+    let mut coordinator = Coordinator::new(
+        frost_protocol.group_pk,
+        message,
+        participants,
+        ROUND_TIMEOUT,
+    )
+    .with_public_key_package(frost_protocol.public_key_package())
+    .expect("the dealer's key material is consistent");
+    let schnorr_signature =
+        coordinate(&mut coordinator, &mut transport).expect("signing session completes");
+    transport.wait();
+
+    // Step6: The coordinator verifies the signature
+    let verification_result = frost_protocol.verify(message, schnorr_signature);
+    println!("Signature verification result: {}", verification_result);
+}
+
+/// Takes part in the demo session as a signer process: reads its key package from the first line
+/// of the standard input, then exchanges messages with the coordinator.
+fn signer() {
+    let mut line = String::new();
+    let key_package: KeyPackage = io::stdin()
+        .read_line(&mut line)
+        .ok()
+        .and_then(|_| decode_hex(line.trim()))
+        .and_then(|bytes| KeyPackage::from_bytes(&bytes).ok())
+        .unwrap_or_else(|| {
+            eprintln!("expected the hex encoding of a key package on the first line");
+            process::exit(1);
+        });
+    let mut signer = FrostSigner::from_key_package(&key_package);
+
+    let (sender, inbox) = mpsc::channel();
+    forward_messages(Party::Coordinator, BufReader::new(io::stdin()), sender);
+    let mut transport = StdioTransport { inbox };
+    if let Err(error) = participate(&mut signer, &mut transport, ROUND_TIMEOUT) {
+        eprintln!("signer {:?}: {error}", key_package.identifier);
+        process::exit(1);
+    }
+}
+
+/// The coordinator's `Transport` to signers running in child processes.
+struct SignerProcesses {
+    signers: Vec<(Party, Child, ChildStdin)>,
+    inbox: Receiver<(Party, GroupMessage)>,
+}
+
+impl SignerProcesses {
+    /// Starts a signer process for each of `key_packages`, and hands it its key package.
+    fn spawn(key_packages: impl IntoIterator<Item = KeyPackage>) -> io::Result<Self> {
+        let executable = env::current_exe()?;
+        let (sender, inbox) = mpsc::channel();
+        let mut signers = Vec::new();
+        for key_package in key_packages {
+            let mut child = Command::new(&executable)
+                .arg("signer")
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()?;
+            let mut stdin = child.stdin.take().expect("stdin is piped");
+            writeln!(stdin, "{}", encode_hex(&key_package.to_bytes()))?;
+
+            let party = Party::Signer(key_package.identifier);
+            let stdout = child.stdout.take().expect("stdout is piped");
+            forward_messages(party, BufReader::new(stdout), sender.clone());
+            signers.push((party, child, stdin));
+        }
+        Ok(SignerProcesses { signers, inbox })
+    }
+
+    /// Closes the input of every signer process and waits for it to exit.
+    fn wait(self) {
+        for (party, mut child, stdin) in self.signers {
+            drop(stdin);
+            match child.wait() {
+                Ok(status) if status.success() => {}
+                result => eprintln!("signer process {party:?} failed: {result:?}"),
+            }
+        }
+    }
+}
+
+impl Transport for SignerProcesses {
+    fn send(&mut self, to: Party, message: GroupMessage) -> Result<(), TransportError> {
+        let (_, _, stdin) = self
+            .signers
+            .iter_mut()
+            .find(|(party, _, _)| *party == to)
+            .ok_or(TransportError::UnknownRecipient)?;
+        writeln!(stdin, "{}", encode_hex(&message.to_bytes()))
+            .and_then(|()| stdin.flush())
+            .map_err(|_| TransportError::Disconnected)
+    }
+
+    fn recv(&mut self, timeout: Duration) -> Result<(Party, GroupMessage), TransportError> {
+        receive(&self.inbox, timeout)
     }
-    let binding_factors =
-        compute_binding_factors(frost_protocol.group_pk, &commitments, message.to_vec());
+}
+
+/// A signer process's `Transport` to the coordinator, over its standard input and output.
+struct StdioTransport {
+    inbox: Receiver<(Party, GroupMessage)>,
+}
+
+impl Transport for StdioTransport {
+    fn send(&mut self, _to: Party, message: GroupMessage) -> Result<(), TransportError> {
+        let mut stdout = io::stdout().lock();
+        writeln!(stdout, "{}", encode_hex(&message.to_bytes()))
+            .and_then(|()| stdout.flush())
+            .map_err(|_| TransportError::Disconnected)
+    }
+
+    fn recv(&mut self, timeout: Duration) -> Result<(Party, GroupMessage), TransportError> {
+        receive(&self.inbox, timeout)
+    }
+}
+
+/// Forwards the messages read from `input` to `inbox`, attributed to `party`, on a thread of its
+/// own.
+fn forward_messages(
+    party: Party,
+    input: impl BufRead + Send + 'static,
+    inbox: Sender<(Party, GroupMessage)>,
+) {
+    thread::spawn(move || {
+        for message in input
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| decode_line(&line))
+        {
+            if inbox.send((party, message)).is_err() {
+                break;
+            }
+        }
+    });
+}
 
-    // Step3: Each signer obtains its own binding factor rho
-    frost_protocol.update_binding_factors(binding_factors.clone());
+/// Decodes a line holding the hex encoding of a `GroupMessage`, reporting malformed lines.
+fn decode_line(line: &str) -> Option<GroupMessage> {
+    let message = decode_hex(line.trim()).and_then(|bytes| GroupMessage::from_bytes(&bytes).ok());
+    if message.is_none() {
+        eprintln!("dropping malformed message: {}", line.trim());
+    }
+    message
+}
+
+fn receive(
+    inbox: &Receiver<(Party, GroupMessage)>,
+    timeout: Duration,
+) -> Result<(Party, GroupMessage), TransportError> {
+    inbox.recv_timeout(timeout).map_err(|error| match error {
+        RecvTimeoutError::Timeout => TransportError::Timeout,
+        RecvTimeoutError::Disconnected => TransportError::Disconnected,
+    })
+}
 
-    // Step4: Each signer generates a signature share using its secret share, nonces and binding factor
-    let x_coordinates: Vec<NonZeroScalar> = frost_protocol
-        .signers
-        .iter()
-        .map(|signer| NonZeroScalar::new(signer.get_identifier()))
+fn ceremony(args: &[String]) {
+    match args.get(2).map(String::as_str) {
+        Some("deal") if args.len() == 3 => deal(),
+        _ => {
+            eprintln!("usage: {} ceremony deal", args[0]);
+            process::exit(2);
+        }
+    }
+}
+
+fn deal() {
+    let mut input = io::stdin().lock();
+    let threshold: usize = prompt(&mut input, "threshold: ", |line| line.parse().ok());
+    let total_signers: usize = prompt(&mut input, "number of signers: ", |line| {
+        line.parse().ok().filter(|total| *total >= threshold)
+    });
+
+    let roles: Vec<SignerRole> = (1..=total_signers)
+        .map(|index| {
+            prompt(
+                &mut input,
+                &format!("signer {index} is a blind custodian [y/N]: "),
+                |line| match line {
+                    "y" | "Y" => Some(SignerRole::Blind),
+                    "" | "n" | "N" => Some(SignerRole::Regular),
+                    _ => None,
+                },
+            )
+        })
         .collect();
-    let group_commitment = compute_group_commitment(&commitments, binding_factors);
-    let challenge = compute_challenge(group_commitment, frost_protocol.group_pk, message.to_vec());
-    let mut signature_shares = Vec::new();
-    for signer in frost_protocol.clone().signers {
-        let sig_share = signer.sign(ScalarField::from(challenge), &x_coordinates);
-        signature_shares.push(sig_share);
+
+    let frost: Frost =
+        Frost::signature_share_with_rng(threshold, &roles, generator(), &mut secure_rng());
+    for (index, identifier) in (1..).zip(frost.participants()) {
+        let key_package = frost
+            .key_package(identifier)
+            .expect("every participant has a key package");
+        println!("key package of signer {index}:");
+        print_qr(encode_hex(&key_package.to_bytes()).as_bytes());
+        prompt(&mut input, "press enter once it is scanned ", |_| Some(()));
     }
+    println!("group public key: {}", frost.group_pk);
+}
 
-    // Step5: The coordinator aggregates the signature shares to produce a signature
-    // TODO: we only need to aggregate threshold number of shares, not all
-    let signature = frost_protocol.signature_aggregate(signature_shares);
-    let schnorr_signature = SchnorrSignature {
-        R: group_commitment,
-        s: signature,
-    };
+/// The base point of the group, which every device enrolls over.
+fn generator() -> Element<Ed25519Sha512> {
+    Element::<Ed25519Sha512>::generator()
+}
 
-    // Step6: The coordinator verifies the signature
-    let verification_result = frost_protocol.verify(schnorr_signature, challenge);
-    println!("Signature verification result: {}", verification_result);
+/// Prompts until `parse` accepts the trimmed line read from `input`.
+fn prompt<T>(input: &mut impl BufRead, message: &str, parse: impl Fn(&str) -> Option<T>) -> T {
+    loop {
+        print!("{message}");
+        io::stdout().flush().expect("stdout is writable");
+        let mut line = String::new();
+        if input.read_line(&mut line).unwrap_or(0) == 0 {
+            eprintln!("\nceremony aborted");
+            process::exit(1);
+        }
+        match parse(line.trim()) {
+            Some(value) => return value,
+            None => eprintln!("invalid input: {}", line.trim()),
+        }
+    }
+}
+
+fn print_qr(data: &[u8]) {
+    let qr = QrCode::encode(data).expect("data fits in a QR code");
+    print!("{}", qr.to_terminal_string());
 }
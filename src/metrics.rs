@@ -0,0 +1,158 @@
+//! This module implements metrics for a coordinator service.
+//!
+//! A `Metrics` instance is shared by every `Coordinator` of a service and records sessions
+//! started, completed and aborted, the latency of each round and how often each signer caused a
+//! failure. `Metrics::render` produces the Prometheus text exposition format, to be served from
+//! the service's metrics endpoint.
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        Mutex, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use crate::error::Round;
+
+/// Upper bounds, in seconds, of the round latency histogram buckets.
+const LATENCY_BUCKETS: [f64; 10] = [0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0];
+
+/// A cumulative histogram of durations with `LATENCY_BUCKETS` as bucket bounds.
+#[derive(Debug, Default)]
+struct Histogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS.len()],
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    fn observe(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        for (bucket, bound) in self.buckets.iter().zip(LATENCY_BUCKETS) {
+            if seconds <= bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    fn render(&self, output: &mut String, name: &str, labels: &str) {
+        for (bucket, bound) in self.buckets.iter().zip(LATENCY_BUCKETS) {
+            let _ = writeln!(
+                output,
+                "{name}_bucket{{{labels},le=\"{bound}\"}} {}",
+                bucket.load(Ordering::Relaxed)
+            );
+        }
+        let count = self.count.load(Ordering::Relaxed);
+        let sum = self.sum_micros.load(Ordering::Relaxed) as f64 / 1e6;
+        let _ = writeln!(output, "{name}_bucket{{{labels},le=\"+Inf\"}} {count}");
+        let _ = writeln!(output, "{name}_sum{{{labels}}} {sum}");
+        let _ = writeln!(output, "{name}_count{{{labels}}} {count}");
+    }
+}
+
+/// Counters and histograms of a coordinator service. Cheap to update from many threads.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    sessions_started: AtomicU64,
+    sessions_completed: AtomicU64,
+    sessions_aborted: AtomicU64,
+    commitment_round_latency: Histogram,
+    signature_share_round_latency: Histogram,
+    /// Failure counts keyed by signer identifier.
+    signer_failures: Mutex<BTreeMap<String, u64>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn session_started(&self) {
+        self.sessions_started.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn session_completed(&self) {
+        self.sessions_completed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn session_aborted(&self) {
+        self.sessions_aborted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that `round` completed after `duration`.
+    pub fn round_completed(&self, round: Round, duration: Duration) {
+        match round {
+            Round::Commitment => self.commitment_round_latency.observe(duration),
+            Round::SignatureShare => self.signature_share_round_latency.observe(duration),
+        }
+    }
+
+    /// Records a failure, such as a timeout or a duplicate message, caused by the signer with the
+    /// given `identifier`.
+    pub fn signer_failed(&self, identifier: impl ToString) {
+        *self
+            .signer_failures
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(identifier.to_string())
+            .or_default() += 1;
+    }
+
+    /// Renders all metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+
+        for (name, help, counter) in [
+            (
+                "frost_coordinator_sessions_started_total",
+                "Signing sessions started.",
+                &self.sessions_started,
+            ),
+            (
+                "frost_coordinator_sessions_completed_total",
+                "Signing sessions that produced a signature.",
+                &self.sessions_completed,
+            ),
+            (
+                "frost_coordinator_sessions_aborted_total",
+                "Signing sessions aborted after a round timed out.",
+                &self.sessions_aborted,
+            ),
+        ] {
+            let _ = writeln!(output, "# HELP {name} {help}");
+            let _ = writeln!(output, "# TYPE {name} counter");
+            let _ = writeln!(output, "{name} {}", counter.load(Ordering::Relaxed));
+        }
+
+        let name = "frost_coordinator_round_duration_seconds";
+        let _ = writeln!(
+            output,
+            "# HELP {name} Time taken by each round to complete."
+        );
+        let _ = writeln!(output, "# TYPE {name} histogram");
+        self.commitment_round_latency
+            .render(&mut output, name, "round=\"commitment\"");
+        self.signature_share_round_latency
+            .render(&mut output, name, "round=\"signature_share\"");
+
+        let name = "frost_coordinator_signer_failures_total";
+        let _ = writeln!(output, "# HELP {name} Failures caused by each signer.");
+        let _ = writeln!(output, "# TYPE {name} counter");
+        for (signer, failures) in self
+            .signer_failures
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+        {
+            let _ = writeln!(output, "{name}{{signer=\"{signer}\"}} {failures}");
+        }
+
+        output
+    }
+}
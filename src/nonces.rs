@@ -0,0 +1,188 @@
+//! This module implements the nonces of a signer.
+//!
+//! In round one every signer draws a pair of `SigningNonces`, a hiding nonce `d_i` and a binding
+//! nonce `e_i`, and publishes the `SigningCommitments` `D_i = g^{d_i}` and `E_i = g^{e_i}` to
+//! them. Regular and blind signers use the same types: a blind signer simply draws a zero binding
+//! nonce, so that its binding commitment is the identity.
+use std::{fmt, str::FromStr};
+
+use ark_ec::AdditiveGroup;
+use ark_ff::PrimeField;
+#[cfg(any(test, feature = "testing"))]
+use ark_ff::UniformRand;
+use ark_serialize::SerializationError;
+#[cfg(any(test, feature = "testing"))]
+use ark_std::rand::Rng;
+use rand::RngCore;
+
+use crate::{
+    ciphersuite::{Ciphersuite, Ed25519Sha512, Element, ScalarField},
+    frost::SignerRole,
+    helper::{Decoder, NonZeroScalar, decode_hex, encode_hex, scalars_eq, serialize_scalar},
+    secret::Secret,
+};
+
+/// The secret nonces a signer draws for a single signing session. They are deliberately not
+/// `Clone`: a copy of unused nonces could sign a second package, and signing two packages with the
+/// same nonces reveals the secret share.
+pub struct SigningNonces<C: Ciphersuite = Ed25519Sha512> {
+    /// The hiding nonce `d_i`.
+    hiding: Secret<ScalarField<C>>,
+    /// The binding nonce `e_i`, zero for a blind signer.
+    binding: Secret<ScalarField<C>>,
+}
+
+/// Compares the nonces in constant time.
+impl<C: Ciphersuite> PartialEq for SigningNonces<C> {
+    fn eq(&self, other: &Self) -> bool {
+        // `&` rather than `&&`, so that the binding nonces are compared even if the hiding ones
+        // differ
+        scalars_eq::<C>(self.hiding.expose_secret(), other.hiding.expose_secret())
+            & scalars_eq::<C>(self.binding.expose_secret(), other.binding.expose_secret())
+    }
+}
+
+impl<C: Ciphersuite> Eq for SigningNonces<C> {}
+
+/// Redacts the nonces, so that `SigningNonces` can be logged safely.
+impl<C: Ciphersuite> fmt::Debug for SigningNonces<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SigningNonces")
+            .field("hiding", &format_args!("<redacted>"))
+            .field("binding", &format_args!("<redacted>"))
+            .finish()
+    }
+}
+
+impl<C: Ciphersuite> SigningNonces<C> {
+    /// Draws the nonces of a signer with the given `role` and secret share `secret`, hedging fresh
+    /// randomness with the secret as in `nonce_generate`.
+    pub fn generate(secret: ScalarField<C>, role: SignerRole) -> Self {
+        let hiding = nonce_generate::<C>(secret);
+        let binding = match role {
+            SignerRole::Regular => nonce_generate::<C>(secret),
+            SignerRole::Blind => ScalarField::<C>::ZERO,
+        };
+        Self::from_scalars(hiding, binding)
+    }
+
+    /// Draws the nonces of a signer with the given `role` from `rng`.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn generate_with_rng<R: Rng + ?Sized>(role: SignerRole, rng: &mut R) -> Self {
+        let hiding = ScalarField::<C>::rand(rng);
+        let binding = match role {
+            SignerRole::Regular => ScalarField::<C>::rand(rng),
+            SignerRole::Blind => ScalarField::<C>::ZERO,
+        };
+        Self::from_scalars(hiding, binding)
+    }
+
+    /// Uses the given nonces, e.g. to replay a test vector. The signer is blind if `binding` is
+    /// zero.
+    pub(crate) fn from_scalars(hiding: ScalarField<C>, binding: ScalarField<C>) -> Self {
+        SigningNonces {
+            hiding: Secret::new(hiding),
+            binding: Secret::new(binding),
+        }
+    }
+
+    /// Returns the commitments to these nonces of the signer with the given `identifier`.
+    pub fn commit(
+        &self,
+        identifier: NonZeroScalar<C>,
+        generator: Element<C>,
+    ) -> SigningCommitments<C> {
+        SigningCommitments {
+            identifier,
+            hiding: generator * self.hiding(),
+            binding: generator * self.binding(),
+        }
+    }
+
+    /// Whether these are the nonces of a blind signer, i.e. the binding nonce is zero.
+    pub fn is_blind(&self) -> bool {
+        *self.binding.expose_secret() == ScalarField::<C>::ZERO
+    }
+
+    pub(crate) fn hiding(&self) -> ScalarField<C> {
+        *self.hiding.expose_secret()
+    }
+
+    pub(crate) fn binding(&self) -> ScalarField<C> {
+        *self.binding.expose_secret()
+    }
+}
+
+/// The commitments a participant publishes in round one: `D_i = g^{d_i}` to its hiding nonce and
+/// `E_i = g^{e_i}` to its binding nonce.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SigningCommitments<C: Ciphersuite = Ed25519Sha512> {
+    /// Identifier of the participant.
+    pub identifier: NonZeroScalar<C>,
+    /// Commitment `D_i` to the hiding nonce.
+    pub hiding: Element<C>,
+    /// Commitment `E_i` to the binding nonce.
+    pub binding: Element<C>,
+}
+
+impl<C: Ciphersuite> SigningCommitments<C> {
+    /// Encodes the commitments as `identifier || D_i || E_i`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [
+            serialize_scalar::<C>(&self.identifier.to_scalar()),
+            C::serialize_element(&self.hiding),
+            C::serialize_element(&self.binding),
+        ]
+        .concat()
+    }
+
+    /// Parses commitments encoded by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let mut decoder = Decoder::new(bytes);
+        let commitments = decoder.commitments()?;
+        decoder.finish()?;
+        Ok(commitments)
+    }
+}
+
+/// Formats the commitments as the hex encoding of `to_bytes`.
+impl<C: Ciphersuite> fmt::Display for SigningCommitments<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&encode_hex(&self.to_bytes()))
+    }
+}
+
+/// Parses the hex encoding of `to_bytes`.
+impl<C: Ciphersuite> FromStr for SigningCommitments<C> {
+    type Err = SerializationError;
+
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        Self::from_bytes(&decode_hex(hex).ok_or(SerializationError::InvalidData)?)
+    }
+}
+
+pub fn nonce_generate<C: Ciphersuite>(secret: ScalarField<C>) -> ScalarField<C> {
+    // Generate a 32-byte random number
+    let mut rng = rand::rng();
+    let mut random_bytes = [0u8; 32];
+    rng.fill_bytes(&mut random_bytes);
+
+    nonce_generate_from_randomness::<C>(random_bytes, secret)
+}
+
+/// Derives a nonce from `secret` and the given `random_bytes` instead of fresh randomness.
+///
+/// Only meant for replaying test vectors; reusing `random_bytes` across signing sessions leaks
+/// the secret.
+pub fn nonce_generate_from_randomness<C: Ciphersuite>(
+    random_bytes: [u8; 32],
+    secret: ScalarField<C>,
+) -> ScalarField<C> {
+    let mut message = Vec::with_capacity(64);
+    message.extend_from_slice(&random_bytes);
+    message.extend_from_slice(&serialize_scalar::<C>(&secret));
+
+    // return H3
+    let hash_output = C::H3(&message);
+    ScalarField::<C>::from_le_bytes_mod_order(&hash_output)
+}
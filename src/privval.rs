@@ -0,0 +1,355 @@
+//! This module implements a remote signer for a Tendermint/CometBFT validator whose consensus key
+//! is the key of a FROST group.
+//!
+//! `PrivValidator` signs the canonical sign bytes of votes and proposals by coordinating a
+//! signing session among a threshold of the group's signers over a `Transport`, so that the key
+//! packages stay on the signers' own devices, and returns plain ed25519 signatures. The group must
+//! therefore use the standard basepoint, see `solana::solana_group`. Every signature share is
+//! checked against the signer's verification share, and the aggregate signature is verified under
+//! the group key before it is persisted.
+//!
+//! Before signing, it enforces double-sign protection: the height, round and step of every
+//! request must not go back, and a request for the last signed height, round and step is only
+//! answered with the signature already produced for the same sign bytes. The last signed state is
+//! persisted before the signature is returned.
+//!
+//! The privval wire protocol itself, protobuf messages over a SecretConnection, is not included.
+#![allow(non_snake_case)]
+
+use std::{
+    cmp::Ordering,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use ark_ec::PrimeGroup;
+
+use crate::{
+    ciphersuite::{Ed25519Sha512, Element},
+    coordinator::Coordinator,
+    error::FrostError,
+    helper::{NonZeroScalar, decode_hex, encode_hex},
+    keys::{PublicKeyPackage, verify_message_signature},
+    schnorr::SIGNATURE_LENGTH,
+    transport::{Transport, coordinate},
+};
+
+/// The consensus step a sign request belongs to, ordered as within a round.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Step {
+    Proposal = 1,
+    Prevote = 2,
+    Precommit = 3,
+}
+
+/// A request to sign a vote or a proposal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignRequest {
+    pub height: u64,
+    pub round: u32,
+    pub step: Step,
+    /// The canonical sign bytes of the vote or proposal.
+    pub sign_bytes: Vec<u8>,
+}
+
+/// The last request signed, with its signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SignState {
+    request: SignRequest,
+    signature: [u8; SIGNATURE_LENGTH],
+}
+
+/// A validator signer coordinating a threshold of the signers of a FROST group.
+#[derive(Debug)]
+pub struct PrivValidator<T: Transport<Ed25519Sha512>> {
+    public_key_package: PublicKeyPackage<Ed25519Sha512>,
+
+    /// The signers asked for a share of every signature.
+    participants: Vec<NonZeroScalar<Ed25519Sha512>>,
+
+    /// The transport to the participants.
+    transport: T,
+
+    /// Time each round of a signing session is given to complete.
+    round_timeout: Duration,
+
+    /// The last request signed, if any.
+    state: Option<SignState>,
+
+    /// File the last signed state is persisted to.
+    state_path: PathBuf,
+}
+
+impl<T: Transport<Ed25519Sha512>> PrivValidator<T> {
+    /// Creates a signer that asks `participants`, reached over `transport`, for a share of every
+    /// signature, and loads the last signed state from `state_path` if the file exists.
+    ///
+    /// Returns an error if `public_key_package` does not verify or is not over the standard
+    /// basepoint, if the participants are fewer than the threshold, listed twice or not signers
+    /// of the group, or if the state file cannot be read.
+    pub fn open(
+        public_key_package: PublicKeyPackage<Ed25519Sha512>,
+        participants: Vec<NonZeroScalar<Ed25519Sha512>>,
+        transport: T,
+        round_timeout: Duration,
+        state_path: impl AsRef<Path>,
+    ) -> io::Result<Self> {
+        let invalid = |reason: String| Err(io::Error::new(io::ErrorKind::InvalidInput, reason));
+        if let Err(error) = public_key_package.verify() {
+            return invalid(error.to_string());
+        }
+        if public_key_package.generator != Element::<Ed25519Sha512>::generator() {
+            return invalid("the group does not use the ed25519 basepoint".to_string());
+        }
+        if participants.len() < public_key_package.threshold {
+            return invalid(format!(
+                "{} participants for a threshold of {}",
+                participants.len(),
+                public_key_package.threshold
+            ));
+        }
+        for (position, identifier) in participants.iter().enumerate() {
+            if participants[..position].contains(identifier) {
+                return invalid(format!("duplicate participant {}", identifier.to_scalar()));
+            }
+            if public_key_package.verification_share(*identifier).is_none() {
+                return invalid(format!(
+                    "participant {} is not a signer of the group",
+                    identifier.to_scalar()
+                ));
+            }
+        }
+
+        let state_path = state_path.as_ref().to_path_buf();
+        let state = match fs::read_to_string(&state_path) {
+            Ok(contents) => Some(parse_state(contents.trim()).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "malformed sign state")
+            })?),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+            Err(error) => return Err(error),
+        };
+
+        Ok(PrivValidator {
+            public_key_package,
+            participants,
+            transport,
+            round_timeout,
+            state,
+            state_path,
+        })
+    }
+
+    /// Signs `request`, returning the 64-byte ed25519 signature of its sign bytes.
+    ///
+    /// Returns an error if signing would be a double sign, if the signing session fails or its
+    /// signature does not verify, or if the new state cannot be persisted.
+    pub fn sign(
+        &mut self,
+        request: &SignRequest,
+    ) -> Result<[u8; SIGNATURE_LENGTH], FrostError<Ed25519Sha512>> {
+        if let Some(state) = &self.state {
+            let last = &state.request;
+            match (request.height, request.round, request.step).cmp(&(
+                last.height,
+                last.round,
+                last.step,
+            )) {
+                Ordering::Less => {
+                    return Err(FrostError::DoubleSign {
+                        height: request.height,
+                        round: request.round,
+                    });
+                }
+                Ordering::Equal if request.sign_bytes == last.sign_bytes => {
+                    return Ok(state.signature);
+                }
+                Ordering::Equal => {
+                    return Err(FrostError::DoubleSign {
+                        height: request.height,
+                        round: request.round,
+                    });
+                }
+                Ordering::Greater => {}
+            }
+        }
+
+        let group_pk = self.public_key_package.group_pk;
+        let mut coordinator = Coordinator::new(
+            group_pk,
+            &request.sign_bytes,
+            self.participants.clone(),
+            self.round_timeout,
+        )
+        .with_public_key_package(self.public_key_package.clone())?;
+        let signature = coordinate(&mut coordinator, &mut self.transport)?;
+        // never persist, nor return, a signature that does not verify as plain ed25519
+        if !verify_message_signature(
+            &signature,
+            &request.sign_bytes,
+            group_pk.to_element(),
+            self.public_key_package.generator,
+        ) {
+            return Err(FrostError::InvalidSignature);
+        }
+        let signature = signature
+            .to_bytes()
+            .try_into()
+            .expect("ed25519 signatures are 64 bytes");
+
+        let state = SignState {
+            request: request.clone(),
+            signature,
+        };
+        persist_state(&self.state_path, &state)
+            .map_err(|error| FrostError::Storage(error.to_string()))?;
+        self.state = Some(state);
+
+        Ok(signature)
+    }
+}
+
+/// Parses the `height round step sign_bytes signature` line of a state file.
+fn parse_state(line: &str) -> Option<SignState> {
+    let [height, round, step, sign_bytes, signature] = line.split(' ').collect::<Vec<_>>()[..]
+    else {
+        return None;
+    };
+    let step = match step {
+        "1" => Step::Proposal,
+        "2" => Step::Prevote,
+        "3" => Step::Precommit,
+        _ => return None,
+    };
+
+    Some(SignState {
+        request: SignRequest {
+            height: height.parse().ok()?,
+            round: round.parse().ok()?,
+            step,
+            sign_bytes: decode_hex(sign_bytes)?,
+        },
+        signature: decode_hex(signature)?.try_into().ok()?,
+    })
+}
+
+/// Rewrites the state file atomically, by writing a temporary file and renaming it.
+fn persist_state(path: &Path, state: &SignState) -> io::Result<()> {
+    let temporary = path.with_extension("tmp");
+    let mut file = fs::File::create(&temporary)?;
+    let request = &state.request;
+    writeln!(
+        file,
+        "{} {} {} {} {}",
+        request.height,
+        request.round,
+        request.step as u8,
+        encode_hex(&request.sign_bytes),
+        encode_hex(&state.signature)
+    )?;
+    file.sync_data()?;
+    fs::rename(temporary, path)
+}
+
+#[test]
+fn test_double_sign_protection() {
+    use std::thread;
+
+    use crate::{
+        frost::{FrostSigner, SignerRole},
+        keys::KeyPackage,
+        solana::{public_key, solana_group, verify_solana_signature},
+        transport::{ChannelTransport, participate},
+    };
+
+    let path = std::env::temp_dir().join(format!("privval-{}", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let frost = solana_group(2, &[SignerRole::Regular; 3]);
+    let participants: Vec<_> = frost.participants().take(2).collect();
+    let timeout = Duration::from_secs(10);
+    // each signer answers sessions on its own thread until the validator goes away
+    let connect = || {
+        let (transport, signer_transports) = ChannelTransport::network(&participants);
+        for (identifier, mut transport) in participants.iter().zip(signer_transports) {
+            let key_package: KeyPackage = frost.key_package(*identifier).unwrap();
+            thread::spawn(move || {
+                while participate(
+                    &mut FrostSigner::from_key_package(&key_package),
+                    &mut transport,
+                    timeout,
+                )
+                .is_ok()
+                {}
+            });
+        }
+        transport
+    };
+    let open = || {
+        PrivValidator::open(
+            frost.public_key_package(),
+            participants.clone(),
+            connect(),
+            timeout,
+            &path,
+        )
+    };
+    let vote = |height, step, sign_bytes: &[u8]| SignRequest {
+        height,
+        round: 0,
+        step,
+        sign_bytes: sign_bytes.to_vec(),
+    };
+
+    let mut validator = open().unwrap();
+    let prevote = vote(10, Step::Prevote, b"prevote for block A");
+    let signature = validator.sign(&prevote).unwrap();
+    assert!(verify_solana_signature(
+        &public_key(&frost),
+        &prevote.sign_bytes,
+        &signature
+    ));
+
+    // the same request is answered with the same signature, a conflicting one is refused
+    assert_eq!(validator.sign(&prevote), Ok(signature));
+    let conflicting = vote(10, Step::Prevote, b"prevote for block B");
+    assert!(validator.sign(&conflicting).is_err());
+
+    // the state survives a restart, and signing can only move forward
+    let mut validator = open().unwrap();
+    assert!(validator.sign(&conflicting).is_err());
+    assert!(validator.sign(&vote(9, Step::Precommit, b"old")).is_err());
+    let precommit = vote(10, Step::Precommit, b"precommit");
+    let signature = validator.sign(&precommit).unwrap();
+    assert!(verify_solana_signature(
+        &public_key(&frost),
+        &precommit.sign_bytes,
+        &signature
+    ));
+    fs::remove_file(&path).unwrap();
+
+    // fewer participants than the threshold, or an unknown one, are refused
+    let (transport, _) = ChannelTransport::network(&participants);
+    assert!(
+        PrivValidator::open(
+            frost.public_key_package(),
+            participants[..1].to_vec(),
+            transport,
+            timeout,
+            &path
+        )
+        .is_err()
+    );
+    let (transport, _) = ChannelTransport::network(&participants);
+    let stranger = NonZeroScalar::derive("stranger").unwrap();
+    assert!(
+        PrivValidator::open(
+            frost.public_key_package(),
+            vec![participants[0], stranger],
+            transport,
+            timeout,
+            &path
+        )
+        .is_err()
+    );
+}
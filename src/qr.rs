@@ -0,0 +1,574 @@
+//! This module implements a minimal QR code encoder, to move key material to air-gapped devices.
+//!
+//! Data is encoded in byte mode at error correction level M, in the smallest version that fits,
+//! with the mask that scores the lowest penalty. The codes can be rendered as SVG or as text for
+//! a terminal.
+//!
+//! Reference: ISO/IEC 18004:2015
+use std::fmt::Write;
+
+/// Error correction codewords per block at level M, indexed by version.
+const ECC_CODEWORDS_PER_BLOCK: [usize; 41] = [
+    0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26, 26, 28, 28,
+    28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28,
+];
+
+/// Error correction blocks at level M, indexed by version.
+const NUM_ERROR_CORRECTION_BLOCKS: [usize; 41] = [
+    0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16, 17, 17, 18, 20, 21, 23,
+    25, 26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47, 49,
+];
+
+/// The format information bits of level M.
+const LEVEL_M_FORMAT_BITS: u32 = 0;
+
+const PENALTY_N1: usize = 3;
+const PENALTY_N2: usize = 3;
+const PENALTY_N3: usize = 40;
+const PENALTY_N4: usize = 10;
+
+/// A QR code: a square grid of dark and light modules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QrCode {
+    version: usize,
+    size: usize,
+    /// Whether each module is dark, row by row.
+    modules: Vec<bool>,
+    /// Whether each module belongs to a function pattern rather than data.
+    is_function: Vec<bool>,
+}
+
+impl QrCode {
+    /// Encodes `data` in the smallest QR code it fits in, or returns `None` if it is longer than
+    /// the 2331 bytes of a version 40 code.
+    pub fn encode(data: &[u8]) -> Option<Self> {
+        let version = (1..=40).find(|version| {
+            4 + char_count_bits(*version) + 8 * data.len() <= num_data_codewords(*version) * 8
+        })?;
+
+        let mut bits = BitBuffer::default();
+        bits.append(0b0100, 4);
+        bits.append(data.len() as u32, char_count_bits(version));
+        for byte in data {
+            bits.append(u32::from(*byte), 8);
+        }
+        let capacity = num_data_codewords(version) * 8;
+        bits.append(0, (capacity - bits.0.len()).min(4));
+        bits.append(0, (8 - bits.0.len() % 8) % 8);
+        for pad in [0xEC, 0x11].into_iter().cycle() {
+            if bits.0.len() >= capacity {
+                break;
+            }
+            bits.append(pad, 8);
+        }
+        let codewords: Vec<u8> = bits
+            .0
+            .chunks(8)
+            .map(|byte| byte.iter().fold(0, |acc, bit| (acc << 1) | u8::from(*bit)))
+            .collect();
+
+        let size = version * 4 + 17;
+        let mut qr = QrCode {
+            version,
+            size,
+            modules: vec![false; size * size],
+            is_function: vec![false; size * size],
+        };
+        qr.draw_function_patterns();
+        qr.draw_codewords(&add_ecc_and_interleave(version, &codewords));
+
+        let mask = (0..8)
+            .min_by_key(|mask| {
+                qr.apply_mask(*mask);
+                qr.draw_format_bits(*mask);
+                let penalty = qr.penalty_score();
+                qr.apply_mask(*mask);
+                penalty
+            })
+            .expect("there are eight masks");
+        qr.apply_mask(mask);
+        qr.draw_format_bits(mask);
+
+        Some(qr)
+    }
+
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    /// Returns the number of modules along each side.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns `true` if the module in column `x` and row `y` is dark.
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+
+    /// Renders the code as an SVG image with a quiet zone of four modules.
+    pub fn to_svg(&self) -> String {
+        let dimension = self.size + 8;
+        let mut path = String::new();
+        for y in 0..self.size {
+            for x in 0..self.size {
+                if self.get(x, y) {
+                    let _ = write!(path, "M{},{}h1v1h-1z", x + 4, y + 4);
+                }
+            }
+        }
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" \
+             viewBox=\"0 0 {dimension} {dimension}\" stroke=\"none\">\n\
+             <rect width=\"100%\" height=\"100%\" fill=\"#FFFFFF\"/>\n\
+             <path d=\"{path}\" fill=\"#000000\"/>\n\
+             </svg>\n"
+        )
+    }
+
+    /// Renders the code as text for a terminal with a dark background, two rows per line, with
+    /// the light modules and a quiet zone of two modules drawn as blocks.
+    pub fn to_terminal_string(&self) -> String {
+        let light = |x: isize, y: isize| {
+            let size = self.size as isize;
+            !(0..size).contains(&x) || !(0..size).contains(&y) || !self.get(x as usize, y as usize)
+        };
+        let (start, end) = (-2, self.size as isize + 2);
+        let mut output = String::new();
+        for y in (start..end).step_by(2) {
+            for x in start..end {
+                output.push(match (light(x, y), light(x, y + 1)) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                });
+            }
+            output.push('\n');
+        }
+        output
+    }
+
+    fn set_function_module(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+        self.is_function[y * self.size + x] = true;
+    }
+
+    fn draw_function_patterns(&mut self) {
+        for i in 0..self.size {
+            self.set_function_module(6, i, i % 2 == 0);
+            self.set_function_module(i, 6, i % 2 == 0);
+        }
+
+        let far = self.size - 4;
+        for (x, y) in [(3, 3), (far, 3), (3, far)] {
+            self.draw_finder_pattern(x, y);
+        }
+
+        let positions = alignment_pattern_positions(self.version);
+        let last = positions.len().saturating_sub(1);
+        for (i, x) in positions.iter().enumerate() {
+            for (j, y) in positions.iter().enumerate() {
+                // skip the three corners occupied by finder patterns
+                if (i, j) != (0, 0) && (i, j) != (0, last) && (i, j) != (last, 0) {
+                    self.draw_alignment_pattern(*x, *y);
+                }
+            }
+        }
+
+        // reserve the format areas, drawn for real once the mask is chosen
+        self.draw_format_bits(0);
+        self.draw_version();
+    }
+
+    fn draw_finder_pattern(&mut self, x: usize, y: usize) {
+        for dy in -4isize..=4 {
+            for dx in -4isize..=4 {
+                let (xx, yy) = (x as isize + dx, y as isize + dy);
+                if (0..self.size as isize).contains(&xx) && (0..self.size as isize).contains(&yy) {
+                    let distance = dx.abs().max(dy.abs());
+                    self.set_function_module(
+                        xx as usize,
+                        yy as usize,
+                        distance != 2 && distance != 4,
+                    );
+                }
+            }
+        }
+    }
+
+    fn draw_alignment_pattern(&mut self, x: usize, y: usize) {
+        for dy in -2isize..=2 {
+            for dx in -2isize..=2 {
+                self.set_function_module(
+                    (x as isize + dx) as usize,
+                    (y as isize + dy) as usize,
+                    dx.abs().max(dy.abs()) != 1,
+                );
+            }
+        }
+    }
+
+    fn draw_format_bits(&mut self, mask: u32) {
+        let bits = format_bits(mask);
+        let bit = |i: usize| (bits >> i) & 1 != 0;
+
+        // first copy, around the top left finder pattern
+        for i in 0..=5 {
+            self.set_function_module(8, i, bit(i));
+        }
+        self.set_function_module(8, 7, bit(6));
+        self.set_function_module(8, 8, bit(7));
+        self.set_function_module(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function_module(14 - i, 8, bit(i));
+        }
+
+        // second copy, split between the other two finder patterns
+        let size = self.size;
+        for i in 0..8 {
+            self.set_function_module(size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function_module(8, size - 15 + i, bit(i));
+        }
+        self.set_function_module(8, size - 8, true);
+    }
+
+    fn draw_version(&mut self) {
+        if self.version < 7 {
+            return;
+        }
+        let bits = version_bits(self.version);
+        for i in 0..18 {
+            let dark = (bits >> i) & 1 != 0;
+            let (a, b) = (self.size - 11 + i % 3, i / 3);
+            self.set_function_module(a, b, dark);
+            self.set_function_module(b, a, dark);
+        }
+    }
+
+    /// Places the codewords in the two-module wide zigzag from the bottom right corner.
+    fn draw_codewords(&mut self, codewords: &[u8]) {
+        let mut i = 0;
+        let mut right = self.size - 1;
+        while right >= 1 {
+            // the vertical timing pattern is skipped entirely
+            if right == 6 {
+                right = 5;
+            }
+            for vertical in 0..self.size {
+                for j in 0..2 {
+                    let x = right - j;
+                    let upward = (right + 1) & 2 == 0;
+                    let y = if upward {
+                        self.size - 1 - vertical
+                    } else {
+                        vertical
+                    };
+                    if !self.is_function[y * self.size + x] && i < codewords.len() * 8 {
+                        self.modules[y * self.size + x] =
+                            (codewords[i >> 3] >> (7 - (i & 7))) & 1 != 0;
+                        i += 1;
+                    }
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+    }
+
+    /// Inverts the data modules selected by `mask`. Applying a mask twice undoes it.
+    fn apply_mask(&mut self, mask: u32) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                let index = y * self.size + x;
+                self.modules[index] ^= invert && !self.is_function[index];
+            }
+        }
+    }
+
+    fn penalty_score(&self) -> usize {
+        let size = self.size;
+        let mut result = 0;
+
+        // runs of five or more modules of the same color, and finder-like patterns
+        for transpose in [false, true] {
+            for a in 0..size {
+                let module = |b: usize| {
+                    if transpose {
+                        self.get(a, b)
+                    } else {
+                        self.get(b, a)
+                    }
+                };
+                let mut run_color = false;
+                let mut run_length = 0;
+                let mut history = RunHistory::new(size);
+                for b in 0..size {
+                    if module(b) == run_color {
+                        run_length += 1;
+                        if run_length == 5 {
+                            result += PENALTY_N1;
+                        } else if run_length > 5 {
+                            result += 1;
+                        }
+                    } else {
+                        history.push(run_length);
+                        if !run_color {
+                            result += history.count_patterns() * PENALTY_N3;
+                        }
+                        run_color = module(b);
+                        run_length = 1;
+                    }
+                }
+                result += history.terminate_and_count(run_color, run_length) * PENALTY_N3;
+            }
+        }
+
+        // 2x2 blocks of the same color
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let color = self.get(x, y);
+                if color == self.get(x + 1, y)
+                    && color == self.get(x, y + 1)
+                    && color == self.get(x + 1, y + 1)
+                {
+                    result += PENALTY_N2;
+                }
+            }
+        }
+
+        // balance of dark and light modules
+        let dark = self.modules.iter().filter(|dark| **dark).count();
+        let total = size * size;
+        let k = (dark * 20).abs_diff(total * 10).div_ceil(total) - 1;
+        result + k * PENALTY_N4
+    }
+}
+
+/// The lengths of the last seven runs in a row or column, most recent first, to detect patterns
+/// resembling a finder pattern.
+struct RunHistory {
+    size: usize,
+    runs: [usize; 7],
+}
+
+impl RunHistory {
+    fn new(size: usize) -> Self {
+        RunHistory { size, runs: [0; 7] }
+    }
+
+    fn push(&mut self, mut run_length: usize) {
+        // the light border before the first run counts towards it
+        if self.runs[0] == 0 {
+            run_length += self.size;
+        }
+        self.runs.copy_within(0..6, 1);
+        self.runs[0] = run_length;
+    }
+
+    /// Counts the dark-light-dark-dark-dark-light-dark patterns, in the ratio 1:1:3:1:1, with a
+    /// light run of ratio 4 on either side.
+    fn count_patterns(&self) -> usize {
+        let [before, n, b, c, d, e, after] = self.runs;
+        let core = n > 0 && b == n && c == n * 3 && d == n && e == n;
+        usize::from(core && before >= n * 4 && after >= n)
+            + usize::from(core && after >= n * 4 && before >= n)
+    }
+
+    fn terminate_and_count(&mut self, run_color: bool, mut run_length: usize) -> usize {
+        // close a dark run, then add the light border after the last run
+        if run_color {
+            self.push(run_length);
+            run_length = 0;
+        }
+        self.push(run_length + self.size);
+        self.count_patterns()
+    }
+}
+
+#[derive(Default)]
+struct BitBuffer(Vec<bool>);
+
+impl BitBuffer {
+    fn append(&mut self, value: u32, length: usize) {
+        self.0
+            .extend((0..length).rev().map(|i| (value >> i) & 1 != 0));
+    }
+}
+
+fn char_count_bits(version: usize) -> usize {
+    if version < 10 { 8 } else { 16 }
+}
+
+/// Returns the number of modules available for data and error correction in `version`.
+fn num_raw_data_modules(version: usize) -> usize {
+    let mut result = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let num_align = version / 7 + 2;
+        result -= (25 * num_align - 10) * num_align - 55;
+        if version >= 7 {
+            result -= 36;
+        }
+    }
+    result
+}
+
+fn num_data_codewords(version: usize) -> usize {
+    num_raw_data_modules(version) / 8
+        - ECC_CODEWORDS_PER_BLOCK[version] * NUM_ERROR_CORRECTION_BLOCKS[version]
+}
+
+fn alignment_pattern_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+    let num_align = version / 7 + 2;
+    let step = (version * 8 + num_align * 3 + 5) / (num_align * 4 - 4) * 2;
+    let size = version * 4 + 17;
+    let mut positions: Vec<usize> = (0..num_align - 1).map(|i| size - 7 - i * step).collect();
+    positions.push(6);
+    positions.reverse();
+    positions
+}
+
+/// Returns the 15 format bits for level M and `mask`, protected by a BCH code.
+fn format_bits(mask: u32) -> u32 {
+    let data = (LEVEL_M_FORMAT_BITS << 3) | mask;
+    let mut remainder = data;
+    for _ in 0..10 {
+        remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+    }
+    ((data << 10) | remainder) ^ 0x5412
+}
+
+/// Returns the 18 version bits of `version`, protected by a BCH code.
+fn version_bits(version: usize) -> u32 {
+    let version = version as u32;
+    let mut remainder = version;
+    for _ in 0..12 {
+        remainder = (remainder << 1) ^ ((remainder >> 11) * 0x1F25);
+    }
+    (version << 12) | remainder
+}
+
+/// Splits `data` into blocks, appends the Reed-Solomon error correction codewords of each block
+/// and interleaves the blocks.
+fn add_ecc_and_interleave(version: usize, data: &[u8]) -> Vec<u8> {
+    let num_blocks = NUM_ERROR_CORRECTION_BLOCKS[version];
+    let block_ecc_len = ECC_CODEWORDS_PER_BLOCK[version];
+    let raw_codewords = num_raw_data_modules(version) / 8;
+    let num_short_blocks = num_blocks - raw_codewords % num_blocks;
+    let short_block_len = raw_codewords / num_blocks;
+
+    let divisor = reed_solomon_divisor(block_ecc_len);
+    let mut blocks = Vec::with_capacity(num_blocks);
+    let mut offset = 0;
+    for i in 0..num_blocks {
+        let data_len = short_block_len - block_ecc_len + usize::from(i >= num_short_blocks);
+        let mut block = data[offset..offset + data_len].to_vec();
+        offset += data_len;
+        let ecc = reed_solomon_remainder(&block, &divisor);
+        if i < num_short_blocks {
+            // padding, skipped when interleaving
+            block.push(0);
+        }
+        block.extend(ecc);
+        blocks.push(block);
+    }
+
+    let mut result = Vec::with_capacity(raw_codewords);
+    for i in 0..blocks[0].len() {
+        for (j, block) in blocks.iter().enumerate() {
+            if i != short_block_len - block_ecc_len || j >= num_short_blocks {
+                result.push(block[i]);
+            }
+        }
+    }
+    result
+}
+
+/// Returns the coefficients of the generator polynomial of the given `degree`, highest first and
+/// without the leading 1.
+fn reed_solomon_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0; degree];
+    result[degree - 1] = 1;
+    let mut root = 1;
+    for _ in 0..degree {
+        for j in 0..degree {
+            result[j] = gf_multiply(result[j], root);
+            if j + 1 < degree {
+                result[j] ^= result[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    result
+}
+
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0; divisor.len()];
+    for byte in data {
+        let factor = byte ^ result.remove(0);
+        result.push(0);
+        for (coefficient, term) in result.iter_mut().zip(divisor) {
+            *coefficient ^= gf_multiply(*term, factor);
+        }
+    }
+    result
+}
+
+/// Multiplies in GF(2^8) modulo `x^8 + x^4 + x^3 + x^2 + 1`.
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z: u16 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x11D);
+        z ^= u16::from((y >> i) & 1) * u16::from(x);
+    }
+    z as u8
+}
+
+#[test]
+fn test_qr_encoding() {
+    // error correction of the "HELLO WORLD" 1-M example of the standard
+    let data = [
+        32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236, 17, 236, 17,
+    ];
+    assert_eq!(
+        reed_solomon_remainder(&data, &reed_solomon_divisor(10)),
+        [196, 35, 39, 119, 235, 215, 231, 226, 93, 23]
+    );
+    assert_eq!(format_bits(0), 0b101010000010010);
+    assert_eq!(version_bits(7), 0b000111110010010100);
+    assert_eq!(alignment_pattern_positions(7), [6, 22, 38]);
+
+    // byte capacities at level M
+    for (version, capacity) in [(1, 14), (10, 213), (40, 2331)] {
+        let qr = QrCode::encode(&vec![0xAB; capacity]).unwrap();
+        assert_eq!(qr.version(), version);
+        assert_eq!(qr.size(), version * 4 + 17);
+        assert!(QrCode::encode(&vec![0xAB; capacity + 1]).is_none_or(|qr| qr.version() > version));
+    }
+    assert!(QrCode::encode(&[0; 2332]).is_none());
+
+    let qr = QrCode::encode(b"testing").unwrap();
+    // finder pattern in the top left corner, and the dark module
+    assert!((0..7).all(|i| qr.get(i, 0) && qr.get(0, i)));
+    assert!(!qr.get(7, 0) && qr.get(8, qr.size() - 8));
+    assert!(qr.to_svg().starts_with("<?xml"));
+    assert_eq!(qr.to_terminal_string().lines().count(), (qr.size() + 5) / 2);
+}
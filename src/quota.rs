@@ -0,0 +1,151 @@
+//! This module implements per-label signing quotas for the custodian.
+//!
+//! Beyond the global rate limit of a `CustodianPolicy`, a custodian can cap how many signatures
+//! each application-supplied label, e.g. an account id, receives per period, enforcing rules
+//! such as "at most 5 signatures per account per day". Periods are fixed windows aligned to the
+//! Unix epoch, and usage can be persisted so that it survives restarts.
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::helper::{decode_hex, encode_hex};
+
+/// The number of signatures each label may receive per period.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Quota {
+    pub max_signatures: u64,
+    /// Length of a period, at least one second.
+    pub period: Duration,
+}
+
+/// Signatures issued per label in the current period.
+#[derive(Debug)]
+pub struct QuotaTracker {
+    quota: Quota,
+
+    /// Period index and signature count of each label.
+    usage: BTreeMap<String, (u64, u64)>,
+
+    /// File the usage is persisted to, if any.
+    path: Option<PathBuf>,
+}
+
+impl QuotaTracker {
+    /// Creates a tracker that keeps usage in memory only.
+    pub fn new(quota: Quota) -> Self {
+        QuotaTracker {
+            quota,
+            usage: BTreeMap::new(),
+            path: None,
+        }
+    }
+
+    /// Opens a tracker that persists usage to `path`, loading the usage recorded there if the
+    /// file exists.
+    pub fn open(quota: Quota, path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut usage = BTreeMap::new();
+        match fs::read_to_string(&path) {
+            Ok(contents) => {
+                for line in contents.lines() {
+                    let entry = match line.split(' ').collect::<Vec<_>>()[..] {
+                        [label, period, count] => decode_hex(label)
+                            .and_then(|label| String::from_utf8(label).ok())
+                            .zip(period.parse().ok())
+                            .zip(count.parse().ok()),
+                        _ => None,
+                    };
+                    let ((label, period), count) = entry.ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("malformed quota entry: {line}"),
+                        )
+                    })?;
+                    usage.insert(label, (period, count));
+                }
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => return Err(error),
+        }
+
+        Ok(QuotaTracker {
+            quota,
+            usage,
+            path: Some(path),
+        })
+    }
+
+    pub fn quota(&self) -> Quota {
+        self.quota
+    }
+
+    /// Returns the number of signatures `label` received in the current period.
+    pub fn usage(&self, label: &str) -> u64 {
+        match self.usage.get(label) {
+            Some((period, count)) if *period == self.current_period() => *count,
+            _ => 0,
+        }
+    }
+
+    /// Returns the number of signatures `label` may still receive in the current period.
+    pub fn remaining(&self, label: &str) -> u64 {
+        self.quota.max_signatures.saturating_sub(self.usage(label))
+    }
+
+    /// Records a signature for `label`, persisting the usage if the tracker has a file.
+    pub fn record(&mut self, label: &str) -> io::Result<()> {
+        let period = self.current_period();
+        let count = self.usage(label) + 1;
+        self.usage.insert(label.to_string(), (period, count));
+        self.persist()
+    }
+
+    fn current_period(&self) -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        now.as_secs() / self.quota.period.as_secs().max(1)
+    }
+
+    /// Rewrites the usage file atomically, by writing a temporary file and renaming it.
+    fn persist(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let temporary = path.with_extension("tmp");
+        let mut file = fs::File::create(&temporary)?;
+        for (label, (period, count)) in &self.usage {
+            writeln!(file, "{} {period} {count}", encode_hex(label.as_bytes()))?;
+        }
+        file.sync_data()?;
+        fs::rename(temporary, path)
+    }
+}
+
+#[test]
+fn test_quota_tracker_persists_usage() {
+    let path = std::env::temp_dir().join(format!("quota-{}", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let quota = Quota {
+        max_signatures: 2,
+        period: Duration::from_secs(86400),
+    };
+
+    let mut tracker = QuotaTracker::open(quota, &path).unwrap();
+    tracker.record("alice").unwrap();
+    tracker.record("alice").unwrap();
+    tracker.record("bob").unwrap();
+    assert_eq!(tracker.remaining("alice"), 0);
+    assert_eq!(tracker.remaining("carol"), 2);
+
+    let tracker = QuotaTracker::open(quota, &path).unwrap();
+    assert_eq!(tracker.usage("alice"), 2);
+    assert_eq!(tracker.remaining("bob"), 1);
+
+    fs::remove_file(&path).unwrap();
+}
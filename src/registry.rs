@@ -0,0 +1,304 @@
+//! This module implements a thread-safe registry of signing sessions.
+//!
+//! A server typically handles the messages of many concurrent signing sessions from different
+//! request handlers. `SessionRegistry` is cheap to clone and can be shared across threads; each
+//! session sits behind its own lock, so handlers working on different sessions do not contend.
+//!
+//! A registry created with `SessionRegistry::durable` also writes every session to a `Storage`
+//! after each change, so a coordinator that crashes mid-round resumes its sessions with the
+//! commitments and signature shares it had already received, instead of forcing every signer to
+//! redo round one.
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError, RwLock},
+};
+
+use crate::{
+    ciphersuite::{Ciphersuite, Ed25519Sha512},
+    coordinator::Coordinator,
+    error::FrostError,
+    frost::SessionId,
+    helper::encode_hex,
+    storage::Storage,
+};
+
+type SharedCoordinator<C> = Arc<Mutex<Coordinator<C>>>;
+type SharedStorage<C> = Arc<Mutex<Box<dyn Storage<C>>>>;
+
+/// A thread-safe collection of `Coordinator`s, keyed by their `SessionId`.
+#[derive(Debug)]
+pub struct SessionRegistry<C: Ciphersuite = Ed25519Sha512> {
+    sessions: Arc<RwLock<HashMap<SessionId, SharedCoordinator<C>>>>,
+    /// Where sessions are persisted, if the registry is durable.
+    storage: Option<SharedStorage<C>>,
+}
+
+impl<C: Ciphersuite> Clone for SessionRegistry<C> {
+    fn clone(&self) -> Self {
+        SessionRegistry {
+            sessions: Arc::clone(&self.sessions),
+            storage: self.storage.clone(),
+        }
+    }
+}
+
+impl<C: Ciphersuite> Default for SessionRegistry<C> {
+    fn default() -> Self {
+        SessionRegistry {
+            sessions: Arc::new(RwLock::new(HashMap::new())),
+            storage: None,
+        }
+    }
+}
+
+impl<C: Ciphersuite> SessionRegistry<C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry that persists its sessions to `storage`, and resumes the sessions
+    /// already stored there.
+    pub fn durable(storage: impl Storage<C> + 'static) -> Result<Self, FrostError<C>> {
+        let mut sessions = HashMap::new();
+        for group_id in storage.group_ids().map_err(storage_error)? {
+            for session_id in storage.session_ids(&group_id).map_err(storage_error)? {
+                let Some(state) = storage
+                    .get_session(&group_id, &session_id)
+                    .map_err(storage_error)?
+                else {
+                    continue;
+                };
+                let coordinator = Coordinator::from_bytes(&state).map_err(|_| {
+                    FrostError::Storage(format!(
+                        "malformed state of session {}",
+                        encode_hex(&session_id)
+                    ))
+                })?;
+                sessions.insert(session_id, Arc::new(Mutex::new(coordinator)));
+            }
+        }
+
+        Ok(SessionRegistry {
+            sessions: Arc::new(RwLock::new(sessions)),
+            storage: Some(Arc::new(Mutex::new(Box::new(storage)))),
+        })
+    }
+
+    /// Registers `coordinator` and returns the `SessionId` it can be looked up with.
+    ///
+    /// A session that cannot be stored yet holds nothing worth recovering; it is stored by the
+    /// first `with_session` call instead.
+    pub fn insert(&self, coordinator: Coordinator<C>) -> SessionId {
+        let session_id = coordinator.get_session_id();
+        let _ = self.persist(&coordinator);
+        self.sessions
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(session_id, Arc::new(Mutex::new(coordinator)));
+        session_id
+    }
+
+    /// Runs `f` with exclusive access to the session identified by `session_id`.
+    ///
+    /// Only the session itself is locked while `f` runs, so other sessions can be driven
+    /// concurrently. In a durable registry the session is stored once `f` returns, and a failure
+    /// to store it is returned as `FrostError::Storage`.
+    pub fn with_session<R>(
+        &self,
+        session_id: &SessionId,
+        f: impl FnOnce(&mut Coordinator<C>) -> Result<R, FrostError<C>>,
+    ) -> Result<R, FrostError<C>> {
+        let session = self
+            .sessions
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(session_id)
+            .cloned()
+            .ok_or(FrostError::UnknownSession(*session_id))?;
+
+        let mut coordinator = session.lock().unwrap_or_else(PoisonError::into_inner);
+        let result = f(&mut coordinator);
+        let persisted = self.persist(&coordinator);
+        let result = result?;
+        persisted?;
+        Ok(result)
+    }
+
+    /// Removes the session identified by `session_id`, returning its final state.
+    pub fn remove(&self, session_id: &SessionId) -> Option<Coordinator<C>> {
+        let session = self
+            .sessions
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(session_id)?;
+
+        let coordinator = match Arc::try_unwrap(session) {
+            Ok(coordinator) => coordinator
+                .into_inner()
+                .unwrap_or_else(PoisonError::into_inner),
+            Err(session) => session
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone(),
+        };
+        if let Some(storage) = &self.storage {
+            let _ = storage
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .remove_session(&coordinator.get_group_pk().group_id(), session_id);
+        }
+        Some(coordinator)
+    }
+
+    /// Removes the sessions whose current round is past its deadline, whether they timed out or
+    /// completed, and returns how many were removed.
+    pub fn remove_expired(&self) -> usize {
+        let expired: Vec<SessionId> = self
+            .sessions
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .filter(|(_, session)| {
+                session
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .time_remaining()
+                    .is_zero()
+            })
+            .map(|(session_id, _)| *session_id)
+            .collect();
+        for session_id in &expired {
+            self.remove(session_id);
+        }
+        expired.len()
+    }
+
+    /// Returns the number of registered sessions.
+    pub fn len(&self) -> usize {
+        self.sessions
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn persist(&self, coordinator: &Coordinator<C>) -> Result<(), FrostError<C>> {
+        let Some(storage) = &self.storage else {
+            return Ok(());
+        };
+        storage
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .put_session(
+                &coordinator.get_group_pk().group_id(),
+                coordinator.get_session_id(),
+                &coordinator.to_bytes(),
+            )
+            .map_err(storage_error)
+    }
+}
+
+fn storage_error<C: Ciphersuite>(error: std::io::Error) -> FrostError<C> {
+    FrostError::Storage(error.to_string())
+}
+
+#[test]
+fn test_concurrent_sessions() {
+    use std::{thread, time::Duration};
+
+    use crate::frost::Frost;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<SessionRegistry>();
+
+    let frost: Arc<Frost> = Arc::new(Frost::signature_share(2, 3));
+    let registry = SessionRegistry::new();
+    let session_id = registry.insert(Coordinator::new(
+        frost.group_pk,
+        b"testing",
+        frost.participants().collect(),
+        Duration::from_secs(60),
+    ));
+
+    // each signer's commitment is handled on its own thread
+    let handles: Vec<_> = (0..frost.signers.len())
+        .map(|i| {
+            let (frost, registry) = (Arc::clone(&frost), registry.clone());
+            thread::spawn(move || {
+                let commitment = frost.signers[i].get_commitments();
+                registry.with_session(&session_id, |coordinator| {
+                    coordinator.receive_commitment(commitment)
+                })
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap().unwrap();
+    }
+
+    let signing_package = registry
+        .with_session(&session_id, |coordinator| coordinator.signing_package())
+        .unwrap();
+    assert_eq!(signing_package.session_id, session_id);
+    assert!(registry.remove(&session_id).is_some());
+    assert!(registry.is_empty());
+}
+
+#[test]
+fn test_durable_sessions_survive_restart() {
+    use std::time::Duration;
+
+    use crate::{frost::Frost, storage::FileStorage};
+
+    let directory = std::env::temp_dir().join(format!("sessions-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    let frost: Frost = Frost::signature_share(2, 3);
+    let (mut first, mut second) = (frost.signers[0].clone(), frost.signers[1].clone());
+    first.refresh_nonces();
+    second.refresh_nonces();
+
+    let registry = SessionRegistry::durable(FileStorage::open(&directory).unwrap()).unwrap();
+    let session_id = registry.insert(Coordinator::new(
+        frost.group_pk,
+        b"testing",
+        vec![
+            first.get_commitments().identifier,
+            second.get_commitments().identifier,
+        ],
+        Duration::from_secs(60),
+    ));
+    let signing_package = registry
+        .with_session(&session_id, |coordinator| {
+            coordinator.receive_commitment(first.get_commitments())?;
+            coordinator.receive_commitment(second.get_commitments())?;
+            coordinator.signing_package()
+        })
+        .unwrap();
+    registry
+        .with_session(&session_id, |coordinator| {
+            coordinator.receive_share(first.sign(&signing_package)?)
+        })
+        .unwrap();
+    drop(registry);
+
+    // the restarted coordinator only waits for the outstanding share
+    let registry: SessionRegistry =
+        SessionRegistry::durable(FileStorage::open(&directory).unwrap()).unwrap();
+    let signature = registry
+        .with_session(&session_id, |coordinator| {
+            assert_eq!(coordinator.get_signing_package(), Some(&signing_package));
+            coordinator.receive_share(second.sign(&signing_package)?)?;
+            coordinator.aggregate()
+        })
+        .unwrap();
+    assert!(frost.verify(b"testing", signature));
+
+    registry.remove(&session_id);
+    let registry: SessionRegistry =
+        SessionRegistry::durable(FileStorage::open(&directory).unwrap()).unwrap();
+    assert!(registry.is_empty());
+    std::fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,418 @@
+//! This module implements the verification report of a keygen ceremony.
+//!
+//! After keygen, the dealer publishes a `KeygenReport` binding the group public key, the roster,
+//! the threshold, the verification shares and the VSS commitments `A_k = g^{a_k}` to the
+//! coefficients of the sharing polynomial. Each signer checks its own key package against the
+//! report and acknowledges it with its identity key; once every signer has, the report is a
+//! record any party can later verify to prove what was agreed at setup.
+//!
+//! The VSS commitments are recovered from the verification shares by interpolation in the
+//! exponent, so a report can be produced for any group, whatever dealt its shares.
+use ark_ff::{AdditiveGroup, Field};
+use ark_serialize::SerializationError;
+use ark_std::rand::Rng;
+
+use crate::{
+    ciphersuite::{Ciphersuite, Ed25519Sha512, Element, ScalarField},
+    error::FrostError,
+    frost::{Frost, SignerRole},
+    helper::{Decoder, NonZeroScalar, serialize_scalar},
+    identity::IdentityKey,
+    keys::{GroupPublicKey, KeyPackage, VerificationShare},
+    roster::{MemberRole, Roster},
+    schnorr::SchnorrSignature,
+};
+
+/// Domain separator prefixed to the statement signed by an acknowledgment.
+const REPORT_CONTEXT: &[u8] = b"FROST-KEYGEN-REPORT-v1";
+
+/// The record of a keygen ceremony, see the module documentation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeygenReport<C: Ciphersuite = Ed25519Sha512> {
+    /// The public key of the group.
+    pub group_pk: GroupPublicKey<C>,
+    /// Number of signers required to sign.
+    pub threshold: usize,
+    /// The members of the group and their identity keys.
+    pub roster: Roster<C>,
+    /// The verification share of every signer, ordered by identifier.
+    pub verification_shares: Vec<(NonZeroScalar<C>, VerificationShare<C>)>,
+    /// Commitments `A_0 .. A_{t-1}` to the coefficients of the sharing polynomial.
+    pub vss_commitments: Vec<Element<C>>,
+    /// Signatures of the signers over the report, by their identity keys.
+    pub acknowledgments: Vec<(NonZeroScalar<C>, SchnorrSignature<C>)>,
+}
+
+impl<C: Ciphersuite> KeygenReport<C> {
+    /// Builds the report of the group dealt by `frost`, whose members are listed in `roster`.
+    ///
+    /// Returns an error if the signers of `roster` are not those of `frost` with the same roles,
+    /// or if the shares do not lie on a polynomial of degree `threshold - 1`.
+    pub fn new(frost: &Frost<C>, roster: Roster<C>) -> Result<Self, FrostError<C>> {
+        let mut verification_shares: Vec<(NonZeroScalar<C>, VerificationShare<C>)> = frost
+            .signers()
+            .map(|signer| {
+                (
+                    signer.get_commitments().identifier,
+                    signer.get_verification_share(),
+                )
+            })
+            .collect();
+        verification_shares.sort_by_key(|(identifier, _)| identifier.to_scalar());
+
+        for signer in frost.signers() {
+            let identifier = signer.get_commitments().identifier;
+            let member = roster
+                .get(identifier)
+                .ok_or(FrostError::UnknownParticipant(identifier.to_scalar()))?;
+            if member.role != MemberRole::Signer(signer.role()) {
+                return Err(FrostError::InvalidKeygenReport(format!(
+                    "signer {} has another role in the roster",
+                    identifier.to_scalar()
+                )));
+            }
+        }
+
+        let vss_commitments = interpolate_commitments(&verification_shares, frost.threshold)?;
+        let report = KeygenReport {
+            group_pk: frost.group_pk,
+            threshold: frost.threshold,
+            roster,
+            verification_shares,
+            vss_commitments,
+            acknowledgments: Vec::new(),
+        };
+        report.verify_statement()?;
+        Ok(report)
+    }
+
+    /// Checks `key_package` against the report and, if it matches, acknowledges the report as
+    /// its signer with the identity key `key`.
+    ///
+    /// Returns an error if the group public key, the role or the verification share of the
+    /// package differ from the report.
+    pub fn acknowledge<R: Rng>(
+        &self,
+        key_package: &KeyPackage<C>,
+        key: &IdentityKey<C>,
+        rng: &mut R,
+    ) -> Result<SchnorrSignature<C>, FrostError<C>> {
+        let identifier = key_package.identifier;
+        let mismatch = |what: &str| {
+            FrostError::InvalidKeygenReport(format!(
+                "the {what} of signer {} differs from the report",
+                identifier.to_scalar()
+            ))
+        };
+        if key_package.group_pk != self.group_pk {
+            return Err(mismatch("group public key"));
+        }
+        if self.roster.get(identifier).map(|member| member.role)
+            != Some(MemberRole::Signer(key_package.role))
+        {
+            return Err(mismatch("role"));
+        }
+        if key_package.generator * key_package.secret_share.to_scalar()
+            != key_package.verification_share.to_element()
+            || self.verification_share(identifier) != Some(key_package.verification_share)
+        {
+            return Err(mismatch("verification share"));
+        }
+
+        Ok(key.sign(&self.signed_message(), rng))
+    }
+
+    /// Records the acknowledgment of the signer `identifier`.
+    ///
+    /// Returns an error if the signature does not verify under the signer's identity key.
+    pub fn add_acknowledgment(
+        &mut self,
+        identifier: NonZeroScalar<C>,
+        signature: SchnorrSignature<C>,
+    ) -> Result<(), FrostError<C>> {
+        self.roster
+            .identities()
+            .verify(identifier, &self.signed_message(), &signature)?;
+        self.acknowledgments
+            .retain(|(acknowledged, _)| *acknowledged != identifier);
+        self.acknowledgments.push((identifier, signature));
+        self.acknowledgments
+            .sort_by_key(|(identifier, _)| identifier.to_scalar());
+        Ok(())
+    }
+
+    /// Verifies the report: that the VSS commitments open to the group public key and every
+    /// verification share, and that every signer acknowledged the report.
+    pub fn verify(&self) -> Result<(), FrostError<C>> {
+        self.verify_statement()?;
+
+        let identities = self.roster.identities();
+        for (identifier, _) in &self.verification_shares {
+            let (_, signature) = self
+                .acknowledgments
+                .iter()
+                .find(|(acknowledged, _)| acknowledged == identifier)
+                .ok_or_else(|| {
+                    FrostError::InvalidKeygenReport(format!(
+                        "signer {} has not acknowledged the report",
+                        identifier.to_scalar()
+                    ))
+                })?;
+            identities.verify(*identifier, &self.signed_message(), signature)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the verification share of the signer `identifier`.
+    pub fn verification_share(&self, identifier: NonZeroScalar<C>) -> Option<VerificationShare<C>> {
+        self.verification_shares
+            .iter()
+            .find(|(signer, _)| *signer == identifier)
+            .map(|(_, verification_share)| *verification_share)
+    }
+
+    /// Encodes the report as the statement signed by the acknowledgments, followed by the count
+    /// of acknowledgments and each as `identifier || signature`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.statement();
+        bytes.extend(encode_len(self.acknowledgments.len()));
+        for (identifier, signature) in &self.acknowledgments {
+            bytes.extend(serialize_scalar::<C>(&identifier.to_scalar()));
+            bytes.extend(signature.to_bytes());
+        }
+        bytes
+    }
+
+    /// Parses a report encoded by `to_bytes`. The report is not verified.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let mut decoder = Decoder::new(bytes);
+        let group_pk = GroupPublicKey::new(decoder.element::<C>()?);
+        let threshold = decoder.u32()? as usize;
+        let roster_len = decoder.u32()? as usize;
+        let roster = Roster::from_bytes(decoder.take(roster_len)?)?;
+        let verification_shares = (0..decoder.u32()?)
+            .map(|_| {
+                Ok((
+                    decoder.identifier()?,
+                    VerificationShare::new(decoder.element::<C>()?),
+                ))
+            })
+            .collect::<Result<_, SerializationError>>()?;
+        let vss_commitments = (0..decoder.u32()?)
+            .map(|_| decoder.element::<C>())
+            .collect::<Result<_, _>>()?;
+        let acknowledgments = (0..decoder.u32()?)
+            .map(|_| {
+                let identifier = decoder.identifier()?;
+                let signature = SchnorrSignature::from_bytes(
+                    decoder.take(SchnorrSignature::<C>::encoded_length())?,
+                )?;
+                Ok((identifier, signature))
+            })
+            .collect::<Result<_, SerializationError>>()?;
+        decoder.finish()?;
+
+        Ok(KeygenReport {
+            group_pk,
+            threshold,
+            roster,
+            verification_shares,
+            vss_commitments,
+            acknowledgments,
+        })
+    }
+
+    /// Encodes everything the signers acknowledge: `group_pk || threshold || len(roster) ||
+    /// roster || count || (identifier || Y_i)* || count || A_k*`, with lengths and counts as
+    /// little-endian `u32`s.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a list is longer than `u32::MAX`.
+    fn statement(&self) -> Vec<u8> {
+        let roster = self.roster.to_bytes();
+        let mut bytes = C::serialize_element(&self.group_pk.to_element());
+        bytes.extend(encode_len(self.threshold));
+        bytes.extend(encode_len(roster.len()));
+        bytes.extend(roster);
+        bytes.extend(encode_len(self.verification_shares.len()));
+        for (identifier, verification_share) in &self.verification_shares {
+            bytes.extend(serialize_scalar::<C>(&identifier.to_scalar()));
+            bytes.extend(C::serialize_element(&verification_share.to_element()));
+        }
+        bytes.extend(encode_len(self.vss_commitments.len()));
+        for commitment in &self.vss_commitments {
+            bytes.extend(C::serialize_element(commitment));
+        }
+        bytes
+    }
+
+    fn signed_message(&self) -> Vec<u8> {
+        [REPORT_CONTEXT, &self.statement()].concat()
+    }
+
+    /// Checks that the VSS commitments open to the group public key and to every verification
+    /// share, and that the roster lists exactly the signers of the report.
+    fn verify_statement(&self) -> Result<(), FrostError<C>> {
+        let invalid = |reason: String| Err(FrostError::InvalidKeygenReport(reason));
+        if self.vss_commitments.len() != self.threshold || self.threshold < 2 {
+            return invalid(format!(
+                "{} VSS commitments for a threshold of {}",
+                self.vss_commitments.len(),
+                self.threshold
+            ));
+        }
+        if self.vss_commitments[0] != self.group_pk.to_element() {
+            return invalid("the VSS commitments do not open to the group public key".to_string());
+        }
+        for (identifier, verification_share) in &self.verification_shares {
+            if evaluate_commitments::<C>(&self.vss_commitments, identifier.to_scalar())
+                != verification_share.to_element()
+            {
+                return invalid(format!(
+                    "the verification share of signer {} is not on the committed polynomial",
+                    identifier.to_scalar()
+                ));
+            }
+        }
+
+        let signers: Vec<NonZeroScalar<C>> = self
+            .roster
+            .members()
+            .filter(|member| matches!(member.role, MemberRole::Signer(_)))
+            .map(|member| member.identifier)
+            .collect();
+        let shareholders: Vec<NonZeroScalar<C>> = self
+            .verification_shares
+            .iter()
+            .map(|(identifier, _)| *identifier)
+            .collect();
+        if signers != shareholders {
+            return invalid("the roster does not list the signers of the report".to_string());
+        }
+        let blind_signers = self
+            .roster
+            .members()
+            .filter(|member| member.role == MemberRole::Signer(SignerRole::Blind))
+            .count();
+        if blind_signers >= self.threshold {
+            return Err(FrostError::BlindSignersReachThreshold {
+                blind_signers,
+                threshold: self.threshold,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Recovers the commitments `A_k = g^{a_k}` to the coefficients of the sharing polynomial from
+/// the first `threshold` verification shares, by Lagrange interpolation in the exponent.
+fn interpolate_commitments<C: Ciphersuite>(
+    verification_shares: &[(NonZeroScalar<C>, VerificationShare<C>)],
+    threshold: usize,
+) -> Result<Vec<Element<C>>, FrostError<C>> {
+    if verification_shares.len() < threshold {
+        return Err(FrostError::InsufficientSigners {
+            available: verification_shares.len(),
+            threshold,
+        });
+    }
+    let points = &verification_shares[..threshold];
+
+    let mut commitments = vec![Element::<C>::ZERO; threshold];
+    for (i, (x_i, y_i)) in points.iter().enumerate() {
+        // coefficients of the Lagrange basis polynomial L_i(x), from the constant term up
+        let mut basis = vec![ScalarField::<C>::ONE];
+        let mut denominator = ScalarField::<C>::ONE;
+        for (j, (x_j, _)) in points.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+            let mut product = vec![ScalarField::<C>::ZERO; basis.len() + 1];
+            for (k, coefficient) in basis.iter().enumerate() {
+                product[k + 1] += coefficient;
+                product[k] -= *coefficient * x_j.to_scalar();
+            }
+            basis = product;
+            denominator *= x_i.to_scalar() - x_j.to_scalar();
+        }
+        let inverse = denominator
+            .inverse()
+            .ok_or(FrostError::DuplicateCommitment(x_i.to_scalar()))?;
+        for (commitment, coefficient) in commitments.iter_mut().zip(basis) {
+            *commitment += y_i.to_element() * (coefficient * inverse);
+        }
+    }
+    Ok(commitments)
+}
+
+/// Evaluates the committed polynomial at `x`: `sum_k A_k * x^k`.
+fn evaluate_commitments<C: Ciphersuite>(
+    commitments: &[Element<C>],
+    x: ScalarField<C>,
+) -> Element<C> {
+    let mut power = ScalarField::<C>::ONE;
+    let mut result = Element::<C>::ZERO;
+    for commitment in commitments {
+        result += *commitment * power;
+        power *= x;
+    }
+    result
+}
+
+fn encode_len(len: usize) -> Vec<u8> {
+    u32::try_from(len)
+        .expect("list too long")
+        .to_le_bytes()
+        .to_vec()
+}
+
+#[test]
+fn test_keygen_report() {
+    use crate::{helper::secure_rng, roster::Member};
+
+    let mut rng = secure_rng();
+    let frost: Frost = Frost::signature_share(2, 3);
+    let mut roster = Roster::new(frost.generator);
+    let mut identity_keys = Vec::new();
+    for signer in frost.signers() {
+        let identity_key = IdentityKey::generate(frost.generator, &mut rng);
+        roster
+            .add(Member {
+                identifier: signer.get_commitments().identifier,
+                identity_key: identity_key.public_key(),
+                role: MemberRole::Signer(signer.role()),
+                endpoint: String::new(),
+            })
+            .unwrap();
+        identity_keys.push(identity_key);
+    }
+
+    let mut report = KeygenReport::new(&frost, roster).unwrap();
+    assert_eq!(report.vss_commitments.len(), 2);
+    for (identifier, identity_key) in frost.participants().zip(&identity_keys) {
+        let key_package = frost.key_package(identifier).unwrap();
+        let signature = report
+            .acknowledge(&key_package, identity_key, &mut rng)
+            .unwrap();
+        // every signer must acknowledge before the report verifies
+        assert!(report.verify().is_err());
+        report.add_acknowledgment(identifier, signature).unwrap();
+    }
+    report.verify().unwrap();
+
+    let decoded = KeygenReport::from_bytes(&report.to_bytes()).unwrap();
+    assert_eq!(decoded, report);
+
+    // a signer refuses a report with a substituted verification share
+    let first = frost.participants().next().unwrap();
+    let mut tampered = report.clone();
+    tampered.verification_shares[0].1 = VerificationShare::new(frost.generator);
+    assert!(tampered.verify().is_err());
+    let key_package = frost.key_package(first).unwrap();
+    assert!(
+        tampered
+            .acknowledge(&key_package, &identity_keys[0], &mut rng)
+            .is_err()
+    );
+}
@@ -0,0 +1,479 @@
+//! This module implements the rosters of a group.
+//!
+//! The `Roster` is the single source of truth on the members of a group: the identity key, the
+//! role and the endpoint of every signer and coordinator. It is updated as members join, leave,
+//! move or rotate their identity keys, and hands the coordinator the signers of the group and the
+//! `IdentityRoster` to authenticate their messages with. `Roster::select` picks the signers of a
+//! session with a `SelectionStrategy`, and `Roster::select_live` skips signers that do not answer
+//! a ping.
+//!
+//! A group can include blind custodians run by several independent organizations, each with its
+//! own `CustodianPolicy`. The coordinator picks the participants of each session with
+//! `CustodianRoster::select`, which includes the first available custodians in order of
+//! preference, and marks custodians that time out as unavailable so that a retry picks another
+//! one.
+use std::collections::BTreeMap;
+
+use ark_serialize::SerializationError;
+
+use crate::{
+    ciphersuite::{Ciphersuite, Ed25519Sha512, Element, ScalarField},
+    custodian::CustodianPolicy,
+    error::FrostError,
+    frost::{Frost, SignerRole},
+    helper::{Decoder, NonZeroScalar, serialize_scalar},
+    identity::IdentityRoster,
+    selection::{Candidate, SelectionStrategy, select_live},
+};
+
+/// The role of a member of a group.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MemberRole {
+    /// A signer holding a key share.
+    Signer(SignerRole),
+    /// A coordinator, which holds no key share.
+    Coordinator,
+}
+
+/// A member of a group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Member<C: Ciphersuite = Ed25519Sha512> {
+    /// Identifier of the member.
+    pub identifier: NonZeroScalar<C>,
+    /// The member's public identity key.
+    pub identity_key: Element<C>,
+    /// The member's role.
+    pub role: MemberRole,
+    /// Where the member can be reached, e.g. `host:port`.
+    pub endpoint: String,
+}
+
+/// The members of a group, ordered by identifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Roster<C: Ciphersuite = Ed25519Sha512> {
+    /// The generator identity keys are over.
+    generator: Element<C>,
+    members: BTreeMap<ScalarField<C>, Member<C>>,
+}
+
+impl<C: Ciphersuite> Roster<C> {
+    pub fn new(generator: Element<C>) -> Self {
+        Roster {
+            generator,
+            members: BTreeMap::new(),
+        }
+    }
+
+    /// Adds `member` to the group.
+    ///
+    /// Returns an error if a member with the same identifier is already listed.
+    pub fn add(&mut self, member: Member<C>) -> Result<(), FrostError<C>> {
+        let identifier = member.identifier.to_scalar();
+        if self.members.contains_key(&identifier) {
+            return Err(FrostError::ParticipantExists(identifier));
+        }
+        self.members.insert(identifier, member);
+        Ok(())
+    }
+
+    /// Removes the member with the given `identifier` and returns it.
+    ///
+    /// Returns an error if there is no such member.
+    pub fn remove(&mut self, identifier: NonZeroScalar<C>) -> Result<Member<C>, FrostError<C>> {
+        self.members
+            .remove(&identifier.to_scalar())
+            .ok_or(FrostError::UnknownParticipant(identifier.to_scalar()))
+    }
+
+    /// Moves the member with the given `identifier` to `endpoint`.
+    ///
+    /// Returns an error if there is no such member.
+    pub fn set_endpoint(
+        &mut self,
+        identifier: NonZeroScalar<C>,
+        endpoint: &str,
+    ) -> Result<(), FrostError<C>> {
+        self.get_mut(identifier)?.endpoint = endpoint.to_string();
+        Ok(())
+    }
+
+    /// Replaces the identity key of the member with the given `identifier`, e.g. after it was
+    /// rotated.
+    ///
+    /// Returns an error if there is no such member.
+    pub fn set_identity_key(
+        &mut self,
+        identifier: NonZeroScalar<C>,
+        identity_key: Element<C>,
+    ) -> Result<(), FrostError<C>> {
+        self.get_mut(identifier)?.identity_key = identity_key;
+        Ok(())
+    }
+
+    /// The generator identity keys are over, which is also the generator of the group.
+    pub fn generator(&self) -> Element<C> {
+        self.generator
+    }
+
+    pub fn get(&self, identifier: NonZeroScalar<C>) -> Option<&Member<C>> {
+        self.members.get(&identifier.to_scalar())
+    }
+
+    pub fn members(&self) -> impl Iterator<Item = &Member<C>> {
+        self.members.values()
+    }
+
+    /// Returns the identifiers of the signers of the group, i.e. the participants a coordinator
+    /// can select from.
+    pub fn signers(&self) -> impl Iterator<Item = NonZeroScalar<C>> + '_ {
+        self.members()
+            .filter(|member| matches!(member.role, MemberRole::Signer(_)))
+            .map(|member| member.identifier)
+    }
+
+    /// Returns the signers of the group as candidates for a `SelectionStrategy`.
+    pub fn candidates(&self) -> Vec<Candidate<C>> {
+        self.members()
+            .filter_map(|member| match member.role {
+                MemberRole::Signer(role) => Some(Candidate {
+                    identifier: member.identifier,
+                    role,
+                }),
+                MemberRole::Coordinator => None,
+            })
+            .collect()
+    }
+
+    /// Selects `threshold` of the signers of the group for a session with `strategy`.
+    ///
+    /// Returns an error if the group has fewer signers.
+    pub fn select(
+        &self,
+        strategy: &mut dyn SelectionStrategy<C>,
+        threshold: usize,
+    ) -> Result<Vec<NonZeroScalar<C>>, FrostError<C>> {
+        strategy.select(&self.candidates(), threshold)
+    }
+
+    /// Like `select`, but only returns signers that respond to `ping`, e.g. `ping_endpoint` on
+    /// their endpoint. Unresponsive signers are replaced from the rest of the group, see
+    /// `select_live`.
+    pub fn select_live(
+        &self,
+        strategy: &mut dyn SelectionStrategy<C>,
+        threshold: usize,
+        mut ping: impl FnMut(&Member<C>) -> bool,
+    ) -> Result<Vec<NonZeroScalar<C>>, FrostError<C>> {
+        select_live(strategy, &self.candidates(), threshold, |identifier| {
+            self.get(identifier).is_some_and(&mut ping)
+        })
+    }
+
+    /// Returns the identity keys of all members, to authenticate their messages with.
+    pub fn identities(&self) -> IdentityRoster<C> {
+        let mut identities = IdentityRoster::new(self.generator);
+        for member in self.members() {
+            identities
+                .insert(member.identifier, member.identity_key)
+                .expect("members have distinct identifiers");
+        }
+        identities
+    }
+
+    /// Encodes the roster as `generator || count || members`, where `count` is a little-endian
+    /// `u32` and each member is encoded as `identifier || identity_key || role || len(endpoint) ||
+    /// endpoint`. The role is a single byte: 0 for a regular signer, 1 for a blind signer and 2
+    /// for a coordinator.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than `u32::MAX` members or an endpoint is longer than that.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = C::serialize_element(&self.generator);
+        let count = u32::try_from(self.members.len()).expect("too many members");
+        bytes.extend_from_slice(&count.to_le_bytes());
+        for member in self.members() {
+            bytes.extend(serialize_scalar::<C>(&member.identifier.to_scalar()));
+            bytes.extend(C::serialize_element(&member.identity_key));
+            bytes.push(match member.role {
+                MemberRole::Signer(SignerRole::Regular) => 0,
+                MemberRole::Signer(SignerRole::Blind) => 1,
+                MemberRole::Coordinator => 2,
+            });
+            let endpoint_len = u32::try_from(member.endpoint.len()).expect("endpoint too long");
+            bytes.extend_from_slice(&endpoint_len.to_le_bytes());
+            bytes.extend_from_slice(member.endpoint.as_bytes());
+        }
+        bytes
+    }
+
+    /// Parses a roster encoded by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let mut decoder = Decoder::new(bytes);
+        let mut roster = Roster::new(decoder.element::<C>()?);
+        for _ in 0..decoder.u32()? {
+            let identifier = decoder.identifier()?;
+            let identity_key = decoder.element::<C>()?;
+            let role = match decoder.take(1)? {
+                [0] => MemberRole::Signer(SignerRole::Regular),
+                [1] => MemberRole::Signer(SignerRole::Blind),
+                [2] => MemberRole::Coordinator,
+                _ => return Err(SerializationError::InvalidData),
+            };
+            let endpoint_len = decoder.u32()? as usize;
+            let endpoint = String::from_utf8(decoder.take(endpoint_len)?.to_vec())
+                .map_err(|_| SerializationError::InvalidData)?;
+            roster
+                .add(Member {
+                    identifier,
+                    identity_key,
+                    role,
+                    endpoint,
+                })
+                .map_err(|_| SerializationError::InvalidData)?;
+        }
+        decoder.finish()?;
+
+        Ok(roster)
+    }
+
+    fn get_mut(&mut self, identifier: NonZeroScalar<C>) -> Result<&mut Member<C>, FrostError<C>> {
+        self.members
+            .get_mut(&identifier.to_scalar())
+            .ok_or(FrostError::UnknownParticipant(identifier.to_scalar()))
+    }
+}
+
+/// A blind custodian of the group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustodianEntry<C: Ciphersuite = Ed25519Sha512> {
+    /// Identifier of the custodian's signer.
+    pub identifier: NonZeroScalar<C>,
+    /// The organization running the custodian.
+    pub organization: String,
+    /// The policy the custodian enforces.
+    pub policy: CustodianPolicy,
+    /// Whether the custodian is currently considered reachable.
+    pub available: bool,
+}
+
+/// The blind custodians of a group, in order of preference.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustodianRoster<C: Ciphersuite = Ed25519Sha512> {
+    custodians: Vec<CustodianEntry<C>>,
+}
+
+impl<C: Ciphersuite> CustodianRoster<C> {
+    pub fn new() -> Self {
+        CustodianRoster {
+            custodians: Vec::new(),
+        }
+    }
+
+    /// Adds the blind signer of `frost` with the given `identifier` as an available custodian run
+    /// by `organization`, with the lowest preference so far.
+    ///
+    /// Returns an error if the identifier does not belong to a blind signer, or is already listed.
+    pub fn add(
+        &mut self,
+        frost: &Frost<C>,
+        identifier: NonZeroScalar<C>,
+        organization: &str,
+        policy: CustodianPolicy,
+    ) -> Result<(), FrostError<C>> {
+        if !frost
+            .signers()
+            .any(|signer| signer.get_identifier() == identifier.to_scalar() && signer.is_blind())
+        {
+            return Err(FrostError::UnknownParticipant(identifier.to_scalar()));
+        }
+        if self.get(identifier).is_some() {
+            return Err(FrostError::ParticipantExists(identifier.to_scalar()));
+        }
+
+        self.custodians.push(CustodianEntry {
+            identifier,
+            organization: organization.to_string(),
+            policy,
+            available: true,
+        });
+        Ok(())
+    }
+
+    pub fn get(&self, identifier: NonZeroScalar<C>) -> Option<&CustodianEntry<C>> {
+        self.custodians
+            .iter()
+            .find(|custodian| custodian.identifier == identifier)
+    }
+
+    pub fn custodians(&self) -> impl Iterator<Item = &CustodianEntry<C>> {
+        self.custodians.iter()
+    }
+
+    /// Marks the custodian with the given `identifier` as available or unavailable. Identifiers
+    /// that are not custodians are ignored.
+    pub fn set_available(&mut self, identifier: NonZeroScalar<C>, available: bool) {
+        if let Some(custodian) = self
+            .custodians
+            .iter_mut()
+            .find(|custodian| custodian.identifier == identifier)
+        {
+            custodian.available = available;
+        }
+    }
+
+    /// Marks the custodians among the participants missing from a timed out session as
+    /// unavailable, see `FrostError::Timeout`.
+    pub fn record_timeout(&mut self, missing: &[NonZeroScalar<C>]) {
+        for identifier in missing {
+            self.set_available(*identifier, false);
+        }
+    }
+
+    /// Returns the participants of a session: the `regular` signers together with the first
+    /// `custodians` available custodians.
+    ///
+    /// Returns an error if fewer custodians are available.
+    pub fn select(
+        &self,
+        regular: &[NonZeroScalar<C>],
+        custodians: usize,
+    ) -> Result<Vec<NonZeroScalar<C>>, FrostError<C>> {
+        let available: Vec<NonZeroScalar<C>> = self
+            .custodians
+            .iter()
+            .filter(|custodian| custodian.available)
+            .map(|custodian| custodian.identifier)
+            .take(custodians)
+            .collect();
+        if available.len() < custodians {
+            return Err(FrostError::CustodiansUnavailable {
+                available: available.len(),
+                required: custodians,
+            });
+        }
+
+        Ok([regular, &available].concat())
+    }
+}
+
+impl<C: Ciphersuite> Default for CustodianRoster<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn test_roster_selects_available_custodian() {
+    use std::time::Duration;
+
+    let roles = [
+        SignerRole::Regular,
+        SignerRole::Regular,
+        SignerRole::Blind,
+        SignerRole::Blind,
+    ];
+    let frost: Frost = Frost::signature_share_with_roles(3, &roles);
+    let participants: Vec<_> = frost.participants().collect();
+    let policy = CustodianPolicy {
+        max_signatures: 10,
+        window: Duration::from_secs(60),
+        max_pending: 10,
+        pending_ttl: Duration::from_secs(60),
+        digest_only: false,
+    };
+
+    let mut roster = CustodianRoster::new();
+    roster
+        .add(&frost, participants[2], "first", policy)
+        .unwrap();
+    roster
+        .add(&frost, participants[3], "second", policy)
+        .unwrap();
+    assert!(
+        roster
+            .add(&frost, participants[0], "regular", policy)
+            .is_err()
+    );
+
+    let regular = &participants[..2];
+    assert_eq!(
+        roster.select(regular, 1).unwrap(),
+        [participants[0], participants[1], participants[2]]
+    );
+
+    // the second custodian takes over when the first one times out
+    roster.record_timeout(&[participants[2]]);
+    assert_eq!(
+        roster.select(regular, 1).unwrap(),
+        [participants[0], participants[1], participants[3]]
+    );
+
+    roster.set_available(participants[3], false);
+    assert_eq!(
+        roster.select(regular, 1),
+        Err(FrostError::CustodiansUnavailable {
+            available: 0,
+            required: 1
+        })
+    );
+}
+
+#[test]
+fn test_roster_membership() {
+    use std::time::Duration;
+
+    use crate::{coordinator::Coordinator, identity::IdentityKey};
+
+    let frost: Frost = Frost::signature_share(2, 3);
+    let mut rng = ark_std::test_rng();
+    let mut roster = Roster::new(frost.generator);
+    for signer in frost.signers() {
+        roster
+            .add(Member {
+                identifier: signer.get_commitments().identifier,
+                identity_key: IdentityKey::<Ed25519Sha512>::generate(frost.generator, &mut rng)
+                    .public_key(),
+                role: MemberRole::Signer(signer.role()),
+                endpoint: "localhost:7000".to_string(),
+            })
+            .unwrap();
+    }
+    let coordinator = NonZeroScalar::new(ark_ed25519::Fr::from(100u64)).unwrap();
+    let coordinator_key: IdentityKey = IdentityKey::generate(frost.generator, &mut rng);
+    let member = Member {
+        identifier: coordinator,
+        identity_key: coordinator_key.public_key(),
+        role: MemberRole::Coordinator,
+        endpoint: "localhost:8000".to_string(),
+    };
+    roster.add(member.clone()).unwrap();
+    assert_eq!(
+        roster.add(member),
+        Err(FrostError::ParticipantExists(coordinator.to_scalar()))
+    );
+
+    let signer = frost.participants().next().unwrap();
+    roster.set_endpoint(signer, "localhost:7001").unwrap();
+    roster
+        .set_identity_key(coordinator, frost.generator)
+        .unwrap();
+    let roster = Roster::from_bytes(&roster.to_bytes()).unwrap();
+    assert_eq!(roster.get(signer).unwrap().endpoint, "localhost:7001");
+    assert_eq!(roster.identities().get(coordinator), Some(frost.generator));
+
+    // the coordinator selects among the signers only
+    let participants: Vec<_> = roster.signers().collect();
+    assert_eq!(participants, frost.participants().collect::<Vec<_>>());
+    let selected = roster
+        .select(&mut crate::selection::PreferNonBlind, frost.threshold)
+        .unwrap();
+    assert_eq!(selected, participants[..2]);
+    let session = Coordinator::new(
+        frost.group_pk,
+        b"testing",
+        participants,
+        Duration::from_secs(1),
+    );
+    assert_eq!(session.missing().len(), 3);
+}
@@ -0,0 +1,114 @@
+//! This module implements group key rotation.
+//!
+//! A fresh group key is dealt to the current roster of signers, and the outgoing group signs a
+//! handover statement endorsing the incoming group public key. Relying parties that trust the old
+//! key can then verify the handover and move their trust to the new key.
+#![allow(non_snake_case)]
+
+use crate::{
+    ciphersuite::{Ciphersuite, Ed25519Sha512, Element},
+    frost::{Frost, FrostSigner},
+    helper::{compute_challenge, secure_rng},
+    keys::GroupPublicKey,
+    schnorr::SchnorrSignature,
+};
+
+/// Domain separator prefixed to the message signed during a handover.
+const HANDOVER_CONTEXT: &[u8] = b"FROST-KEY-ROTATION-v1";
+
+/// A statement, signed by the outgoing group key, that endorses the incoming group key.
+#[derive(Debug, Copy, Clone)]
+pub struct HandoverStatement<C: Ciphersuite = Ed25519Sha512> {
+    pub old_group_pk: GroupPublicKey<C>,
+    pub new_group_pk: GroupPublicKey<C>,
+    pub signature: SchnorrSignature<C>,
+}
+
+impl<C: Ciphersuite> HandoverStatement<C> {
+    /// Returns the message that the outgoing group signs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if serialization fails.
+    pub fn message(old_group_pk: GroupPublicKey<C>, new_group_pk: GroupPublicKey<C>) -> Vec<u8> {
+        [
+            HANDOVER_CONTEXT.to_vec(),
+            C::serialize_element(&old_group_pk.to_element()),
+            C::serialize_element(&new_group_pk.to_element()),
+        ]
+        .concat()
+    }
+
+    /// Verifies that the handover was signed by `old_group_pk` over the given `generator`.
+    pub fn verify(&self, generator: Element<C>) -> bool {
+        let message = Self::message(self.old_group_pk, self.new_group_pk);
+        let old_group_pk = self.old_group_pk.to_element();
+        let challenge = compute_challenge::<C>(self.signature.R, old_group_pk, &message);
+
+        generator * self.signature.s == self.signature.R + old_group_pk * challenge.to_scalar()
+    }
+}
+
+/// Rotates the group key of `current`.
+///
+/// A fresh key is dealt to the same signers, keeping their roles, with the same threshold and
+/// generator. The outgoing group signs a `HandoverStatement` over the new group public key, and
+/// the outgoing `Frost` instance is moved into `archive`.
+pub fn rotate_group_key<C: Ciphersuite>(
+    mut current: Frost<C>,
+    archive: &mut Vec<Frost<C>>,
+) -> (Frost<C>, HandoverStatement<C>) {
+    let mut rng = secure_rng();
+    let roles: Vec<_> = current.signers().map(FrostSigner::role).collect();
+    let next =
+        Frost::signature_share_with_rng(current.threshold, &roles, current.generator, &mut rng);
+
+    let message = HandoverStatement::<C>::message(current.group_pk, next.group_pk);
+    let signature = sign_with_group(&mut current, &message);
+
+    let handover = HandoverStatement {
+        old_group_pk: current.group_pk,
+        new_group_pk: next.group_pk,
+        signature,
+    };
+    archive.push(current);
+
+    (next, handover)
+}
+
+/// Runs the two FROST rounds among all signers of `group` over `message`.
+fn sign_with_group<C: Ciphersuite>(group: &mut Frost<C>, message: &[u8]) -> SchnorrSignature<C> {
+    let signing_package = group.signing_package(message);
+    let (R, _) = signing_package
+        .group_commitment_and_challenge(group.group_pk)
+        .expect("signing package is built from the group's own commitments");
+
+    let sig_shares: Vec<_> = group
+        .signers_mut()
+        .map(|signer| {
+            signer
+                .sign(&signing_package)
+                .expect("signing package is built from the group's own commitments")
+        })
+        .collect();
+
+    SchnorrSignature {
+        R,
+        s: group.signature_aggregate(sig_shares),
+    }
+}
+
+#[test]
+fn test_rotate_group_key() {
+    let current: Frost = Frost::signature_share(3, 5);
+    let old_group_pk = current.group_pk;
+    let mut archive = Vec::new();
+
+    let (next, handover) = rotate_group_key(current, &mut archive);
+
+    assert_ne!(next.group_pk, old_group_pk);
+    assert_eq!(handover.old_group_pk, old_group_pk);
+    assert_eq!(handover.new_group_pk, next.group_pk);
+    assert!(handover.verify(next.generator));
+    assert_eq!(archive.len(), 1);
+}
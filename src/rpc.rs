@@ -0,0 +1,550 @@
+//! This module exposes the coordinator and signer roles over JSON-RPC 2.0.
+//!
+//! Wallet backends that already speak JSON-RPC can drive signing sessions without a custom
+//! framing. Each request and response is a single line of JSON; batches are supported, and
+//! notifications (requests without an `id`) get no response. Byte strings are hex encoded, in
+//! the encodings of the types' `to_bytes`.
+//!
+//! The coordinator methods are:
+//!
+//! - `createSession {message, participants}`, returning `{sessionId}`.
+//! - `submitCommitment {sessionId, commitment}`, returning `{signingPackage}` once every
+//!   participant has committed, and `null` before.
+//! - `getSigningPackage {sessionId}`, returning `{signingPackage}`, `null` until round one ends.
+//! - `submitShare {sessionId, share}`, returning `{signature}` once every participant has sent
+//!   its share, and `null` before.
+//! - `getSignature {sessionId}`, returning `{signature}`, `null` until the session completes.
+//!
+//! A session is dropped once its current round is past its deadline, and `createSession` is
+//! refused while too many sessions are open.
+//!
+//! The signer methods are `commit {}`, returning `{commitment}` with fresh nonces, and
+//! `sign {signingPackage}`, returning `{share}`. The signing package must be sent
+//! `Authenticated` by one of the identity keys the signer accepts requests from, and each
+//! commitment is signed with at most once.
+//!
+//! Requests longer than `MAX_REQUEST_LEN` bytes close the connection.
+//!
+//! Reference: https://www.jsonrpc.org/specification
+use std::{
+    io::{self, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    str::FromStr,
+    sync::{Arc, Mutex, PoisonError},
+    thread,
+    time::Duration,
+};
+
+use ark_serialize::SerializationError;
+
+use crate::{
+    ciphersuite::{Ciphersuite, Ed25519Sha512},
+    coordinator::Coordinator,
+    error::FrostError,
+    frost::{FrostSigner, SessionId, SignatureShare, SigningPackage},
+    helper::{NonZeroScalar, decode_hex, encode_hex, read_bounded_line},
+    identity::{Authenticated, IdentityRoster},
+    json::{Json, JsonError},
+    keys::{GroupPublicKey, KeyPackage},
+    nonces::SigningCommitments,
+    registry::SessionRegistry,
+};
+
+/// Maximum length of a request line, in bytes.
+pub const MAX_REQUEST_LEN: usize = 1 << 20;
+
+/// Default maximum number of sessions an `RpcCoordinator` keeps open at once.
+pub const MAX_SESSIONS: usize = 1024;
+
+/// The request is not valid JSON.
+pub const PARSE_ERROR: i64 = -32700;
+/// The request is not a valid JSON-RPC request object.
+pub const INVALID_REQUEST: i64 = -32600;
+/// The method does not exist.
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// The parameters of the method are missing or malformed.
+pub const INVALID_PARAMS: i64 = -32602;
+/// The protocol rejected the request, e.g. a commitment for another session.
+pub const PROTOCOL_ERROR: i64 = -32000;
+
+/// A service answering JSON-RPC 2.0 requests.
+pub trait JsonRpc {
+    /// Handles a request, or a batch of requests, and returns the response, or `None` if the
+    /// request consists only of notifications.
+    fn handle(&mut self, request: &str) -> Option<String>;
+}
+
+/// An error object of a JSON-RPC response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl ToString) -> Self {
+        RpcError {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<JsonError> for RpcError {
+    fn from(error: JsonError) -> Self {
+        RpcError::new(INVALID_PARAMS, error)
+    }
+}
+
+impl<C: Ciphersuite> From<FrostError<C>> for RpcError {
+    fn from(error: FrostError<C>) -> Self {
+        RpcError::new(PROTOCOL_ERROR, error)
+    }
+}
+
+/// The coordinator role, running sessions among the signers of a group.
+#[derive(Debug, Clone)]
+pub struct RpcCoordinator<C: Ciphersuite = Ed25519Sha512> {
+    group_pk: GroupPublicKey<C>,
+    round_timeout: Duration,
+    max_sessions: usize,
+    sessions: SessionRegistry<C>,
+}
+
+impl<C: Ciphersuite> RpcCoordinator<C> {
+    /// Creates a coordinator for the group with public key `group_pk`, giving each round of its
+    /// sessions `round_timeout` to complete and keeping at most `MAX_SESSIONS` open.
+    pub fn new(group_pk: GroupPublicKey<C>, round_timeout: Duration) -> Self {
+        RpcCoordinator {
+            group_pk,
+            round_timeout,
+            max_sessions: MAX_SESSIONS,
+            sessions: SessionRegistry::new(),
+        }
+    }
+
+    /// Keeps at most `max_sessions` sessions open at once.
+    pub fn with_max_sessions(mut self, max_sessions: usize) -> Self {
+        self.max_sessions = max_sessions;
+        self
+    }
+
+    /// Returns the registry of the sessions created over RPC.
+    pub fn sessions(&self) -> &SessionRegistry<C> {
+        &self.sessions
+    }
+
+    fn call(&self, method: &str, params: &Json) -> Result<Json, RpcError> {
+        match method {
+            "createSession" => {
+                let message = params.field("message")?.bytes()?;
+                let participants = params
+                    .field("participants")?
+                    .array()?
+                    .iter()
+                    .map(|identifier| parse::<NonZeroScalar<C>>(identifier.string()?))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.sessions.remove_expired();
+                if self.sessions.len() >= self.max_sessions {
+                    return Err(RpcError::new(PROTOCOL_ERROR, "too many open sessions"));
+                }
+                let session_id = self.sessions.insert(Coordinator::new(
+                    self.group_pk,
+                    &message,
+                    participants,
+                    self.round_timeout,
+                ));
+                Ok(Json::object([("sessionId", hex(&session_id))]))
+            }
+            "submitCommitment" => {
+                let commitment = decode(params, "commitment", SigningCommitments::from_bytes)?;
+                let signing_package =
+                    self.sessions
+                        .with_session(&session_id(params)?, |coordinator| {
+                            coordinator.receive_commitment(commitment)?;
+                            if coordinator.missing().is_empty() {
+                                return coordinator.signing_package().map(Some);
+                            }
+                            Ok(None)
+                        })?;
+                Ok(Json::object([(
+                    "signingPackage",
+                    signing_package.map_or(Json::Null, |package| hex(&package.to_bytes())),
+                )]))
+            }
+            "getSigningPackage" => {
+                let signing_package = self
+                    .sessions
+                    .with_session(&session_id(params)?, |coordinator| {
+                        Ok(coordinator.get_signing_package().cloned())
+                    })?;
+                Ok(Json::object([(
+                    "signingPackage",
+                    signing_package.map_or(Json::Null, |package| hex(&package.to_bytes())),
+                )]))
+            }
+            "submitShare" => {
+                let share = decode(params, "share", SignatureShare::from_bytes)?;
+                let signature =
+                    self.sessions
+                        .with_session(&session_id(params)?, |coordinator| {
+                            coordinator.receive_share(share)?;
+                            if coordinator.missing().is_empty() {
+                                return coordinator.aggregate().map(Some);
+                            }
+                            Ok(None)
+                        })?;
+                Ok(Json::object([(
+                    "signature",
+                    signature.map_or(Json::Null, |signature| hex(&signature.to_bytes())),
+                )]))
+            }
+            "getSignature" => {
+                let signature = self
+                    .sessions
+                    .with_session(&session_id(params)?, |coordinator| {
+                        Ok(coordinator.get_signature())
+                    })?;
+                Ok(Json::object([(
+                    "signature",
+                    signature.map_or(Json::Null, |signature| hex(&signature.to_bytes())),
+                )]))
+            }
+            _ => Err(RpcError::new(METHOD_NOT_FOUND, "method not found")),
+        }
+    }
+}
+
+impl<C: Ciphersuite> JsonRpc for RpcCoordinator<C> {
+    fn handle(&mut self, request: &str) -> Option<String> {
+        dispatch(request, |method, params| self.call(method, params))
+    }
+}
+
+/// The signer role, holding the key material of a single signer.
+#[derive(Debug)]
+pub struct RpcSigner<C: Ciphersuite = Ed25519Sha512> {
+    key_package: KeyPackage<C>,
+    /// The identity keys of the coordinators allowed to request signature shares.
+    coordinators: IdentityRoster<C>,
+    /// The signer holding the nonces of the last commitment, until it signs.
+    pending: Option<FrostSigner<C>>,
+}
+
+impl<C: Ciphersuite> RpcSigner<C> {
+    /// Creates a signer that only signs packages `Authenticated` by an identity key in
+    /// `coordinators`.
+    pub fn new(key_package: KeyPackage<C>, coordinators: IdentityRoster<C>) -> Self {
+        RpcSigner {
+            key_package,
+            coordinators,
+            pending: None,
+        }
+    }
+
+    fn call(&mut self, method: &str, params: &Json) -> Result<Json, RpcError> {
+        match method {
+            "commit" => {
+                let signer = FrostSigner::from_key_package(&self.key_package);
+                let commitment = signer.get_commitments();
+                self.pending = Some(signer);
+                Ok(Json::object([("commitment", hex(&commitment.to_bytes()))]))
+            }
+            "sign" => {
+                let signing_package = decode(
+                    params,
+                    "signingPackage",
+                    Authenticated::<SigningPackage<C>, C>::from_bytes,
+                )?
+                .verify(&self.coordinators)?;
+                let mut signer = self
+                    .pending
+                    .take()
+                    .ok_or_else(|| RpcError::new(PROTOCOL_ERROR, "no outstanding commitment"))?;
+                let share = signer.sign(&signing_package)?;
+                Ok(Json::object([("share", hex(&share.to_bytes()))]))
+            }
+            _ => Err(RpcError::new(METHOD_NOT_FOUND, "method not found")),
+        }
+    }
+}
+
+impl<C: Ciphersuite> JsonRpc for RpcSigner<C> {
+    fn handle(&mut self, request: &str) -> Option<String> {
+        dispatch(request, |method, params| self.call(method, params))
+    }
+}
+
+/// Serves `service` on `listener`, one request per line, handling each connection on its own
+/// thread.
+///
+/// Only returns if accepting a connection fails.
+pub fn serve<S: JsonRpc + Send + 'static>(service: S, listener: TcpListener) -> io::Result<()> {
+    let service = Arc::new(Mutex::new(service));
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let service = Arc::clone(&service);
+        thread::spawn(move || handle_connection(&service, stream));
+    }
+
+    Ok(())
+}
+
+fn handle_connection<S: JsonRpc>(service: &Mutex<S>, stream: TcpStream) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let mut request = String::new();
+    while read_bounded_line(&mut reader, MAX_REQUEST_LEN, &mut request)? > 0 {
+        let response = service
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .handle(request.trim_end_matches(['\r', '\n']));
+        if let Some(response) = response {
+            writeln!(writer, "{response}")?;
+        }
+        request.clear();
+    }
+
+    Ok(())
+}
+
+/// Parses `request`, runs each call with `call` and serializes the responses.
+fn dispatch(
+    request: &str,
+    mut call: impl FnMut(&str, &Json) -> Result<Json, RpcError>,
+) -> Option<String> {
+    let request = match Json::parse(request) {
+        Ok(request) => request,
+        Err(error) => return Some(response(Json::Null, Err(RpcError::new(PARSE_ERROR, error)))),
+    };
+
+    match request {
+        Json::Array(requests) if requests.is_empty() => Some(response(
+            Json::Null,
+            Err(RpcError::new(INVALID_REQUEST, "empty batch")),
+        )),
+        Json::Array(requests) => {
+            let responses: Vec<String> = requests
+                .iter()
+                .filter_map(|request| dispatch_one(request, &mut call))
+                .collect();
+            (!responses.is_empty()).then(|| format!("[{}]", responses.join(",")))
+        }
+        request => dispatch_one(&request, &mut call),
+    }
+}
+
+fn dispatch_one(
+    request: &Json,
+    call: &mut impl FnMut(&str, &Json) -> Result<Json, RpcError>,
+) -> Option<String> {
+    let id = match request {
+        Json::Object(fields) => fields.get("id").cloned(),
+        _ => None,
+    };
+    let method = match (request.field("jsonrpc"), request.field("method")) {
+        (Ok(Json::String(version)), Ok(Json::String(method))) if version == "2.0" => method,
+        _ => {
+            let error = RpcError::new(INVALID_REQUEST, "invalid request");
+            return Some(response(id.unwrap_or(Json::Null), Err(error)));
+        }
+    };
+    let params = match request.field("params") {
+        Ok(params) => params.clone(),
+        Err(_) => Json::object([]),
+    };
+
+    let result = call(method, &params);
+    // notifications get no response
+    id.map(|id| response(id, result))
+}
+
+fn response(id: Json, result: Result<Json, RpcError>) -> String {
+    let outcome = match result {
+        Ok(result) => ("result", result),
+        Err(error) => (
+            "error",
+            Json::object([
+                ("code", Json::Number(error.code.to_string())),
+                ("message", Json::String(error.message)),
+            ]),
+        ),
+    };
+    Json::object([
+        ("jsonrpc", Json::String("2.0".to_string())),
+        ("id", id),
+        outcome,
+    ])
+    .to_string()
+}
+
+fn hex(bytes: &[u8]) -> Json {
+    Json::String(encode_hex(bytes))
+}
+
+fn parse<T: FromStr>(value: &str) -> Result<T, RpcError> {
+    value
+        .parse()
+        .map_err(|_| RpcError::new(INVALID_PARAMS, format!("malformed value {value}")))
+}
+
+fn decode<T>(
+    params: &Json,
+    name: &str,
+    from_bytes: impl Fn(&[u8]) -> Result<T, SerializationError>,
+) -> Result<T, RpcError> {
+    from_bytes(&params.field(name)?.bytes()?)
+        .map_err(|_| RpcError::new(INVALID_PARAMS, format!("malformed {name}")))
+}
+
+fn session_id(params: &Json) -> Result<SessionId, RpcError> {
+    decode_hex(params.field("sessionId")?.string()?)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| RpcError::new(INVALID_PARAMS, "malformed sessionId"))
+}
+
+#[test]
+fn test_rpc_signing_session() {
+    use crate::{frost::Frost, identity::IdentityKey, schnorr::SchnorrSignature};
+
+    let frost: Frost = Frost::signature_share(2, 3);
+    let mut rng = ark_std::test_rng();
+    let mut coordinator = RpcCoordinator::new(frost.group_pk, Duration::from_secs(60));
+    let coordinator_id = NonZeroScalar::derive("coordinator").unwrap();
+    let coordinator_key = IdentityKey::generate(frost.generator, &mut rng);
+    let mut coordinators = IdentityRoster::new(frost.generator);
+    coordinators
+        .insert(coordinator_id, coordinator_key.public_key())
+        .unwrap();
+    let participants: Vec<_> = frost.participants().take(2).collect();
+    let mut signers: Vec<RpcSigner> = participants
+        .iter()
+        .map(|id| RpcSigner::new(frost.key_package(*id).unwrap(), coordinators.clone()))
+        .collect();
+
+    let call = |service: &mut dyn JsonRpc, method: &str, params: String| {
+        let request =
+            format!(r#"{{"jsonrpc":"2.0","id":7,"method":"{method}","params":{params}}}"#);
+        let response = Json::parse(&service.handle(&request).unwrap()).unwrap();
+        assert_eq!(
+            response.field("id").unwrap(),
+            &Json::Number("7".to_string())
+        );
+        response.field("result").cloned().map_err(|_| {
+            response
+                .field("error")
+                .unwrap()
+                .field("code")
+                .unwrap()
+                .to_string()
+        })
+    };
+    let field =
+        |result: &Json, name: &str| result.field(name).unwrap().string().unwrap().to_string();
+
+    let ids = participants
+        .iter()
+        .map(|id| format!("\"{id}\""))
+        .collect::<Vec<_>>()
+        .join(",");
+    let result = call(
+        &mut coordinator,
+        "createSession",
+        format!(
+            r#"{{"message":"{}","participants":[{ids}]}}"#,
+            encode_hex(b"testing")
+        ),
+    )
+    .unwrap();
+    let session_id = field(&result, "sessionId");
+
+    let mut signing_package = Json::Null;
+    for signer in &mut signers {
+        let commitment = field(
+            &call(signer, "commit", "{}".to_string()).unwrap(),
+            "commitment",
+        );
+        let result = call(
+            &mut coordinator,
+            "submitCommitment",
+            format!(r#"{{"sessionId":"{session_id}","commitment":"{commitment}"}}"#),
+        )
+        .unwrap();
+        signing_package = result.field("signingPackage").unwrap().clone();
+    }
+    let signing_package: SigningPackage =
+        SigningPackage::from_bytes(&signing_package.bytes().unwrap()).unwrap();
+    let authenticated = |key: &IdentityKey, rng: &mut _| {
+        let package = Authenticated::sign(coordinator_id, signing_package.clone(), key, rng);
+        format!(
+            r#"{{"signingPackage":"{}"}}"#,
+            encode_hex(&package.to_bytes())
+        )
+    };
+
+    let mut signature = Json::Null;
+    for signer in &mut signers {
+        // only a trusted coordinator can request a share, and a refused request keeps the nonces
+        let forged_key = IdentityKey::generate(frost.generator, &mut rng);
+        assert_eq!(
+            call(signer, "sign", authenticated(&forged_key, &mut rng)),
+            Err(PROTOCOL_ERROR.to_string())
+        );
+        let params = authenticated(&coordinator_key, &mut rng);
+        let share = field(&call(signer, "sign", params.clone()).unwrap(), "share");
+        // the nonces of a commitment are used at most once
+        assert_eq!(
+            call(signer, "sign", params),
+            Err(PROTOCOL_ERROR.to_string())
+        );
+        let result = call(
+            &mut coordinator,
+            "submitShare",
+            format!(r#"{{"sessionId":"{session_id}","share":"{share}"}}"#),
+        )
+        .unwrap();
+        signature = result.field("signature").unwrap().clone();
+    }
+    let result = call(
+        &mut coordinator,
+        "getSignature",
+        format!(r#"{{"sessionId":"{session_id}"}}"#),
+    )
+    .unwrap();
+    assert_eq!(result.field("signature").unwrap(), &signature);
+    let signature: SchnorrSignature = signature.string().unwrap().parse().unwrap();
+    assert!(frost.verify(b"testing", signature));
+
+    assert_eq!(
+        call(&mut coordinator, "getSignature", "{}".to_string()),
+        Err(INVALID_PARAMS.to_string())
+    );
+    assert_eq!(
+        call(&mut coordinator, "unknown", "{}".to_string()),
+        Err(METHOD_NOT_FOUND.to_string())
+    );
+    assert!(
+        coordinator
+            .handle(r#"{"jsonrpc":"2.0","method":"getSignature","params":{}}"#)
+            .is_none()
+    );
+    assert!(coordinator.handle("{").unwrap().contains("-32700"));
+    assert!(coordinator.handle("[1]").unwrap().starts_with('['));
+
+    // expired sessions are dropped, and no more than `max_sessions` are open at once
+    let mut coordinator =
+        RpcCoordinator::new(frost.group_pk, Duration::from_secs(60)).with_max_sessions(1);
+    let create = format!(
+        r#"{{"message":"{}","participants":[{ids}]}}"#,
+        encode_hex(b"testing")
+    );
+    assert!(call(&mut coordinator, "createSession", create.clone()).is_ok());
+    assert_eq!(
+        call(&mut coordinator, "createSession", create.clone()),
+        Err(PROTOCOL_ERROR.to_string())
+    );
+    let mut coordinator = RpcCoordinator::new(frost.group_pk, Duration::ZERO).with_max_sessions(1);
+    assert!(call(&mut coordinator, "createSession", create.clone()).is_ok());
+    assert!(call(&mut coordinator, "createSession", create).is_ok());
+    assert_eq!(coordinator.sessions().len(), 1);
+}
@@ -1,19 +1,90 @@
 //! This module implements Schnorr signatures.
 #![allow(non_snake_case)]
 
-use ark_ec::CurveGroup;
+use ark_ec::{AdditiveGroup, CurveGroup};
 use ark_ed25519::{EdwardsProjective as G, Fr as ScalarField};
 use ark_ff::{Field, UniformRand};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
 use sha2::{Digest, Sha512};
 
+use std::{fmt, str::FromStr};
+
+use crate::{
+    ciphersuite::{self, Ciphersuite, Ed25519Sha512, Element},
+    helper::{decode_hex, encode_hex},
+};
+
+/// Length in bytes of an encoded FROST(Ed25519, SHA-512) `SchnorrSignature`, i.e. `R || s`.
+pub const SIGNATURE_LENGTH: usize = 64;
+
 /// A Schnorr signature contains a point R which is commitment of nonce k
 /// R = g^k where g is the generator of the group,
 /// and a scalar s which is the signature value
 /// s = k + H(R || P || m) * x
 /// where H is a hash function, P is the public key, m is the message, and x is the private key.
-pub struct SchnorrSignature {
-    pub R: G,
-    pub s: ScalarField,
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SchnorrSignature<C: Ciphersuite = Ed25519Sha512> {
+    pub R: Element<C>,
+    pub s: ciphersuite::ScalarField<C>,
+}
+
+impl<C: Ciphersuite> SchnorrSignature<C> {
+    /// Encodes the signature as the `R || s` layout, where `R` is the point encoded by
+    /// `Ciphersuite::serialize_element` and `s` is the scalar. For FROST(Ed25519, SHA-512) this
+    /// is `SIGNATURE_LENGTH` bytes long.
+    ///
+    /// # Panics
+    ///
+    /// Panics if serialization fails.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = C::serialize_element(&self.R);
+        self.s
+            .serialize_compressed(&mut bytes)
+            .expect("serialization failed");
+        bytes
+    }
+
+    /// Parses a signature from the `R || s` layout.
+    ///
+    /// Returns an error if `bytes` has the wrong length, if `R` is not a canonically encoded curve
+    /// point or if `s` is not less than the group order.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        if bytes.len() != Self::encoded_length() {
+            return Err(SerializationError::InvalidData);
+        }
+        let R_length = C::serialize_element(&Element::<C>::ZERO).len();
+
+        let R = C::deserialize_element(&bytes[..R_length])?;
+        let s = ciphersuite::ScalarField::<C>::deserialize_compressed(&bytes[R_length..])?;
+
+        // reject non-canonical encodings of R that still decode to a valid point
+        if C::serialize_element(&R)[..] != bytes[..R_length] {
+            return Err(SerializationError::InvalidData);
+        }
+
+        Ok(SchnorrSignature { R, s })
+    }
+
+    pub(crate) fn encoded_length() -> usize {
+        C::serialize_element(&Element::<C>::ZERO).len()
+            + ciphersuite::ScalarField::<C>::ZERO.compressed_size()
+    }
+}
+
+/// Formats the signature as the hex encoding of `to_bytes`.
+impl<C: Ciphersuite> fmt::Display for SchnorrSignature<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&encode_hex(&self.to_bytes()))
+    }
+}
+
+/// Parses the hex encoding of `to_bytes`.
+impl<C: Ciphersuite> FromStr for SchnorrSignature<C> {
+    type Err = SerializationError;
+
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        Self::from_bytes(&decode_hex(hex).ok_or(SerializationError::InvalidData)?)
+    }
 }
 
 #[allow(dead_code)]
@@ -79,6 +150,15 @@ pub fn schnorr_verify(signature: &SchnorrSignature, message: &[u8], P: G, g: G)
     lhs == rhs
 }
 
+/// Verifies an encoded signature, rejecting it outright if `R` or `s` are not canonically encoded
+/// so that malleated byte strings never verify.
+pub fn schnorr_verify_encoded(signature_bytes: &[u8], message: &[u8], P: G, g: G) -> bool {
+    match SchnorrSignature::<Ed25519Sha512>::from_bytes(signature_bytes) {
+        Ok(signature) => schnorr_verify(&signature, message, P, g),
+        Err(_) => false,
+    }
+}
+
 #[test]
 fn test_signature_verification() {
     let message = b"testing";
@@ -89,3 +169,44 @@ fn test_signature_verification() {
 
     assert!(is_valid, "Signature verification failed");
 }
+
+#[test]
+fn test_signature_bytes_roundtrip() {
+    let signer = Signer::new(ScalarField::from(42u64));
+    let signature = signer.sign(b"testing");
+
+    let bytes = signature.to_bytes();
+    assert_eq!(SchnorrSignature::from_bytes(&bytes).unwrap(), signature);
+
+    // s = 2^256 - 1 is never a valid scalar
+    let mut malformed = bytes.clone();
+    malformed[32..].copy_from_slice(&[0xff; 32]);
+    assert!(SchnorrSignature::<Ed25519Sha512>::from_bytes(&malformed).is_err());
+
+    let hex = signature.to_string();
+    assert_eq!(hex.parse::<SchnorrSignature>().unwrap(), signature);
+    assert!(hex[2..].parse::<SchnorrSignature>().is_err());
+}
+
+#[test]
+fn test_malleated_signature_rejected() {
+    let message = b"testing";
+
+    let signer = Signer::new(ScalarField::from(42u64));
+    let bytes = signer.sign(message).to_bytes();
+    assert!(schnorr_verify_encoded(&bytes, message, signer.P, signer.g));
+
+    // s + l encodes the same scalar modulo the group order but must not verify
+    use ark_ff::{BigInteger, PrimeField};
+    let l_bytes = ScalarField::MODULUS.to_bytes_le();
+    let mut malleated = bytes.clone();
+    let mut carry = 0u16;
+    for (byte, l_byte) in malleated[32..].iter_mut().zip(l_bytes) {
+        let sum = *byte as u16 + l_byte as u16 + carry;
+        *byte = sum as u8;
+        carry = sum >> 8;
+    }
+    assert!(!schnorr_verify_encoded(
+        &malleated, message, signer.P, signer.g
+    ));
+}
@@ -0,0 +1,50 @@
+//! This module implements the `Secret` wrapper for secret material.
+//!
+//! Key shares, nonces and the coefficients of the Shamir polynomial are held in a `Secret`, which
+//! redacts its `Debug` output and implements neither `Clone`, `Copy`, `PartialEq` nor
+//! serialization, so that reading or copying the wrapped value takes an explicit call to
+//! `expose_secret`. It plays the role of `SecretBox` in the `secrecy` crate, which is not a
+//! dependency of this crate. The value is zeroized when the `Secret` is dropped.
+use std::fmt;
+
+use zeroize::Zeroize;
+
+/// A secret value, e.g. a key share or a nonce.
+pub struct Secret<T: Zeroize>(T);
+
+impl<T: Zeroize> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Secret(value)
+    }
+
+    /// Returns the wrapped value. Every call site is a place secret material can leak from.
+    pub fn expose_secret(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Zeroize> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Secret::new(value)
+    }
+}
+
+/// Redacts the value, so that a `Secret` can be logged safely.
+impl<T: Zeroize> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(<redacted>)")
+    }
+}
+
+impl<T: Zeroize> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[test]
+fn test_secret_debug_is_redacted() {
+    let secret = Secret::new(1234567u64);
+    assert_eq!(format!("{secret:?}"), "Secret(<redacted>)");
+    assert_eq!(*secret.expose_secret(), 1234567);
+}
@@ -0,0 +1,344 @@
+//! This module implements strategies for selecting the signers of a session.
+//!
+//! A coordinator needs `threshold` of the group's signers for each session. Which ones it asks is
+//! up to a `SelectionStrategy`: `RoundRobin` spreads the load evenly, `Priority` follows a fixed
+//! order of preference, `WeightedRandom` draws signers in proportion to a weight, and
+//! `PreferNonBlind` only includes blind custodians when there are not enough regular signers, so
+//! that a custodian is contacted only when strictly needed.
+//!
+//! `select_live` pings the selected signers before round one and replaces the unresponsive ones
+//! from the remaining candidates, so that a dead device does not force the session to abort.
+use std::{collections::BTreeMap, fmt};
+
+use ark_std::rand::{Rng, rngs::StdRng};
+
+use crate::{
+    ciphersuite::{Ciphersuite, Ed25519Sha512, ScalarField},
+    error::FrostError,
+    frost::SignerRole,
+    helper::{NonZeroScalar, secure_rng},
+};
+
+/// A signer that can be selected for a session.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Candidate<C: Ciphersuite = Ed25519Sha512> {
+    /// Identifier of the signer.
+    pub identifier: NonZeroScalar<C>,
+    /// The signer's role.
+    pub role: SignerRole,
+}
+
+/// Selects the participants of a session among the candidate signers.
+pub trait SelectionStrategy<C: Ciphersuite>: fmt::Debug + Send {
+    /// Selects `threshold` distinct signers among `candidates`.
+    ///
+    /// Returns an error if there are fewer than `threshold` candidates.
+    fn select(
+        &mut self,
+        candidates: &[Candidate<C>],
+        threshold: usize,
+    ) -> Result<Vec<NonZeroScalar<C>>, FrostError<C>>;
+}
+
+/// Selects `threshold` responsive signers among `candidates` with `strategy`.
+///
+/// Each selected signer is checked with `ping` before it is returned. Signers that do not respond
+/// are dropped and the selection is repeated over the remaining candidates, until every selected
+/// signer has responded. Each signer is pinged at most once.
+///
+/// Returns an error if fewer than `threshold` candidates respond.
+pub fn select_live<C: Ciphersuite>(
+    strategy: &mut dyn SelectionStrategy<C>,
+    candidates: &[Candidate<C>],
+    threshold: usize,
+    mut ping: impl FnMut(NonZeroScalar<C>) -> bool,
+) -> Result<Vec<NonZeroScalar<C>>, FrostError<C>> {
+    let mut candidates = candidates.to_vec();
+    let mut responsive: Vec<NonZeroScalar<C>> = Vec::new();
+    loop {
+        let selected = strategy.select(&candidates, threshold)?;
+        let mut unresponsive = Vec::new();
+        for identifier in &selected {
+            if responsive.contains(identifier) {
+                continue;
+            }
+            if ping(*identifier) {
+                responsive.push(*identifier);
+            } else {
+                unresponsive.push(*identifier);
+            }
+        }
+        if unresponsive.is_empty() {
+            return Ok(selected);
+        }
+        candidates.retain(|candidate| !unresponsive.contains(&candidate.identifier));
+    }
+}
+
+fn check_available<C: Ciphersuite>(
+    candidates: &[Candidate<C>],
+    threshold: usize,
+) -> Result<(), FrostError<C>> {
+    if candidates.len() < threshold {
+        return Err(FrostError::InsufficientSigners {
+            available: candidates.len(),
+            threshold,
+        });
+    }
+    Ok(())
+}
+
+/// Cycles through the candidates, each session starting after the last signer selected for the
+/// previous one.
+#[derive(Debug, Default)]
+pub struct RoundRobin {
+    next: usize,
+}
+
+impl RoundRobin {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<C: Ciphersuite> SelectionStrategy<C> for RoundRobin {
+    fn select(
+        &mut self,
+        candidates: &[Candidate<C>],
+        threshold: usize,
+    ) -> Result<Vec<NonZeroScalar<C>>, FrostError<C>> {
+        check_available(candidates, threshold)?;
+        if candidates.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let start = self.next % candidates.len();
+        self.next = start + threshold;
+        Ok(candidates
+            .iter()
+            .cycle()
+            .skip(start)
+            .take(threshold)
+            .map(|candidate| candidate.identifier)
+            .collect())
+    }
+}
+
+/// Selects the candidates in a fixed order of preference. Candidates missing from the order are
+/// selected last.
+#[derive(Debug, Clone)]
+pub struct Priority<C: Ciphersuite = Ed25519Sha512> {
+    order: Vec<NonZeroScalar<C>>,
+}
+
+impl<C: Ciphersuite> Priority<C> {
+    pub fn new(order: Vec<NonZeroScalar<C>>) -> Self {
+        Priority { order }
+    }
+
+    fn rank(&self, identifier: NonZeroScalar<C>) -> usize {
+        self.order
+            .iter()
+            .position(|preferred| *preferred == identifier)
+            .unwrap_or(self.order.len())
+    }
+}
+
+impl<C: Ciphersuite> SelectionStrategy<C> for Priority<C> {
+    fn select(
+        &mut self,
+        candidates: &[Candidate<C>],
+        threshold: usize,
+    ) -> Result<Vec<NonZeroScalar<C>>, FrostError<C>> {
+        check_available(candidates, threshold)?;
+
+        let mut ranked: Vec<NonZeroScalar<C>> = candidates
+            .iter()
+            .map(|candidate| candidate.identifier)
+            .collect();
+        ranked.sort_by_key(|identifier| self.rank(*identifier));
+        ranked.truncate(threshold);
+        Ok(ranked)
+    }
+}
+
+/// Draws the signers at random, each with a probability proportional to its weight. Candidates
+/// without a weight have weight 1, and candidates with weight 0 are only selected when there are
+/// not enough others.
+#[derive(Debug)]
+pub struct WeightedRandom<C: Ciphersuite = Ed25519Sha512> {
+    weights: BTreeMap<ScalarField<C>, u64>,
+    rng: StdRng,
+}
+
+impl<C: Ciphersuite> WeightedRandom<C> {
+    pub fn new(weights: &[(NonZeroScalar<C>, u64)]) -> Self {
+        WeightedRandom {
+            weights: weights
+                .iter()
+                .map(|(identifier, weight)| (identifier.to_scalar(), *weight))
+                .collect(),
+            rng: secure_rng(),
+        }
+    }
+
+    fn weight(&self, identifier: NonZeroScalar<C>) -> u64 {
+        self.weights
+            .get(&identifier.to_scalar())
+            .copied()
+            .unwrap_or(1)
+    }
+}
+
+impl<C: Ciphersuite> SelectionStrategy<C> for WeightedRandom<C> {
+    fn select(
+        &mut self,
+        candidates: &[Candidate<C>],
+        threshold: usize,
+    ) -> Result<Vec<NonZeroScalar<C>>, FrostError<C>> {
+        check_available(candidates, threshold)?;
+
+        let mut remaining: Vec<(NonZeroScalar<C>, u64)> = candidates
+            .iter()
+            .map(|candidate| (candidate.identifier, self.weight(candidate.identifier)))
+            .collect();
+        let mut selected = Vec::with_capacity(threshold);
+        while selected.len() < threshold {
+            let total: u64 = remaining.iter().map(|(_, weight)| weight).sum();
+            let index = if total == 0 {
+                0
+            } else {
+                let mut draw = self.rng.gen_range(0..total);
+                remaining
+                    .iter()
+                    .position(|(_, weight)| {
+                        if draw < *weight {
+                            return true;
+                        }
+                        draw -= weight;
+                        false
+                    })
+                    .expect("draw is below the total weight")
+            };
+            selected.push(remaining.remove(index).0);
+        }
+        Ok(selected)
+    }
+}
+
+/// Selects the regular signers first, and only as many blind signers as needed to reach the
+/// threshold.
+#[derive(Debug, Default)]
+pub struct PreferNonBlind;
+
+impl<C: Ciphersuite> SelectionStrategy<C> for PreferNonBlind {
+    fn select(
+        &mut self,
+        candidates: &[Candidate<C>],
+        threshold: usize,
+    ) -> Result<Vec<NonZeroScalar<C>>, FrostError<C>> {
+        check_available(candidates, threshold)?;
+
+        let (regular, blind): (Vec<&Candidate<C>>, Vec<_>) = candidates
+            .iter()
+            .partition(|candidate| candidate.role == SignerRole::Regular);
+        Ok(regular
+            .into_iter()
+            .chain(blind)
+            .take(threshold)
+            .map(|candidate| candidate.identifier)
+            .collect())
+    }
+}
+
+#[test]
+fn test_selection_strategies() {
+    use crate::frost::Frost;
+
+    let roles = [
+        SignerRole::Blind,
+        SignerRole::Regular,
+        SignerRole::Regular,
+        SignerRole::Regular,
+    ];
+    let frost: Frost = Frost::signature_share_with_roles(3, &roles);
+    let candidates: Vec<Candidate> = frost
+        .signers()
+        .map(|signer| Candidate {
+            identifier: signer.get_commitments().identifier,
+            role: signer.role(),
+        })
+        .collect();
+    let ids: Vec<_> = frost.participants().collect();
+
+    let mut round_robin = RoundRobin::new();
+    assert_eq!(round_robin.select(&candidates, 3).unwrap(), ids[..3]);
+    assert_eq!(
+        round_robin.select(&candidates, 3).unwrap(),
+        [ids[3], ids[0], ids[1]]
+    );
+
+    let mut priority = Priority::new(vec![ids[3], ids[1]]);
+    assert_eq!(
+        priority.select(&candidates, 3).unwrap(),
+        [ids[3], ids[1], ids[0]]
+    );
+
+    // the custodian is only contacted when the regular signers do not suffice
+    assert_eq!(
+        PreferNonBlind.select(&candidates, 3).unwrap(),
+        [ids[1], ids[2], ids[3]]
+    );
+    assert_eq!(
+        PreferNonBlind.select(&candidates[..3], 3).unwrap(),
+        [ids[1], ids[2], ids[0]]
+    );
+
+    let mut weighted = WeightedRandom::new(&[(ids[0], 0), (ids[1], 5)]);
+    for _ in 0..10 {
+        let mut selected = weighted.select(&candidates, 3).unwrap();
+        selected.sort_by_key(|id| id.to_scalar());
+        assert_eq!(selected, ids[1..]);
+    }
+
+    assert_eq!(
+        PreferNonBlind.select(&candidates[..2], 3),
+        Err(FrostError::InsufficientSigners {
+            available: 2,
+            threshold: 3
+        })
+    );
+}
+
+#[test]
+fn test_select_live() {
+    use crate::frost::Frost;
+
+    let frost: Frost = Frost::signature_share(3, 5);
+    let candidates: Vec<Candidate> = frost
+        .signers()
+        .map(|signer| Candidate {
+            identifier: signer.get_commitments().identifier,
+            role: signer.role(),
+        })
+        .collect();
+    let ids: Vec<_> = frost.participants().collect();
+
+    // the dead signer is replaced by the next one in order of preference
+    let mut pings = Vec::new();
+    let selected = select_live(&mut Priority::new(ids.clone()), &candidates, 3, |id| {
+        pings.push(id);
+        id != ids[1]
+    })
+    .unwrap();
+    assert_eq!(selected, [ids[0], ids[2], ids[3]]);
+    assert_eq!(pings, [ids[0], ids[1], ids[2], ids[3]]);
+
+    assert_eq!(
+        select_live(&mut RoundRobin::new(), &candidates, 3, |id| id == ids[0]),
+        Err(FrostError::InsufficientSigners {
+            available: 1,
+            threshold: 3
+        })
+    );
+}
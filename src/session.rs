@@ -0,0 +1,531 @@
+//! This module encodes the lifecycle of a signing session in the type system.
+//!
+//! A `Session` moves from `AwaitingCommitments` to `AwaitingShares` and ends in `Complete` or
+//! `Aborted`. Each transition consumes the session, and each method only exists in the states it
+//! applies to: the shares of a session awaiting commitments cannot be received, and a session
+//! cannot be aggregated before its signing package has been sent. The rounds themselves are run
+//! by the wrapped `Coordinator`.
+//!
+//! A `SigningSession`, configured with `SigningSession::builder`, runs a whole session among the
+//! signers of a `Frost` instance held in one process.
+use std::time::Duration;
+
+use ark_std::rand::RngCore;
+
+use crate::{
+    ciphersuite::{Ciphersuite, Ed25519Sha512},
+    coordinator::Coordinator,
+    error::FrostError,
+    frost::{Frost, FrostSigner, SessionId, SignatureShare, SignerRole, SigningPackage},
+    helper::{CommitmentProof, NonZeroScalar},
+    keys::{GroupPublicKey, KeyPackage},
+    nonces::SigningCommitments,
+    schnorr::SchnorrSignature,
+    transcript::SessionTranscript,
+};
+
+/// Round one: the session collects the participants' commitments.
+#[derive(Debug, Clone)]
+pub struct AwaitingCommitments;
+
+/// Round two: the signing package has been sent and the session collects signature shares.
+#[derive(Debug, Clone)]
+pub struct AwaitingShares<C: Ciphersuite = Ed25519Sha512> {
+    signing_package: SigningPackage<C>,
+}
+
+/// The signature has been aggregated.
+#[derive(Debug, Clone)]
+pub struct Complete<C: Ciphersuite = Ed25519Sha512> {
+    signature: SchnorrSignature<C>,
+}
+
+/// The session failed and can only be retried.
+#[derive(Debug, Clone)]
+pub struct Aborted<C: Ciphersuite = Ed25519Sha512> {
+    error: FrostError<C>,
+}
+
+/// A signing session in state `S`.
+#[derive(Debug, Clone)]
+pub struct Session<S, C: Ciphersuite = Ed25519Sha512> {
+    coordinator: Box<Coordinator<C>>,
+    state: S,
+}
+
+impl<S, C: Ciphersuite> Session<S, C> {
+    pub fn session_id(&self) -> SessionId {
+        self.coordinator.get_session_id()
+    }
+
+    /// Returns the participants that have not yet responded in the current round.
+    pub fn missing(&self) -> Vec<NonZeroScalar<C>> {
+        self.coordinator.missing()
+    }
+
+    fn transition<T>(self, state: T) -> Session<T, C> {
+        Session {
+            coordinator: self.coordinator,
+            state,
+        }
+    }
+
+    fn abort(self, error: FrostError<C>) -> Session<Aborted<C>, C> {
+        self.transition(Aborted { error })
+    }
+}
+
+impl<C: Ciphersuite> Session<AwaitingCommitments, C> {
+    /// Starts a new session over `message` among `participants`, see `Coordinator::new`.
+    pub fn new(
+        group_pk: GroupPublicKey<C>,
+        message: &[u8],
+        participants: Vec<NonZeroScalar<C>>,
+        round_timeout: Duration,
+    ) -> Self {
+        Self::start(Coordinator::new(
+            group_pk,
+            message,
+            participants,
+            round_timeout,
+        ))
+    }
+
+    /// Starts a session driven by `coordinator`, e.g. one configured with metrics or commitment
+    /// proofs. The coordinator must not have left round one.
+    pub fn start(coordinator: Coordinator<C>) -> Self {
+        Session {
+            coordinator: Box::new(coordinator),
+            state: AwaitingCommitments,
+        }
+    }
+
+    /// Records the round one `commitment` of a participant.
+    pub fn receive_commitment(
+        &mut self,
+        commitment: SigningCommitments<C>,
+    ) -> Result<(), FrostError<C>> {
+        self.coordinator.receive_commitment(commitment)
+    }
+
+    /// Records the round one `commitment` of a participant together with its `proof` of
+    /// possession.
+    pub fn receive_proven_commitment(
+        &mut self,
+        commitment: SigningCommitments<C>,
+        proof: &CommitmentProof<C>,
+    ) -> Result<(), FrostError<C>> {
+        self.coordinator
+            .receive_proven_commitment(commitment, proof)
+    }
+
+    /// Ends round one, moving on to collect signature shares for the signing package.
+    ///
+    /// Call it once `missing` is empty. The session is aborted if a commitment is still missing
+    /// or the round timed out.
+    pub fn send_signing_package(
+        mut self,
+    ) -> Result<Session<AwaitingShares<C>, C>, Session<Aborted<C>, C>> {
+        match self.coordinator.signing_package() {
+            Ok(signing_package) => Ok(self.transition(AwaitingShares { signing_package })),
+            Err(error) => Err(self.abort(error)),
+        }
+    }
+}
+
+impl<C: Ciphersuite> Session<AwaitingShares<C>, C> {
+    /// Returns the `SigningPackage` to send to every participant.
+    pub fn signing_package(&self) -> &SigningPackage<C> {
+        &self.state.signing_package
+    }
+
+    /// Records the round two `sig_share` of a participant.
+    pub fn receive_share(&mut self, sig_share: SignatureShare<C>) -> Result<(), FrostError<C>> {
+        self.coordinator.receive_share(sig_share)
+    }
+
+    /// Aggregates the collected signature shares, completing the session.
+    ///
+    /// Call it once `missing` is empty. The session is aborted if a share is still missing or
+    /// the round timed out.
+    pub fn aggregate(mut self) -> Result<Session<Complete<C>, C>, Session<Aborted<C>, C>> {
+        match self.coordinator.aggregate() {
+            Ok(signature) => Ok(self.transition(Complete { signature })),
+            Err(error) => Err(self.abort(error)),
+        }
+    }
+}
+
+impl<C: Ciphersuite> Session<Complete<C>, C> {
+    pub fn signature(&self) -> SchnorrSignature<C> {
+        self.state.signature
+    }
+
+    /// Returns the transcript of the session, for an auditor to re-verify.
+    pub fn transcript(&self) -> SessionTranscript<C> {
+        self.coordinator
+            .transcript()
+            .expect("completed sessions have a transcript")
+    }
+}
+
+impl<C: Ciphersuite> Session<Aborted<C>, C> {
+    /// Returns the error the session was aborted with.
+    pub fn error(&self) -> &FrostError<C> {
+        &self.state.error
+    }
+
+    /// Restarts the session at round one without the participants with the given `identifiers`,
+    /// see `Coordinator::retry_without`.
+    ///
+    /// Returns the session, still aborted but with the error of the retry, if it cannot be
+    /// retried.
+    pub fn retry_without(
+        mut self,
+        identifiers: &[NonZeroScalar<C>],
+    ) -> Result<Session<AwaitingCommitments, C>, Self> {
+        match self.coordinator.retry_without(identifiers) {
+            Ok(()) => Ok(self.transition(AwaitingCommitments)),
+            Err(error) => Err(self.abort(error)),
+        }
+    }
+}
+
+/// Which blind signers a `SigningSession` may include.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum BlindSignerPolicy {
+    /// Blind signers may participate.
+    #[default]
+    Allow,
+    /// At least one blind signer must participate, e.g. so that the custodian co-signs.
+    Require,
+    /// Only regular signers may participate.
+    Exclude,
+}
+
+/// A signing session among the signers of a `Frost` instance, run in one process.
+#[derive(Debug)]
+pub struct SigningSession<C: Ciphersuite = Ed25519Sha512> {
+    signers: Vec<FrostSigner<C>>,
+    session: Session<AwaitingCommitments, C>,
+}
+
+/// Configures a `SigningSession`, see `SigningSession::builder`.
+pub struct SigningSessionBuilder<'a, C: Ciphersuite = Ed25519Sha512> {
+    frost: &'a Frost<C>,
+    message: Option<Vec<u8>>,
+    participants: Option<Vec<NonZeroScalar<C>>>,
+    round_timeout: Duration,
+    blind_policy: BlindSignerPolicy,
+    rng: Option<&'a mut dyn RngCore>,
+}
+
+impl<C: Ciphersuite> SigningSession<C> {
+    /// Starts configuring a session among the signers of `frost`.
+    ///
+    /// By default every signer participates, each round is given a minute, blind signers are
+    /// allowed and the nonces come from the hedged `nonce_generate`. The message must be set.
+    pub fn builder(frost: &Frost<C>) -> SigningSessionBuilder<'_, C> {
+        SigningSessionBuilder {
+            frost,
+            message: None,
+            participants: None,
+            round_timeout: Duration::from_secs(60),
+            blind_policy: BlindSignerPolicy::Allow,
+            rng: None,
+        }
+    }
+
+    pub fn session_id(&self) -> SessionId {
+        self.session.session_id()
+    }
+
+    /// Returns the identifiers of the participants.
+    pub fn participants(&self) -> Vec<NonZeroScalar<C>> {
+        self.signers
+            .iter()
+            .map(|signer| signer.get_commitments().identifier)
+            .collect()
+    }
+
+    /// Runs both rounds of the session and aggregates the signature.
+    ///
+    /// Returns the aborted session if a signer or the coordinator fails.
+    pub fn run(self) -> Result<Session<Complete<C>, C>, Session<Aborted<C>, C>> {
+        self.commit()?.collect_shares()?.aggregate()
+    }
+
+    /// Runs round one: collects the commitment of every participant and builds the signing
+    /// package.
+    pub(crate) fn commit(self) -> Result<SharesRound<C>, Session<Aborted<C>, C>> {
+        let mut session = self.session;
+        for signer in &self.signers {
+            if let Err(error) = session.receive_commitment(signer.get_commitments()) {
+                return Err(session.abort(error));
+            }
+        }
+        Ok(SharesRound {
+            signers: self.signers,
+            session: session.send_signing_package()?,
+        })
+    }
+}
+
+/// Round two of a `SigningSession`, whose signing package has been built.
+#[derive(Debug)]
+pub(crate) struct SharesRound<C: Ciphersuite = Ed25519Sha512> {
+    signers: Vec<FrostSigner<C>>,
+    session: Session<AwaitingShares<C>, C>,
+}
+
+impl<C: Ciphersuite> SharesRound<C> {
+    /// Collects the signature share of every participant, leaving the session to aggregate.
+    pub(crate) fn collect_shares(
+        mut self,
+    ) -> Result<Session<AwaitingShares<C>, C>, Session<Aborted<C>, C>> {
+        for signer in &mut self.signers {
+            let result = signer
+                .sign(self.session.signing_package())
+                .and_then(|sig_share| self.session.receive_share(sig_share));
+            if let Err(error) = result {
+                return Err(self.session.abort(error));
+            }
+        }
+        Ok(self.session)
+    }
+}
+
+impl<'a, C: Ciphersuite> SigningSessionBuilder<'a, C> {
+    /// Returns the group the session signs for.
+    pub(crate) fn frost(&self) -> &'a Frost<C> {
+        self.frost
+    }
+
+    pub fn message(mut self, message: &[u8]) -> Self {
+        self.message = Some(message.to_vec());
+        self
+    }
+
+    /// Restricts the session to the signers with the given identifiers.
+    pub fn participants(mut self, participants: Vec<NonZeroScalar<C>>) -> Self {
+        self.participants = Some(participants);
+        self
+    }
+
+    /// Sets the time each round is given to complete.
+    pub fn timeout(mut self, round_timeout: Duration) -> Self {
+        self.round_timeout = round_timeout;
+        self
+    }
+
+    pub fn blind_policy(mut self, blind_policy: BlindSignerPolicy) -> Self {
+        self.blind_policy = blind_policy;
+        self
+    }
+
+    /// Draws the signers' nonces from `rng`, e.g. a seeded one to reproduce a session.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn rng(mut self, rng: &'a mut dyn RngCore) -> Self {
+        self.rng = Some(rng);
+        self
+    }
+
+    /// Validates the configuration and creates the session, with fresh nonces for every
+    /// participant.
+    ///
+    /// Returns an error if no message is set, if a participant is not a signer of the group or
+    /// is listed twice, if there are fewer participants than the threshold, or if the
+    /// participants violate the blind signer policy.
+    pub fn build(self) -> Result<SigningSession<C>, FrostError<C>> {
+        let message = self
+            .message
+            .ok_or_else(|| FrostError::InvalidSession("no message to sign".to_string()))?;
+        let participants = self
+            .participants
+            .unwrap_or_else(|| self.frost.participants().collect());
+
+        let mut key_packages: Vec<KeyPackage<C>> = Vec::with_capacity(participants.len());
+        for identifier in &participants {
+            if key_packages
+                .iter()
+                .any(|key_package| key_package.identifier == *identifier)
+            {
+                return Err(FrostError::InvalidSession(format!(
+                    "signer {} is listed twice",
+                    identifier.to_scalar()
+                )));
+            }
+            let key_package = self
+                .frost
+                .key_package(*identifier)
+                .ok_or(FrostError::UnknownParticipant(identifier.to_scalar()))?;
+            key_packages.push(key_package);
+        }
+        if participants.len() < self.frost.threshold {
+            return Err(FrostError::InsufficientSigners {
+                available: participants.len(),
+                threshold: self.frost.threshold,
+            });
+        }
+
+        let blind = key_packages
+            .iter()
+            .any(|key_package| key_package.role == SignerRole::Blind);
+        match self.blind_policy {
+            BlindSignerPolicy::Require if !blind => {
+                return Err(FrostError::InvalidSession(
+                    "no blind signer participates".to_string(),
+                ));
+            }
+            BlindSignerPolicy::Exclude if blind => {
+                return Err(FrostError::InvalidSession(
+                    "blind signers are excluded".to_string(),
+                ));
+            }
+            _ => {}
+        }
+
+        let mut rng = self.rng;
+        let signers = key_packages
+            .iter()
+            .map(|key_package| match &mut rng {
+                #[cfg(any(test, feature = "testing"))]
+                Some(rng) => FrostSigner::from_key_package_with_rng(key_package, *rng),
+                _ => FrostSigner::from_key_package(key_package),
+            })
+            .collect();
+
+        let coordinator = Coordinator::new(
+            self.frost.group_pk,
+            &message,
+            participants,
+            self.round_timeout,
+        )
+        .with_public_key_package(self.frost.public_key_package())?;
+        Ok(SigningSession {
+            signers,
+            session: Session::start(coordinator),
+        })
+    }
+}
+
+#[test]
+fn test_session_lifecycle() {
+    use crate::frost::{Frost, FrostSigner, SignerRole};
+
+    let roles = [SignerRole::Regular, SignerRole::Regular, SignerRole::Blind];
+    let frost: Frost = Frost::signature_share_with_roles(2, &roles);
+    let ids: Vec<_> = frost.participants().collect();
+    let coordinator = Coordinator::new(
+        frost.group_pk,
+        b"testing",
+        vec![ids[0], ids[1]],
+        Duration::from_secs(60),
+    )
+    .with_standby(vec![ids[2]]);
+    let mut session = Session::start(coordinator);
+
+    // the second signer never commits
+    session
+        .receive_commitment(frost.signers[0].get_commitments())
+        .unwrap();
+    let aborted = session.send_signing_package().unwrap_err();
+    assert_eq!(
+        aborted.error(),
+        &FrostError::MissingCommitment(ids[1].to_scalar())
+    );
+
+    let mut session = aborted.retry_without(&[ids[1]]).unwrap();
+    let mut signers: Vec<_> = [ids[0], ids[2]]
+        .iter()
+        .map(|id| FrostSigner::from_key_package(&frost.key_package(*id).unwrap()))
+        .collect();
+    for signer in &signers {
+        session
+            .receive_commitment(signer.get_commitments())
+            .unwrap();
+    }
+    let mut session = session.send_signing_package().unwrap();
+    for signer in &mut signers {
+        let sig_share = signer.sign(session.signing_package()).unwrap();
+        session.receive_share(sig_share).unwrap();
+    }
+    let session = session.aggregate().unwrap();
+
+    assert!(frost.verify(b"testing", session.signature()));
+    assert!(
+        session
+            .transcript()
+            .verify(
+                frost.group_pk,
+                frost.generator,
+                &signers
+                    .iter()
+                    .map(|signer| (
+                        signer.get_commitments().identifier,
+                        signer.get_verification_share()
+                    ))
+                    .collect::<Vec<_>>()
+            )
+            .is_ok()
+    );
+}
+
+#[test]
+fn test_signing_session_builder() {
+    use ark_std::rand::{SeedableRng, rngs::StdRng};
+
+    let frost: Frost = Frost::signature_share(2, 3);
+    let ids: Vec<_> = frost.participants().collect();
+
+    let session = SigningSession::builder(&frost)
+        .message(b"testing")
+        .build()
+        .unwrap();
+    assert_eq!(session.participants(), ids);
+    let signature = session.run().unwrap().signature();
+    assert!(frost.verify(b"testing", signature));
+
+    // the same rng yields the same nonces
+    let commitments = |seed: u64| {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let session = SigningSession::builder(&frost)
+            .message(b"testing")
+            .participants(vec![ids[0], ids[1]])
+            .rng(&mut rng)
+            .build()
+            .unwrap();
+        session.signers[0].get_commitments()
+    };
+    assert_eq!(commitments(1), commitments(1));
+    assert_ne!(commitments(1), commitments(2));
+
+    let builder = || SigningSession::builder(&frost).message(b"testing");
+    assert!(SigningSession::builder(&frost).build().is_err());
+    assert!(
+        builder()
+            .participants(vec![ids[0], ids[0]])
+            .build()
+            .is_err()
+    );
+    assert_eq!(
+        builder().participants(vec![ids[0]]).build().unwrap_err(),
+        FrostError::InsufficientSigners {
+            available: 1,
+            threshold: 2
+        }
+    );
+    assert!(
+        builder()
+            .participants(vec![ids[0], ids[1]])
+            .blind_policy(BlindSignerPolicy::Require)
+            .build()
+            .is_err()
+    );
+    assert!(
+        builder()
+            .blind_policy(BlindSignerPolicy::Exclude)
+            .build()
+            .is_err()
+    );
+}
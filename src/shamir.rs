@@ -1,41 +1,99 @@
 //! This module contains a simple Shamir Secret Sharing implementation used during FROST setup.
-use ark_ed25519::Fr as ScalarField;
-use ark_ff::{AdditiveGroup, Field, UniformRand};
+//!
+//! The implementation is generic over the prime field the secret lives in, defaulting to the
+//! Ed25519 scalar field.
+use std::fmt;
 
-pub struct ShamirShare {
+use ark_ed25519::Fr;
+use ark_ff::PrimeField;
+use ark_std::rand::Rng;
+
+use crate::secret::Secret;
+
+pub struct ShamirShare<ScalarField: PrimeField = Fr> {
     pub index: usize,        // index of the share
     pub secret: ScalarField, // secret share
 }
 
-pub fn shamir_split(secret: ScalarField, t: usize, n: usize) -> Vec<ShamirShare> {
-    assert!(t <= n, "threshold cannot exceed number of shares");
-    assert!(t >= 2, "threshold must be at least 2");
+/// Redacts the secret share, so that a `ShamirShare` can be logged safely.
+impl<ScalarField: PrimeField> fmt::Debug for ShamirShare<ScalarField> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShamirShare")
+            .field("index", &self.index)
+            .field("secret", &format_args!("<redacted>"))
+            .finish()
+    }
+}
 
+#[cfg(any(test, feature = "testing"))]
+pub fn shamir_split<ScalarField: PrimeField>(
+    secret: ScalarField,
+    t: usize,
+    n: usize,
+) -> Vec<ShamirShare<ScalarField>> {
     let mut rng = ark_std::test_rng();
+    shamir_split_with_rng(secret, t, n, &mut rng)
+}
+
+/// Same as `shamir_split`, but draws the polynomial coefficients from the given `rng`.
+pub fn shamir_split_with_rng<ScalarField: PrimeField, R: Rng>(
+    secret: ScalarField,
+    t: usize,
+    n: usize,
+    rng: &mut R,
+) -> Vec<ShamirShare<ScalarField>> {
+    let x_coordinates: Vec<ScalarField> = (1..=n as u64).map(ScalarField::from).collect();
+    shamir_split_at_with_rng(secret, t, &x_coordinates, rng)
+        .into_iter()
+        .enumerate()
+        .map(|(i, y)| ShamirShare {
+            index: i + 1,
+            secret: y,
+        })
+        .collect()
+}
+
+/// Same as `shamir_split_with_rng`, but evaluates the polynomial at arbitrary nonzero
+/// `x_coordinates` rather than at `1..=n`, e.g. at identifiers derived from names. Returns the
+/// share at each of them, in order.
+pub fn shamir_split_at_with_rng<ScalarField: PrimeField, R: Rng>(
+    secret: ScalarField,
+    t: usize,
+    x_coordinates: &[ScalarField],
+    rng: &mut R,
+) -> Vec<ScalarField> {
+    assert!(
+        t <= x_coordinates.len(),
+        "threshold cannot exceed number of shares"
+    );
+    assert!(t >= 2, "threshold must be at least 2");
+    assert!(
+        !x_coordinates.contains(&ScalarField::ZERO),
+        "shares cannot be issued at zero"
+    );
 
     // generate random coefficients a_1 .. a_{t-1}
-    let mut coeffs = vec![secret];
+    let mut coeffs = vec![Secret::new(secret)];
     for _ in 1..t {
-        coeffs.push(ScalarField::rand(&mut rng));
+        coeffs.push(Secret::new(ScalarField::rand(rng)));
     }
 
-    // evaluate polynomial at x = 1..n to get shares
-    (1..=n)
-        .map(|i| {
-            let x = ScalarField::from(i as u64);
+    // evaluate polynomial at every x-coordinate to get shares
+    x_coordinates
+        .iter()
+        .map(|x| {
             let mut y = ScalarField::ZERO;
             for (j, coeff) in coeffs.iter().enumerate() {
-                y += *coeff * x.pow([j as u64]);
-            }
-            ShamirShare {
-                index: i,
-                secret: y,
+                y += *coeff.expose_secret() * x.pow([j as u64]);
             }
+            y
         })
         .collect()
 }
 
-pub fn shamir_reconstruct(shares: &[ShamirShare]) -> ScalarField {
+pub fn shamir_reconstruct<ScalarField: PrimeField>(
+    shares: &[ShamirShare<ScalarField>],
+) -> ScalarField {
     let mut secret = ScalarField::ZERO;
 
     for (
@@ -64,7 +122,7 @@ pub fn shamir_reconstruct(shares: &[ShamirShare]) -> ScalarField {
         }
 
         let lagrange_coeff = numerator * denominator.inverse().unwrap(); // Lagrange basis L_i(0)
-        secret += y_i * &lagrange_coeff;
+        secret += *y_i * lagrange_coeff;
     }
 
     secret
@@ -72,7 +130,7 @@ pub fn shamir_reconstruct(shares: &[ShamirShare]) -> ScalarField {
 
 #[test]
 fn test_shamir_split_reconstruct() {
-    let secret = ScalarField::from(42u64);
+    let secret = Fr::from(42u64);
     let t = 3; // threshold
     let n = 5; // total shares
     let shares = shamir_split(secret, t, n);
@@ -0,0 +1,213 @@
+//! This module implements a simulation harness that runs full signing sessions with configurable
+//! adversaries.
+//!
+//! It is meant for exercising abort-handling paths: signers that return wrong signature shares, a
+//! coordinator that sends an inconsistent signing package to some signer, and messages that are
+//! dropped on the way to the coordinator.
+#![allow(non_snake_case)]
+
+use std::{thread, time::Duration};
+
+use ark_ff::One;
+
+use crate::{
+    blame::{BlameCertificate, verify_signature_share},
+    ciphersuite::{Ciphersuite, Ed25519Sha512, ScalarField},
+    coordinator::Coordinator,
+    error::FrostError,
+    frost::{Frost, FrostSigner},
+    helper::NonZeroScalar,
+    schnorr::SchnorrSignature,
+};
+
+/// A deviation from the honest protocol injected into a simulated session.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Adversary<C: Ciphersuite = Ed25519Sha512> {
+    /// The signer returns a signature share that does not verify.
+    WrongShare(NonZeroScalar<C>),
+    /// The coordinator sends the signer a signing package with a substituted commitment.
+    InconsistentPackage(NonZeroScalar<C>),
+    /// The signer's commitment never reaches the coordinator.
+    DropCommitment(NonZeroScalar<C>),
+    /// The signer's signature share never reaches the coordinator.
+    DropShare(NonZeroScalar<C>),
+}
+
+/// The result of a simulated session.
+#[derive(Debug, Clone)]
+pub enum Outcome<C: Ciphersuite = Ed25519Sha512> {
+    /// The session produced a signature.
+    Signed(SchnorrSignature<C>),
+    /// The session aborted with an error.
+    Aborted(FrostError<C>),
+    /// The coordinator detected invalid signature shares.
+    Blamed(Vec<BlameCertificate<C>>),
+}
+
+/// A signing session among all signers of a `Frost` instance, with injected `Adversary`s.
+#[derive(Debug, Clone)]
+pub struct Simulation<C: Ciphersuite = Ed25519Sha512> {
+    frost: Frost<C>,
+    adversaries: Vec<Adversary<C>>,
+    round_timeout: Duration,
+}
+
+impl<C: Ciphersuite> Simulation<C> {
+    pub fn new(frost: Frost<C>, round_timeout: Duration) -> Self {
+        Simulation {
+            frost,
+            adversaries: Vec::new(),
+            round_timeout,
+        }
+    }
+
+    pub fn with_adversary(mut self, adversary: Adversary<C>) -> Self {
+        self.adversaries.push(adversary);
+        self
+    }
+
+    /// Runs a full signing session over `message`.
+    pub fn run(&self, message: &[u8]) -> Outcome<C> {
+        match self.try_run(message) {
+            Ok(outcome) => outcome,
+            Err(error) => Outcome::Aborted(error),
+        }
+    }
+
+    fn try_run(&self, message: &[u8]) -> Result<Outcome<C>, FrostError<C>> {
+        let mut coordinator = Coordinator::new(
+            self.frost.group_pk,
+            message,
+            self.frost.participants().collect(),
+            self.round_timeout,
+        );
+
+        // every run is a new session, with fresh nonces
+        let mut signers: Vec<FrostSigner<C>> = self.frost.signers().cloned().collect();
+        signers.iter_mut().for_each(|signer| {
+            signer.refresh_nonces();
+        });
+
+        // round one
+        for signer in &signers {
+            let identifier = signer.get_commitments().identifier;
+            if self.has(Adversary::DropCommitment(identifier)) {
+                continue;
+            }
+            coordinator.receive_commitment(signer.get_commitments())?;
+        }
+        self.wait_for_deadline(&coordinator)?;
+        let signing_package = coordinator.signing_package()?;
+
+        // round two
+        let mut sig_shares = Vec::new();
+        for signer in &mut signers {
+            let identifier = signer.get_commitments().identifier;
+
+            let mut package = signing_package.clone();
+            if self.has(Adversary::InconsistentPackage(identifier)) {
+                for commitment in package.commitments.iter_mut() {
+                    if commitment.identifier == identifier {
+                        commitment.hiding += self.frost.generator;
+                    }
+                }
+            }
+
+            let mut sig_share = signer.sign(&package)?;
+            if self.has(Adversary::WrongShare(identifier)) {
+                sig_share.z_i += ScalarField::<C>::one();
+            }
+            if self.has(Adversary::DropShare(identifier)) {
+                continue;
+            }
+
+            coordinator.receive_share(sig_share)?;
+            sig_shares.push((signer.get_verification_share(), sig_share));
+        }
+        self.wait_for_deadline(&coordinator)?;
+
+        // the coordinator checks every share before aggregating
+        let mut certificates = Vec::new();
+        for (verification_share, sig_share) in sig_shares {
+            match verify_signature_share(
+                sig_share,
+                &signing_package,
+                self.frost.group_pk,
+                verification_share,
+                self.frost.generator,
+            ) {
+                Ok(()) => {}
+                Err(FrostError::InvalidSignatureShare(certificate)) => {
+                    certificates.push(*certificate)
+                }
+                Err(error) => return Err(error),
+            }
+        }
+        if !certificates.is_empty() {
+            return Ok(Outcome::Blamed(certificates));
+        }
+
+        Ok(Outcome::Signed(coordinator.aggregate()?))
+    }
+
+    fn has(&self, adversary: Adversary<C>) -> bool {
+        self.adversaries.contains(&adversary)
+    }
+
+    /// Waits out the current round if some participant has not responded, so that a dropped
+    /// message surfaces as `FrostError::Timeout`.
+    fn wait_for_deadline(&self, coordinator: &Coordinator<C>) -> Result<(), FrostError<C>> {
+        if !coordinator.missing().is_empty() {
+            thread::sleep(self.round_timeout);
+        }
+        coordinator.check_deadline()
+    }
+}
+
+#[test]
+fn test_simulated_adversaries() {
+    use crate::error::Round;
+
+    let frost: Frost = Frost::signature_share(2, 3);
+    let first = frost.participants().next().unwrap();
+    let simulation = Simulation::new(frost.clone(), Duration::from_millis(500));
+
+    match simulation.run(b"testing") {
+        Outcome::Signed(signature) => assert!(frost.verify(b"testing", signature)),
+        outcome => panic!("unexpected outcome {outcome:?}"),
+    }
+
+    match simulation
+        .clone()
+        .with_adversary(Adversary::WrongShare(first))
+        .run(b"testing")
+    {
+        Outcome::Blamed(certificates) => {
+            assert_eq!(certificates.len(), 1);
+            assert_eq!(certificates[0].identifier(), first);
+            assert!(certificates[0].verify());
+        }
+        outcome => panic!("unexpected outcome {outcome:?}"),
+    }
+
+    match simulation
+        .clone()
+        .with_adversary(Adversary::InconsistentPackage(first))
+        .run(b"testing")
+    {
+        Outcome::Aborted(FrostError::CommitmentMismatch(id)) => assert_eq!(id, first.to_scalar()),
+        outcome => panic!("unexpected outcome {outcome:?}"),
+    }
+
+    match simulation
+        .clone()
+        .with_adversary(Adversary::DropShare(first))
+        .run(b"testing")
+    {
+        Outcome::Aborted(FrostError::Timeout { round, missing }) => {
+            assert_eq!(round, Round::SignatureShare);
+            assert_eq!(missing, vec![first.to_scalar()]);
+        }
+        outcome => panic!("unexpected outcome {outcome:?}"),
+    }
+}
@@ -0,0 +1,281 @@
+//! This module implements a simulated network between the parties of a signing session, in one
+//! process.
+//!
+//! Unlike `ChannelTransport`, which hands the `GroupMessage`s themselves to the recipient, a
+//! `SimTransport` sends the wire encoding of every message and parses it on receipt, and can
+//! drop messages or deliver them late and out of order. Running `coordinate` and `participate`
+//! over a `SimNetwork` therefore exercises the encoding and the timeouts of a session the way a
+//! real network would, without leaving the process.
+use std::{
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use ark_std::rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::{
+    ciphersuite::{Ciphersuite, Ed25519Sha512},
+    helper::{NonZeroScalar, secure_rng},
+    transport::{GroupMessage, Party, Transport, TransportError},
+};
+
+/// How often a `SimTransport` waiting for a message checks whether a delayed one is due.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// The encoding of a message in flight, with its sender and the time it is delivered at.
+#[derive(Debug)]
+struct Datagram<C: Ciphersuite> {
+    from: Party<C>,
+    deliver_at: Instant,
+    bytes: Vec<u8>,
+}
+
+/// The conditions of a simulated network: the probability that a message is lost, and the range
+/// its delivery is delayed by.
+///
+/// By default, no message is lost and every message is delivered at once.
+#[derive(Debug)]
+pub struct SimNetwork {
+    loss: f64,
+    min_delay: Duration,
+    max_delay: Duration,
+    rng: StdRng,
+}
+
+impl Default for SimNetwork {
+    fn default() -> Self {
+        SimNetwork::new()
+    }
+}
+
+impl SimNetwork {
+    pub fn new() -> Self {
+        SimNetwork {
+            loss: 0.0,
+            min_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+            rng: secure_rng(),
+        }
+    }
+
+    /// Drops every message with probability `loss`, which is clamped to `[0, 1]`.
+    pub fn with_loss(mut self, loss: f64) -> Self {
+        self.loss = loss.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Delays every message by a duration drawn uniformly between `min` and `max`, so that
+    /// messages may arrive out of order.
+    pub fn with_delay(mut self, min: Duration, max: Duration) -> Self {
+        self.min_delay = min.min(max);
+        self.max_delay = max.max(min);
+        self
+    }
+
+    /// Connects a coordinator to the signers with the given identifiers, like
+    /// `ChannelTransport::network`, and returns the coordinator's transport and those of the
+    /// signers, in the same order.
+    pub fn connect<C: Ciphersuite>(
+        &mut self,
+        participants: &[NonZeroScalar<C>],
+    ) -> (SimTransport<C>, Vec<SimTransport<C>>) {
+        let parties: Vec<Party<C>> = std::iter::once(Party::Coordinator)
+            .chain(participants.iter().copied().map(Party::Signer))
+            .collect();
+        let (senders, inboxes): (Vec<_>, Vec<_>) = parties.iter().map(|_| mpsc::channel()).unzip();
+        let peers: Vec<_> = parties.iter().copied().zip(senders).collect();
+
+        let mut transports: Vec<SimTransport<C>> = parties
+            .into_iter()
+            .zip(inboxes)
+            .map(|(party, inbox)| SimTransport {
+                party,
+                inbox,
+                in_flight: Vec::new(),
+                peers: peers.clone(),
+                loss: self.loss,
+                min_delay: self.min_delay,
+                max_delay: self.max_delay,
+                rng: StdRng::seed_from_u64(self.rng.r#gen()),
+            })
+            .collect();
+        let coordinator = transports.remove(0);
+        (coordinator, transports)
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl SimNetwork {
+    /// Draws the lost messages and the delays from `seed`, so that a simulated run can be
+    /// reproduced.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+}
+
+/// A `Transport` over a `SimNetwork`.
+#[derive(Debug)]
+pub struct SimTransport<C: Ciphersuite = Ed25519Sha512> {
+    party: Party<C>,
+    inbox: Receiver<Datagram<C>>,
+    /// Messages received from the inbox that are not yet due.
+    in_flight: Vec<Datagram<C>>,
+    peers: Vec<(Party<C>, Sender<Datagram<C>>)>,
+    loss: f64,
+    min_delay: Duration,
+    max_delay: Duration,
+    rng: StdRng,
+}
+
+impl<C: Ciphersuite> SimTransport<C> {
+    pub fn party(&self) -> Party<C> {
+        self.party
+    }
+
+    fn delay(&mut self) -> Duration {
+        if self.min_delay == self.max_delay {
+            self.min_delay
+        } else {
+            self.rng.gen_range(self.min_delay..=self.max_delay)
+        }
+    }
+
+    /// Moves the messages waiting in the inbox in flight. Returns `TransportError::Disconnected`
+    /// if every sender has gone away and no message is left to deliver.
+    fn drain_inbox(&mut self) -> Result<(), TransportError> {
+        loop {
+            match self.inbox.try_recv() {
+                Ok(datagram) => self.in_flight.push(datagram),
+                Err(TryRecvError::Empty) => return Ok(()),
+                Err(TryRecvError::Disconnected) if self.in_flight.is_empty() => {
+                    return Err(TransportError::Disconnected);
+                }
+                Err(TryRecvError::Disconnected) => return Ok(()),
+            }
+        }
+    }
+
+    /// Removes the earliest message in flight that is due at `now`.
+    fn take_due(&mut self, now: Instant) -> Option<Datagram<C>> {
+        let (index, _) = self
+            .in_flight
+            .iter()
+            .enumerate()
+            .filter(|(_, datagram)| datagram.deliver_at <= now)
+            .min_by_key(|(_, datagram)| datagram.deliver_at)?;
+        Some(self.in_flight.swap_remove(index))
+    }
+}
+
+impl<C: Ciphersuite> Transport<C> for SimTransport<C> {
+    fn send(&mut self, to: Party<C>, message: GroupMessage<C>) -> Result<(), TransportError> {
+        if !self.peers.iter().any(|(party, _)| *party == to) {
+            return Err(TransportError::UnknownRecipient);
+        }
+        // a lost message is not reported to the sender, as on a real network
+        if self.rng.gen_bool(self.loss) {
+            return Ok(());
+        }
+
+        let deliver_at = Instant::now() + self.delay();
+        let (_, sender) = self
+            .peers
+            .iter()
+            .find(|(party, _)| *party == to)
+            .expect("the recipient is a peer");
+        sender
+            .send(Datagram {
+                from: self.party,
+                deliver_at,
+                bytes: message.to_bytes(),
+            })
+            .map_err(|_| TransportError::Disconnected)
+    }
+
+    /// Waits up to `timeout` for the next message that is due. Messages that do not parse are
+    /// dropped.
+    fn recv(&mut self, timeout: Duration) -> Result<(Party<C>, GroupMessage<C>), TransportError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            self.drain_inbox()?;
+            let now = Instant::now();
+            if let Some(datagram) = self.take_due(now) {
+                match GroupMessage::from_bytes(&datagram.bytes) {
+                    Ok(message) => return Ok((datagram.from, message)),
+                    Err(_) => continue,
+                }
+            }
+            if now >= deadline {
+                return Err(TransportError::Timeout);
+            }
+            thread::sleep(POLL_INTERVAL.min(deadline - now));
+        }
+    }
+}
+
+#[test]
+fn test_sim_network() {
+    use crate::{
+        coordinator::Coordinator,
+        error::{FrostError, Round},
+        frost::Frost,
+        transport::{coordinate, participate},
+    };
+
+    let frost: Frost = Frost::signature_share(2, 3);
+    let participants: Vec<NonZeroScalar> = frost.participants().collect();
+    let timeout = Duration::from_millis(500);
+
+    // delayed messages still make a signature
+    let mut network = SimNetwork::new()
+        .with_delay(Duration::from_millis(1), Duration::from_millis(20))
+        .with_seed(7);
+    let (mut transport, signer_transports) = network.connect(&participants);
+    let handles: Vec<_> = frost
+        .signers()
+        .cloned()
+        .zip(signer_transports)
+        .map(|(mut signer, mut transport)| {
+            signer.refresh_nonces();
+            thread::spawn(move || participate(&mut signer, &mut transport, timeout))
+        })
+        .collect();
+    let mut coordinator =
+        Coordinator::new(frost.group_pk, b"testing", participants.clone(), timeout);
+    let signature = coordinate(&mut coordinator, &mut transport).unwrap();
+    assert!(frost.verify(b"testing", signature));
+    for handle in handles {
+        assert!(handle.join().unwrap().is_ok());
+    }
+
+    // on a network that loses every message, the first round times out
+    let mut network = SimNetwork::new().with_loss(1.0).with_seed(7);
+    let (mut transport, signer_transports) = network.connect(&participants);
+    let handles: Vec<_> = frost
+        .signers()
+        .cloned()
+        .zip(signer_transports)
+        .map(|(mut signer, mut transport)| {
+            signer.refresh_nonces();
+            thread::spawn(move || participate(&mut signer, &mut transport, timeout))
+        })
+        .collect();
+    let mut coordinator =
+        Coordinator::new(frost.group_pk, b"testing", participants.clone(), timeout);
+    match coordinate(&mut coordinator, &mut transport) {
+        Err(FrostError::Timeout { round, missing }) => {
+            assert_eq!(round, Round::Commitment);
+            assert_eq!(missing.len(), participants.len());
+        }
+        result => panic!("unexpected result {result:?}"),
+    }
+    drop(handles);
+
+    let (mut transport, _) = network.connect(&participants);
+    assert_eq!(
+        transport.recv(Duration::from_millis(10)).unwrap_err(),
+        TransportError::Timeout
+    );
+}
@@ -0,0 +1,688 @@
+//! This module implements export and import of secrets as SLIP-0039 mnemonics.
+//!
+//! SLIP-0039 splits a master secret into mnemonic shares over GF(256), in two levels: the secret
+//! is split among groups, and each group's share among its members. Every mnemonic carries the
+//! group and member thresholds, so that a wallet can tell how many more shares it needs. The
+//! master secret is encrypted with a passphrase before it is split.
+//!
+//! A signer can export the secret share of its `KeyPackage` this way, and keep it in the same
+//! kind of backup as the seed of a hardware wallet. The mnemonics of a secret share are not a
+//! FROST sharing: any holder of enough of them recovers the signer's whole share.
+//!
+//! Reference: <https://github.com/satoshilabs/slips/blob/master/slip-0039.md>
+mod wordlist;
+
+use std::{collections::BTreeMap, fmt};
+
+use ark_std::rand::Rng;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    ciphersuite::{Ciphersuite, Element},
+    frost::SignerRole,
+    helper::{Decoder, NonZeroScalar, constant_time_eq, serialize_scalar},
+    keys::{GroupPublicKey, KeyPackage, SecretShare, VerificationShare},
+};
+
+use wordlist::WORDLIST;
+
+/// Rounds of the Feistel network encrypting the master secret.
+const ROUND_COUNT: u8 = 4;
+/// PBKDF2 iterations of each round, multiplied by `2^e` for the iteration exponent `e`.
+const BASE_ITERATION_COUNT: u32 = 2500;
+/// Index of the share holding the digest of the secret.
+const DIGEST_INDEX: u8 = 254;
+/// Index of the share holding the secret.
+const SECRET_INDEX: u8 = 255;
+const DIGEST_LENGTH: usize = 4;
+/// Words of the identifier, the iteration exponent, the group and the member parameters.
+const METADATA_WORDS: usize = 4;
+const CHECKSUM_WORDS: usize = 3;
+/// The generator of the RS1024 checksum.
+const CHECKSUM_GENERATOR: [u32; 10] = [
+    0xE0E040, 0x1C1C080, 0x3838100, 0x7070200, 0xE0E0009, 0x1C0C2412, 0x38086C24, 0x3090FC48,
+    0x21B1F890, 0x3F3F120,
+];
+
+/// Errors returned while splitting a secret into mnemonics or combining them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Slip39Error {
+    /// The secret, thresholds, group sizes or passphrase cannot be shared.
+    InvalidParameters(String),
+    /// A mnemonic contains a word that is not in the wordlist.
+    UnknownWord(String),
+    /// A mnemonic has an invalid length or padding.
+    InvalidMnemonic,
+    /// The checksum of a mnemonic does not match.
+    InvalidChecksum,
+    /// The mnemonics do not belong to the same set of shares, or disagree on its parameters.
+    InconsistentShares(String),
+    /// Fewer shares than the thresholds require.
+    InsufficientShares,
+    /// The recovered secret does not match its digest, e.g. because a share is wrong.
+    InvalidDigest,
+    /// The recovered secret is not a valid secret share.
+    InvalidSecretShare,
+}
+
+impl fmt::Display for Slip39Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Slip39Error::InvalidParameters(reason) => write!(f, "invalid parameters: {reason}"),
+            Slip39Error::UnknownWord(word) => write!(f, "unknown word {word:?}"),
+            Slip39Error::InvalidMnemonic => write!(f, "invalid mnemonic length or padding"),
+            Slip39Error::InvalidChecksum => write!(f, "invalid mnemonic checksum"),
+            Slip39Error::InconsistentShares(reason) => write!(f, "inconsistent shares: {reason}"),
+            Slip39Error::InsufficientShares => write!(f, "not enough shares to recover the secret"),
+            Slip39Error::InvalidDigest => write!(f, "recovered secret does not match its digest"),
+            Slip39Error::InvalidSecretShare => write!(f, "recovered secret is not a secret share"),
+        }
+    }
+}
+
+impl std::error::Error for Slip39Error {}
+
+/// A single mnemonic share, decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Share {
+    identifier: u16,
+    extendable: bool,
+    iteration_exponent: u8,
+    group_index: u8,
+    group_threshold: u8,
+    group_count: u8,
+    member_index: u8,
+    member_threshold: u8,
+    value: Vec<u8>,
+}
+
+impl Share {
+    fn to_mnemonic(&self) -> String {
+        let header = (u32::from(self.identifier) << 5)
+            | (u32::from(self.extendable) << 4)
+            | u32::from(self.iteration_exponent);
+        let parameters = (u32::from(self.group_index) << 16)
+            | (u32::from(self.group_threshold - 1) << 12)
+            | (u32::from(self.group_count - 1) << 8)
+            | (u32::from(self.member_index) << 4)
+            | u32::from(self.member_threshold - 1);
+
+        let mut words = vec![
+            header >> 10,
+            header & 0x3FF,
+            parameters >> 10,
+            parameters & 0x3FF,
+        ];
+        words.extend(bytes_to_words(&self.value));
+        let checksum = create_checksum(self.extendable, &words);
+        words.extend([checksum >> 20, (checksum >> 10) & 0x3FF, checksum & 0x3FF]);
+
+        words
+            .iter()
+            .map(|word| WORDLIST[*word as usize])
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn from_mnemonic(mnemonic: &str) -> Result<Self, Slip39Error> {
+        let words = mnemonic
+            .split_whitespace()
+            .map(|word| {
+                let word = word.to_lowercase();
+                WORDLIST
+                    .binary_search(&word.as_str())
+                    .map(|index| index as u32)
+                    .map_err(|_| Slip39Error::UnknownWord(word))
+            })
+            .collect::<Result<Vec<u32>, _>>()?;
+
+        // the shortest secret is 128 bits, in 13 words
+        let value_words = words
+            .len()
+            .checked_sub(METADATA_WORDS + CHECKSUM_WORDS)
+            .filter(|value_words| *value_words >= 13)
+            .ok_or(Slip39Error::InvalidMnemonic)?;
+        if value_words * 10 % 16 > 8 {
+            return Err(Slip39Error::InvalidMnemonic);
+        }
+
+        let header = (words[0] << 10) | words[1];
+        let extendable = (header >> 4) & 1 == 1;
+        if !verify_checksum(extendable, &words) {
+            return Err(Slip39Error::InvalidChecksum);
+        }
+
+        let parameters = (words[2] << 10) | words[3];
+        let share = Share {
+            identifier: (header >> 5) as u16,
+            extendable,
+            iteration_exponent: (header & 0xF) as u8,
+            group_index: (parameters >> 16) as u8,
+            group_threshold: ((parameters >> 12) & 0xF) as u8 + 1,
+            group_count: ((parameters >> 8) & 0xF) as u8 + 1,
+            member_index: ((parameters >> 4) & 0xF) as u8,
+            member_threshold: (parameters & 0xF) as u8 + 1,
+            value: words_to_bytes(&words[METADATA_WORDS..words.len() - CHECKSUM_WORDS])
+                .ok_or(Slip39Error::InvalidMnemonic)?,
+        };
+        if share.group_threshold > share.group_count {
+            return Err(Slip39Error::InvalidMnemonic);
+        }
+        Ok(share)
+    }
+}
+
+/// Splits `master_secret` into mnemonics, encrypted with `passphrase`.
+///
+/// The secret is split among `groups`, any `group_threshold` of which recover it. Each group is
+/// given as `(member_threshold, member_count)`, and the mnemonics are returned by group. The
+/// encryption runs `10000 * 2^iteration_exponent` iterations of PBKDF2.
+///
+/// Returns an error if the secret is shorter than 128 bits or of odd length, if the passphrase
+/// is not printable ASCII, or if the thresholds and counts are out of range.
+pub fn split_master_secret<R: Rng>(
+    master_secret: &[u8],
+    passphrase: &[u8],
+    group_threshold: u8,
+    groups: &[(u8, u8)],
+    iteration_exponent: u8,
+    rng: &mut R,
+) -> Result<Vec<Vec<String>>, Slip39Error> {
+    let invalid = |reason: &str| Err(Slip39Error::InvalidParameters(reason.to_string()));
+    if master_secret.len() < 16 || !master_secret.len().is_multiple_of(2) {
+        return invalid("the secret must be an even number of bytes, at least 16");
+    }
+    if !passphrase.iter().all(|byte| (32..=126).contains(byte)) {
+        return invalid("the passphrase must be printable ASCII");
+    }
+    if iteration_exponent > 15 {
+        return invalid("the iteration exponent must be at most 15");
+    }
+    if group_threshold == 0 || usize::from(group_threshold) > groups.len() || groups.len() > 16 {
+        return invalid(
+            "the group threshold must be between 1 and the number of groups, at most 16",
+        );
+    }
+    for (member_threshold, member_count) in groups {
+        if *member_threshold == 0 || member_threshold > member_count || *member_count > 16 {
+            return invalid("a member threshold must be between 1 and the group size, at most 16");
+        }
+        if *member_threshold == 1 && *member_count > 1 {
+            return invalid("a group with member threshold 1 must have a single member");
+        }
+    }
+
+    let identifier = rng.gen_range(0..1 << 15);
+    let encrypted = encrypt(
+        master_secret,
+        passphrase,
+        iteration_exponent,
+        &salt(identifier, false),
+    );
+
+    let group_shares = split_secret(group_threshold, groups.len() as u8, &encrypted, rng);
+    Ok(groups
+        .iter()
+        .zip(group_shares)
+        .map(
+            |((member_threshold, member_count), (group_index, group_secret))| {
+                split_secret(*member_threshold, *member_count, &group_secret, rng)
+                    .into_iter()
+                    .map(|(member_index, value)| {
+                        Share {
+                            identifier,
+                            extendable: false,
+                            iteration_exponent,
+                            group_index,
+                            group_threshold,
+                            group_count: groups.len() as u8,
+                            member_index,
+                            member_threshold: *member_threshold,
+                            value,
+                        }
+                        .to_mnemonic()
+                    })
+                    .collect()
+            },
+        )
+        .collect())
+}
+
+/// Recovers the master secret from `mnemonics` and the `passphrase` they were encrypted with.
+///
+/// A wrong passphrase cannot be detected: it recovers a different secret.
+///
+/// Returns an error if a mnemonic is invalid, if the mnemonics are not from the same set of
+/// shares, or if there are not enough of them.
+pub fn combine_mnemonics(mnemonics: &[&str], passphrase: &[u8]) -> Result<Vec<u8>, Slip39Error> {
+    let shares = mnemonics
+        .iter()
+        .map(|mnemonic| Share::from_mnemonic(mnemonic))
+        .collect::<Result<Vec<_>, _>>()?;
+    let first = shares.first().ok_or(Slip39Error::InsufficientShares)?;
+    let inconsistent = |reason: &str| Err(Slip39Error::InconsistentShares(reason.to_string()));
+    if shares.iter().any(|share| {
+        (share.identifier, share.extendable, share.iteration_exponent)
+            != (first.identifier, first.extendable, first.iteration_exponent)
+    }) {
+        return inconsistent("the mnemonics are from different sets of shares");
+    }
+    if shares.iter().any(|share| {
+        (share.group_threshold, share.group_count) != (first.group_threshold, first.group_count)
+    }) {
+        return inconsistent("the mnemonics disagree on the group parameters");
+    }
+    if shares
+        .iter()
+        .any(|share| share.value.len() != first.value.len())
+    {
+        return inconsistent("the mnemonics have different lengths");
+    }
+
+    let mut groups: BTreeMap<u8, Vec<&Share>> = BTreeMap::new();
+    for share in &shares {
+        groups.entry(share.group_index).or_default().push(share);
+    }
+
+    let mut group_shares = Vec::new();
+    for members in groups.values() {
+        let member_threshold = members[0].member_threshold;
+        if members
+            .iter()
+            .any(|share| share.member_threshold != member_threshold)
+        {
+            return inconsistent("the mnemonics of a group disagree on its threshold");
+        }
+        let mut member_shares: Vec<(u8, Vec<u8>)> = members
+            .iter()
+            .map(|share| (share.member_index, share.value.clone()))
+            .collect();
+        member_shares.sort();
+        member_shares.dedup();
+        if member_shares.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+            return inconsistent("two mnemonics have the same index but different values");
+        }
+        if member_shares.len() >= usize::from(member_threshold) {
+            member_shares.truncate(usize::from(member_threshold));
+            group_shares.push((
+                members[0].group_index,
+                recover_secret(member_threshold, &member_shares)?,
+            ));
+        }
+    }
+
+    if group_shares.len() < usize::from(first.group_threshold) {
+        return Err(Slip39Error::InsufficientShares);
+    }
+    group_shares.truncate(usize::from(first.group_threshold));
+    let encrypted = recover_secret(first.group_threshold, &group_shares)?;
+
+    Ok(decrypt(
+        &encrypted,
+        passphrase,
+        first.iteration_exponent,
+        &salt(first.identifier, first.extendable),
+    ))
+}
+
+impl<C: Ciphersuite> KeyPackage<C> {
+    /// Exports the secret share as SLIP-0039 mnemonics, split and encrypted as in
+    /// `split_master_secret`, with an iteration exponent of 1.
+    pub fn to_slip39<R: Rng>(
+        &self,
+        passphrase: &[u8],
+        group_threshold: u8,
+        groups: &[(u8, u8)],
+        rng: &mut R,
+    ) -> Result<Vec<Vec<String>>, Slip39Error> {
+        split_master_secret(
+            &serialize_scalar::<C>(&self.secret_share.to_scalar()),
+            passphrase,
+            group_threshold,
+            groups,
+            1,
+            rng,
+        )
+    }
+
+    /// Imports the secret share from SLIP-0039 `mnemonics` exported by `to_slip39`. The public
+    /// parts of the package are not in the mnemonics and must be given.
+    ///
+    /// Returns an error if the mnemonics do not recover a secret share, e.g. because the
+    /// passphrase is wrong.
+    pub fn from_slip39(
+        mnemonics: &[&str],
+        passphrase: &[u8],
+        identifier: NonZeroScalar<C>,
+        group_pk: GroupPublicKey<C>,
+        generator: Element<C>,
+        role: SignerRole,
+    ) -> Result<Self, Slip39Error> {
+        let master_secret = combine_mnemonics(mnemonics, passphrase)?;
+        let mut decoder = Decoder::new(&master_secret);
+        let secret_share = decoder
+            .scalar::<C>()
+            .and_then(|scalar| decoder.finish().map(|_| scalar))
+            .map_err(|_| Slip39Error::InvalidSecretShare)?;
+
+        Ok(KeyPackage {
+            identifier,
+            secret_share: SecretShare::new(secret_share),
+            verification_share: VerificationShare::new(generator * secret_share),
+            group_pk,
+            generator,
+            role,
+        })
+    }
+}
+
+/// Splits `secret` into `count` shares, any `threshold` of which recover it. The shares hold a
+/// digest of the secret at `DIGEST_INDEX`, to detect wrong shares when recovering.
+fn split_secret<R: Rng>(
+    threshold: u8,
+    count: u8,
+    secret: &[u8],
+    rng: &mut R,
+) -> Vec<(u8, Vec<u8>)> {
+    if threshold == 1 {
+        return (0..count).map(|index| (index, secret.to_vec())).collect();
+    }
+
+    let random_bytes = |rng: &mut R, len: usize| {
+        let mut bytes = vec![0; len];
+        rng.fill_bytes(&mut bytes);
+        bytes
+    };
+    let mut shares: Vec<(u8, Vec<u8>)> = (0..threshold - 2)
+        .map(|index| (index, random_bytes(rng, secret.len())))
+        .collect();
+    let random_part = random_bytes(rng, secret.len() - DIGEST_LENGTH);
+    let mut digest = hmac_sha256(&random_part, secret)[..DIGEST_LENGTH].to_vec();
+    digest.extend(random_part);
+
+    let mut base_shares = shares.clone();
+    base_shares.push((DIGEST_INDEX, digest));
+    base_shares.push((SECRET_INDEX, secret.to_vec()));
+    for index in threshold - 2..count {
+        shares.push((index, interpolate(&base_shares, index)));
+    }
+    shares
+}
+
+fn recover_secret(threshold: u8, shares: &[(u8, Vec<u8>)]) -> Result<Vec<u8>, Slip39Error> {
+    if threshold == 1 {
+        return Ok(shares[0].1.clone());
+    }
+
+    let secret = interpolate(shares, SECRET_INDEX);
+    let digest = interpolate(shares, DIGEST_INDEX);
+    let (digest, random_part) = digest.split_at(DIGEST_LENGTH);
+    if !constant_time_eq(&hmac_sha256(random_part, &secret)[..DIGEST_LENGTH], digest) {
+        return Err(Slip39Error::InvalidDigest);
+    }
+    Ok(secret)
+}
+
+/// Evaluates at `x` the polynomial through `shares`, byte by byte, with Lagrange interpolation.
+fn interpolate(shares: &[(u8, Vec<u8>)], x: u8) -> Vec<u8> {
+    if let Some((_, value)) = shares.iter().find(|(index, _)| *index == x) {
+        return value.clone();
+    }
+
+    let mut result = vec![0; shares[0].1.len()];
+    for (i, (xi, yi)) in shares.iter().enumerate() {
+        // in GF(256), subtraction is xor
+        let basis = shares
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .fold(1, |basis, (_, (xj, _))| {
+                gf_multiply(basis, gf_multiply(x ^ xj, gf_inverse(xi ^ xj)))
+            });
+        for (byte, y) in result.iter_mut().zip(yi) {
+            *byte ^= gf_multiply(basis, *y);
+        }
+    }
+    result
+}
+
+/// Multiplies in GF(256) modulo `x^8 + x^4 + x^3 + x + 1`.
+fn gf_multiply(mut x: u8, mut y: u8) -> u8 {
+    let mut product = 0;
+    while y != 0 {
+        if y & 1 == 1 {
+            product ^= x;
+        }
+        x = (x << 1) ^ if x & 0x80 != 0 { 0x1B } else { 0 };
+        y >>= 1;
+    }
+    product
+}
+
+/// Inverts a non-zero element of GF(256), as `x^254`.
+fn gf_inverse(x: u8) -> u8 {
+    (0..7)
+        .fold((1, gf_multiply(x, x)), |(result, square), _| {
+            (gf_multiply(result, square), gf_multiply(square, square))
+        })
+        .0
+}
+
+fn salt(identifier: u16, extendable: bool) -> Vec<u8> {
+    if extendable {
+        return Vec::new();
+    }
+    [b"shamir".as_slice(), &identifier.to_be_bytes()].concat()
+}
+
+/// Encrypts `master_secret` with a four round Feistel network keyed by the passphrase.
+fn encrypt(
+    master_secret: &[u8],
+    passphrase: &[u8],
+    iteration_exponent: u8,
+    salt: &[u8],
+) -> Vec<u8> {
+    feistel(
+        master_secret,
+        passphrase,
+        iteration_exponent,
+        salt,
+        0..ROUND_COUNT,
+    )
+}
+
+fn decrypt(encrypted: &[u8], passphrase: &[u8], iteration_exponent: u8, salt: &[u8]) -> Vec<u8> {
+    feistel(
+        encrypted,
+        passphrase,
+        iteration_exponent,
+        salt,
+        (0..ROUND_COUNT).rev(),
+    )
+}
+
+fn feistel(
+    input: &[u8],
+    passphrase: &[u8],
+    iteration_exponent: u8,
+    salt: &[u8],
+    rounds: impl Iterator<Item = u8>,
+) -> Vec<u8> {
+    let (left, right) = input.split_at(input.len() / 2);
+    let (mut left, mut right) = (left.to_vec(), right.to_vec());
+    let iterations = BASE_ITERATION_COUNT << iteration_exponent;
+    for round in rounds {
+        let password = [&[round], passphrase].concat();
+        let key = pbkdf2_hmac_sha256(&password, &[salt, &right].concat(), iterations, right.len());
+        let next = left.iter().zip(key).map(|(byte, key)| byte ^ key).collect();
+        left = std::mem::replace(&mut right, next);
+    }
+    [right, left].concat()
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; 64];
+    if key.len() > block.len() {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let pad = |byte: u8| block.iter().map(|key| key ^ byte).collect::<Vec<_>>();
+    let inner = Sha256::new()
+        .chain_update(pad(0x36))
+        .chain_update(message)
+        .finalize();
+    Sha256::new()
+        .chain_update(pad(0x5C))
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+fn pbkdf2_hmac_sha256(password: &[u8], salt: &[u8], iterations: u32, len: usize) -> Vec<u8> {
+    let mut output = Vec::with_capacity(len);
+    for block in 1u32.. {
+        if output.len() >= len {
+            break;
+        }
+        let mut u = hmac_sha256(password, &[salt, &block.to_be_bytes()].concat());
+        let mut t = u;
+        for _ in 1..iterations {
+            u = hmac_sha256(password, &u);
+            t.iter_mut().zip(u).for_each(|(t, u)| *t ^= u);
+        }
+        output.extend(t);
+    }
+    output.truncate(len);
+    output
+}
+
+/// Evaluates the RS1024 checksum polynomial over the customization string and `words`.
+fn polymod(extendable: bool, words: &[u32]) -> u32 {
+    let customization: &[u8] = if extendable {
+        b"shamir_extendable"
+    } else {
+        b"shamir"
+    };
+    customization
+        .iter()
+        .map(|byte| u32::from(*byte))
+        .chain(words.iter().copied())
+        .fold(1, |checksum, value| {
+            let top = checksum >> 20;
+            let checksum = ((checksum & 0xFFFFF) << 10) ^ value;
+            (0..10)
+                .filter(|i| (top >> i) & 1 == 1)
+                .fold(checksum, |checksum, i| checksum ^ CHECKSUM_GENERATOR[i])
+        })
+}
+
+fn create_checksum(extendable: bool, words: &[u32]) -> u32 {
+    polymod(extendable, &[words, &[0; CHECKSUM_WORDS]].concat()) ^ 1
+}
+
+fn verify_checksum(extendable: bool, words: &[u32]) -> bool {
+    polymod(extendable, words) == 1
+}
+
+/// Encodes `bytes` in 10-bit words, with leading zero bits to fill the first word.
+fn bytes_to_words(bytes: &[u8]) -> Vec<u32> {
+    let word_count = (bytes.len() * 8).div_ceil(10);
+    let padding = word_count * 10 - bytes.len() * 8;
+    let bits = std::iter::repeat_n(false, padding).chain(
+        bytes
+            .iter()
+            .flat_map(|byte| (0..8).rev().map(move |i| (byte >> i) & 1 == 1)),
+    );
+    bits.collect::<Vec<_>>()
+        .chunks(10)
+        .map(|word| word.iter().fold(0, |acc, bit| (acc << 1) | u32::from(*bit)))
+        .collect()
+}
+
+/// Decodes words encoded by `bytes_to_words`, or returns `None` if the padding is not zero.
+fn words_to_bytes(words: &[u32]) -> Option<Vec<u8>> {
+    let byte_count = words.len() * 10 / 16 * 2;
+    let padding = words.len() * 10 - byte_count * 8;
+    let bits: Vec<bool> = words
+        .iter()
+        .flat_map(|word| (0..10).rev().map(move |i| (word >> i) & 1 == 1))
+        .collect();
+    if bits[..padding].iter().any(|bit| *bit) {
+        return None;
+    }
+    Some(
+        bits[padding..]
+            .chunks(8)
+            .map(|byte| byte.iter().fold(0, |acc, bit| (acc << 1) | u8::from(*bit)))
+            .collect(),
+    )
+}
+
+#[test]
+fn test_slip39_vector() {
+    use crate::helper::encode_hex;
+
+    // the first test vector of SLIP-0039
+    let mnemonic = "duckling enlarge academic academic agency result length solution fridge kidney coal piece deal husband erode duke ajar critical decision keyboard";
+    assert_eq!(
+        encode_hex(&combine_mnemonics(&[mnemonic], b"TREZOR").unwrap()),
+        "bb54aac4b89dc868ba37d9cc21b2cece"
+    );
+    assert_eq!(
+        Share::from_mnemonic(mnemonic).unwrap().to_mnemonic(),
+        mnemonic
+    );
+
+    let tampered = mnemonic.replace("keyboard", "kidney");
+    assert_eq!(
+        combine_mnemonics(&[&tampered], b"TREZOR"),
+        Err(Slip39Error::InvalidChecksum)
+    );
+}
+
+#[test]
+fn test_key_package_slip39_roundtrip() {
+    use crate::frost::Frost;
+
+    let frost: Frost = Frost::signature_share(2, 3);
+    let key_package = frost
+        .key_package(frost.participants().next().unwrap())
+        .unwrap();
+    let mut rng = ark_std::test_rng();
+
+    // two of three groups: a single backup, a 2-of-3 and a 3-of-5
+    let groups = key_package
+        .to_slip39(b"passphrase", 2, &[(1, 1), (2, 3), (3, 5)], &mut rng)
+        .unwrap();
+    assert_eq!(groups.iter().map(Vec::len).collect::<Vec<_>>(), [1, 3, 5]);
+    assert_eq!(groups[0][0].split_whitespace().count(), 33);
+
+    let import = |mnemonics: &[&str], passphrase: &[u8]| {
+        KeyPackage::from_slip39(
+            mnemonics,
+            passphrase,
+            key_package.identifier,
+            key_package.group_pk,
+            key_package.generator,
+            key_package.role,
+        )
+    };
+    let mnemonics = [&groups[0][0], &groups[2][4], &groups[2][0], &groups[2][2]];
+    let mnemonics: Vec<&str> = mnemonics.iter().map(|mnemonic| mnemonic.as_str()).collect();
+    assert_eq!(import(&mnemonics, b"passphrase").unwrap(), key_package);
+    assert_ne!(
+        import(&mnemonics, b"wrong").map(|imported| imported.secret_share),
+        Ok(key_package.secret_share)
+    );
+    assert_eq!(
+        import(&mnemonics[..3], b"passphrase"),
+        Err(Slip39Error::InsufficientShares)
+    );
+
+    assert!(matches!(
+        split_master_secret(&[0; 32], b"", 1, &[(1, 2)], 0, &mut rng),
+        Err(Slip39Error::InvalidParameters(_))
+    ));
+}
@@ -0,0 +1,242 @@
+//! This module implements a SOCKS5 client, to reach a custodian through Tor.
+//!
+//! Blinding hides the message from the custodian, but not the network identity of the user who
+//! requests the co-signature. Connecting through a SOCKS5 proxy, typically the local Tor client,
+//! hides it too: host names, including `.onion` addresses, are resolved by the proxy, so no DNS
+//! request leaks from the wallet. A custodian is reachable as an onion service by pointing a
+//! `HiddenServicePort` of the Tor configuration at the address it listens on.
+//!
+//! Reference: https://www.rfc-editor.org/rfc/rfc1928, https://www.rfc-editor.org/rfc/rfc1929
+use std::{
+    io::{self, Read, Write},
+    net::{Ipv4Addr, SocketAddr, TcpStream},
+    time::Duration,
+};
+
+const SOCKS_VERSION: u8 = 5;
+const METHOD_NO_AUTHENTICATION: u8 = 0x00;
+const METHOD_USERNAME_PASSWORD: u8 = 0x02;
+const METHOD_NONE_ACCEPTABLE: u8 = 0xFF;
+const COMMAND_CONNECT: u8 = 0x01;
+const ADDRESS_IPV4: u8 = 0x01;
+const ADDRESS_DOMAIN: u8 = 0x03;
+const ADDRESS_IPV6: u8 = 0x04;
+
+/// A SOCKS5 proxy to open connections through.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Socks5Proxy {
+    address: SocketAddr,
+    /// Username and password sent to the proxy, if any.
+    credentials: Option<(String, String)>,
+    timeout: Option<Duration>,
+}
+
+impl Socks5Proxy {
+    pub fn new(address: SocketAddr) -> Self {
+        Socks5Proxy {
+            address,
+            credentials: None,
+            timeout: None,
+        }
+    }
+
+    /// The SOCKS port of a local Tor client with its default configuration.
+    pub fn tor() -> Self {
+        Self::new(SocketAddr::from((Ipv4Addr::LOCALHOST, 9050)))
+    }
+
+    /// Authenticates with `username` and `password`. Tor does not check them, but routes
+    /// connections with different credentials over different circuits, so that the custodian
+    /// cannot link the sessions of one wallet by their exit or rendezvous circuit.
+    pub fn with_credentials(mut self, username: &str, password: &str) -> Self {
+        self.credentials = Some((username.to_string(), password.to_string()));
+        self
+    }
+
+    /// Fails the connection if it is not established within `timeout`, and every later read or
+    /// write that takes longer.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Connects to `host` on `port` through the proxy, which resolves `host` itself.
+    pub fn connect(&self, host: &str, port: u16) -> io::Result<TcpStream> {
+        let host_len = u8::try_from(host.len())
+            .map_err(|_| invalid_input("host names are at most 255 bytes"))?;
+        let mut stream = match self.timeout {
+            Some(timeout) => TcpStream::connect_timeout(&self.address, timeout)?,
+            None => TcpStream::connect(self.address)?,
+        };
+        stream.set_read_timeout(self.timeout)?;
+        stream.set_write_timeout(self.timeout)?;
+
+        let method = match self.credentials {
+            Some(_) => METHOD_USERNAME_PASSWORD,
+            None => METHOD_NO_AUTHENTICATION,
+        };
+        stream.write_all(&[SOCKS_VERSION, 1, method])?;
+        let mut reply = [0u8; 2];
+        stream.read_exact(&mut reply)?;
+        if reply[0] != SOCKS_VERSION {
+            return Err(proxy_error("not a SOCKS5 proxy"));
+        }
+        match reply[1] {
+            METHOD_NO_AUTHENTICATION if self.credentials.is_none() => {}
+            METHOD_USERNAME_PASSWORD => self.authenticate(&mut stream)?,
+            METHOD_NONE_ACCEPTABLE => {
+                return Err(proxy_error(
+                    "the proxy requires another authentication method",
+                ));
+            }
+            _ => return Err(proxy_error("the proxy selected an unexpected method")),
+        }
+
+        let mut request = vec![SOCKS_VERSION, COMMAND_CONNECT, 0, ADDRESS_DOMAIN, host_len];
+        request.extend_from_slice(host.as_bytes());
+        request.extend_from_slice(&port.to_be_bytes());
+        stream.write_all(&request)?;
+
+        let mut reply = [0u8; 4];
+        stream.read_exact(&mut reply)?;
+        if reply[0] != SOCKS_VERSION {
+            return Err(proxy_error("not a SOCKS5 proxy"));
+        }
+        if reply[1] != 0 {
+            return Err(proxy_error(reply_message(reply[1])));
+        }
+        // skip the address the proxy bound to, which is of no use to the client
+        let address_len = match reply[3] {
+            ADDRESS_IPV4 => 4,
+            ADDRESS_IPV6 => 16,
+            ADDRESS_DOMAIN => {
+                let mut len = [0u8; 1];
+                stream.read_exact(&mut len)?;
+                usize::from(len[0])
+            }
+            _ => {
+                return Err(proxy_error(
+                    "the proxy replied with an unknown address type",
+                ));
+            }
+        };
+        stream.read_exact(&mut vec![0u8; address_len + 2])?;
+
+        Ok(stream)
+    }
+
+    fn authenticate(&self, stream: &mut TcpStream) -> io::Result<()> {
+        let (username, password) = self
+            .credentials
+            .as_ref()
+            .ok_or_else(|| proxy_error("the proxy requires credentials"))?;
+        let username_len = u8::try_from(username.len())
+            .map_err(|_| invalid_input("usernames are at most 255 bytes"))?;
+        let password_len = u8::try_from(password.len())
+            .map_err(|_| invalid_input("passwords are at most 255 bytes"))?;
+
+        let mut request = vec![1, username_len];
+        request.extend_from_slice(username.as_bytes());
+        request.push(password_len);
+        request.extend_from_slice(password.as_bytes());
+        stream.write_all(&request)?;
+
+        let mut reply = [0u8; 2];
+        stream.read_exact(&mut reply)?;
+        if reply[1] != 0 {
+            return Err(proxy_error("the proxy rejected the credentials"));
+        }
+        Ok(())
+    }
+}
+
+fn reply_message(code: u8) -> &'static str {
+    match code {
+        0x01 => "general SOCKS server failure",
+        0x02 => "connection not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unknown SOCKS error",
+    }
+}
+
+fn proxy_error(reason: &str) -> io::Error {
+    io::Error::other(format!("SOCKS5: {reason}"))
+}
+
+fn invalid_input(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, reason)
+}
+
+/// Serves a single SOCKS5 connection on `listener` without authentication, relaying it to the
+/// IPv4 or IPv6 address written as the requested host.
+#[cfg(test)]
+fn relay_once(listener: std::net::TcpListener) -> io::Result<()> {
+    use std::net::{IpAddr, Ipv6Addr};
+
+    let (mut client, _) = listener.accept()?;
+    let mut greeting = [0u8; 3];
+    client.read_exact(&mut greeting)?;
+    assert_eq!(greeting, [SOCKS_VERSION, 1, METHOD_NO_AUTHENTICATION]);
+    client.write_all(&[SOCKS_VERSION, METHOD_NO_AUTHENTICATION])?;
+
+    let mut request = [0u8; 5];
+    client.read_exact(&mut request)?;
+    assert_eq!(request[3], ADDRESS_DOMAIN);
+    let mut host = vec![0u8; usize::from(request[4])];
+    client.read_exact(&mut host)?;
+    let mut port = [0u8; 2];
+    client.read_exact(&mut port)?;
+    let host: IpAddr = String::from_utf8(host).unwrap().parse().unwrap();
+    let mut target = TcpStream::connect((host, u16::from_be_bytes(port)))?;
+
+    let bound = Ipv6Addr::UNSPECIFIED.octets();
+    client.write_all(&[&[SOCKS_VERSION, 0, 0, ADDRESS_IPV6][..], &bound, &[0, 0]].concat())?;
+
+    let (mut client_reader, mut target_writer) = (client.try_clone()?, target.try_clone()?);
+    std::thread::spawn(move || io::copy(&mut client_reader, &mut target_writer));
+    io::copy(&mut target, &mut client)?;
+    Ok(())
+}
+
+#[test]
+fn test_custodian_over_socks5() {
+    use std::{net::TcpListener, thread};
+
+    use crate::{
+        client::CustodianClient,
+        custodian::{Custodian, CustodianPolicy, serve},
+        frost::Frost,
+    };
+
+    let frost: Frost = Frost::signature_share(2, 3);
+    let identifier = frost.participants().last().unwrap();
+    let policy = CustodianPolicy {
+        max_signatures: 1,
+        window: Duration::from_secs(60),
+        max_pending: 1,
+        pending_ttl: Duration::from_secs(60),
+        digest_only: false,
+    };
+    let custodian = Custodian::new(frost.key_package(identifier).unwrap(), policy);
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let custodian_address = listener.local_addr().unwrap();
+    thread::spawn(move || serve(custodian, listener));
+
+    let proxy_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let proxy =
+        Socks5Proxy::new(proxy_listener.local_addr().unwrap()).with_timeout(Duration::from_secs(5));
+    thread::spawn(move || relay_once(proxy_listener));
+
+    let mut client = CustodianClient::connect_via(
+        &proxy,
+        &custodian_address.ip().to_string(),
+        custodian_address.port(),
+    )
+    .unwrap();
+    assert!(client.ping::<crate::ciphersuite::Ed25519Sha512>().is_ok());
+}
@@ -0,0 +1,123 @@
+//! This module implements signatures for Solana's ed25519 program.
+//!
+//! Solana verifies ed25519 signatures as specified in RFC 8032: over the standard basepoint, with
+//! the challenge `SHA-512(R || A || M)` and the 64-byte `R || s` encoding. FROST(Ed25519, SHA-512)
+//! produces exactly such signatures when the group uses the standard basepoint, which
+//! `solana_group` sets up.
+//!
+//! Reference: https://www.rfc-editor.org/rfc/rfc8032.html#section-5.1.7
+#![allow(non_snake_case)]
+
+use ark_ec::PrimeGroup;
+use ark_ed25519::{EdwardsProjective, Fr};
+use ark_ff::PrimeField;
+use sha2::{Digest, Sha512};
+
+use crate::{
+    ciphersuite::{Ciphersuite, Ed25519Sha512},
+    frost::{Frost, SignerRole},
+    helper::secure_rng,
+    schnorr::{SIGNATURE_LENGTH, SchnorrSignature},
+};
+
+/// Instantiates a FROST group over the standard ed25519 basepoint, so that its signatures verify
+/// as plain ed25519 signatures. The signer with index `i` is assigned `roles[i - 1]`.
+///
+/// # Panics
+///
+/// Panics if the signers marked as blind could reach `threshold` on their own.
+pub fn solana_group(threshold: usize, roles: &[SignerRole]) -> Frost<Ed25519Sha512> {
+    Frost::signature_share_with_rng(
+        threshold,
+        roles,
+        EdwardsProjective::generator(),
+        &mut secure_rng(),
+    )
+}
+
+/// Returns the 32-byte ed25519 public key of the group, i.e. its Solana address.
+pub fn public_key(frost: &Frost<Ed25519Sha512>) -> [u8; 32] {
+    Ed25519Sha512::serialize_element(&frost.group_pk.to_element())
+        .try_into()
+        .expect("ed25519 elements are 32 bytes")
+}
+
+/// Encodes `signature` in the 64-byte layout expected by Solana's ed25519 program.
+pub fn to_solana_signature(signature: &SchnorrSignature<Ed25519Sha512>) -> [u8; SIGNATURE_LENGTH] {
+    signature
+        .to_bytes()
+        .try_into()
+        .expect("ed25519 signatures are 64 bytes")
+}
+
+/// Verifies an ed25519 signature as specified in RFC 8032, independently of any `Frost` group.
+///
+/// Rejects non-canonical encodings of `R`, `s` and the public key.
+pub fn verify_solana_signature(
+    public_key: &[u8; 32],
+    message: &[u8],
+    signature: &[u8; SIGNATURE_LENGTH],
+) -> bool {
+    let Ok(A) = Ed25519Sha512::deserialize_element(public_key) else {
+        return false;
+    };
+    let Ok(signature) = SchnorrSignature::<Ed25519Sha512>::from_bytes(signature) else {
+        return false;
+    };
+    if Ed25519Sha512::serialize_element(&A)[..] != public_key[..] {
+        return false;
+    }
+
+    let challenge = Sha512::new()
+        .chain_update(Ed25519Sha512::serialize_element(&signature.R))
+        .chain_update(public_key)
+        .chain_update(message)
+        .finalize();
+    let k = Fr::from_le_bytes_mod_order(&challenge);
+
+    EdwardsProjective::generator() * signature.s == signature.R + A * k
+}
+
+#[test]
+fn test_rfc8032_signature_verifies() {
+    use crate::helper::decode_hex;
+
+    // RFC 8032, section 7.1, TEST 1
+    let rfc_public_key =
+        decode_hex("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a")
+            .unwrap()
+            .try_into()
+            .unwrap();
+    let signature = decode_hex(concat!(
+        "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e06522490155",
+        "5fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b"
+    ))
+    .unwrap()
+    .try_into()
+    .unwrap();
+    assert!(verify_solana_signature(&rfc_public_key, b"", &signature));
+    assert!(!verify_solana_signature(&rfc_public_key, b"x", &signature));
+
+    // a threshold signature of a group over the standard basepoint verifies the same way
+    let mut frost = solana_group(
+        2,
+        &[SignerRole::Regular, SignerRole::Regular, SignerRole::Blind],
+    );
+    let signing_package = frost.signing_package(b"transfer");
+    let (R, _) = signing_package
+        .group_commitment_and_challenge(frost.group_pk)
+        .unwrap();
+    let sig_shares: Vec<_> = frost
+        .signers_mut()
+        .map(|signer| signer.sign(&signing_package).unwrap())
+        .collect();
+    let signature = SchnorrSignature {
+        R,
+        s: frost.signature_aggregate(sig_shares),
+    };
+    assert!(verify_solana_signature(
+        &public_key(&frost),
+        b"transfer",
+        &to_solana_signature(&signature)
+    ));
+}
@@ -0,0 +1,379 @@
+//! This module implements pluggable storage for the state of long-running signers and
+//! coordinators.
+//!
+//! A `Storage` keeps the key packages of the signers a service runs, the state of its pending
+//! sessions and, as a `NonceLedger`, the nonce commitments it issued and used. Session states are
+//! stored as opaque bytes, encoded by whoever owns the session.
+//!
+//! Key packages and sessions are scoped by the `GroupId` of their group, so that one service can
+//! hold shares of many groups in a single storage.
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    ciphersuite::{Ciphersuite, Ed25519Sha512, ScalarField},
+    frost::SessionId,
+    helper::{NonZeroScalar, decode_hex, encode_hex},
+    keys::{GroupId, KeyPackage},
+    ledger::{FileNonceLedger, InMemoryNonceLedger, NonceLedger},
+};
+
+/// Storage of key packages, pending sessions and used nonces.
+pub trait Storage<C: Ciphersuite = Ed25519Sha512>: NonceLedger {
+    /// Stores `key_package` in the scope of its group, replacing any package with the same
+    /// identifier.
+    fn put_key_package(&mut self, key_package: &KeyPackage<C>) -> io::Result<()>;
+
+    fn get_key_package(
+        &self,
+        group_id: &GroupId,
+        identifier: NonZeroScalar<C>,
+    ) -> io::Result<Option<KeyPackage<C>>>;
+
+    /// Stores the `state` of a pending session of `group_id`, replacing any previous state.
+    fn put_session(
+        &mut self,
+        group_id: &GroupId,
+        session_id: SessionId,
+        state: &[u8],
+    ) -> io::Result<()>;
+
+    fn get_session(
+        &self,
+        group_id: &GroupId,
+        session_id: &SessionId,
+    ) -> io::Result<Option<Vec<u8>>>;
+
+    /// Removes a session once it has completed or been abandoned.
+    fn remove_session(&mut self, group_id: &GroupId, session_id: &SessionId) -> io::Result<()>;
+
+    /// Returns the identifiers of all stored sessions of `group_id`.
+    fn session_ids(&self, group_id: &GroupId) -> io::Result<Vec<SessionId>>;
+
+    /// Returns the groups with a stored key package or session.
+    fn group_ids(&self) -> io::Result<Vec<GroupId>>;
+}
+
+/// Storage kept in memory only, for services whose state does not outlive the process.
+#[derive(Debug)]
+pub struct InMemoryStorage<C: Ciphersuite = Ed25519Sha512> {
+    key_packages: BTreeMap<(GroupId, ScalarField<C>), KeyPackage<C>>,
+    sessions: BTreeMap<(GroupId, SessionId), Vec<u8>>,
+    nonces: InMemoryNonceLedger,
+}
+
+impl<C: Ciphersuite> InMemoryStorage<C> {
+    pub fn new() -> Self {
+        InMemoryStorage {
+            key_packages: BTreeMap::new(),
+            sessions: BTreeMap::new(),
+            nonces: InMemoryNonceLedger::new(),
+        }
+    }
+}
+
+impl<C: Ciphersuite> Default for InMemoryStorage<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Ciphersuite> NonceLedger for InMemoryStorage<C> {
+    fn issue(&mut self, commitments: &[u8]) -> io::Result<bool> {
+        self.nonces.issue(commitments)
+    }
+
+    fn consume(&mut self, commitments: &[u8]) -> io::Result<bool> {
+        self.nonces.consume(commitments)
+    }
+}
+
+impl<C: Ciphersuite> Storage<C> for InMemoryStorage<C> {
+    fn put_key_package(&mut self, key_package: &KeyPackage<C>) -> io::Result<()> {
+        self.key_packages.insert(
+            (
+                key_package.group_pk.group_id(),
+                key_package.identifier.to_scalar(),
+            ),
+            key_package.clone(),
+        );
+        Ok(())
+    }
+
+    fn get_key_package(
+        &self,
+        group_id: &GroupId,
+        identifier: NonZeroScalar<C>,
+    ) -> io::Result<Option<KeyPackage<C>>> {
+        Ok(self
+            .key_packages
+            .get(&(*group_id, identifier.to_scalar()))
+            .cloned())
+    }
+
+    fn put_session(
+        &mut self,
+        group_id: &GroupId,
+        session_id: SessionId,
+        state: &[u8],
+    ) -> io::Result<()> {
+        self.sessions
+            .insert((*group_id, session_id), state.to_vec());
+        Ok(())
+    }
+
+    fn get_session(
+        &self,
+        group_id: &GroupId,
+        session_id: &SessionId,
+    ) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.sessions.get(&(*group_id, *session_id)).cloned())
+    }
+
+    fn remove_session(&mut self, group_id: &GroupId, session_id: &SessionId) -> io::Result<()> {
+        self.sessions.remove(&(*group_id, *session_id));
+        Ok(())
+    }
+
+    fn session_ids(&self, group_id: &GroupId) -> io::Result<Vec<SessionId>> {
+        Ok(self
+            .sessions
+            .keys()
+            .filter(|(group, _)| group == group_id)
+            .map(|(_, session_id)| *session_id)
+            .collect())
+    }
+
+    fn group_ids(&self) -> io::Result<Vec<GroupId>> {
+        let mut group_ids: Vec<GroupId> = self
+            .key_packages
+            .keys()
+            .map(|(group_id, _)| *group_id)
+            .chain(self.sessions.keys().map(|(group_id, _)| *group_id))
+            .collect();
+        group_ids.sort();
+        group_ids.dedup();
+        Ok(group_ids)
+    }
+}
+
+/// Storage in a directory: for each group, one hex file per key package in
+/// `groups/<group id>/key-packages` and one file per session in `groups/<group id>/sessions`, and
+/// a `FileNonceLedger` shared by all groups in `nonces`.
+///
+/// Files are replaced atomically, by writing a temporary file and renaming it, so a crash never
+/// leaves a truncated key package or session behind.
+#[derive(Debug)]
+pub struct FileStorage {
+    directory: PathBuf,
+    nonces: FileNonceLedger,
+}
+
+impl FileStorage {
+    /// Opens the storage in `directory`, creating it if it does not exist.
+    pub fn open(directory: impl AsRef<Path>) -> io::Result<Self> {
+        let directory = directory.as_ref().to_path_buf();
+        fs::create_dir_all(directory.join("groups"))?;
+        let nonces = FileNonceLedger::open(directory.join("nonces"))?;
+        Ok(FileStorage { directory, nonces })
+    }
+
+    fn group_path(&self, group_id: &GroupId) -> PathBuf {
+        self.directory.join("groups").join(group_id.to_string())
+    }
+
+    fn key_package_path<C: Ciphersuite>(
+        &self,
+        group_id: &GroupId,
+        identifier: NonZeroScalar<C>,
+    ) -> PathBuf {
+        self.group_path(group_id)
+            .join("key-packages")
+            .join(identifier.to_string())
+    }
+
+    fn session_path(&self, group_id: &GroupId, session_id: &SessionId) -> PathBuf {
+        self.group_path(group_id)
+            .join("sessions")
+            .join(encode_hex(session_id))
+    }
+}
+
+impl NonceLedger for FileStorage {
+    fn issue(&mut self, commitments: &[u8]) -> io::Result<bool> {
+        self.nonces.issue(commitments)
+    }
+
+    fn consume(&mut self, commitments: &[u8]) -> io::Result<bool> {
+        self.nonces.consume(commitments)
+    }
+}
+
+impl<C: Ciphersuite> Storage<C> for FileStorage {
+    fn put_key_package(&mut self, key_package: &KeyPackage<C>) -> io::Result<()> {
+        let path = self.key_package_path(&key_package.group_pk.group_id(), key_package.identifier);
+        write_atomically(&path, &key_package.to_bytes())
+    }
+
+    fn get_key_package(
+        &self,
+        group_id: &GroupId,
+        identifier: NonZeroScalar<C>,
+    ) -> io::Result<Option<KeyPackage<C>>> {
+        let Some(bytes) = read_if_exists(&self.key_package_path(group_id, identifier))? else {
+            return Ok(None);
+        };
+        KeyPackage::from_bytes(&bytes)
+            .map(Some)
+            .map_err(|_| invalid_data("malformed key package"))
+    }
+
+    fn put_session(
+        &mut self,
+        group_id: &GroupId,
+        session_id: SessionId,
+        state: &[u8],
+    ) -> io::Result<()> {
+        write_atomically(&self.session_path(group_id, &session_id), state)
+    }
+
+    fn get_session(
+        &self,
+        group_id: &GroupId,
+        session_id: &SessionId,
+    ) -> io::Result<Option<Vec<u8>>> {
+        read_if_exists(&self.session_path(group_id, session_id))
+    }
+
+    fn remove_session(&mut self, group_id: &GroupId, session_id: &SessionId) -> io::Result<()> {
+        match fs::remove_file(self.session_path(group_id, session_id)) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        }
+    }
+
+    fn session_ids(&self, group_id: &GroupId) -> io::Result<Vec<SessionId>> {
+        let directory = self.group_path(group_id).join("sessions");
+        if !directory.exists() {
+            return Ok(Vec::new());
+        }
+        let mut session_ids = Vec::new();
+        for entry in fs::read_dir(directory)? {
+            let name = entry?.file_name();
+            // skips temporary files left by an interrupted write
+            let session_id = name
+                .to_str()
+                .and_then(decode_hex)
+                .and_then(|bytes| SessionId::try_from(bytes).ok());
+            if let Some(session_id) = session_id {
+                session_ids.push(session_id);
+            }
+        }
+        session_ids.sort();
+        Ok(session_ids)
+    }
+
+    fn group_ids(&self) -> io::Result<Vec<GroupId>> {
+        let mut group_ids = Vec::new();
+        for entry in fs::read_dir(self.directory.join("groups"))? {
+            if let Some(group_id) = entry?
+                .file_name()
+                .to_str()
+                .and_then(|name| name.parse().ok())
+            {
+                group_ids.push(group_id);
+            }
+        }
+        group_ids.sort();
+        Ok(group_ids)
+    }
+}
+
+/// Writes the hex encoding of `bytes` to `path`, replacing the file atomically.
+fn write_atomically(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let temporary = path.with_extension("tmp");
+    let mut file = fs::File::create(&temporary)?;
+    writeln!(file, "{}", encode_hex(bytes))?;
+    file.sync_data()?;
+    fs::rename(temporary, path)
+}
+
+fn read_if_exists(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    match fs::read_to_string(path) {
+        Ok(hex) => decode_hex(hex.trim())
+            .map(Some)
+            .ok_or_else(|| invalid_data("malformed hex")),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+fn invalid_data(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason)
+}
+
+#[test]
+fn test_file_storage_survives_restart() {
+    use crate::{frost::Frost, keys::GroupPublicKey};
+
+    let directory = std::env::temp_dir().join(format!("storage-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    let frost: Frost = Frost::signature_share(2, 3);
+    let identifier = frost.participants().next().unwrap();
+    let key_package = frost.key_package(identifier).unwrap();
+    let group_id = frost.group_pk.group_id();
+    let other_group = GroupPublicKey::<Ed25519Sha512>::new(frost.generator).group_id();
+
+    let mut storages: Vec<Box<dyn Storage>> = vec![
+        Box::new(InMemoryStorage::new()),
+        Box::new(FileStorage::open(&directory).unwrap()),
+    ];
+    for storage in storages.iter_mut() {
+        storage.put_key_package(&key_package).unwrap();
+        storage.put_session(&group_id, [1; 32], b"pending").unwrap();
+        storage
+            .put_session(&group_id, [2; 32], b"abandoned")
+            .unwrap();
+        storage.remove_session(&group_id, &[2; 32]).unwrap();
+        // sessions of another group do not show up in the scope of this one
+        storage
+            .put_session(&other_group, [3; 32], b"other")
+            .unwrap();
+        assert!(storage.issue(b"commitments").unwrap());
+        assert!(storage.consume(b"commitments").unwrap());
+    }
+
+    let storage = FileStorage::open(&directory).unwrap();
+    let stored: KeyPackage = storage
+        .get_key_package(&group_id, identifier)
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored, key_package);
+    let stored: Option<KeyPackage> = storage.get_key_package(&other_group, identifier).unwrap();
+    assert!(stored.is_none());
+    assert_eq!(
+        Storage::<Ed25519Sha512>::session_ids(&storage, &group_id).unwrap(),
+        vec![[1; 32]]
+    );
+    assert_eq!(
+        Storage::<Ed25519Sha512>::get_session(&storage, &group_id, &[1; 32]).unwrap(),
+        Some(b"pending".to_vec())
+    );
+    let mut group_ids = vec![group_id, other_group];
+    group_ids.sort();
+    assert_eq!(
+        Storage::<Ed25519Sha512>::group_ids(&storage).unwrap(),
+        group_ids
+    );
+    let mut storage = storage;
+    assert!(!storage.consume(b"commitments").unwrap());
+
+    fs::remove_dir_all(&directory).unwrap();
+}
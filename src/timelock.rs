@@ -0,0 +1,263 @@
+//! This module implements the timelocked recovery pattern for the custodian.
+//!
+//! The custodian only co-signs a message once a configured delay has passed since a user
+//! registered an `Intent` to sign it. During the countdown any authorized user can cancel the
+//! intent, so a user who lost a key share has time to stop an attacker holding it. Each intent
+//! allows a single signature.
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+use ark_serialize::SerializationError;
+use ark_std::rand::Rng;
+
+use crate::{
+    ciphersuite::{Ciphersuite, Ed25519Sha512, Element},
+    error::FrostError,
+    helper::{Decoder, NonZeroScalar, serialize_scalar},
+    keys::{KeyPackage, VerificationShare, verify_message_signature},
+    schnorr::SchnorrSignature,
+};
+
+/// Domain separator prefixed to the message signed for an intent.
+const INTENT_CONTEXT: &[u8] = b"FROST-CUSTODIAN-INTENT-v1";
+
+/// A user's signed request to register, or cancel, the intent to sign `message`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Intent<C: Ciphersuite = Ed25519Sha512> {
+    /// Identifier of the user that signed the intent.
+    pub identifier: NonZeroScalar<C>,
+    /// The message the custodian is asked to sign.
+    pub message: Vec<u8>,
+    /// Whether the intent cancels an earlier one instead of registering it.
+    pub cancel: bool,
+    /// Signature of the user, under their verification share.
+    pub signature: SchnorrSignature<C>,
+}
+
+impl<C: Ciphersuite> Intent<C> {
+    /// Signs the intent to sign `message` with the secret share of `key_package`.
+    pub fn register<R: Rng>(key_package: &KeyPackage<C>, message: &[u8], rng: &mut R) -> Self {
+        Self::sign(key_package, message, false, rng)
+    }
+
+    /// Signs the cancellation of the intent to sign `message`.
+    pub fn cancel<R: Rng>(key_package: &KeyPackage<C>, message: &[u8], rng: &mut R) -> Self {
+        Self::sign(key_package, message, true, rng)
+    }
+
+    fn sign<R: Rng>(
+        key_package: &KeyPackage<C>,
+        message: &[u8],
+        cancel: bool,
+        rng: &mut R,
+    ) -> Self {
+        let identifier = key_package.identifier;
+        let signature =
+            key_package.sign_message(&Self::signed_message(identifier, message, cancel), rng);
+
+        Intent {
+            identifier,
+            message: message.to_vec(),
+            cancel,
+            signature,
+        }
+    }
+
+    /// Returns `true` if the intent is signed under `verification_share`.
+    pub fn verify(&self, verification_share: VerificationShare<C>, generator: Element<C>) -> bool {
+        verify_message_signature(
+            &self.signature,
+            &Self::signed_message(self.identifier, &self.message, self.cancel),
+            verification_share.to_element(),
+            generator,
+        )
+    }
+
+    /// Encodes the intent as `signature || identifier || cancel || len(message) || message`, where
+    /// `cancel` is a single byte and the length a 4-byte little-endian integer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the message is longer than `u32::MAX`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.signature.to_bytes();
+        bytes.extend(serialize_scalar::<C>(&self.identifier.to_scalar()));
+        bytes.push(self.cancel as u8);
+        let message_len = u32::try_from(self.message.len()).expect("message too long");
+        bytes.extend_from_slice(&message_len.to_le_bytes());
+        bytes.extend_from_slice(&self.message);
+        bytes
+    }
+
+    /// Parses an intent encoded by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let mut decoder = Decoder::new(bytes);
+        let signature =
+            SchnorrSignature::from_bytes(decoder.take(SchnorrSignature::<C>::encoded_length())?)?;
+        let identifier = decoder.identifier()?;
+        let cancel = match decoder.take(1)? {
+            [0] => false,
+            [1] => true,
+            _ => return Err(SerializationError::InvalidData),
+        };
+        let message_len = decoder.u32()? as usize;
+        let message = decoder.take(message_len)?.to_vec();
+        decoder.finish()?;
+
+        Ok(Intent {
+            identifier,
+            message,
+            cancel,
+            signature,
+        })
+    }
+
+    fn signed_message(identifier: NonZeroScalar<C>, message: &[u8], cancel: bool) -> Vec<u8> {
+        [
+            INTENT_CONTEXT,
+            &serialize_scalar::<C>(&identifier.to_scalar()),
+            &[cancel as u8],
+            message,
+        ]
+        .concat()
+    }
+}
+
+/// The intents registered with a custodian and their countdowns.
+#[derive(Debug)]
+pub struct IntentRegistry<C: Ciphersuite = Ed25519Sha512> {
+    /// Time that must pass between registering an intent and signing its message.
+    delay: Duration,
+
+    generator: Element<C>,
+
+    /// Verification shares of the users allowed to register and cancel intents.
+    users: Vec<(NonZeroScalar<C>, VerificationShare<C>)>,
+
+    /// Registration time of each pending intent, keyed by its message.
+    intents: BTreeMap<Vec<u8>, Instant>,
+}
+
+impl<C: Ciphersuite> IntentRegistry<C> {
+    pub fn new(
+        delay: Duration,
+        generator: Element<C>,
+        users: Vec<(NonZeroScalar<C>, VerificationShare<C>)>,
+    ) -> Self {
+        IntentRegistry {
+            delay,
+            generator,
+            users,
+            intents: BTreeMap::new(),
+        }
+    }
+
+    /// Registers or cancels `intent`, and returns the time left until its message can be signed,
+    /// or `None` if it was cancelled.
+    ///
+    /// Registering an intent again does not restart its countdown. Returns an error if the user is
+    /// not authorized or the signature does not verify.
+    pub fn submit(&mut self, intent: &Intent<C>) -> Result<Option<Duration>, FrostError<C>> {
+        let identifier = intent.identifier;
+        let (_, verification_share) = self
+            .users
+            .iter()
+            .find(|(user, _)| *user == identifier)
+            .ok_or(FrostError::UnknownParticipant(identifier.to_scalar()))?;
+        if !intent.verify(*verification_share, self.generator) {
+            return Err(FrostError::InvalidIntent(identifier.to_scalar()));
+        }
+
+        if intent.cancel {
+            self.intents.remove(&intent.message);
+            return Ok(None);
+        }
+        self.intents
+            .entry(intent.message.clone())
+            .or_insert_with(Instant::now);
+        Ok(self.remaining(&intent.message))
+    }
+
+    /// Returns the time left until `message` can be signed, or `None` if no intent to sign it is
+    /// registered.
+    pub fn remaining(&self, message: &[u8]) -> Option<Duration> {
+        let registered = self.intents.get(message)?;
+        Some(self.delay.saturating_sub(registered.elapsed()))
+    }
+
+    /// Checks that `message` can be signed, without consuming its intent.
+    ///
+    /// Returns an error if no intent is registered or its countdown has not finished.
+    pub fn check(&self, message: &[u8]) -> Result<(), FrostError<C>> {
+        match self.remaining(message) {
+            None => Err(FrostError::PolicyViolation(
+                "no intent registered for the message".to_string(),
+            )),
+            Some(remaining) if !remaining.is_zero() => Err(FrostError::PolicyViolation(format!(
+                "message is timelocked for another {remaining:?}"
+            ))),
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Consumes the intent to sign `message`.
+    ///
+    /// Returns an error, leaving the intent in place, if none is registered or its countdown has
+    /// not finished.
+    pub fn consume(&mut self, message: &[u8]) -> Result<(), FrostError<C>> {
+        self.check(message)?;
+        self.intents.remove(message);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_timelocked_intent() {
+    use std::thread;
+
+    use crate::frost::Frost;
+
+    let frost: Frost = Frost::signature_share(2, 3);
+    let participants: Vec<_> = frost.participants().collect();
+    let users = frost
+        .signers()
+        .take(2)
+        .map(|signer| {
+            (
+                signer.get_commitments().identifier,
+                signer.get_verification_share(),
+            )
+        })
+        .collect();
+    let mut registry = IntentRegistry::new(Duration::from_millis(50), frost.generator, users);
+    let mut rng = ark_std::test_rng();
+
+    let owner = frost.key_package(participants[0]).unwrap();
+    let intent = Intent::register(&owner, b"testing", &mut rng);
+    let intent = Intent::from_bytes(&intent.to_bytes()).unwrap();
+    assert!(registry.submit(&intent).unwrap().is_some());
+    assert!(registry.consume(b"testing").is_err());
+
+    // a cancellation by any user stops the countdown
+    let other = frost.key_package(participants[1]).unwrap();
+    assert_eq!(
+        registry.submit(&Intent::cancel(&other, b"testing", &mut rng)),
+        Ok(None)
+    );
+    assert_eq!(registry.remaining(b"testing"), None);
+
+    // the custodian itself is not a user
+    let custodian = frost.key_package(participants[2]).unwrap();
+    assert!(
+        registry
+            .submit(&Intent::register(&custodian, b"testing", &mut rng))
+            .is_err()
+    );
+
+    registry.submit(&intent).unwrap();
+    thread::sleep(Duration::from_millis(60));
+    assert!(registry.consume(b"testing").is_ok());
+    assert!(registry.consume(b"testing").is_err());
+}
@@ -0,0 +1,265 @@
+//! This module implements exportable transcripts of signing sessions for third-party audit.
+//!
+//! Once a session has completed, the coordinator can export a `SessionTranscript` holding the
+//! signing package, the binding factors, every signature share and the final signature, and
+//! attest to it by sending it `Authenticated` with its identity key. An auditor re-verifies the
+//! transcript from public data alone: the group public key and the signers' verification shares.
+use ark_ff::AdditiveGroup;
+use ark_serialize::SerializationError;
+
+use crate::{
+    blame::verify_signature_share,
+    ciphersuite::{Ciphersuite, Ed25519Sha512, Element, ScalarField},
+    error::FrostError,
+    frost::{SignatureShare, SigningPackage},
+    helper::{BindingFactor, BindingFactorList, Decoder, NonZeroScalar, serialize_scalar},
+    identity::ProtocolMessage,
+    keys::{GroupPublicKey, VerificationShare},
+    schnorr::SchnorrSignature,
+};
+
+/// The complete record of a signing session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionTranscript<C: Ciphersuite = Ed25519Sha512> {
+    /// The package the signers signed, including the message and every commitment.
+    pub signing_package: SigningPackage<C>,
+    /// The binding factors of all participants.
+    pub binding_factors: BindingFactorList<C>,
+    /// The signature shares returned by the participants.
+    pub sig_shares: Vec<SignatureShare<C>>,
+    /// The aggregated signature.
+    pub signature: SchnorrSignature<C>,
+}
+
+impl<C: Ciphersuite> SessionTranscript<C> {
+    /// Re-verifies the session under `group_pk`, given the `verification_shares` of the
+    /// participants.
+    ///
+    /// Returns an error if the binding factors do not follow from the signing package, if the
+    /// shares do not answer the package, if a share is invalid or missing, or if the signature
+    /// is not the valid aggregate of the shares.
+    pub fn verify(
+        &self,
+        group_pk: GroupPublicKey<C>,
+        generator: Element<C>,
+        verification_shares: &[(NonZeroScalar<C>, VerificationShare<C>)],
+    ) -> Result<(), FrostError<C>> {
+        let invalid = |reason: &str| Err(FrostError::InvalidTranscript(reason.to_string()));
+        let participants = self.signing_package.participants()?;
+        if self.signing_package.binding_factors(group_pk)? != self.binding_factors {
+            return invalid("binding factors do not follow from the signing package");
+        }
+        let (group_commitment, challenge) = self
+            .signing_package
+            .group_commitment_and_challenge(group_pk)?;
+
+        let mut identifiers: Vec<NonZeroScalar<C>> = Vec::new();
+        let mut s = ScalarField::<C>::ZERO;
+        for sig_share in &self.sig_shares {
+            let identifier = sig_share.identifier;
+            if sig_share.session_id != self.signing_package.session_id
+                || sig_share.counter != self.signing_package.counter
+                || participants.get(identifier).is_none()
+                || identifiers.contains(&identifier)
+            {
+                return invalid(&format!(
+                    "share of signer {} does not answer the signing package",
+                    identifier.to_scalar()
+                ));
+            }
+            let verification_share = verification_shares
+                .iter()
+                .find(|(id, _)| *id == identifier)
+                .map(|(_, verification_share)| *verification_share)
+                .ok_or(FrostError::UnknownParticipant(identifier.to_scalar()))?;
+            if verify_signature_share(
+                *sig_share,
+                &self.signing_package,
+                group_pk,
+                verification_share,
+                generator,
+            )
+            .is_err()
+            {
+                return invalid(&format!(
+                    "share of signer {} is invalid",
+                    identifier.to_scalar()
+                ));
+            }
+            identifiers.push(identifier);
+            s += sig_share.z_i;
+        }
+
+        if identifiers.len() != participants.x_coordinates().len() {
+            return invalid("signature shares are missing");
+        }
+        if self.signature.R != group_commitment || self.signature.s != s {
+            return invalid("signature is not the aggregate of the shares");
+        }
+        if generator * s != group_commitment + group_pk.to_element() * challenge.to_scalar() {
+            return invalid("signature does not verify");
+        }
+
+        Ok(())
+    }
+
+    /// Encodes the transcript as `len(signing_package) || signing_package || count ||
+    /// binding_factors || count || sig_shares || signature`, with lengths and counts as 4-byte
+    /// little-endian integers and each binding factor as `identifier || rho_i`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an encoded part is longer than `u32::MAX`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let signing_package = self.signing_package.to_bytes();
+        let package_len = u32::try_from(signing_package.len()).expect("signing package too long");
+        let mut bytes = package_len.to_le_bytes().to_vec();
+        bytes.extend(signing_package);
+
+        let count = u32::try_from(self.binding_factors.len()).expect("too many binding factors");
+        bytes.extend_from_slice(&count.to_le_bytes());
+        for binding_factor in self.binding_factors.iter() {
+            bytes.extend(serialize_scalar::<C>(
+                &binding_factor.identifier.to_scalar(),
+            ));
+            bytes.extend(serialize_scalar::<C>(&binding_factor.rho));
+        }
+
+        let count = u32::try_from(self.sig_shares.len()).expect("too many signature shares");
+        bytes.extend_from_slice(&count.to_le_bytes());
+        for sig_share in &self.sig_shares {
+            bytes.extend(sig_share.to_bytes());
+        }
+
+        bytes.extend(self.signature.to_bytes());
+        bytes
+    }
+
+    /// Parses a transcript encoded by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let mut decoder = Decoder::new(bytes);
+        let package_len = decoder.u32()? as usize;
+        let signing_package = SigningPackage::from_bytes(decoder.take(package_len)?)?;
+
+        let binding_factors = (0..decoder.u32()?)
+            .map(|_| {
+                Ok(BindingFactor {
+                    identifier: decoder.identifier()?,
+                    rho: decoder.scalar::<C>()?,
+                })
+            })
+            .collect::<Result<_, SerializationError>>()?;
+
+        // session_id || counter || identifier || z_i
+        let share_len = 32 + 8 + 2 * serialize_scalar::<C>(&ScalarField::<C>::ZERO).len();
+        let sig_shares = (0..decoder.u32()?)
+            .map(|_| SignatureShare::from_bytes(decoder.take(share_len)?))
+            .collect::<Result<_, _>>()?;
+
+        let signature =
+            SchnorrSignature::from_bytes(decoder.take(SchnorrSignature::<C>::encoded_length())?)?;
+        decoder.finish()?;
+
+        Ok(SessionTranscript {
+            signing_package,
+            binding_factors,
+            sig_shares,
+            signature,
+        })
+    }
+}
+
+impl<C: Ciphersuite> ProtocolMessage<C> for SessionTranscript<C> {
+    const KIND: &'static [u8] = b"session-transcript";
+
+    fn owner(&self) -> Option<NonZeroScalar<C>> {
+        None
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        SessionTranscript::to_bytes(self)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        SessionTranscript::from_bytes(bytes)
+    }
+}
+
+#[test]
+fn test_session_transcript() {
+    use std::time::Duration;
+
+    use crate::{
+        coordinator::Coordinator,
+        frost::Frost,
+        identity::{Authenticated, IdentityKey, IdentityRoster},
+    };
+
+    let mut frost: Frost = Frost::signature_share(2, 3);
+    let mut coordinator = Coordinator::new(
+        frost.group_pk,
+        b"testing",
+        frost.participants().collect(),
+        Duration::from_secs(60),
+    );
+    for signer in frost.signers() {
+        coordinator
+            .receive_commitment(signer.get_commitments())
+            .unwrap();
+    }
+    let signing_package = coordinator.signing_package().unwrap();
+    for signer in frost.signers_mut() {
+        coordinator
+            .receive_share(signer.sign(&signing_package).unwrap())
+            .unwrap();
+    }
+    assert!(coordinator.transcript().is_err());
+    coordinator.aggregate().unwrap();
+
+    // the coordinator attests to the transcript with its identity key
+    let mut rng = ark_std::test_rng();
+    let coordinator_id = NonZeroScalar::new(ark_ed25519::Fr::from(100u64)).unwrap();
+    let key: IdentityKey = IdentityKey::generate(frost.generator, &mut rng);
+    let mut identities = IdentityRoster::new(frost.generator);
+    identities.insert(coordinator_id, key.public_key()).unwrap();
+    let attested = Authenticated::sign(
+        coordinator_id,
+        coordinator.transcript().unwrap(),
+        &key,
+        &mut rng,
+    );
+
+    let transcript = Authenticated::<SessionTranscript>::from_bytes(&attested.to_bytes())
+        .unwrap()
+        .verify(&identities)
+        .unwrap();
+    let verification_shares: Vec<_> = frost
+        .signers()
+        .map(|signer| {
+            (
+                signer.get_commitments().identifier,
+                signer.get_verification_share(),
+            )
+        })
+        .collect();
+    assert!(
+        transcript
+            .verify(frost.group_pk, frost.generator, &verification_shares)
+            .is_ok()
+    );
+
+    let mut tampered = transcript.clone();
+    tampered.sig_shares[0].z_i += ark_ed25519::Fr::from(1u64);
+    assert!(
+        tampered
+            .verify(frost.group_pk, frost.generator, &verification_shares)
+            .is_err()
+    );
+    tampered = transcript;
+    tampered.sig_shares.pop();
+    assert!(
+        tampered
+            .verify(frost.group_pk, frost.generator, &verification_shares)
+            .is_err()
+    );
+}
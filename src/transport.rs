@@ -0,0 +1,459 @@
+//! This module abstracts the network between the coordinator and the signers of a session.
+//!
+//! The protocol logic in `coordinate` and `participate` only sends and receives `Message`s through
+//! a `Transport`, so that it can run over any network a deployment brings, and be tested
+//! in-process with `ChannelTransport`, or with the lossy and delayed `sim_net::SimTransport`.
+//!
+//! Every message is sent as a `GroupMessage`, tagged with the `GroupId` of the group whose key it
+//! signs with, so that a process taking part in sessions of many groups over one transport can
+//! route each message to the right group. `coordinate` and `participate` skip messages of other
+//! groups.
+//!
+//! On the wire, a `GroupMessage` is an envelope carrying the version of the encoding, the
+//! ciphersuite, the group id and the message type ahead of the message itself. Parsers reject
+//! versions, ciphersuites and message types they do not know with a `WireError`, rather than
+//! misreading the message, so that the encoding can evolve while older signers are deployed.
+//! The envelope only covers messages sent through a `Transport`; the custodian line protocol, the
+//! JSON-RPC and WebSocket services and the hex encodings of the CLI keep their own formats.
+use std::{
+    fmt,
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    time::Duration,
+};
+
+use ark_serialize::SerializationError;
+
+use crate::{
+    ciphersuite::{Ciphersuite, Ed25519Sha512},
+    coordinator::Coordinator,
+    error::FrostError,
+    frost::{FrostSigner, SignatureShare, SigningPackage},
+    helper::{Decoder, NonZeroScalar},
+    keys::GroupId,
+    nonces::SigningCommitments,
+    schnorr::SchnorrSignature,
+};
+
+/// Version of the wire encoding of `GroupMessage`.
+pub const WIRE_VERSION: u8 = 1;
+
+/// Errors returned when parsing a `GroupMessage`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WireError {
+    /// The message is encoded with a version this implementation does not know.
+    UnsupportedVersion(u8),
+    /// The message is for another ciphersuite, with the given context string.
+    CiphersuiteMismatch(String),
+    /// The message type is not known to this implementation.
+    UnknownMessageType(u8),
+    /// The message could not be decoded.
+    Malformed,
+}
+
+impl fmt::Display for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WireError::UnsupportedVersion(version) => {
+                write!(f, "unsupported wire version {version}")
+            }
+            WireError::CiphersuiteMismatch(ciphersuite) => {
+                write!(f, "message for another ciphersuite: {ciphersuite}")
+            }
+            WireError::UnknownMessageType(message_type) => {
+                write!(f, "unknown message type {message_type}")
+            }
+            WireError::Malformed => write!(f, "malformed message"),
+        }
+    }
+}
+
+impl std::error::Error for WireError {}
+
+impl From<SerializationError> for WireError {
+    fn from(_: SerializationError) -> Self {
+        WireError::Malformed
+    }
+}
+
+/// An endpoint of the network.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Party<C: Ciphersuite = Ed25519Sha512> {
+    Coordinator,
+    Signer(NonZeroScalar<C>),
+}
+
+/// A message of the signing protocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message<C: Ciphersuite = Ed25519Sha512> {
+    /// Round one, from a signer to the coordinator.
+    Commitments(SigningCommitments<C>),
+    /// End of round one, from the coordinator to every signer.
+    SigningPackage(SigningPackage<C>),
+    /// Round two, from a signer to the coordinator.
+    SignatureShare(SignatureShare<C>),
+}
+
+impl<C: Ciphersuite> Message<C> {
+    /// Number of message types, tagged from 0 in the order of the variants.
+    const MESSAGE_TYPES: u8 = 3;
+
+    /// Encodes the message as a tag byte followed by the encoding of its content.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (tag, content) = match self {
+            Message::Commitments(commitments) => (0, commitments.to_bytes()),
+            Message::SigningPackage(signing_package) => (1, signing_package.to_bytes()),
+            Message::SignatureShare(sig_share) => (2, sig_share.to_bytes()),
+        };
+        [&[tag][..], &content].concat()
+    }
+
+    /// Parses a message encoded by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        match bytes.split_first() {
+            Some((0, content)) => Ok(Message::Commitments(SigningCommitments::from_bytes(
+                content,
+            )?)),
+            Some((1, content)) => Ok(Message::SigningPackage(SigningPackage::from_bytes(
+                content,
+            )?)),
+            Some((2, content)) => Ok(Message::SignatureShare(SignatureShare::from_bytes(
+                content,
+            )?)),
+            _ => Err(SerializationError::InvalidData),
+        }
+    }
+}
+
+/// A `Message` of a session of the group `group_id`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupMessage<C: Ciphersuite = Ed25519Sha512> {
+    pub group_id: GroupId,
+    pub message: Message<C>,
+}
+
+impl<C: Ciphersuite> GroupMessage<C> {
+    pub fn new(group_id: GroupId, message: Message<C>) -> Self {
+        GroupMessage { group_id, message }
+    }
+
+    /// Encodes the message in an envelope: `WIRE_VERSION || len(ciphersuite) || ciphersuite ||
+    /// group_id || message`, where the ciphersuite is its context string, prefixed by a length
+    /// byte, and the message starts with its type.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let ciphersuite = C::CONTEXT_STRING.as_bytes();
+        let mut bytes = vec![WIRE_VERSION];
+        bytes.push(u8::try_from(ciphersuite.len()).expect("context string too long"));
+        bytes.extend_from_slice(ciphersuite);
+        bytes.extend_from_slice(&self.group_id.to_bytes());
+        bytes.extend(self.message.to_bytes());
+        bytes
+    }
+
+    /// Parses a message encoded by `to_bytes`, rejecting other versions, ciphersuites and
+    /// unknown message types.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, WireError> {
+        let mut decoder = Decoder::new(bytes);
+        let version = decoder.take(1)?[0];
+        if version != WIRE_VERSION {
+            return Err(WireError::UnsupportedVersion(version));
+        }
+        let ciphersuite_len = decoder.take(1)?[0];
+        let ciphersuite = decoder.take(usize::from(ciphersuite_len))?;
+        if ciphersuite != C::CONTEXT_STRING.as_bytes() {
+            return Err(WireError::CiphersuiteMismatch(
+                String::from_utf8_lossy(ciphersuite).into_owned(),
+            ));
+        }
+        let group_id = GroupId::from_bytes(decoder.array()?);
+        let message = decoder.rest();
+        match message.first() {
+            Some(message_type) if *message_type >= Message::<C>::MESSAGE_TYPES => {
+                Err(WireError::UnknownMessageType(*message_type))
+            }
+            _ => Ok(GroupMessage {
+                group_id,
+                message: Message::from_bytes(message)?,
+            }),
+        }
+    }
+}
+
+/// Errors returned by a `Transport`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransportError {
+    /// No message arrived in time.
+    Timeout,
+    /// The other end of the connection has gone away.
+    Disconnected,
+    /// The recipient is not reachable through this transport.
+    UnknownRecipient,
+}
+
+impl fmt::Display for TransportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransportError::Timeout => write!(f, "timed out waiting for a message"),
+            TransportError::Disconnected => write!(f, "the connection was closed"),
+            TransportError::UnknownRecipient => write!(f, "the recipient is unknown"),
+        }
+    }
+}
+
+impl std::error::Error for TransportError {}
+
+impl<C: Ciphersuite> From<TransportError> for FrostError<C> {
+    fn from(error: TransportError) -> Self {
+        FrostError::Transport(error.to_string())
+    }
+}
+
+/// Delivers messages between the parties of a signing session.
+///
+/// Implementations must attribute each received message to the party that actually sent it,
+/// e.g. through an authenticated channel, since the coordinator trusts that attribution.
+pub trait Transport<C: Ciphersuite = Ed25519Sha512> {
+    fn send(&mut self, to: Party<C>, message: GroupMessage<C>) -> Result<(), TransportError>;
+
+    /// Waits up to `timeout` for the next message, and returns it with its sender.
+    fn recv(&mut self, timeout: Duration) -> Result<(Party<C>, GroupMessage<C>), TransportError>;
+}
+
+/// A message together with its sender.
+type Envelope<C> = (Party<C>, GroupMessage<C>);
+
+/// A `Transport` between threads of one process, over channels.
+#[derive(Debug)]
+pub struct ChannelTransport<C: Ciphersuite = Ed25519Sha512> {
+    party: Party<C>,
+    inbox: Receiver<Envelope<C>>,
+    peers: Vec<(Party<C>, Sender<Envelope<C>>)>,
+}
+
+impl<C: Ciphersuite> ChannelTransport<C> {
+    /// Connects a coordinator to the signers with the given identifiers, and returns the
+    /// coordinator's transport and those of the signers, in the same order.
+    pub fn network(participants: &[NonZeroScalar<C>]) -> (Self, Vec<Self>) {
+        let parties: Vec<Party<C>> = std::iter::once(Party::Coordinator)
+            .chain(participants.iter().copied().map(Party::Signer))
+            .collect();
+        let (senders, inboxes): (Vec<_>, Vec<_>) = parties.iter().map(|_| mpsc::channel()).unzip();
+        let peers: Vec<_> = parties.iter().copied().zip(senders).collect();
+
+        let mut transports =
+            parties
+                .into_iter()
+                .zip(inboxes)
+                .map(|(party, inbox)| ChannelTransport {
+                    party,
+                    inbox,
+                    peers: peers.clone(),
+                });
+        let coordinator = transports
+            .next()
+            .expect("the coordinator is always a party");
+        (coordinator, transports.collect())
+    }
+
+    pub fn party(&self) -> Party<C> {
+        self.party
+    }
+}
+
+impl<C: Ciphersuite> Transport<C> for ChannelTransport<C> {
+    fn send(&mut self, to: Party<C>, message: GroupMessage<C>) -> Result<(), TransportError> {
+        let (_, sender) = self
+            .peers
+            .iter()
+            .find(|(party, _)| *party == to)
+            .ok_or(TransportError::UnknownRecipient)?;
+        sender
+            .send((self.party, message))
+            .map_err(|_| TransportError::Disconnected)
+    }
+
+    fn recv(&mut self, timeout: Duration) -> Result<(Party<C>, GroupMessage<C>), TransportError> {
+        self.inbox
+            .recv_timeout(timeout)
+            .map_err(|error| match error {
+                RecvTimeoutError::Timeout => TransportError::Timeout,
+                RecvTimeoutError::Disconnected => TransportError::Disconnected,
+            })
+    }
+}
+
+/// Runs both rounds of `coordinator`'s session over `transport` and returns the signature.
+///
+/// Messages of other groups than the coordinator's are skipped.
+/// Returns `FrostError::Timeout` if some participant does not respond before the deadline of a
+/// round, and `FrostError::UnknownParticipant` if a message comes from another party than the
+/// signer it names.
+pub fn coordinate<C: Ciphersuite, T: Transport<C>>(
+    coordinator: &mut Coordinator<C>,
+    transport: &mut T,
+) -> Result<SchnorrSignature<C>, FrostError<C>> {
+    let group_id = coordinator.get_group_pk().group_id();
+    while !coordinator.missing().is_empty() {
+        match transport.recv(coordinator.time_remaining()) {
+            Ok((_, message)) if message.group_id != group_id => {}
+            Ok((from, GroupMessage { message, .. })) => {
+                if let Message::Commitments(commitments) = message {
+                    check_sender(from, commitments.identifier)?;
+                    coordinator.receive_commitment(commitments)?;
+                }
+            }
+            Err(TransportError::Timeout) => coordinator.check_deadline()?,
+            Err(error) => return Err(error.into()),
+        }
+    }
+
+    let signing_package = coordinator.signing_package()?;
+    for commitments in &signing_package.commitments {
+        transport.send(
+            Party::Signer(commitments.identifier),
+            GroupMessage::new(group_id, Message::SigningPackage(signing_package.clone())),
+        )?;
+    }
+
+    while !coordinator.missing().is_empty() {
+        match transport.recv(coordinator.time_remaining()) {
+            Ok((_, message)) if message.group_id != group_id => {}
+            Ok((from, GroupMessage { message, .. })) => {
+                if let Message::SignatureShare(sig_share) = message {
+                    check_sender(from, sig_share.identifier)?;
+                    coordinator.receive_share(sig_share)?;
+                }
+            }
+            Err(TransportError::Timeout) => coordinator.check_deadline()?,
+            Err(error) => return Err(error.into()),
+        }
+    }
+
+    coordinator.aggregate()
+}
+
+/// Takes part in a session as `signer`: sends its commitments to the coordinator, waits up to
+/// `timeout` for the signing package of its group and sends back the signature share, which it
+/// also returns.
+pub fn participate<C: Ciphersuite, T: Transport<C>>(
+    signer: &mut FrostSigner<C>,
+    transport: &mut T,
+    timeout: Duration,
+) -> Result<SignatureShare<C>, FrostError<C>> {
+    let group_id = signer.get_group_pk().group_id();
+    transport.send(
+        Party::Coordinator,
+        GroupMessage::new(group_id, Message::Commitments(signer.get_commitments())),
+    )?;
+
+    let signing_package = loop {
+        match transport.recv(timeout)? {
+            (
+                Party::Coordinator,
+                GroupMessage {
+                    group_id: message_group,
+                    message: Message::SigningPackage(signing_package),
+                },
+            ) if message_group == group_id => {
+                break signing_package;
+            }
+            _ => continue,
+        }
+    };
+
+    let sig_share = signer.sign(&signing_package)?;
+    transport.send(
+        Party::Coordinator,
+        GroupMessage::new(group_id, Message::SignatureShare(sig_share)),
+    )?;
+    Ok(sig_share)
+}
+
+fn check_sender<C: Ciphersuite>(
+    from: Party<C>,
+    identifier: NonZeroScalar<C>,
+) -> Result<(), FrostError<C>> {
+    if from == Party::Signer(identifier) {
+        Ok(())
+    } else {
+        Err(FrostError::UnknownParticipant(identifier.to_scalar()))
+    }
+}
+
+#[test]
+fn test_channel_transport() {
+    use std::thread;
+
+    use crate::{error::Round, frost::Frost};
+
+    let mut frost: Frost = Frost::signature_share(2, 3);
+    let participants: Vec<NonZeroScalar> = frost.participants().collect();
+    let timeout = Duration::from_millis(500);
+
+    let (mut transport, signer_transports) = ChannelTransport::network(&participants);
+    let handles: Vec<_> = frost
+        .signers()
+        .cloned()
+        .zip(signer_transports)
+        .map(|(mut signer, mut transport)| {
+            signer.refresh_nonces();
+            thread::spawn(move || participate(&mut signer, &mut transport, timeout))
+        })
+        .collect();
+    let mut coordinator =
+        Coordinator::new(frost.group_pk, b"testing", participants.clone(), timeout);
+    let signature = coordinate(&mut coordinator, &mut transport).unwrap();
+    assert!(frost.verify(b"testing", signature));
+    for handle in handles {
+        assert!(handle.join().unwrap().is_ok());
+    }
+
+    let signing_package = frost.signing_package(b"testing");
+    let share = frost.signers[0].sign(&signing_package);
+    let message = GroupMessage::new(
+        frost.group_pk.group_id(),
+        Message::SignatureShare(share.unwrap()),
+    );
+    let bytes = message.to_bytes();
+    assert_eq!(GroupMessage::from_bytes(&bytes).unwrap(), message);
+
+    // messages of a later version or of unknown types are rejected cleanly
+    let mut later = bytes.clone();
+    later[0] = WIRE_VERSION + 1;
+    assert_eq!(
+        GroupMessage::<Ed25519Sha512>::from_bytes(&later),
+        Err(WireError::UnsupportedVersion(WIRE_VERSION + 1))
+    );
+    let mut unknown = bytes.clone();
+    unknown[2 + Ed25519Sha512::CONTEXT_STRING.len() + 32] = 7;
+    assert_eq!(
+        GroupMessage::<Ed25519Sha512>::from_bytes(&unknown),
+        Err(WireError::UnknownMessageType(7))
+    );
+    assert_eq!(
+        GroupMessage::<Ed25519Sha512>::from_bytes(&bytes[..40]),
+        Err(WireError::Malformed)
+    );
+
+    // a signer that never responds times the round out
+    let (mut transport, mut signer_transports) = ChannelTransport::network(&participants);
+    let _silent = signer_transports.pop();
+    let handles: Vec<_> = frost
+        .signers()
+        .cloned()
+        .zip(signer_transports)
+        .map(|(mut signer, mut transport)| {
+            signer.refresh_nonces();
+            thread::spawn(move || participate(&mut signer, &mut transport, timeout))
+        })
+        .collect();
+    let mut coordinator =
+        Coordinator::new(frost.group_pk, b"testing", participants.clone(), timeout);
+    match coordinate(&mut coordinator, &mut transport) {
+        Err(FrostError::Timeout { round, missing }) => {
+            assert_eq!(round, Round::Commitment);
+            assert_eq!(missing, vec![participants[2].to_scalar()]);
+        }
+        result => panic!("unexpected result {result:?}"),
+    }
+    drop(handles);
+}
@@ -0,0 +1,337 @@
+//! This module loads the JSON test vectors published with RFC 9591 and replays them.
+//!
+//! Replaying a vector runs key generation from the vector's group secret key and polynomial
+//! coefficients, then signs with the vector's fixed nonce randomness instead of fresh randomness,
+//! checking every intermediate value along the way.
+//!
+//! Source: https://www.rfc-editor.org/rfc/rfc9591.html#name-test-vectors
+#![allow(non_snake_case)]
+
+use std::{collections::BTreeMap, fmt};
+
+use ark_ec::PrimeGroup;
+use ark_ff::AdditiveGroup;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+use crate::{
+    ciphersuite::{Ciphersuite, Element, ScalarField},
+    frost::{FrostSigner, SigningPackage},
+    json::{Json, JsonError},
+    keys::GroupPublicKey,
+    nonces::nonce_generate_from_randomness,
+    schnorr::SchnorrSignature,
+};
+
+/// The round one values of a single participant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundOneOutput {
+    pub identifier: u64,
+    pub hiding_nonce_randomness: Vec<u8>,
+    pub binding_nonce_randomness: Vec<u8>,
+    pub hiding_nonce: Vec<u8>,
+    pub binding_nonce: Vec<u8>,
+    pub hiding_nonce_commitment: Vec<u8>,
+    pub binding_nonce_commitment: Vec<u8>,
+    pub binding_factor: Vec<u8>,
+}
+
+/// A test vector in the JSON format of the RFC, with all byte strings hex-decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestVector {
+    /// Name of the ciphersuite, e.g. `FROST(Ed25519, SHA-512)`.
+    pub name: String,
+    pub max_participants: usize,
+    pub min_participants: usize,
+    /// Identifiers of the participants that sign.
+    pub participant_list: Vec<u64>,
+    pub group_secret_key: Vec<u8>,
+    pub group_public_key: Vec<u8>,
+    pub message: Vec<u8>,
+    /// Coefficients of the sharing polynomial, excluding the constant term `group_secret_key`.
+    pub share_polynomial_coefficients: Vec<Vec<u8>>,
+    /// Pairs of (identifier, secret key share) for every participant.
+    pub participant_shares: Vec<(u64, Vec<u8>)>,
+    pub round_one_outputs: Vec<RoundOneOutput>,
+    /// Pairs of (identifier, signature share) for every signing participant.
+    pub round_two_outputs: Vec<(u64, Vec<u8>)>,
+    /// The encoded final signature.
+    pub sig: Vec<u8>,
+}
+
+/// Error returned when a test vector cannot be loaded or does not replay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TestVectorError {
+    /// The vector is not valid JSON, misses a field or contains a malformed value.
+    Malformed(String),
+    /// The value computed during replay differs from the one in the vector.
+    Mismatch(String),
+}
+
+impl fmt::Display for TestVectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TestVectorError::Malformed(reason) => write!(f, "malformed test vector: {reason}"),
+            TestVectorError::Mismatch(value) => write!(f, "test vector mismatch in {value}"),
+        }
+    }
+}
+
+impl std::error::Error for TestVectorError {}
+
+impl From<JsonError> for TestVectorError {
+    fn from(error: JsonError) -> Self {
+        TestVectorError::Malformed(error.0)
+    }
+}
+
+impl TestVector {
+    /// Parses a test vector from the JSON format of the RFC.
+    pub fn from_json(json: &str) -> Result<Self, TestVectorError> {
+        let root = Json::parse(json)?;
+
+        let config = root.field("config")?;
+        let inputs = root.field("inputs")?;
+
+        let participant_shares = inputs
+            .field("participant_shares")?
+            .array()?
+            .iter()
+            .map(|share| {
+                Ok((
+                    share.field("identifier")?.integer()?,
+                    share.field("participant_share")?.bytes()?,
+                ))
+            })
+            .collect::<Result<_, TestVectorError>>()?;
+
+        let round_one_outputs = root
+            .field("round_one_outputs")?
+            .field("outputs")?
+            .array()?
+            .iter()
+            .map(|output| {
+                Ok(RoundOneOutput {
+                    identifier: output.field("identifier")?.integer()?,
+                    hiding_nonce_randomness: output.field("hiding_nonce_randomness")?.bytes()?,
+                    binding_nonce_randomness: output.field("binding_nonce_randomness")?.bytes()?,
+                    hiding_nonce: output.field("hiding_nonce")?.bytes()?,
+                    binding_nonce: output.field("binding_nonce")?.bytes()?,
+                    hiding_nonce_commitment: output.field("hiding_nonce_commitment")?.bytes()?,
+                    binding_nonce_commitment: output.field("binding_nonce_commitment")?.bytes()?,
+                    binding_factor: output.field("binding_factor")?.bytes()?,
+                })
+            })
+            .collect::<Result<_, TestVectorError>>()?;
+
+        let round_two_outputs = root
+            .field("round_two_outputs")?
+            .field("outputs")?
+            .array()?
+            .iter()
+            .map(|output| {
+                Ok((
+                    output.field("identifier")?.integer()?,
+                    output.field("sig_share")?.bytes()?,
+                ))
+            })
+            .collect::<Result<_, TestVectorError>>()?;
+
+        Ok(TestVector {
+            name: config.field("name")?.string()?.to_string(),
+            max_participants: config.field("MAX_PARTICIPANTS")?.integer()? as usize,
+            min_participants: config.field("MIN_PARTICIPANTS")?.integer()? as usize,
+            participant_list: inputs
+                .field("participant_list")?
+                .array()?
+                .iter()
+                .map(Json::integer)
+                .collect::<Result<_, _>>()?,
+            group_secret_key: inputs.field("group_secret_key")?.bytes()?,
+            group_public_key: inputs.field("group_public_key")?.bytes()?,
+            message: inputs.field("message")?.bytes()?,
+            share_polynomial_coefficients: inputs
+                .field("share_polynomial_coefficients")?
+                .array()?
+                .iter()
+                .map(Json::bytes)
+                .collect::<Result<_, _>>()?,
+            participant_shares,
+            round_one_outputs,
+            round_two_outputs,
+            sig: root.field("final_output")?.field("sig")?.bytes()?,
+        })
+    }
+}
+
+/// Replays `vector` under the ciphersuite `C` and returns the resulting signature.
+///
+/// Key generation uses the vector's group secret key and polynomial coefficients, and each signer
+/// derives its nonces from the vector's fixed randomness. Every public key, share, nonce,
+/// commitment, binding factor and signature share is compared against the vector, as is the
+/// final signature.
+pub fn replay<C: Ciphersuite>(vector: &TestVector) -> Result<SchnorrSignature<C>, TestVectorError> {
+    let g = Element::<C>::generator();
+
+    // key generation
+    let group_secret_key = scalar::<C>(&vector.group_secret_key)?;
+    let group_pk = GroupPublicKey::new(g * group_secret_key);
+    check(
+        C::serialize_element(&group_pk.to_element()) == vector.group_public_key,
+        "group_public_key",
+    )?;
+
+    let coefficients: Vec<ScalarField<C>> = vector
+        .share_polynomial_coefficients
+        .iter()
+        .map(|coefficient| scalar::<C>(coefficient))
+        .collect::<Result<_, _>>()?;
+    let mut shares = BTreeMap::new();
+    for (identifier, expected) in &vector.participant_shares {
+        let x = ScalarField::<C>::from(*identifier);
+        // evaluate the polynomial at x using Horner's method
+        let share = coefficients
+            .iter()
+            .rev()
+            .fold(ScalarField::<C>::ZERO, |acc, coefficient| {
+                acc * x + coefficient
+            })
+            * x
+            + group_secret_key;
+        check(scalar_bytes::<C>(share) == *expected, "participant_shares")?;
+        shares.insert(*identifier, share);
+    }
+
+    // round one
+    let mut signers = Vec::with_capacity(vector.participant_list.len());
+    for identifier in &vector.participant_list {
+        let output = vector
+            .round_one_outputs
+            .iter()
+            .find(|output| output.identifier == *identifier)
+            .ok_or_else(|| {
+                TestVectorError::Malformed(format!("no round one output for {identifier}"))
+            })?;
+        let share = *shares
+            .get(identifier)
+            .ok_or_else(|| TestVectorError::Malformed(format!("no share for {identifier}")))?;
+
+        let d = nonce_generate_from_randomness::<C>(
+            randomness(&output.hiding_nonce_randomness)?,
+            share,
+        );
+        let e = nonce_generate_from_randomness::<C>(
+            randomness(&output.binding_nonce_randomness)?,
+            share,
+        );
+        check(scalar_bytes::<C>(d) == output.hiding_nonce, "hiding_nonce")?;
+        check(
+            scalar_bytes::<C>(e) == output.binding_nonce,
+            "binding_nonce",
+        )?;
+
+        let signer = FrostSigner::<C>::with_nonces(*identifier as usize, share, g, group_pk, d, e);
+        let commitment = signer.get_commitments();
+        check(
+            C::serialize_element(&commitment.hiding) == output.hiding_nonce_commitment,
+            "hiding_nonce_commitment",
+        )?;
+        check(
+            C::serialize_element(&commitment.binding) == output.binding_nonce_commitment,
+            "binding_nonce_commitment",
+        )?;
+        signers.push((signer, output));
+    }
+
+    let commitments = signers
+        .iter()
+        .map(|(signer, _)| signer.get_commitments())
+        .collect();
+    let signing_package = SigningPackage::new(&vector.message, commitments);
+
+    // round two
+    let binding_factors = signing_package
+        .binding_factors(group_pk)
+        .map_err(|error| TestVectorError::Malformed(error.to_string()))?;
+    let mut s = ScalarField::<C>::ZERO;
+    for (signer, output) in &mut signers {
+        let identifier = signer.get_commitments().identifier;
+        let rho = binding_factors
+            .get(identifier)
+            .ok_or_else(|| TestVectorError::Mismatch("binding_factor".to_string()))?;
+        check(
+            scalar_bytes::<C>(rho) == output.binding_factor,
+            "binding_factor",
+        )?;
+
+        let sig_share = signer
+            .sign(&signing_package)
+            .map_err(|error| TestVectorError::Mismatch(error.to_string()))?;
+        let expected = vector
+            .round_two_outputs
+            .iter()
+            .find(|(id, _)| *id == output.identifier)
+            .map(|(_, sig_share)| sig_share);
+        check(
+            Some(&scalar_bytes::<C>(sig_share.z_i)) == expected,
+            "sig_share",
+        )?;
+        s += sig_share.z_i;
+    }
+
+    let (R, _) = signing_package
+        .group_commitment_and_challenge(group_pk)
+        .map_err(|error| TestVectorError::Malformed(error.to_string()))?;
+    let signature = SchnorrSignature { R, s };
+    check(signature.to_bytes() == vector.sig, "sig")?;
+
+    Ok(signature)
+}
+
+fn check(condition: bool, value: &str) -> Result<(), TestVectorError> {
+    if condition {
+        Ok(())
+    } else {
+        Err(TestVectorError::Mismatch(value.to_string()))
+    }
+}
+
+fn scalar<C: Ciphersuite>(bytes: &[u8]) -> Result<ScalarField<C>, TestVectorError> {
+    ScalarField::<C>::deserialize_compressed(bytes)
+        .map_err(|_| TestVectorError::Malformed("invalid scalar".to_string()))
+}
+
+fn scalar_bytes<C: Ciphersuite>(scalar: ScalarField<C>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    scalar
+        .serialize_compressed(&mut bytes)
+        .expect("serialization failed");
+    bytes
+}
+
+fn randomness(bytes: &[u8]) -> Result<[u8; 32], TestVectorError> {
+    bytes
+        .try_into()
+        .map_err(|_| TestVectorError::Malformed("nonce randomness must be 32 bytes".to_string()))
+}
+
+#[test]
+fn test_replay_ed25519_sha512_vector() {
+    use crate::ciphersuite::Ed25519Sha512;
+
+    let vector = TestVector::from_json(include_str!("../vectors/ed25519-sha512.json")).unwrap();
+    assert_eq!(vector.name, "FROST(Ed25519, SHA-512)");
+    assert_eq!(vector.participant_list, vec![1, 3]);
+
+    let signature = replay::<Ed25519Sha512>(&vector).unwrap();
+    assert_eq!(
+        SchnorrSignature::<Ed25519Sha512>::from_bytes(&vector.sig).unwrap(),
+        signature
+    );
+
+    let mut tampered = vector.clone();
+    tampered.round_two_outputs[0].1[0] ^= 1;
+    assert_eq!(
+        replay::<Ed25519Sha512>(&tampered),
+        Err(TestVectorError::Mismatch("sig_share".to_string()))
+    );
+}
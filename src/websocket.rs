@@ -0,0 +1,527 @@
+//! This module implements a WebSocket transport for the JSON-RPC interface.
+//!
+//! Clients that cannot open a raw TCP connection, such as a web page using the browser's
+//! WebSocket API, can hold a WebSocket open to the coordinator and exchange both rounds over it
+//! without reconnecting. Each text message carries one JSON-RPC request or response, as in `rpc`.
+//! This crate has no bindings for the browser: such a client implements the JSON-RPC methods
+//! itself.
+//!
+//! A browser lets any web page open a WebSocket to any server, with the user's cookies and
+//! network position. The server therefore only accepts handshakes whose `Origin` header, which
+//! browsers always send, is one of the origins it is configured with. Handshakes without an
+//! `Origin` come from clients other than browsers, e.g. `WebSocket::connect`, and are accepted.
+//!
+//! Only what the transport needs of the protocol is implemented: the opening handshake, text
+//! frames, fragmentation, ping and close. Extensions and subprotocols are not negotiated.
+//!
+//! Reference: https://www.rfc-editor.org/rfc/rfc6455
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::{Arc, Mutex, PoisonError},
+    thread,
+};
+
+use rand::RngCore;
+
+use crate::{helper::read_bounded_line, rpc::JsonRpc};
+
+/// Appended to the client's key to compute the accept key of the handshake.
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+/// Largest message accepted, so that a peer cannot exhaust the memory of the other end.
+const MAX_MESSAGE_LEN: usize = 1 << 20;
+/// Largest line of the HTTP head of the handshake accepted, for the same reason.
+const MAX_HEADER_LINE_LEN: usize = 8192;
+/// Largest payload of a control frame, see RFC 6455, section 5.5.
+const MAX_CONTROL_PAYLOAD_LEN: u64 = 125;
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+/// An open WebSocket connection, on the client or the server side.
+#[derive(Debug)]
+pub struct WebSocket {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    /// Clients mask the frames they send, servers do not.
+    is_client: bool,
+    closed: bool,
+}
+
+impl WebSocket {
+    /// Accepts the opening handshake of a client on `stream`, if it sends no `Origin` header or
+    /// one of `allowed_origins`, e.g. `https://wallet.example`.
+    ///
+    /// Returns an error if the request is not a WebSocket upgrade, or comes from a web page of
+    /// another origin, which is answered with `403 Forbidden`.
+    pub fn accept(stream: TcpStream, allowed_origins: &[String]) -> io::Result<Self> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let headers = read_http_head(&mut reader)?;
+        let key = header(&headers, "sec-websocket-key")
+            .filter(|_| {
+                header(&headers, "upgrade")
+                    .is_some_and(|value| value.eq_ignore_ascii_case("websocket"))
+            })
+            .ok_or_else(|| invalid_data("not a WebSocket upgrade request"))?;
+
+        let mut writer = stream;
+        if let Some(origin) = header(&headers, "origin")
+            && !allowed_origins
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(origin))
+        {
+            write!(
+                writer,
+                "HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\n\r\n"
+            )?;
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("origin {origin} is not allowed"),
+            ));
+        }
+        write!(
+            writer,
+            "HTTP/1.1 101 Switching Protocols\r\n\
+             Upgrade: websocket\r\n\
+             Connection: Upgrade\r\n\
+             Sec-WebSocket-Accept: {}\r\n\r\n",
+            accept_key(key)
+        )?;
+
+        Ok(WebSocket {
+            reader,
+            writer,
+            is_client: false,
+            closed: false,
+        })
+    }
+
+    /// Connects to the server at `address` and opens a WebSocket at `path`.
+    ///
+    /// Returns an error if the server does not accept the handshake.
+    pub fn connect(address: impl ToSocketAddrs, host: &str, path: &str) -> io::Result<Self> {
+        let stream = TcpStream::connect(address)?;
+        let mut nonce = [0u8; 16];
+        rand::rng().fill_bytes(&mut nonce);
+        let key = base64_encode(&nonce);
+
+        let mut writer = stream.try_clone()?;
+        write!(
+            writer,
+            "GET {path} HTTP/1.1\r\n\
+             Host: {host}\r\n\
+             Upgrade: websocket\r\n\
+             Connection: Upgrade\r\n\
+             Sec-WebSocket-Key: {key}\r\n\
+             Sec-WebSocket-Version: 13\r\n\r\n"
+        )?;
+
+        let mut reader = BufReader::new(stream);
+        let headers = read_http_head(&mut reader)?;
+        if !headers[0].starts_with("HTTP/1.1 101")
+            || header(&headers, "sec-websocket-accept") != Some(&accept_key(&key))
+        {
+            return Err(invalid_data("the server refused the WebSocket handshake"));
+        }
+
+        Ok(WebSocket {
+            reader,
+            writer,
+            is_client: true,
+            closed: false,
+        })
+    }
+
+    /// Sends `text` as a single text message.
+    pub fn send_text(&mut self, text: &str) -> io::Result<()> {
+        self.send_frame(OPCODE_TEXT, text.as_bytes())
+    }
+
+    /// Waits for the next text message, answering pings along the way.
+    ///
+    /// Returns `None` once the peer has closed the connection.
+    pub fn receive_text(&mut self) -> io::Result<Option<String>> {
+        let mut message: Option<Vec<u8>> = None;
+        loop {
+            if self.closed {
+                return Ok(None);
+            }
+            let (fin, opcode, payload) = self.read_frame()?;
+            match opcode {
+                OPCODE_PING => self.send_frame(OPCODE_PONG, &payload)?,
+                OPCODE_PONG => {}
+                OPCODE_CLOSE => {
+                    self.close()?;
+                    return Ok(None);
+                }
+                OPCODE_TEXT if message.is_none() => message = Some(payload),
+                OPCODE_CONTINUATION if message.is_some() => {
+                    let buffer = message.as_mut().expect("a message is in progress");
+                    if buffer.len() + payload.len() > MAX_MESSAGE_LEN {
+                        return Err(invalid_data("message too long"));
+                    }
+                    buffer.extend(payload);
+                }
+                OPCODE_BINARY => return Err(invalid_data("unexpected binary message")),
+                _ => return Err(invalid_data("unexpected frame")),
+            }
+            if fin && let Some(message) = message.take() {
+                return String::from_utf8(message)
+                    .map(Some)
+                    .map_err(|_| invalid_data("text message is not UTF-8"));
+            }
+        }
+    }
+
+    /// Sends a close frame, if none was sent yet.
+    pub fn close(&mut self) -> io::Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+        self.closed = true;
+        self.send_frame(OPCODE_CLOSE, &[])
+    }
+
+    fn send_frame(&mut self, opcode: u8, payload: &[u8]) -> io::Result<()> {
+        let mut frame = vec![0x80 | opcode];
+        let mask_bit = if self.is_client { 0x80 } else { 0 };
+        match payload.len() {
+            len @ 0..=125 => frame.push(mask_bit | len as u8),
+            len @ 126..=0xFFFF => {
+                frame.push(mask_bit | 126);
+                frame.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+            len => {
+                frame.push(mask_bit | 127);
+                frame.extend_from_slice(&(len as u64).to_be_bytes());
+            }
+        }
+
+        if self.is_client {
+            let mut mask = [0u8; 4];
+            rand::rng().fill_bytes(&mut mask);
+            frame.extend_from_slice(&mask);
+            frame.extend(
+                payload
+                    .iter()
+                    .zip(mask.iter().cycle())
+                    .map(|(byte, mask)| byte ^ mask),
+            );
+        } else {
+            frame.extend_from_slice(payload);
+        }
+        self.writer.write_all(&frame)
+    }
+
+    fn read_frame(&mut self) -> io::Result<(bool, u8, Vec<u8>)> {
+        let mut head = [0u8; 2];
+        self.reader.read_exact(&mut head)?;
+        let fin = head[0] & 0x80 != 0;
+        let opcode = head[0] & 0x0F;
+        if head[0] & 0x70 != 0 {
+            return Err(invalid_data("reserved bits set without an extension"));
+        }
+        // clients must mask their frames, and servers must not
+        let masked = head[1] & 0x80 != 0;
+        if masked == self.is_client {
+            return Err(invalid_data("unexpected frame masking"));
+        }
+
+        let len = match head[1] & 0x7F {
+            126 => {
+                let mut len = [0u8; 2];
+                self.reader.read_exact(&mut len)?;
+                u64::from(u16::from_be_bytes(len))
+            }
+            127 => {
+                let mut len = [0u8; 8];
+                self.reader.read_exact(&mut len)?;
+                u64::from_be_bytes(len)
+            }
+            len => u64::from(len),
+        };
+        if len > MAX_MESSAGE_LEN as u64 {
+            return Err(invalid_data("message too long"));
+        }
+        // control frames interleave with fragmented messages, so they cannot be fragmented
+        // themselves
+        let is_control = opcode & 0x08 != 0;
+        if is_control && (!fin || len > MAX_CONTROL_PAYLOAD_LEN) {
+            return Err(invalid_data("fragmented or oversized control frame"));
+        }
+
+        let mut mask = [0u8; 4];
+        if masked {
+            self.reader.read_exact(&mut mask)?;
+        }
+        let mut payload = vec![0u8; len as usize];
+        self.reader.read_exact(&mut payload)?;
+        if masked {
+            payload
+                .iter_mut()
+                .zip(mask.iter().cycle())
+                .for_each(|(byte, mask)| *byte ^= mask);
+        }
+        Ok((fin, opcode, payload))
+    }
+}
+
+/// Serves `service` on `listener` over WebSocket, one JSON-RPC request per text message,
+/// handling each connection on its own thread. Web pages are only served if their origin is one
+/// of `allowed_origins`, see `WebSocket::accept`.
+///
+/// Only returns if accepting a connection fails.
+pub fn serve<S: JsonRpc + Send + 'static>(
+    service: S,
+    listener: TcpListener,
+    allowed_origins: Vec<String>,
+) -> io::Result<()> {
+    let service = Arc::new(Mutex::new(service));
+    let allowed_origins = Arc::new(allowed_origins);
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let service = Arc::clone(&service);
+        let allowed_origins = Arc::clone(&allowed_origins);
+        thread::spawn(move || handle_connection(&service, stream, &allowed_origins));
+    }
+
+    Ok(())
+}
+
+fn handle_connection<S: JsonRpc>(
+    service: &Mutex<S>,
+    stream: TcpStream,
+    allowed_origins: &[String],
+) -> io::Result<()> {
+    let mut socket = WebSocket::accept(stream, allowed_origins)?;
+    while let Some(request) = socket.receive_text()? {
+        let response = service
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .handle(&request);
+        if let Some(response) = response {
+            socket.send_text(&response)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the request or status line and the headers of an HTTP message, up to the empty line.
+fn read_http_head(reader: &mut impl BufRead) -> io::Result<Vec<String>> {
+    let mut lines = Vec::new();
+    loop {
+        let mut line = String::new();
+        if read_bounded_line(reader, MAX_HEADER_LINE_LEN, &mut line)? == 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if lines.len() > 100 {
+            return Err(invalid_data("too many headers"));
+        }
+        lines.push(line.to_string());
+    }
+    if lines.is_empty() {
+        return Err(invalid_data("missing request line"));
+    }
+    Ok(lines)
+}
+
+/// Returns the value of the header `name`, compared case-insensitively.
+fn header<'a>(lines: &'a [String], name: &str) -> Option<&'a str> {
+    lines[1..].iter().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then_some(value.trim())
+    })
+}
+
+/// Computes the `Sec-WebSocket-Accept` value answering the client's `key`.
+fn accept_key(key: &str) -> String {
+    base64_encode(&sha1(format!("{key}{HANDSHAKE_GUID}").as_bytes()))
+}
+
+fn invalid_data(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason)
+}
+
+/// SHA-1, which the handshake requires. It is not used for anything security-relevant.
+fn sha1(message: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    let mut padded = message.to_vec();
+    padded.push(0x80);
+    while padded.len() % 64 != 56 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&((message.len() as u64) * 8).to_be_bytes());
+
+    for block in padded.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes(word.try_into().expect("4-byte chunk"));
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (value, add) in state.iter_mut().zip([a, b, c, d, e]) {
+            *value = value.wrapping_add(add);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (chunk, value) in digest.chunks_mut(4).zip(state) {
+        chunk.copy_from_slice(&value.to_be_bytes());
+    }
+    digest
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, byte)| {
+            group | (u32::from(*byte) << (16 - 8 * i))
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * i)) as usize & 0x3F] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[test]
+fn test_websocket_handshake() {
+    use crate::helper::encode_hex;
+
+    assert_eq!(
+        encode_hex(&sha1(b"abc")),
+        "a9993e364706816aba3e25717850c26c9cd0d89d"
+    );
+    assert_eq!(base64_encode(b"fo"), "Zm8=");
+    // the example of the RFC
+    assert_eq!(
+        accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+        "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+    );
+}
+
+#[test]
+fn test_websocket_signing_session() {
+    use std::time::Duration;
+
+    use crate::{frost::Frost, json::Json, rpc::RpcCoordinator};
+
+    let frost: Frost = Frost::signature_share(2, 3);
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let coordinator = RpcCoordinator::new(frost.group_pk, Duration::from_secs(60));
+    let allowed_origins = vec!["https://wallet.example".to_string()];
+    thread::spawn(move || serve(coordinator, listener, allowed_origins));
+
+    let mut socket = WebSocket::connect(address, "localhost", "/").unwrap();
+    let ids = frost
+        .participants()
+        .map(|id| format!("\"{id}\""))
+        .collect::<Vec<_>>()
+        .join(",");
+    // longer than 125 bytes, to exercise the extended payload length
+    let message = "ab".repeat(100);
+    socket
+        .send_text(&format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"createSession","params":{{"message":"{message}","participants":[{ids}]}}}}"#
+        ))
+        .unwrap();
+    let response = Json::parse(&socket.receive_text().unwrap().unwrap()).unwrap();
+    let session_id = response
+        .field("result")
+        .unwrap()
+        .field("sessionId")
+        .unwrap();
+
+    // the connection stays open for the next request
+    socket
+        .send_text(&format!(
+            r#"{{"jsonrpc":"2.0","id":2,"method":"getSignature","params":{{"sessionId":{session_id}}}}}"#
+        ))
+        .unwrap();
+    let response = Json::parse(&socket.receive_text().unwrap().unwrap()).unwrap();
+    assert_eq!(
+        response
+            .field("result")
+            .unwrap()
+            .field("signature")
+            .unwrap(),
+        &Json::Null
+    );
+
+    socket.close().unwrap();
+    assert_eq!(socket.receive_text().unwrap(), None);
+
+    // a ping is answered, but a fragmented one closes the connection
+    let mut socket = WebSocket::connect(address, "localhost", "/").unwrap();
+    socket.send_frame(OPCODE_PING, b"ping").unwrap();
+    assert_eq!(
+        socket.read_frame().unwrap(),
+        (true, OPCODE_PONG, b"ping".to_vec())
+    );
+    socket
+        .writer
+        .write_all(&[OPCODE_PING, 0x80, 0, 0, 0, 0])
+        .unwrap();
+    assert!(socket.receive_text().is_err());
+
+    // web pages of other origins are refused, the configured one is served
+    let handshake = |origin: &str| {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "GET / HTTP/1.1\r\n\
+             Host: localhost\r\n\
+             Origin: {origin}\r\n\
+             Upgrade: websocket\r\n\
+             Connection: Upgrade\r\n\
+             Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+             Sec-WebSocket-Version: 13\r\n\r\n"
+        )
+        .unwrap();
+        let mut status = String::new();
+        BufReader::new(stream).read_line(&mut status).unwrap();
+        status
+    };
+    assert!(handshake("https://evil.example").starts_with("HTTP/1.1 403"));
+    assert!(handshake("https://wallet.example").starts_with("HTTP/1.1 101"));
+}